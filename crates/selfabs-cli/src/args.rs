@@ -0,0 +1,167 @@
+//! Command-line parsing.
+
+use std::path::PathBuf;
+
+use selfabs::{Edge, SelfAbsError};
+
+pub const USAGE: &str = "\
+usage: selfabs-cli <booth|troger|fluo|atoms|ameyanagi> [options]
+
+Corrects a two- or three-column ASCII file (x, signal[, sigma]) for
+self-absorption. x is E in eV or k in 1/Å, detected from its range; the
+signal is chi, or normalized mu(E) for fluo.
+
+required:
+  --input FILE          input column file
+  --output FILE         corrected column file
+  --formula FORMULA     sample formula, e.g. Fe2O3
+  --element SYMBOL      absorbing element
+
+options:
+  --edge EDGE           absorption edge (default K)
+  --phi DEG             incident angle from the surface (default 45)
+  --theta DEG           exit angle from the surface (default 45)
+  --thickness-um UM     sample thickness in μm (booth, ameyanagi)
+  --density G_CM3       sample density (default: looked up by formula)
+  --chi CHI             chi of the reported suppression (ameyanagi,
+                        default 0.1)
+  --sidecar FILE        JSON parameter file (default OUTPUT.json)
+  -h, --help            print this help
+";
+
+/// Correction the subcommand selects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Booth,
+    Troger,
+    Fluo,
+    Atoms,
+    Ameyanagi,
+}
+
+impl Command {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Booth => "booth",
+            Self::Troger => "troger",
+            Self::Fluo => "fluo",
+            Self::Atoms => "atoms",
+            Self::Ameyanagi => "ameyanagi",
+        }
+    }
+}
+
+/// Parsed command line.
+#[derive(Debug, Clone)]
+pub struct Args {
+    pub command: Command,
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub sidecar: PathBuf,
+    pub formula: String,
+    pub element: String,
+    pub edge: Edge,
+    pub phi_deg: f64,
+    pub theta_deg: f64,
+    pub thickness_um: Option<f64>,
+    pub density_g_cm3: Option<f64>,
+    pub chi: f64,
+}
+
+/// Outcome of [`parse`] other than a complete [`Args`].
+#[derive(Debug)]
+pub enum ParseError {
+    /// `-h`/`--help` was given.
+    Help,
+    /// Invalid command line, with the reason.
+    Usage(String),
+}
+
+/// Parse the arguments after the program name.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Args, ParseError> {
+    let mut args = args.into_iter();
+    let command = match args.next().as_deref() {
+        Some("booth") => Command::Booth,
+        Some("troger") => Command::Troger,
+        Some("fluo") => Command::Fluo,
+        Some("atoms") => Command::Atoms,
+        Some("ameyanagi") => Command::Ameyanagi,
+        Some("-h" | "--help") => return Err(ParseError::Help),
+        Some(other) => return Err(ParseError::Usage(format!("unknown command '{other}'"))),
+        None => return Err(ParseError::Usage("missing command".to_string())),
+    };
+
+    let mut input = None;
+    let mut output = None;
+    let mut sidecar = None;
+    let mut formula = None;
+    let mut element = None;
+    let mut edge = Edge::K;
+    let mut phi_deg = 45.0;
+    let mut theta_deg = 45.0;
+    let mut thickness_um = None;
+    let mut density_g_cm3 = None;
+    let mut chi = 0.1;
+
+    while let Some(flag) = args.next() {
+        if flag == "-h" || flag == "--help" {
+            return Err(ParseError::Help);
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| ParseError::Usage(format!("{flag} needs a value")))?;
+        let number = || {
+            value
+                .parse::<f64>()
+                .map_err(|_| ParseError::Usage(format!("{flag}: '{value}' is not a number")))
+        };
+        match flag.as_str() {
+            "--input" => input = Some(PathBuf::from(&value)),
+            "--output" => output = Some(PathBuf::from(&value)),
+            "--sidecar" => sidecar = Some(PathBuf::from(&value)),
+            "--formula" => formula = Some(value.clone()),
+            "--element" => element = Some(value.clone()),
+            "--edge" => {
+                edge = value
+                    .parse()
+                    .map_err(|e: SelfAbsError| ParseError::Usage(format!("--edge: {e}")))?
+            }
+            "--phi" => phi_deg = number()?,
+            "--theta" => theta_deg = number()?,
+            "--thickness-um" => thickness_um = Some(number()?),
+            "--density" => density_g_cm3 = Some(number()?),
+            "--chi" => chi = number()?,
+            _ => return Err(ParseError::Usage(format!("unknown option '{flag}'"))),
+        }
+    }
+
+    let output: PathBuf = required(output, "--output")?;
+    if matches!(command, Command::Booth | Command::Ameyanagi) && thickness_um.is_none() {
+        return Err(ParseError::Usage(format!(
+            "{} needs --thickness-um",
+            command.name()
+        )));
+    }
+    Ok(Args {
+        command,
+        input: required(input, "--input")?,
+        sidecar: sidecar.unwrap_or_else(|| {
+            let mut path = output.clone().into_os_string();
+            path.push(".json");
+            path.into()
+        }),
+        output,
+        formula: required(formula, "--formula")?,
+        element: required(element, "--element")?,
+        edge,
+        phi_deg,
+        theta_deg,
+        thickness_um,
+        density_g_cm3,
+        chi,
+    })
+}
+
+fn required<T>(value: Option<T>, flag: &str) -> Result<T, ParseError> {
+    value.ok_or_else(|| ParseError::Usage(format!("{flag} is required")))
+}
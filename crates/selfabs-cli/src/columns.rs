@@ -0,0 +1,49 @@
+//! Abscissa detection and output formatting of column files. Input files are
+//! read by [`selfabs::io::read_columns`].
+
+use std::fmt::Write as _;
+
+/// Largest abscissa of a file read as k (Å⁻¹); anything above is E (eV).
+/// No absorption edge used for XAS lies this low in energy.
+pub const K_MAX: f64 = 50.0;
+
+/// Abscissa of a column file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Domain {
+    /// Energy in eV.
+    Energy,
+    /// Photoelectron wavenumber in Å⁻¹.
+    K,
+}
+
+impl Domain {
+    /// k if every abscissa is below [`K_MAX`], otherwise energy.
+    pub fn of(x: &[f64]) -> Self {
+        if x.iter().all(|&x| x < K_MAX) {
+            Self::K
+        } else {
+            Self::Energy
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Energy => "energy",
+            Self::K => "k",
+        }
+    }
+}
+
+/// Format `header` lines as comments followed by the columns.
+pub fn format(header: &[String], columns: &[&[f64]]) -> String {
+    let mut text = String::new();
+    for line in header {
+        let _ = writeln!(text, "# {line}");
+    }
+    let rows = columns.first().map_or(0, |c| c.len());
+    for i in 0..rows {
+        let row: Vec<String> = columns.iter().map(|c| c[i].to_string()).collect();
+        let _ = writeln!(text, "{}", row.join(" "));
+    }
+    text
+}
@@ -0,0 +1,249 @@
+//! Self-absorption correction of χ or μ(E) column files.
+//!
+//! ```text
+//! selfabs-cli troger --input fe2o3.chi --output fe2o3_corr.chi \
+//!     --formula Fe2O3 --element Fe --edge K
+//! ```
+//!
+//! Writes the corrected columns and a JSON sidecar with the correction
+//! parameters. Exits with 2 on a usage error and 1 on any other failure.
+
+mod args;
+mod columns;
+
+use std::fmt;
+use std::fs;
+use std::process::ExitCode;
+
+use selfabs::ameyanagi::{AmeyanagiCorrection, AmeyanagiSuppressionSettings};
+use selfabs::atoms::{AtomsOptions, atoms_with_sample_info};
+use selfabs::booth::{BoothCorrection, BoothOptions, booth_with_sample_info};
+use selfabs::fluo::{FluoCorrection, FluoOptions, correct_mu, fluo_params_with_sample_info};
+use selfabs::io::read_columns;
+use selfabs::troger::{TrogerOptions, troger_with_sample_info};
+use selfabs::{
+    CorrectionSummary, FluorescenceGeometry, SelfAbsContext, SelfAbsCorrection, SelfAbsError,
+    k_to_energies, lookup_density, validate_energy_grid,
+};
+
+use crate::args::{Args, Command, ParseError, USAGE};
+use crate::columns::Domain;
+
+/// Signal step of the finite difference propagating the third column.
+const SIGMA_STEP: f64 = 1e-6;
+
+/// Failure of a run.
+#[derive(Debug)]
+enum CliError {
+    /// Reading or writing a file.
+    Io(String),
+    /// Malformed input file.
+    Input(String),
+    /// The correction itself.
+    SelfAbs(SelfAbsError),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(s) | Self::Input(s) => f.write_str(s),
+            Self::SelfAbs(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl From<SelfAbsError> for CliError {
+    fn from(e: SelfAbsError) -> Self {
+        Self::SelfAbs(e)
+    }
+}
+
+type Correct = Box<dyn Fn(&[f64]) -> Result<Vec<f64>, SelfAbsError>>;
+
+fn to_json<T: serde::Serialize + ?Sized>(value: &T) -> Result<serde_json::Value, CliError> {
+    serde_json::to_value(value).map_err(|e| CliError::Io(format!("cannot encode the sidecar: {e}")))
+}
+
+fn main() -> ExitCode {
+    let args = match args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(ParseError::Help) => {
+            print!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(ParseError::Usage(reason)) => {
+            eprintln!("error: {reason}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &Args) -> Result<(), CliError> {
+    let data = read_columns(&args.input)?;
+
+    let ctx = SelfAbsContext::new();
+    let info = ctx.sample_info(&args.formula, &args.element, args.edge)?;
+    let domain = Domain::of(&data.x);
+    let energies = match domain {
+        Domain::Energy => data.x.clone(),
+        Domain::K => {
+            if args.command == Command::Fluo {
+                return Err(CliError::Input(
+                    "fluo corrects normalized mu(E) and needs an energy grid".to_string(),
+                ));
+            }
+            if let Some(k) = data.x.iter().find(|&&k| k < 0.0) {
+                return Err(CliError::Input(format!("negative k {k} in the input")));
+            }
+            k_to_energies(&data.x, info.edge_energy())
+        }
+    };
+    validate_energy_grid(&energies, info.edge_energy())?;
+
+    let geometry = FluorescenceGeometry {
+        theta_incident_deg: args.phi_deg,
+        theta_fluorescence_deg: args.theta_deg,
+        ..Default::default()
+    };
+    let density = || match args.density_g_cm3 {
+        Some(density) => Ok(density),
+        None => lookup_density(&ctx, &args.formula).map(|(density, _)| density),
+    };
+    let thickness_um = args.thickness_um.unwrap_or_default();
+
+    let (correct, parameters, summary, density_used): (Correct, _, CorrectionSummary, _) =
+        match args.command {
+            Command::Troger => {
+                let result = troger_with_sample_info(
+                    &ctx,
+                    &info,
+                    &energies,
+                    Some(geometry),
+                    &TrogerOptions::default(),
+                )?;
+                let parameters = to_json(&result)?;
+                let summary = result.summary();
+                let correct = move |chi: &[f64]| result.correct_chi(chi);
+                (Box::new(correct), parameters, summary, None)
+            }
+            Command::Booth => {
+                let density = density()?;
+                let result = booth_with_sample_info(
+                    &ctx,
+                    &info,
+                    &energies,
+                    Some(geometry),
+                    thickness_um,
+                    &BoothOptions::default(),
+                )?;
+                let parameters = to_json(&result)?;
+                let correction = BoothCorrection::new(result, density, thickness_um)?;
+                let summary = correction.summary();
+                let correct = move |chi: &[f64]| correction.correct_chi(chi);
+                (Box::new(correct), parameters, summary, Some(density))
+            }
+            Command::Atoms => {
+                let options = AtomsOptions {
+                    geometry: Some(geometry),
+                    ..Default::default()
+                };
+                let result = atoms_with_sample_info(&ctx, &info, &energies, &options)?;
+                let parameters = to_json(&result)?;
+                let summary = result.summary();
+                let correct = move |chi: &[f64]| SelfAbsCorrection::correct_chi(&result, chi);
+                (Box::new(correct), parameters, summary, None)
+            }
+            Command::Ameyanagi => {
+                let density = density()?;
+                let settings = AmeyanagiSuppressionSettings::builder()
+                    .density(density)
+                    .angles_deg(args.phi_deg, args.theta_deg)
+                    .thickness_cm(thickness_um * 1e-4)
+                    .chi(args.chi)
+                    .build()?;
+                let correction = AmeyanagiCorrection::new(&ctx, &info, &energies, settings)?;
+                let parameters = to_json(correction.result())?;
+                let summary = correction.summary();
+                let correct = move |chi: &[f64]| correction.correct_chi(chi);
+                (Box::new(correct), parameters, summary, Some(density))
+            }
+            Command::Fluo => {
+                let params = fluo_params_with_sample_info(
+                    &ctx,
+                    &info,
+                    &energies,
+                    Some(geometry),
+                    &FluoOptions::default(),
+                )?;
+                let parameters = to_json(&params)?;
+                let correction = FluoCorrection::new(params, &energies)?;
+                let summary = correction.summary();
+                let correct = move |mu: &[f64]| Ok(correct_mu(correction.params(), mu));
+                (Box::new(correct), parameters, summary, None)
+            }
+        };
+
+    let corrected = correct(&data.y)?;
+    // σ of the corrected signal from the local slope of the correction.
+    let sigma = match &data.sigma {
+        Some(sigma) => {
+            let shifted = |step: f64| -> Vec<f64> { data.y.iter().map(|s| s + step).collect() };
+            let up = correct(&shifted(SIGMA_STEP))?;
+            let down = correct(&shifted(-SIGMA_STEP))?;
+            Some(
+                sigma
+                    .iter()
+                    .zip(up.iter().zip(&down))
+                    .map(|(s, (u, d))| ((u - d) / (2.0 * SIGMA_STEP)).abs() * s)
+                    .collect::<Vec<f64>>(),
+            )
+        }
+        None => None,
+    };
+
+    let mut out_columns: Vec<&[f64]> = vec![&data.x, &corrected];
+    let mut names = format!("{} corrected", domain.name());
+    if let Some(sigma) = &sigma {
+        out_columns.push(sigma);
+        names.push_str(" sigma");
+    }
+    let header = [
+        format!(
+            "selfabs-cli {}: {} {} {}",
+            args.command.name(),
+            args.formula,
+            args.element,
+            args.edge
+        ),
+        names,
+    ];
+    fs::write(&args.output, columns::format(&header, &out_columns))
+        .map_err(|e| CliError::Io(format!("cannot write {}: {e}", args.output.display())))?;
+
+    let sidecar = serde_json::json!({
+        "command": args.command.name(),
+        "input": args.input.display().to_string(),
+        "domain": domain.name(),
+        "formula": args.formula,
+        "central_element": args.element,
+        "edge": args.edge.as_str(),
+        "edge_energy": info.edge_energy(),
+        "geometry": to_json(&geometry)?,
+        "thickness_um": args.thickness_um,
+        "density_g_cm3": density_used,
+        "summary": to_json(&summary)?,
+        "parameters": parameters,
+    });
+    let json = serde_json::to_string_pretty(&sidecar)
+        .map_err(|e| CliError::Io(format!("cannot encode the sidecar: {e}")))?;
+    fs::write(&args.sidecar, json + "\n")
+        .map_err(|e| CliError::Io(format!("cannot write {}: {e}", args.sidecar.display())))?;
+    Ok(())
+}
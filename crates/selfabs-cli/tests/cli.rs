@@ -0,0 +1,197 @@
+//! Drives the selfabs-cli binary on the synthetic files in `tests/data`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use serde_json::Value;
+
+fn data(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/data")
+        .join(name)
+}
+
+fn out_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_TARGET_TMPDIR")).join(name)
+}
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_selfabs-cli"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Run `command` on `input`, returning the output columns and the sidecar.
+fn correct(command: &str, input: &str, extra: &[&str]) -> (Vec<Vec<f64>>, Value) {
+    let output = out_path(&format!("{command}_{input}"));
+    let input_path = data(input);
+    let mut args = vec![
+        command,
+        "--input",
+        input_path.to_str().unwrap(),
+        "--output",
+        output.to_str().unwrap(),
+        "--formula",
+        "Fe2O3",
+        "--element",
+        "Fe",
+    ];
+    args.extend_from_slice(extra);
+    let result = run(&args);
+    assert!(
+        result.status.success(),
+        "{command} {input}: {}",
+        String::from_utf8_lossy(&result.stderr)
+    );
+
+    let rows = fs::read_to_string(&output)
+        .unwrap()
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .map(|line| {
+            line.split_whitespace()
+                .map(|v| v.parse().unwrap())
+                .collect()
+        })
+        .collect();
+    let sidecar = fs::read_to_string(format!("{}.json", output.display())).unwrap();
+    (rows, serde_json::from_str(&sidecar).unwrap())
+}
+
+fn read_rows(name: &str) -> Vec<Vec<f64>> {
+    fs::read_to_string(data(name))
+        .unwrap()
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .map(|line| {
+            line.split_whitespace()
+                .map(|v| v.parse().unwrap())
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn test_troger_on_k_file_matches_library() {
+    let (rows, sidecar) = correct("troger", "fe2o3_chi_k.dat", &[]);
+    let input = read_rows("fe2o3_chi_k.dat");
+    assert_eq!(rows.len(), input.len());
+    assert_eq!(sidecar["command"], "troger");
+    assert_eq!(sidecar["domain"], "k");
+
+    let factor = sidecar["parameters"]["correction_factor"]
+        .as_array()
+        .unwrap();
+    assert_eq!(factor.len(), input.len());
+    for ((row, original), f) in rows.iter().zip(&input).zip(factor) {
+        assert_eq!(row.len(), 2);
+        assert_eq!(row[0], original[0]);
+        let expected = original[1] * f.as_f64().unwrap();
+        assert!((row[1] - expected).abs() <= 1e-12 * expected.abs().max(1.0));
+    }
+    let r_mean = sidecar["summary"]["r_mean"].as_f64().unwrap();
+    assert!(r_mean > 0.1 && r_mean < 1.0, "{r_mean}");
+}
+
+#[test]
+fn test_every_command_runs() {
+    let thickness = ["--thickness-um", "20"];
+    for command in ["troger", "booth", "atoms", "ameyanagi"] {
+        let (rows, sidecar) = correct(command, "fe2o3_chi_e.dat", &thickness);
+        let input = read_rows("fe2o3_chi_e.dat");
+        assert_eq!(rows.len(), input.len(), "{command}");
+        assert_eq!(sidecar["domain"], "energy");
+        assert!(sidecar["edge_energy"].as_f64().unwrap() > 7100.0);
+
+        // Three columns in, three out; the correction amplifies χ and its σ
+        // above the edge.
+        let above = rows.iter().zip(&input).filter(|(_, i)| i[0] > 7200.0);
+        for (row, original) in above {
+            assert_eq!(row.len(), 3, "{command}");
+            assert!(row[2] > original[2], "{command}: {row:?}");
+        }
+        let gain: f64 = rows.iter().map(|r| r[1].abs()).sum::<f64>()
+            / input.iter().map(|r| r[1].abs()).sum::<f64>();
+        assert!(gain > 1.05, "{command}: {gain}");
+    }
+
+    let (rows, sidecar) = correct("fluo", "fe2o3_mu_e.dat", &[]);
+    assert_eq!(sidecar["command"], "fluo");
+    assert!(sidecar["parameters"]["beta"].as_f64().unwrap() > 0.0);
+    assert_eq!(rows.len(), read_rows("fe2o3_mu_e.dat").len());
+}
+
+#[test]
+fn test_failures_exit_nonzero_with_message() {
+    let output = out_path("failure.dat");
+    let output = output.to_str().unwrap();
+    let k_file = data("fe2o3_chi_k.dat");
+    let k_file = k_file.to_str().unwrap();
+
+    // A SelfAbsError is reported as such.
+    let bad_formula = run(&[
+        "troger",
+        "--input",
+        k_file,
+        "--output",
+        output,
+        "--formula",
+        "Fe2Q3",
+        "--element",
+        "Fe",
+    ]);
+    assert_eq!(bad_formula.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&bad_formula.stderr);
+    assert!(stderr.starts_with("error: invalid formula"), "{stderr}");
+
+    // Grid validation.
+    let unsorted = out_path("unsorted.dat");
+    fs::write(&unsorted, "7000 0\n7010 0\n7005 0\n").unwrap();
+    let result = run(&[
+        "troger",
+        "--input",
+        unsorted.to_str().unwrap(),
+        "--output",
+        output,
+        "--formula",
+        "Fe2O3",
+        "--element",
+        "Fe",
+    ]);
+    assert_eq!(result.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(stderr.contains("strictly increasing"), "{stderr}");
+
+    // Fluo needs μ(E), not χ(k).
+    let result = run(&[
+        "fluo",
+        "--input",
+        k_file,
+        "--output",
+        output,
+        "--formula",
+        "Fe2O3",
+        "--element",
+        "Fe",
+    ]);
+    assert_eq!(result.status.code(), Some(1));
+
+    // Usage errors.
+    let result = run(&[
+        "booth",
+        "--input",
+        k_file,
+        "--output",
+        output,
+        "--formula",
+        "Fe2O3",
+        "--element",
+        "Fe",
+    ]);
+    assert_eq!(result.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&result.stderr).contains("--thickness-um"));
+    assert_eq!(run(&["unknown"]).status.code(), Some(2));
+    assert!(run(&["--help"]).status.success());
+}
@@ -0,0 +1,474 @@
+//! Sample-preparation advice from the exact Ameyanagi model.
+
+use std::f64::consts::PI;
+
+use crate::ameyanagi::{
+    AmeyanagiThicknessInput, BinderSpec, ameyanagi_suppression_exact_with_sample_info,
+    settings_for_geometry,
+};
+use crate::common::{
+    EXAFS_OFFSET_EV, Edge, ElementRef, FluorescenceGeometry, SelfAbsError, lookup_density,
+};
+use crate::context::SelfAbsContext;
+use crate::correction::NOMINAL_CHI;
+
+/// Smallest sample mass fraction the search considers.
+const MIN_SAMPLE_FRACTION: f64 = 1e-4;
+/// Relative tolerance of the sample mass fraction.
+const FRACTION_RTOL: f64 = 1e-4;
+/// Thickness (cm) standing in for an infinitely thick sample.
+const THICK_LIMIT_CM: f64 = 10.0;
+
+/// Pellet the diluted sample is pressed into.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ThicknessConstraint {
+    /// Pellet of fixed thickness and diameter (cm).
+    Pellet { thickness_cm: f64, diameter_cm: f64 },
+    /// Thick-limit sample; no pellet mass is reported.
+    Thick,
+}
+
+/// Result of [`dilution_for_target`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DilutionAdvice {
+    /// The undiluted sample already meets the target.
+    pub no_dilution_needed: bool,
+    /// Largest sample mass fraction of the mixture meeting the target.
+    pub sample_mass_fraction: f64,
+    /// Binder mass per unit sample mass.
+    pub binder_to_sample_ratio: f64,
+    /// r_min over the EXAFS region (E > E₀ + 30 eV) at that fraction.
+    pub r_min: f64,
+    /// Mixture density (g/cm³) assumed: sample and binder volumes add, with
+    /// no porosity.
+    pub effective_density_g_cm3: f64,
+    /// Total pellet mass (g) for a [`ThicknessConstraint::Pellet`].
+    pub pellet_mass_g: Option<f64>,
+    /// Sample share of `pellet_mass_g`.
+    pub sample_mass_g: Option<f64>,
+}
+
+/// Least dilution with `binder_formula` that keeps the suppression at or
+/// above `target_r_min` over the EXAFS region.
+///
+/// The exact model runs at χ = [`NOMINAL_CHI`] on the sample–binder mixture
+/// for each trial mass fraction; r_min rises monotonically on dilution, so
+/// the fraction is found by bisection in log space. Sample and binder
+/// densities are looked up by formula. Errors when even a sample fraction
+/// of 10⁻⁴ misses the target, which is a geometry problem rather than one
+/// dilution can solve.
+#[allow(clippy::too_many_arguments)]
+pub fn dilution_for_target(
+    formula: &str,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies: &[f64],
+    geometry: Option<FluorescenceGeometry>,
+    binder_formula: &str,
+    target_r_min: f64,
+    thickness_constraint: ThicknessConstraint,
+) -> Result<DilutionAdvice, SelfAbsError> {
+    dilution_for_target_on(
+        &SelfAbsContext::new(),
+        formula,
+        central_element,
+        edge,
+        energies,
+        geometry,
+        binder_formula,
+        target_r_min,
+        thickness_constraint,
+    )
+}
+
+/// [`dilution_for_target`] on `ctx`, sharing its database and cached
+/// samples with other calls.
+#[allow(clippy::too_many_arguments)]
+pub fn dilution_for_target_on(
+    ctx: &SelfAbsContext,
+    formula: &str,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies: &[f64],
+    geometry: Option<FluorescenceGeometry>,
+    binder_formula: &str,
+    target_r_min: f64,
+    thickness_constraint: ThicknessConstraint,
+) -> Result<DilutionAdvice, SelfAbsError> {
+    if !target_r_min.is_finite() {
+        return Err(SelfAbsError::InsufficientData(
+            "target r_min must be finite".to_string(),
+        ));
+    }
+    let (thickness_cm, diameter_cm) = match thickness_constraint {
+        ThicknessConstraint::Pellet {
+            thickness_cm,
+            diameter_cm,
+        } => {
+            if !(thickness_cm.is_finite() && thickness_cm > 0.0) {
+                return Err(SelfAbsError::InsufficientData(
+                    "pellet thickness must be finite and > 0".to_string(),
+                ));
+            }
+            if !(diameter_cm.is_finite() && diameter_cm > 0.0) {
+                return Err(SelfAbsError::InsufficientData(
+                    "pellet diameter must be finite and > 0".to_string(),
+                ));
+            }
+            (thickness_cm, Some(diameter_cm))
+        }
+        ThicknessConstraint::Thick => (THICK_LIMIT_CM, None),
+    };
+
+    let info = ctx.sample_info(formula, central_element, edge)?;
+    let (sample_density, _) = lookup_density(ctx, formula)?;
+    let (binder_density, _) = lookup_density(ctx, binder_formula)?;
+    let mixture_density = |w: f64| 1.0 / (w / sample_density + (1.0 - w) / binder_density);
+    // Any diameter works for the attenuation; the pellet masses scale out.
+    let area = PI * (diameter_cm.unwrap_or(1.0) * 0.5).powi(2);
+
+    let r_min_at = |w: f64| -> Result<f64, SelfAbsError> {
+        let (thickness_input, binder) = if w >= 1.0 {
+            (AmeyanagiThicknessInput::ThicknessCm(thickness_cm), None)
+        } else {
+            let total_mass = mixture_density(w) * area * thickness_cm;
+            (
+                AmeyanagiThicknessInput::PelletMassDiameter {
+                    mass_g: w * total_mass,
+                    diameter_cm: diameter_cm.unwrap_or(1.0),
+                },
+                Some(BinderSpec {
+                    formula: binder_formula.to_string(),
+                    mass_g: (1.0 - w) * total_mass,
+                    density_g_cm3: binder_density,
+                }),
+            )
+        };
+        let settings = settings_for_geometry(
+            geometry,
+            sample_density,
+            thickness_input,
+            binder,
+            NOMINAL_CHI,
+        )?;
+        let result = ameyanagi_suppression_exact_with_sample_info(ctx, &info, energies, settings)?;
+        let r_min = energies
+            .iter()
+            .zip(&result.suppression_factor)
+            .filter(|&(&e, _)| e > result.edge_energy + EXAFS_OFFSET_EV)
+            .map(|(_, &r)| r)
+            .fold(f64::INFINITY, f64::min);
+        if r_min.is_infinite() {
+            return Err(SelfAbsError::InsufficientData(format!(
+                "no grid points above E0 + {EXAFS_OFFSET_EV} eV"
+            )));
+        }
+        Ok(r_min)
+    };
+    let advice = |w: f64, r_min: f64| DilutionAdvice {
+        no_dilution_needed: w >= 1.0,
+        sample_mass_fraction: w,
+        binder_to_sample_ratio: (1.0 - w) / w,
+        r_min,
+        effective_density_g_cm3: mixture_density(w),
+        pellet_mass_g: diameter_cm.map(|_| mixture_density(w) * area * thickness_cm),
+        sample_mass_g: diameter_cm.map(|_| w * mixture_density(w) * area * thickness_cm),
+    };
+
+    let r_pure = r_min_at(1.0)?;
+    if r_pure >= target_r_min {
+        return Ok(advice(1.0, r_pure));
+    }
+    let r_dilute = r_min_at(MIN_SAMPLE_FRACTION)?;
+    if r_dilute < target_r_min {
+        return Err(SelfAbsError::TargetUnreachable {
+            target_r_min,
+            best_r_min: r_dilute,
+            reason: format!(
+                "target r_min {target_r_min} is not reached even at sample fraction {MIN_SAMPLE_FRACTION} (r_min = {r_dilute}); change the geometry"
+            ),
+        });
+    }
+
+    let (mut lo, mut hi) = (MIN_SAMPLE_FRACTION, 1.0);
+    let mut r_lo = r_dilute;
+    while hi / lo - 1.0 > FRACTION_RTOL {
+        let mid = (lo * hi).sqrt();
+        let r_mid = r_min_at(mid)?;
+        if r_mid >= target_r_min {
+            lo = mid;
+            r_lo = r_mid;
+        } else {
+            hi = mid;
+        }
+    }
+    Ok(advice(lo, r_lo))
+}
+
+/// Thickness (cm) below which the exact suppression at `chi` stays within
+/// `max_error_fraction` of unity, R ≥ 1 − `max_error_fraction`, over the
+/// EXAFS region (E > E₀ + 30 eV).
+///
+/// A bisection in log-thickness through
+/// [`ameyanagi_max_thickness`](crate::ameyanagi::ameyanagi_max_thickness).
+/// Returns `f64::INFINITY` when even the thick limit meets the threshold,
+/// as for a dilute absorber. `density_g_cm3` of `None` looks the formula
+/// up. Errors when `max_error_fraction` is outside (0, 1) or a 1 nm sample
+/// already misses it.
+#[allow(clippy::too_many_arguments)]
+pub fn thin_limit_thickness(
+    formula: &str,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies: &[f64],
+    geometry: Option<FluorescenceGeometry>,
+    density_g_cm3: Option<f64>,
+    chi: f64,
+    max_error_fraction: f64,
+) -> Result<f64, SelfAbsError> {
+    thin_limit_thickness_on(
+        &SelfAbsContext::new(),
+        formula,
+        central_element,
+        edge,
+        energies,
+        geometry,
+        density_g_cm3,
+        chi,
+        max_error_fraction,
+    )
+}
+
+/// [`thin_limit_thickness`] on `ctx`, sharing its database and cached
+/// samples with other calls.
+#[allow(clippy::too_many_arguments)]
+pub fn thin_limit_thickness_on(
+    ctx: &SelfAbsContext,
+    formula: &str,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies: &[f64],
+    geometry: Option<FluorescenceGeometry>,
+    density_g_cm3: Option<f64>,
+    chi: f64,
+    max_error_fraction: f64,
+) -> Result<f64, SelfAbsError> {
+    if !(max_error_fraction > 0.0 && max_error_fraction < 1.0) {
+        return Err(SelfAbsError::InsufficientData(
+            "max_error_fraction must be in (0, 1)".to_string(),
+        ));
+    }
+    let density = match density_g_cm3 {
+        Some(density) => density,
+        None => lookup_density(ctx, formula)?.0,
+    };
+    // The thickness input is replaced during the search.
+    let settings = settings_for_geometry(
+        geometry,
+        density,
+        AmeyanagiThicknessInput::ThicknessCm(THICK_LIMIT_CM),
+        None,
+        chi,
+    )?;
+    let recommendation = ctx.ameyanagi_max_thickness(
+        formula,
+        central_element,
+        edge,
+        energies,
+        settings,
+        1.0 - max_error_fraction,
+        None,
+    )?;
+    Ok(recommendation.max_thickness_cm.unwrap_or(f64::INFINITY))
+}
+
+/// [`thin_limit_thickness`] in μm.
+#[allow(clippy::too_many_arguments)]
+pub fn thin_limit_thickness_um(
+    formula: &str,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies: &[f64],
+    geometry: Option<FluorescenceGeometry>,
+    density_g_cm3: Option<f64>,
+    chi: f64,
+    max_error_fraction: f64,
+) -> Result<f64, SelfAbsError> {
+    thin_limit_thickness(
+        formula,
+        central_element,
+        edge,
+        energies,
+        geometry,
+        density_g_cm3,
+        chi,
+        max_error_fraction,
+    )
+    .map(|cm| cm * 1e4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ameyanagi::ameyanagi_suppression_exact;
+
+    fn energies() -> Vec<f64> {
+        (7000..=8000).step_by(10).map(|e| e as f64).collect()
+    }
+
+    #[test]
+    fn test_dilution_for_target_fe2o3() {
+        let pellet = ThicknessConstraint::Pellet {
+            thickness_cm: 0.1,
+            diameter_cm: 1.3,
+        };
+        let moderate =
+            dilution_for_target("Fe2O3", "Fe", Edge::K, &energies(), None, "BN", 0.8, pellet)
+                .unwrap();
+        assert!(!moderate.no_dilution_needed);
+        assert!(
+            moderate.r_min >= 0.8 && moderate.r_min < 0.81,
+            "{moderate:?}"
+        );
+        assert!(moderate.sample_mass_fraction < 0.5);
+        assert!(moderate.effective_density_g_cm3 > 2.1 && moderate.effective_density_g_cm3 < 5.24);
+        let pellet_mass = moderate.pellet_mass_g.unwrap();
+        let expected = moderate.effective_density_g_cm3 * PI * 0.65 * 0.65 * 0.1;
+        assert!((pellet_mass - expected).abs() < 1e-12);
+
+        // A stricter target needs more binder.
+        let strict = dilution_for_target(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies(),
+            None,
+            "BN",
+            0.95,
+            pellet,
+        )
+        .unwrap();
+        assert!(strict.sample_mass_fraction < moderate.sample_mass_fraction);
+        assert!(strict.binder_to_sample_ratio > moderate.binder_to_sample_ratio);
+
+        let thick = dilution_for_target(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies(),
+            None,
+            "BN",
+            0.8,
+            ThicknessConstraint::Thick,
+        )
+        .unwrap();
+        assert!(thick.pellet_mass_g.is_none());
+        assert!(thick.sample_mass_fraction <= moderate.sample_mass_fraction);
+    }
+
+    #[test]
+    fn test_dilution_not_needed_or_impossible() {
+        let thin = ThicknessConstraint::Pellet {
+            thickness_cm: 1e-5,
+            diameter_cm: 1.3,
+        };
+        let advice =
+            dilution_for_target("Fe2O3", "Fe", Edge::K, &energies(), None, "BN", 0.9, thin)
+                .unwrap();
+        assert!(advice.no_dilution_needed);
+        assert_eq!(advice.sample_mass_fraction, 1.0);
+        assert_eq!(advice.binder_to_sample_ratio, 0.0);
+
+        // R ≤ 1, so no dilution reaches a target above 1.
+        let err = dilution_for_target(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies(),
+            None,
+            "BN",
+            1.01,
+            ThicknessConstraint::Thick,
+        );
+        assert!(
+            matches!(err, Err(SelfAbsError::TargetUnreachable { best_r_min, .. }) if best_r_min < 1.01)
+        );
+    }
+
+    #[test]
+    fn test_thin_limit_thickness_fe2o3() {
+        let limit = |fraction| {
+            thin_limit_thickness(
+                "Fe2O3",
+                "Fe",
+                Edge::K,
+                &energies(),
+                None,
+                None,
+                0.1,
+                fraction,
+            )
+            .unwrap()
+        };
+        let one = limit(0.01);
+        let five = limit(0.05);
+        assert!(one.is_finite() && five.is_finite());
+        assert!(one > 0.0 && one < five, "{one} {five}");
+
+        // At the limit R_min sits on the threshold.
+        let settings = settings_for_geometry(
+            None,
+            5.24,
+            AmeyanagiThicknessInput::ThicknessCm(five),
+            None,
+            0.1,
+        )
+        .unwrap();
+        let result =
+            ameyanagi_suppression_exact("Fe2O3", "Fe", Edge::K, &energies(), settings).unwrap();
+        let r_min = energies()
+            .iter()
+            .zip(&result.suppression_factor)
+            .filter(|&(&e, _)| e > result.edge_energy + EXAFS_OFFSET_EV)
+            .map(|(_, &r)| r)
+            .fold(f64::INFINITY, f64::min);
+        assert!((r_min - 0.95).abs() < 1e-3, "{r_min}");
+
+        let um =
+            thin_limit_thickness_um("Fe2O3", "Fe", Edge::K, &energies(), None, None, 0.1, 0.05)
+                .unwrap();
+        assert!((um - five * 1e4).abs() < 1e-9 * um);
+    }
+
+    #[test]
+    fn test_thin_limit_thickness_dilute_and_invalid() {
+        let dilute = thin_limit_thickness(
+            "Fe0.001Al2O3",
+            "Fe",
+            Edge::K,
+            &energies(),
+            None,
+            Some(3.95),
+            0.1,
+            0.05,
+        )
+        .unwrap();
+        assert_eq!(dilute, f64::INFINITY);
+
+        for fraction in [0.0, 1.0, f64::NAN] {
+            let err = thin_limit_thickness(
+                "Fe2O3",
+                "Fe",
+                Edge::K,
+                &energies(),
+                None,
+                None,
+                0.1,
+                fraction,
+            );
+            assert!(err.is_err());
+        }
+    }
+}
@@ -10,15 +10,41 @@
 
 use std::f64::consts::PI;
 
-use xraydb::XrayDb;
+use xraydb::CrossSectionKind;
 
 use crate::common::{
-    SampleInfo, SelfAbsError, absorber_edge_mu_linear_trendline, composition_mass_fractions,
-    compound_mu_linear, compound_mu_linear_single,
+    DensitySource, DetectionMode, EXAFS_OFFSET_EV, Edge, ElementRef, EmissionLine,
+    FluorescenceGeometry, LayerIncidence, LayeredSample, MuBackend, PreEdgeBaseline, PreEdgeMethod,
+    PreEdgeOptions, SampleInfo, SampleSpec, SelfAbsError, SelfAbsWarning, absorber_edge_mu_linear,
+    absorber_mu_linear, brent_root, energies_to_k, filter_transmission, lookup_density,
+    parse_composition, resolve_e0, sample_warnings, validate_energy_grid,
 };
+use crate::context::SelfAbsContext;
+use crate::correction::{
+    Algorithm, CorrectionSummary, SelfAbsCorrection, check_chi_into, check_chi_len,
+    check_chi_prefix,
+};
+use crate::mu::{composition_mass_fractions, compound_mu_linear, compound_mu_linear_single};
+
+/// Absolute tolerance on χ_true for the inversion root finder.
+const INVERT_XTOL: f64 = 1e-14;
+/// Iteration cap for the inversion root finder.
+const INVERT_MAX_ITER: usize = 100;
+/// Largest upper bracket tried before a positive χ_measured is declared
+/// unreachable.
+const INVERT_UPPER_LIMIT: f64 = 1e6;
+
+/// Thinnest sample (cm) considered by [`ameyanagi_max_thickness`]; 1 nm is
+/// effectively infinitesimal for sample preparation.
+const MAX_THICKNESS_LOWER_CM: f64 = 1e-7;
+/// Thickest sample (cm) considered; deep in the thick limit for any solid.
+const MAX_THICKNESS_UPPER_CM: f64 = 10.0;
+/// Relative bracket width at which the thickness bisection stops.
+const MAX_THICKNESS_RTOL: f64 = 1e-6;
 
 /// Thickness input for Ameyanagi exact suppression.
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AmeyanagiThicknessInput {
     /// Direct thickness in cm.
     ThicknessCm(f64),
@@ -28,10 +54,18 @@ pub enum AmeyanagiThicknessInput {
     /// d = m / (ρ π (D/2)^2)
     /// ```
     PelletMassDiameter { mass_g: f64, diameter_cm: f64 },
+    /// Areal density in mg/cm^2, e.g. a filter or drop-cast film.
+    ///
+    /// ```text
+    /// d = (m/A) / ρ
+    /// ```
+    ArealDensityMgCm2(f64),
 }
 
 impl AmeyanagiThicknessInput {
-    fn resolve_cm(&self, density_g_cm3: f64) -> Result<f64, SelfAbsError> {
+    /// Resolve to cm. For pellets, `binder_mass_g` is added to the sample
+    /// mass and `density_g_cm3` is the effective density of the mixture.
+    fn resolve_cm(&self, density_g_cm3: f64, binder_mass_g: f64) -> Result<f64, SelfAbsError> {
         if density_g_cm3 <= 0.0 || !density_g_cm3.is_finite() {
             return Err(SelfAbsError::InsufficientData(
                 "density must be finite and > 0".to_string(),
@@ -55,7 +89,15 @@ impl AmeyanagiThicknessInput {
                     ));
                 }
                 let area = PI * (diameter_cm * 0.5).powi(2);
-                mass_g / (density_g_cm3 * area)
+                (mass_g + binder_mass_g) / (density_g_cm3 * area)
+            }
+            Self::ArealDensityMgCm2(areal) => {
+                if areal <= 0.0 || !areal.is_finite() {
+                    return Err(SelfAbsError::InsufficientData(
+                        "areal density must be finite and > 0".to_string(),
+                    ));
+                }
+                areal * 1e-3 / density_g_cm3
             }
         };
 
@@ -68,8 +110,37 @@ impl AmeyanagiThicknessInput {
     }
 }
 
+/// How the absorber edge attenuation μ̄_a(E) is built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AmeyanagiMuAMethod {
+    /// Full absorber photoelectric μ, ρ w_a (μ/ρ)_a, including the pre-edge
+    /// part.
+    Raw,
+    /// Absorber μ minus its pre-edge trendline, as in
+    /// [`booth_suppression_reference`](crate::booth::booth_suppression_reference).
+    #[default]
+    EdgeOnlyTrendline,
+    /// Absorber μ times (1 − 1/J) above the edge, see
+    /// [`PreEdgeMethod::JumpRatio`].
+    JumpRatio,
+}
+
+impl AmeyanagiMuAMethod {
+    /// The edge separation of the edge-only methods; `None` for
+    /// [`Self::Raw`].
+    fn pre_edge_method(self) -> Option<PreEdgeMethod> {
+        match self {
+            Self::Raw => None,
+            Self::EdgeOnlyTrendline => Some(PreEdgeMethod::Trendline),
+            Self::JumpRatio => Some(PreEdgeMethod::JumpRatio),
+        }
+    }
+}
+
 /// Exact Ameyanagi suppression result.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AmeyanagiSuppressionResult {
     /// Incident energy grid in eV.
     pub energies: Vec<f64>,
@@ -89,17 +160,54 @@ pub struct AmeyanagiSuppressionResult {
     pub geometry_g: f64,
     /// Beta factor β = d/sin(phi) in cm.
     pub beta: f64,
-    /// Edge energy in eV.
+    /// E₀ in eV of the EXAFS region: the edge energy or the E₀ override.
     pub edge_energy: f64,
     /// Branching-weighted fluorescence energy in eV.
     pub fluorescence_energy_weighted: f64,
+    /// Fit or fallback of the μ̄_a(E) pre-edge trendline; `None` for
+    /// [`AmeyanagiMuAMethod::Raw`].
+    pub pre_edge_baseline: Option<PreEdgeBaseline>,
+    /// Sample density (g/cm^3) before binder dilution and packing.
+    pub density_g_cm3: f64,
+    /// Where [`Self::density_g_cm3`] came from.
+    pub density_source: DensitySource,
+    /// Density used for the attenuation terms (g/cm^3), after binder
+    /// dilution and packing.
+    pub effective_density_g_cm3: f64,
+    /// Mass fraction of the sample in the pellet (1 without binder).
+    pub sample_mass_fraction: f64,
+    /// s(E) = μ̄_a(E) / α(E), as in `BoothResult::s` but from the linear μ
+    /// used here.
+    pub s: Vec<f64>,
+    /// α(E) = μ_total(E) + g μ_f in cm^-1.
+    pub alpha: Vec<f64>,
+    /// R(E) for each emission line with its own μ_f, keyed by line label.
+    /// Empty unless [`AmeyanagiSuppressionSettings::per_line`] is set.
+    pub per_line: Vec<(String, Vec<f64>)>,
+    /// Conditions that make parts of the result unreliable.
+    pub warnings: Vec<SelfAbsWarning>,
+}
+
+/// Binder mixed into a pressed pellet, e.g. BN or cellulose.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BinderSpec {
+    /// Binder chemical formula.
+    pub formula: String,
+    /// Binder mass in g.
+    pub mass_g: f64,
+    /// Crystallographic binder density in g/cm^3, used to combine the
+    /// sample and binder volumes.
+    pub density_g_cm3: f64,
 }
 
 /// Settings for Ameyanagi exact suppression evaluation.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AmeyanagiSuppressionSettings {
-    /// Effective sample density in g/cm^3.
-    pub density_g_cm3: f64,
+    /// Sample density in g/cm^3. `None` looks the formula up in the
+    /// materials table (or the elemental density for a pure element).
+    pub density_g_cm3: Option<f64>,
     /// Incident angle φ in radians.
     pub phi_rad: f64,
     /// Fluorescence exit angle θ in radians.
@@ -108,416 +216,2941 @@ pub struct AmeyanagiSuppressionSettings {
     pub thickness_input: AmeyanagiThicknessInput,
     /// Assumed finite EXAFS amplitude χ.
     pub chi_assumed: f64,
+    /// Optional binder. Requires [`AmeyanagiThicknessInput::PelletMassDiameter`],
+    /// whose `mass_g` is then the sample mass alone.
+    pub binder: Option<BinderSpec>,
+    /// Pellet packing fraction in (0, 1]; `None` means fully dense.
+    pub packing_fraction: Option<f64>,
+    /// Cross-section kind used for μ_total(E) and μ_f. μ̄_a always uses the
+    /// photoelectric cross-section. `Photo` reproduces the original results.
+    #[cfg_attr(feature = "serde", serde(with = "crate::common::CrossSectionKindDef"))]
+    pub cross_section: CrossSectionKind,
+    /// Also compute R(E) separately for every emission line, as seen by a
+    /// detector gated on that line alone.
+    pub per_line: bool,
+    /// Detector acceptance as (θ in radians, solid-angle weight) pairs. When
+    /// set it replaces `theta_rad`: the detected intensities are summed over
+    /// the exit angles before R is formed, and the reported `geometry_g`,
+    /// `alpha` and `s` use the weight-averaged g.
+    pub exit_angles: Option<Vec<(f64, f64)>>,
+    /// How μ̄_a(E) is built; the edge-only trendline by default.
+    pub mu_a_method: AmeyanagiMuAMethod,
+    /// E₀ in eV for the EXAFS region instead of the tabulated edge energy,
+    /// e.g. a calibrated E₀.
+    pub e0_override: Option<f64>,
+    /// Pre-edge trendline for [`AmeyanagiMuAMethod::EdgeOnlyTrendline`].
+    pub pre_edge_window: PreEdgeOptions,
+    /// Detector type; a total-yield detector mixes scatter into α.
+    pub detection_mode: DetectionMode,
+    /// Cross-section tables of every μ, the caps of
+    /// [`ameyanagi_layered`] included.
+    pub mu_backend: MuBackend,
+    /// Filter in front of the detector as `(formula, density g/cm³,
+    /// thickness μm)`, e.g. a Z−1 foil. Its transmission at each line
+    /// multiplies the line's intensity in every line average.
+    pub detector_filter: Option<(String, f64, f64)>,
 }
 
-/// Compute exact self-absorption suppression factor:
-///
-/// ```text
-/// R(E, χ) = (1/χ) * [ F(E, χ) - 1 ]
-/// ```
-///
-/// with
-///
-/// ```text
-/// F(E, χ) =
-///   [ (1 - exp(-A(E,χ)β)) / (1 - exp(-α(E)β)) ]
-///   * [ α(E)(1+χ) / A(E,χ) ]
-///
-/// A(E,χ) = α(E) + μ_a(E)χ
-/// α(E)   = μ_T(E) + g μ_f
-/// g      = sin(phi)/sin(theta)
-/// β      = d/sin(phi)
-/// ```
-pub fn ameyanagi_suppression_exact(
-    formula: &str,
-    central_element: &str,
-    edge: &str,
-    energies_ev: &[f64],
-    settings: AmeyanagiSuppressionSettings,
-) -> Result<AmeyanagiSuppressionResult, SelfAbsError> {
-    let density_g_cm3 = settings.density_g_cm3;
-    let phi_rad = settings.phi_rad;
-    let theta_rad = settings.theta_rad;
-    let thickness_input = settings.thickness_input;
-    let chi_assumed = settings.chi_assumed;
-
-    if energies_ev.is_empty() {
-        return Err(SelfAbsError::InsufficientData(
-            "energy grid must not be empty".to_string(),
-        ));
-    }
-    if chi_assumed == 0.0 || !chi_assumed.is_finite() {
-        return Err(SelfAbsError::InsufficientData(
-            "chi must be finite and non-zero".to_string(),
-        ));
+impl AmeyanagiSuppressionSettings {
+    /// Start a validating builder. Angles default to 45°/45°, the
+    /// cross-section to `Photo` and the density to a table lookup; thickness
+    /// and χ are required.
+    pub fn builder() -> AmeyanagiSettingsBuilder {
+        AmeyanagiSettingsBuilder::default()
     }
-    if !phi_rad.is_finite() || !theta_rad.is_finite() {
-        return Err(SelfAbsError::InsufficientData(
-            "angles must be finite".to_string(),
-        ));
+
+    /// The sample part of the settings, without χ.
+    pub fn sample(&self) -> AmeyanagiSampleSettings {
+        AmeyanagiSampleSettings {
+            density_g_cm3: self.density_g_cm3,
+            phi_rad: self.phi_rad,
+            theta_rad: self.theta_rad,
+            thickness_input: self.thickness_input,
+            binder: self.binder.clone(),
+            packing_fraction: self.packing_fraction,
+            cross_section: self.cross_section,
+            exit_angles: self.exit_angles.clone(),
+            mu_a_method: self.mu_a_method,
+            e0_override: self.e0_override,
+            pre_edge_window: self.pre_edge_window,
+            detection_mode: self.detection_mode,
+            mu_backend: self.mu_backend,
+            detector_filter: self.detector_filter.clone(),
+        }
     }
+}
 
-    let sin_phi = phi_rad.sin();
-    let sin_theta = theta_rad.sin();
-    if sin_phi <= 0.0 || sin_theta <= 0.0 {
+/// Default settings at `geometry` for the cross-algorithm entry points.
+/// Angular spreads have no counterpart in the exact model and are rejected.
+pub(crate) fn settings_for_geometry(
+    geometry: Option<FluorescenceGeometry>,
+    density_g_cm3: f64,
+    thickness_input: AmeyanagiThicknessInput,
+    binder: Option<BinderSpec>,
+    chi_assumed: f64,
+) -> Result<AmeyanagiSuppressionSettings, SelfAbsError> {
+    let geo = geometry.unwrap_or_default();
+    if geo.incident_spread_deg != 0.0 || geo.exit_spread_deg != 0.0 {
         return Err(SelfAbsError::InsufficientData(
-            "angles must be in (0, pi) with positive sine".to_string(),
+            "the Ameyanagi model does not support angular spreads".to_string(),
         ));
     }
+    Ok(AmeyanagiSuppressionSettings {
+        density_g_cm3: Some(density_g_cm3),
+        phi_rad: geo.theta_incident_deg.to_radians(),
+        theta_rad: geo.theta_fluorescence_deg.to_radians(),
+        thickness_input,
+        chi_assumed,
+        binder,
+        packing_fraction: None,
+        cross_section: CrossSectionKind::Photo,
+        per_line: false,
+        exit_angles: None,
+        mu_a_method: AmeyanagiMuAMethod::default(),
+        e0_override: None,
+        pre_edge_window: PreEdgeOptions::default(),
+        detection_mode: DetectionMode::default(),
+        mu_backend: MuBackend::default(),
+        detector_filter: None,
+    })
+}
 
-    let thickness_cm = thickness_input.resolve_cm(density_g_cm3)?;
-    let geometry_g = sin_phi / sin_theta;
-    let beta = thickness_cm / sin_phi;
-
-    let db = XrayDb::new();
-    let info = SampleInfo::new(&db, formula, central_element, edge)?;
-
-    let mass_fractions = composition_mass_fractions(&db, &info.composition)?;
-    // Step 1/2: linear attenuation terms in cm^-1
-    let mu_total = compound_mu_linear(&db, &mass_fractions, density_g_cm3, energies_ev)?;
-    let mu_a = absorber_edge_mu_linear_trendline(&db, &info, energies_ev, density_g_cm3)?;
-
-    // Step 3: fluorescence attenuation weighted over emission lines.
-    let (mu_f, fluorescence_energy_weighted) = weighted_fluorescence_mu(
-        &db,
-        &mass_fractions,
-        density_g_cm3,
-        &info.central_symbol,
-        edge,
-    )?;
-
-    // Step 5 and final exact suppression formula.
-    let mut r = Vec::with_capacity(energies_ev.len());
-    let mut r_min = f64::INFINITY;
-    let mut r_max = f64::NEG_INFINITY;
-    let mut r_sum = 0.0;
-
-    for i in 0..energies_ev.len() {
-        let alpha = mu_total[i] + geometry_g * mu_f;
-        let mu_a_i = mu_a[i];
-        let a = alpha + mu_a_i * chi_assumed;
-
-        let one_minus_exp_ab = one_minus_exp_neg(a * beta);
-        let one_minus_exp_alphab = one_minus_exp_neg(alpha * beta);
-
-        let denom_main = one_minus_exp_alphab;
-        let denom_ratio = a;
-
-        if denom_main.abs() < 1e-300 || denom_ratio.abs() < 1e-300 {
-            return Err(SelfAbsError::InsufficientData(format!(
-                "unstable denominator at index {i}"
-            )));
-        }
+/// Builder for [`AmeyanagiSuppressionSettings`] that takes degrees or
+/// radians and validates every field in [`build`](Self::build).
+#[derive(Debug, Clone, Default)]
+pub struct AmeyanagiSettingsBuilder {
+    density_g_cm3: Option<f64>,
+    angles_rad: Option<(f64, f64)>,
+    thickness: Option<AmeyanagiThicknessInput>,
+    chi: Option<f64>,
+    binder: Option<BinderSpec>,
+    packing_fraction: Option<f64>,
+    cross_section: Option<CrossSectionKind>,
+    per_line: bool,
+    exit_angles: Option<Vec<(f64, f64)>>,
+    mu_a_method: AmeyanagiMuAMethod,
+    e0_override: Option<f64>,
+    pre_edge_window: PreEdgeOptions,
+    detection_mode: DetectionMode,
+    mu_backend: MuBackend,
+    detector_filter: Option<(String, f64, f64)>,
+}
 
-        let term1 = one_minus_exp_ab / denom_main;
-        let term2 = alpha * (1.0 + chi_assumed) / denom_ratio;
-        let ri = (term1 * term2 - 1.0) / chi_assumed;
+impl AmeyanagiSettingsBuilder {
+    /// Sample density in g/cm^3.
+    pub fn density(mut self, density_g_cm3: f64) -> Self {
+        self.density_g_cm3 = Some(density_g_cm3);
+        self
+    }
 
-        if !ri.is_finite() {
-            return Err(SelfAbsError::InsufficientData(format!(
-                "non-finite suppression factor at index {i}"
-            )));
-        }
+    /// Incident angle φ and exit angle θ in degrees.
+    pub fn angles_deg(mut self, phi_deg: f64, theta_deg: f64) -> Self {
+        self.angles_rad = Some((phi_deg.to_radians(), theta_deg.to_radians()));
+        self
+    }
 
-        r_min = r_min.min(ri);
-        r_max = r_max.max(ri);
-        r_sum += ri;
-        r.push(ri);
+    /// Incident angle φ and exit angle θ in radians.
+    pub fn angles_rad(mut self, phi_rad: f64, theta_rad: f64) -> Self {
+        self.angles_rad = Some((phi_rad, theta_rad));
+        self
     }
 
-    let r_mean = r_sum / r.len() as f64;
+    /// Sample thickness in cm.
+    pub fn thickness_cm(mut self, thickness_cm: f64) -> Self {
+        self.thickness = Some(AmeyanagiThicknessInput::ThicknessCm(thickness_cm));
+        self
+    }
 
-    Ok(AmeyanagiSuppressionResult {
-        energies: energies_ev.to_vec(),
-        suppression_factor: r,
-        r_min,
-        r_max,
-        r_mean,
-        mu_f,
-        thickness_cm,
-        geometry_g,
-        beta,
-        edge_energy: info.edge_energy,
-        fluorescence_energy_weighted,
-    })
-}
+    /// Pellet of `mass_g` pressed to `diameter_cm`.
+    pub fn pellet(mut self, mass_g: f64, diameter_cm: f64) -> Self {
+        self.thickness = Some(AmeyanagiThicknessInput::PelletMassDiameter {
+            mass_g,
+            diameter_cm,
+        });
+        self
+    }
 
-fn weighted_fluorescence_mu(
-    db: &XrayDb,
-    mass_fractions: &[(String, f64)],
-    density_g_cm3: f64,
-    central_symbol: &str,
-    edge: &str,
-) -> Result<(f64, f64), SelfAbsError> {
-    let lines = db.xray_lines(central_symbol, Some(edge), None)?;
-    let mut weighted_mu_f = 0.0;
-    let mut weighted_energy = 0.0;
-    let mut weight_sum = 0.0;
+    /// Areal density in mg/cm^2.
+    pub fn areal_density_mg_cm2(mut self, areal_density: f64) -> Self {
+        self.thickness = Some(AmeyanagiThicknessInput::ArealDensityMgCm2(areal_density));
+        self
+    }
 
-    for line in lines.values() {
-        if !line.intensity.is_finite() || line.intensity <= 0.0 {
-            continue;
-        }
-        let w = line.intensity;
-        let mu_e = compound_mu_linear_single(db, mass_fractions, density_g_cm3, line.energy)?;
-        weighted_mu_f += w * mu_e;
-        weighted_energy += w * line.energy;
-        weight_sum += w;
+    /// Assumed EXAFS amplitude χ.
+    pub fn chi(mut self, chi: f64) -> Self {
+        self.chi = Some(chi);
+        self
     }
 
-    if weight_sum <= 0.0 {
-        return Err(SelfAbsError::NoEmissionLines(format!(
-            "{central_symbol} {edge} has no positive-intensity lines"
-        )));
+    /// Binder mixed into the pellet; requires [`pellet`](Self::pellet).
+    pub fn binder(mut self, binder: BinderSpec) -> Self {
+        self.binder = Some(binder);
+        self
     }
 
-    Ok((weighted_mu_f / weight_sum, weighted_energy / weight_sum))
-}
+    /// Pellet packing fraction in (0, 1].
+    pub fn packing_fraction(mut self, packing_fraction: f64) -> Self {
+        self.packing_fraction = Some(packing_fraction);
+        self
+    }
 
-fn one_minus_exp_neg(x: f64) -> f64 {
-    if x <= 0.0 {
-        0.0
-    } else if x > 700.0 {
-        1.0
-    } else {
-        -(-x).exp_m1()
+    /// Cross-section kind for μ_total(E) and μ_f.
+    pub fn cross_section(mut self, cross_section: CrossSectionKind) -> Self {
+        self.cross_section = Some(cross_section);
+        self
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Also compute the per-line breakdown.
+    pub fn per_line(mut self, per_line: bool) -> Self {
+        self.per_line = per_line;
+        self
+    }
 
-    fn energies() -> Vec<f64> {
-        (7000..=8000).step_by(5).map(|e| e as f64).collect()
+    /// Detector acceptance as (θ in radians, weight) pairs; replaces the
+    /// single exit angle.
+    pub fn exit_angles(mut self, exit_angles: Vec<(f64, f64)>) -> Self {
+        self.exit_angles = Some(exit_angles);
+        self
     }
 
-    #[test]
-    fn test_ameyanagi_exact_fe2o3() {
-        let r = ameyanagi_suppression_exact(
-            "Fe2O3",
-            "Fe",
-            "K",
-            &energies(),
-            AmeyanagiSuppressionSettings {
-                density_g_cm3: 5.24,
-                phi_rad: std::f64::consts::FRAC_PI_4,
-                theta_rad: std::f64::consts::FRAC_PI_4,
-                thickness_input: AmeyanagiThicknessInput::ThicknessCm(0.01),
-                chi_assumed: 0.2,
-            },
-        )
-        .unwrap();
+    /// How μ̄_a(E) is built.
+    pub fn mu_a_method(mut self, mu_a_method: AmeyanagiMuAMethod) -> Self {
+        self.mu_a_method = mu_a_method;
+        self
+    }
 
-        assert_eq!(r.energies.len(), r.suppression_factor.len());
-        assert!(r.suppression_factor.iter().all(|v| v.is_finite()));
-        assert!(r.r_min <= r.r_mean);
-        assert!(r.r_mean <= r.r_max);
+    /// Calibrated E₀ in eV.
+    pub fn e0_override(mut self, e0: f64) -> Self {
+        self.e0_override = Some(e0);
+        self
     }
 
-    #[test]
-    fn test_mass_diameter_matches_thickness() {
-        let density: f64 = 5.24;
-        let diameter: f64 = 1.0;
-        let mass: f64 = 0.05;
-        let d = mass / (density * PI * (diameter * 0.5).powi(2));
+    /// Pre-edge trendline window and Victoreen exponent.
+    pub fn pre_edge_window(mut self, pre_edge_window: PreEdgeOptions) -> Self {
+        self.pre_edge_window = pre_edge_window;
+        self
+    }
 
-        let direct = ameyanagi_suppression_exact(
-            "Fe2O3",
-            "Fe",
-            "K",
-            &energies(),
-            AmeyanagiSuppressionSettings {
-                density_g_cm3: density,
-                phi_rad: std::f64::consts::FRAC_PI_4,
-                theta_rad: std::f64::consts::FRAC_PI_4,
-                thickness_input: AmeyanagiThicknessInput::ThicknessCm(d),
-                chi_assumed: 0.2,
-            },
-        )
-        .unwrap();
+    /// Detector type.
+    pub fn detection_mode(mut self, detection_mode: DetectionMode) -> Self {
+        self.detection_mode = detection_mode;
+        self
+    }
 
-        let pellet = ameyanagi_suppression_exact(
-            "Fe2O3",
-            "Fe",
-            "K",
-            &energies(),
-            AmeyanagiSuppressionSettings {
-                density_g_cm3: density,
-                phi_rad: std::f64::consts::FRAC_PI_4,
-                theta_rad: std::f64::consts::FRAC_PI_4,
-                thickness_input: AmeyanagiThicknessInput::PelletMassDiameter {
-                    mass_g: mass,
-                    diameter_cm: diameter,
-                },
-                chi_assumed: 0.2,
-            },
-        )
-        .unwrap();
+    /// Cross-section tables (Elam by default).
+    pub fn mu_backend(mut self, mu_backend: MuBackend) -> Self {
+        self.mu_backend = mu_backend;
+        self
+    }
 
-        assert!((direct.thickness_cm - pellet.thickness_cm).abs() < 1e-14);
-        assert!((direct.r_mean - pellet.r_mean).abs() < 1e-10);
+    /// Filter in front of the detector: formula, density (g/cm³) and
+    /// thickness (μm).
+    pub fn detector_filter(mut self, formula: &str, density_g_cm3: f64, thickness_um: f64) -> Self {
+        self.detector_filter = Some((formula.to_string(), density_g_cm3, thickness_um));
+        self
     }
 
-    #[test]
-    fn test_thicker_sample_has_smaller_mean_r() {
-        let thin = ameyanagi_suppression_exact(
-            "Fe2O3",
-            "Fe",
-            "K",
-            &energies(),
-            AmeyanagiSuppressionSettings {
-                density_g_cm3: 5.24,
-                phi_rad: std::f64::consts::FRAC_PI_4,
-                theta_rad: std::f64::consts::FRAC_PI_4,
-                thickness_input: AmeyanagiThicknessInput::ThicknessCm(1e-4),
-                chi_assumed: 0.2,
-            },
-        )
-        .unwrap();
+    /// Validate all fields and build the settings.
+    pub fn build(self) -> Result<AmeyanagiSuppressionSettings, SelfAbsError> {
+        let invalid = |msg: String| Err(SelfAbsError::InsufficientData(msg));
+        let positive = |v: f64| v.is_finite() && v > 0.0;
 
-        let thick = ameyanagi_suppression_exact(
-            "Fe2O3",
-            "Fe",
-            "K",
-            &energies(),
-            AmeyanagiSuppressionSettings {
-                density_g_cm3: 5.24,
-                phi_rad: std::f64::consts::FRAC_PI_4,
-                theta_rad: std::f64::consts::FRAC_PI_4,
-                thickness_input: AmeyanagiThicknessInput::ThicknessCm(0.2),
-                chi_assumed: 0.2,
-            },
-        )
-        .unwrap();
+        if let Some(density_g_cm3) = self.density_g_cm3
+            && !positive(density_g_cm3)
+        {
+            return invalid(format!(
+                "density_g_cm3 must be finite and > 0, got {density_g_cm3}"
+            ));
+        }
 
-        assert!(thick.r_mean < thin.r_mean);
-    }
+        let (phi_rad, theta_rad) = self
+            .angles_rad
+            .unwrap_or((std::f64::consts::FRAC_PI_4, std::f64::consts::FRAC_PI_4));
+        for (name, angle) in [("phi_rad", phi_rad), ("theta_rad", theta_rad)] {
+            if !(angle.is_finite() && angle > 0.0 && angle < PI) {
+                return invalid(format!("{name} must be in (0, pi), got {angle}"));
+            }
+        }
 
-    #[test]
-    fn test_positive_chi_gives_positive_suppression_factor() {
-        let r = ameyanagi_suppression_exact(
-            "Fe2O3",
-            "Fe",
-            "K",
-            &energies(),
-            AmeyanagiSuppressionSettings {
-                density_g_cm3: 5.24,
-                phi_rad: std::f64::consts::FRAC_PI_4,
-                theta_rad: std::f64::consts::FRAC_PI_4,
-                thickness_input: AmeyanagiThicknessInput::ThicknessCm(0.01),
-                chi_assumed: 0.2,
-            },
-        )
-        .unwrap();
+        let thickness_input = match self.thickness {
+            None => return invalid("thickness is required".to_string()),
+            Some(AmeyanagiThicknessInput::ThicknessCm(d)) => {
+                if !positive(d) {
+                    return invalid(format!("thickness_cm must be finite and > 0, got {d}"));
+                }
+                AmeyanagiThicknessInput::ThicknessCm(d)
+            }
+            Some(
+                input @ AmeyanagiThicknessInput::PelletMassDiameter {
+                    mass_g,
+                    diameter_cm,
+                },
+            ) => {
+                if !positive(mass_g) {
+                    return invalid(format!(
+                        "pellet mass_g must be finite and > 0, got {mass_g}"
+                    ));
+                }
+                if !positive(diameter_cm) {
+                    return invalid(format!(
+                        "pellet diameter_cm must be finite and > 0, got {diameter_cm}"
+                    ));
+                }
+                input
+            }
+            Some(AmeyanagiThicknessInput::ArealDensityMgCm2(areal)) => {
+                if !positive(areal) {
+                    return invalid(format!(
+                        "areal_density_mg_cm2 must be finite and > 0, got {areal}"
+                    ));
+                }
+                AmeyanagiThicknessInput::ArealDensityMgCm2(areal)
+            }
+        };
 
-        assert!(
-            r.suppression_factor
-                .iter()
-                .all(|&v| v.is_finite() && v > 0.0),
-            "expected all R(E,chi)>0 for positive chi"
-        );
-    }
+        let Some(chi_assumed) = self.chi else {
+            return invalid("chi is required".to_string());
+        };
+        if chi_assumed == 0.0 || !chi_assumed.is_finite() {
+            return invalid(format!(
+                "chi must be finite and non-zero, got {chi_assumed}"
+            ));
+        }
 
-    #[test]
-    fn test_mu_a_trendline_is_nonnegative_and_preedge_small() {
-        let db = XrayDb::new();
-        let info = SampleInfo::new(&db, "Fe2O3", "Fe", "K").unwrap();
-        let e0 = info.edge_energy;
-        let energies: Vec<f64> = (0..=300).map(|i| e0 - 250.0 + 2.0 * i as f64).collect();
-        let mu_a = absorber_edge_mu_linear_trendline(&db, &info, &energies, 5.24).unwrap();
+        if let Some(e0) = self.e0_override
+            && !positive(e0)
+        {
+            return invalid(format!("e0_override must be finite and > 0, got {e0}"));
+        }
 
-        assert_eq!(mu_a.len(), energies.len());
-        assert!(mu_a.iter().all(|v| v.is_finite() && *v >= 0.0));
+        if let Some(packing) = self.packing_fraction
+            && !(packing > 0.0 && packing <= 1.0)
+        {
+            return invalid(format!("packing_fraction must be in (0, 1], got {packing}"));
+        }
 
-        let mut pre_sum = 0.0;
-        let mut pre_n = 0usize;
-        let mut post_sum = 0.0;
-        let mut post_n = 0usize;
-        for (&e, &m) in energies.iter().zip(mu_a.iter()) {
-            if e <= e0 - 40.0 {
-                pre_sum += m;
-                pre_n += 1;
+        if let Some(exit_angles) = &self.exit_angles {
+            if exit_angles.is_empty() {
+                return invalid("exit_angles must not be empty".to_string());
             }
-            if e >= e0 + 40.0 {
-                post_sum += m;
-                post_n += 1;
+            for &(theta, weight) in exit_angles {
+                if !(theta.is_finite() && theta > 0.0 && theta < PI) {
+                    return invalid(format!("exit_angles theta must be in (0, pi), got {theta}"));
+                }
+                if !positive(weight) {
+                    return invalid(format!(
+                        "exit_angles weight must be finite and > 0, got {weight}"
+                    ));
+                }
             }
         }
 
-        assert!(pre_n > 0 && post_n > 0);
-        let pre_mean = pre_sum / pre_n as f64;
-        let post_mean = post_sum / post_n as f64;
-        assert!(pre_mean < 0.25 * post_mean.max(1e-12));
+        if let Some(binder) = &self.binder {
+            if !matches!(
+                thickness_input,
+                AmeyanagiThicknessInput::PelletMassDiameter { .. }
+            ) {
+                return invalid("binder requires a pellet thickness input".to_string());
+            }
+            if !(binder.mass_g.is_finite() && binder.mass_g >= 0.0) {
+                return invalid(format!(
+                    "binder mass_g must be finite and >= 0, got {}",
+                    binder.mass_g
+                ));
+            }
+            if !positive(binder.density_g_cm3) {
+                return invalid(format!(
+                    "binder density_g_cm3 must be finite and > 0, got {}",
+                    binder.density_g_cm3
+                ));
+            }
+        }
+        self.detection_mode.validate()?;
+
+        Ok(AmeyanagiSuppressionSettings {
+            density_g_cm3: self.density_g_cm3,
+            phi_rad,
+            theta_rad,
+            thickness_input,
+            chi_assumed,
+            binder: self.binder,
+            packing_fraction: self.packing_fraction,
+            cross_section: self.cross_section.unwrap_or(CrossSectionKind::Photo),
+            per_line: self.per_line,
+            exit_angles: self.exit_angles,
+            mu_a_method: self.mu_a_method,
+            e0_override: self.e0_override,
+            pre_edge_window: self.pre_edge_window,
+            detection_mode: self.detection_mode,
+            mu_backend: self.mu_backend,
+            detector_filter: self.detector_filter,
+        })
     }
+}
 
-    #[test]
-    fn test_thick_limit_matches_booth_eq6_ratio() {
-        let energies = energies();
-        let chi = 0.2;
-        let density = 5.24;
+/// Sample and geometry settings for the Ameyanagi entry points that take χ
+/// separately (e.g. [`ameyanagi_correct_chi`]).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AmeyanagiSampleSettings {
+    /// Sample density in g/cm^3, see
+    /// [`AmeyanagiSuppressionSettings::density_g_cm3`].
+    pub density_g_cm3: Option<f64>,
+    /// Incident angle φ in radians.
+    pub phi_rad: f64,
+    /// Fluorescence exit angle θ in radians.
+    pub theta_rad: f64,
+    /// Sample thickness input.
+    pub thickness_input: AmeyanagiThicknessInput,
+    /// Optional binder, see [`AmeyanagiSuppressionSettings::binder`].
+    pub binder: Option<BinderSpec>,
+    /// Pellet packing fraction in (0, 1]; `None` means fully dense.
+    pub packing_fraction: Option<f64>,
+    /// Cross-section kind for μ_total(E) and μ_f, see
+    /// [`AmeyanagiSuppressionSettings::cross_section`].
+    #[cfg_attr(feature = "serde", serde(with = "crate::common::CrossSectionKindDef"))]
+    pub cross_section: CrossSectionKind,
+    /// Detector acceptance, see [`AmeyanagiSuppressionSettings::exit_angles`].
+    pub exit_angles: Option<Vec<(f64, f64)>>,
+    /// How μ̄_a(E) is built, see [`AmeyanagiSuppressionSettings::mu_a_method`].
+    pub mu_a_method: AmeyanagiMuAMethod,
+    /// Calibrated E₀, see [`AmeyanagiSuppressionSettings::e0_override`].
+    pub e0_override: Option<f64>,
+    /// Pre-edge trendline, see
+    /// [`AmeyanagiSuppressionSettings::pre_edge_window`].
+    pub pre_edge_window: PreEdgeOptions,
+    /// Detector type, see [`AmeyanagiSuppressionSettings::detection_mode`].
+    pub detection_mode: DetectionMode,
+    /// Cross-section tables, see [`AmeyanagiSuppressionSettings::mu_backend`].
+    pub mu_backend: MuBackend,
+    /// See [`AmeyanagiSuppressionSettings::detector_filter`].
+    pub detector_filter: Option<(String, f64, f64)>,
+}
+
+/// Attenuation terms of the exact model on one energy grid. Everything that
+/// does not depend on χ is computed once here.
+struct AmeyanagiModel {
+    mu_total: Vec<f64>,
+    mu_a: Vec<f64>,
+    mu_f: f64,
+    lines: Vec<FluorescenceLine>,
+    sin_phi: f64,
+    thickness_cm: f64,
+    geometry_g: f64,
+    /// (g_j, w_j) per exit angle when the detector acceptance has more than
+    /// one angle; empty for a single θ.
+    acceptance: Vec<(f64, f64)>,
+    beta: f64,
+    detection_mode: DetectionMode,
+    edge_energy: f64,
+    fluorescence_energy_weighted: f64,
+    pre_edge_baseline: Option<PreEdgeBaseline>,
+    warnings: Vec<SelfAbsWarning>,
+    density_g_cm3: f64,
+    density_source: DensitySource,
+    effective_density_g_cm3: f64,
+    sample_mass_fraction: f64,
+}
+
+impl AmeyanagiModel {
+    fn from_info(
+        ctx: &SelfAbsContext,
+        info: &SampleInfo,
+        energies_ev: &[f64],
+        sample: &AmeyanagiSampleSettings,
+    ) -> Result<Self, SelfAbsError> {
+        let phi_rad = sample.phi_rad;
+        let theta_rad = sample.theta_rad;
+
+        let edge_energy = resolve_e0(info, sample.e0_override)?;
+        let grid = validate_energy_grid(energies_ev, edge_energy)?;
+        sample.detection_mode.validate()?;
+        if !phi_rad.is_finite() || !theta_rad.is_finite() {
+            return Err(SelfAbsError::InsufficientData(
+                "angles must be finite".to_string(),
+            ));
+        }
+
+        let sin_phi = phi_rad.sin();
+        let sin_theta = theta_rad.sin();
+        if sin_phi <= 0.0 || sin_theta <= 0.0 {
+            return Err(SelfAbsError::InsufficientData(
+                "angles must be in (0, pi) with positive sine".to_string(),
+            ));
+        }
+
+        // A single exit angle reduces to the plain model at that θ.
+        let (geometry_g, acceptance) = match sample.exit_angles.as_deref() {
+            None => (sin_phi / sin_theta, Vec::new()),
+            Some([]) => {
+                return Err(SelfAbsError::InsufficientData(
+                    "exit angles must not be empty".to_string(),
+                ));
+            }
+            Some(exit_angles) => {
+                let mut acceptance = Vec::with_capacity(exit_angles.len());
+                for &(theta, weight) in exit_angles {
+                    let sin_theta = theta.sin();
+                    if !theta.is_finite() || sin_theta <= 0.0 {
+                        return Err(SelfAbsError::InsufficientData(format!(
+                            "exit angle {theta} must be in (0, pi)"
+                        )));
+                    }
+                    if weight <= 0.0 || !weight.is_finite() {
+                        return Err(SelfAbsError::InsufficientData(format!(
+                            "exit angle weight {weight} must be finite and > 0"
+                        )));
+                    }
+                    acceptance.push((sin_phi / sin_theta, weight));
+                }
+                if acceptance.len() == 1 {
+                    (acceptance[0].0, Vec::new())
+                } else {
+                    let total: f64 = acceptance.iter().map(|&(_, w)| w).sum();
+                    let mean_g = acceptance.iter().map(|&(g, w)| g * w).sum::<f64>() / total;
+                    (mean_g, acceptance)
+                }
+            }
+        };
+
+        if let Some(density_g_cm3) = sample.density_g_cm3
+            && (density_g_cm3 <= 0.0 || !density_g_cm3.is_finite())
+        {
+            return Err(SelfAbsError::InsufficientData(
+                "density must be finite and > 0".to_string(),
+            ));
+        }
+        let packing_fraction = sample.packing_fraction.unwrap_or(1.0);
+        if !(packing_fraction > 0.0 && packing_fraction <= 1.0) {
+            return Err(SelfAbsError::InsufficientData(format!(
+                "packing fraction must be in (0, 1], got {packing_fraction}"
+            )));
+        }
+
+        let (density_g_cm3, density_source) = match (sample.density_g_cm3, info.formula()) {
+            (Some(density), _) => (density, DensitySource::Supplied),
+            (None, Some(formula)) => lookup_density(ctx, formula)?,
+            (None, None) => {
+                return Err(SelfAbsError::InsufficientData(
+                    "a sample built from a composition needs density_g_cm3".to_string(),
+                ));
+            }
+        };
+        let sample_fractions = composition_mass_fractions(ctx, &info.composition)?;
+
+        // Combine sample and binder by mass; volumes add for the density.
+        let (mass_fractions, sample_mass_fraction, solid_density, binder_mass_g) = match &sample
+            .binder
+        {
+            None => (sample_fractions, 1.0, density_g_cm3, 0.0),
+            Some(binder) => {
+                let AmeyanagiThicknessInput::PelletMassDiameter { mass_g, .. } =
+                    sample.thickness_input
+                else {
+                    return Err(SelfAbsError::InsufficientData(
+                        "a binder requires the pellet mass/diameter thickness input".to_string(),
+                    ));
+                };
+                if binder.mass_g < 0.0 || !binder.mass_g.is_finite() {
+                    return Err(SelfAbsError::InsufficientData(
+                        "binder mass must be finite and >= 0".to_string(),
+                    ));
+                }
+                if binder.density_g_cm3 <= 0.0 || !binder.density_g_cm3.is_finite() {
+                    return Err(SelfAbsError::InsufficientData(
+                        "binder density must be finite and > 0".to_string(),
+                    ));
+                }
+                let binder_fractions =
+                    composition_mass_fractions(ctx, &parse_composition(&binder.formula)?)?;
+
+                let w_sample = mass_g / (mass_g + binder.mass_g);
+                let w_binder = 1.0 - w_sample;
+                let mut combined: Vec<(String, f64)> = sample_fractions
+                    .into_iter()
+                    .map(|(sym, w)| (sym, w * w_sample))
+                    .collect();
+                combined.extend(
+                    binder_fractions
+                        .into_iter()
+                        .map(|(sym, w)| (sym, w * w_binder)),
+                );
+                let solid = 1.0 / (w_sample / density_g_cm3 + w_binder / binder.density_g_cm3);
+                (combined, w_sample, solid, binder.mass_g)
+            }
+        };
+        let effective_density_g_cm3 = packing_fraction * solid_density;
+
+        let thickness_cm = sample
+            .thickness_input
+            .resolve_cm(effective_density_g_cm3, binder_mass_g)?;
+        let beta = thickness_cm / sin_phi;
+
+        // Step 1/2: linear attenuation terms in cm^-1
+        let backend = sample.mu_backend;
+        let mu_total = compound_mu_linear(
+            &ctx.tables(backend),
+            &mass_fractions,
+            effective_density_g_cm3,
+            energies_ev,
+            sample.cross_section,
+        )?;
+        // The absorber only fills the sample share of the pellet mass.
+        let absorber_density = effective_density_g_cm3 * sample_mass_fraction;
+        let (mu_a, pre_edge_baseline) = match sample.mu_a_method.pre_edge_method() {
+            None => (
+                absorber_mu_linear(ctx, info, energies_ev, absorber_density, backend)?,
+                None,
+            ),
+            Some(method) => {
+                let (mu_a, baseline) = absorber_edge_mu_linear(
+                    ctx,
+                    info,
+                    energies_ev,
+                    absorber_density,
+                    method,
+                    &sample.pre_edge_window,
+                    backend,
+                )?;
+                (mu_a, Some(baseline))
+            }
+        };
+
+        // Step 3: fluorescence attenuation weighted over emission lines.
+        let mut lines = fluorescence_lines(
+            ctx,
+            &mass_fractions,
+            effective_density_g_cm3,
+            info.emission_lines(),
+            sample.cross_section,
+            backend,
+        )?;
+        if sample.detector_filter.is_some() {
+            for line in &mut lines {
+                line.intensity *= filter_transmission(
+                    ctx,
+                    sample.detector_filter.as_ref(),
+                    line.energy,
+                    backend,
+                )?;
+            }
+            if lines.iter().all(|line| line.intensity <= 0.0) {
+                return Err(SelfAbsError::NoEmissionLines(format!(
+                    "{} {} has no lines through the detector filter",
+                    info.central_symbol, info.edge
+                )));
+            }
+        }
+        let (mu_f, fluorescence_energy_weighted) = weight_fluorescence_lines(&lines);
+
+        Ok(Self {
+            mu_total,
+            mu_a,
+            mu_f,
+            lines,
+            sin_phi,
+            thickness_cm,
+            geometry_g,
+            acceptance,
+            beta,
+            detection_mode: sample.detection_mode,
+            edge_energy,
+            fluorescence_energy_weighted,
+            pre_edge_baseline,
+            warnings: grid
+                .warning()
+                .into_iter()
+                .chain(sample_warnings(ctx, info, energies_ev, backend)?)
+                .collect(),
+            density_g_cm3,
+            density_source,
+            effective_density_g_cm3,
+            sample_mass_fraction,
+        })
+    }
+
+    /// α = μ_T + g μ_out at grid point `i` for exit geometry factor `g`,
+    /// with μ_out the outgoing attenuation of the detection mode.
+    fn alpha(&self, i: usize, g: f64, mu_f: f64) -> f64 {
+        self.mu_total[i] + g * self.detection_mode.exit_mu(mu_f, self.mu_total[i])
+    }
+
+    /// Detected intensity at χ = 0 per unit incident flux, fluorescence
+    /// yield and solid angle, μ̄_a (1 − exp(−αβ)) / α, averaged over the
+    /// detector acceptance.
+    fn intensity(&self, i: usize) -> f64 {
+        let h = |g: f64| {
+            let alpha = self.alpha(i, g, self.mu_f);
+            one_minus_exp_neg(alpha * self.beta) / alpha
+        };
+        if self.acceptance.is_empty() {
+            return self.mu_a[i] * h(self.geometry_g);
+        }
+        let total: f64 = self.acceptance.iter().map(|&(_, w)| w).sum();
+        self.mu_a[i] * self.acceptance.iter().map(|&(g, w)| w * h(g)).sum::<f64>() / total
+    }
+
+    /// Scale the weight of each exit angle of the detector acceptance by
+    /// `factors`, in the order of the exit angles, and update the mean g. A
+    /// single θ has no weight to change.
+    fn reweight_acceptance(&mut self, factors: &[f64]) {
+        if self.acceptance.is_empty() {
+            return;
+        }
+        for ((_, w), factor) in self.acceptance.iter_mut().zip(factors) {
+            *w *= factor;
+        }
+        let total: f64 = self.acceptance.iter().map(|&(_, w)| w).sum();
+        self.geometry_g = self.acceptance.iter().map(|&(g, w)| g * w).sum::<f64>() / total;
+    }
+
+    /// Switch to another thickness; the attenuation terms do not change.
+    fn set_thickness(&mut self, thickness_cm: f64) -> Result<(), SelfAbsError> {
+        if thickness_cm <= 0.0 || !thickness_cm.is_finite() {
+            return Err(SelfAbsError::InsufficientData(format!(
+                "thickness must be finite and > 0, got {thickness_cm} cm"
+            )));
+        }
+        self.thickness_cm = thickness_cm;
+        self.beta = thickness_cm / self.sin_phi;
+        Ok(())
+    }
+
+    /// F(E, χ) − 1 = R(E, χ)·χ at grid point `i`, or `None` if a
+    /// denominator vanishes.
+    fn chi_exp(&self, i: usize, chi: f64) -> Option<f64> {
+        self.chi_exp_with_mu_f(i, chi, self.mu_f)
+    }
+
+    /// [`Self::chi_exp`] for a single line with attenuation `mu_f`.
+    fn chi_exp_with_mu_f(&self, i: usize, chi: f64, mu_f: f64) -> Option<f64> {
+        if !self.acceptance.is_empty() {
+            return self.chi_exp_acceptance(i, chi, mu_f);
+        }
+        let alpha = self.alpha(i, self.geometry_g, mu_f);
+        let a = alpha + self.mu_a[i] * chi;
+
+        let denom_main = one_minus_exp_neg(alpha * self.beta);
+        let denom_ratio = a;
+        if denom_main.abs() < 1e-300 || denom_ratio.abs() < 1e-300 {
+            return None;
+        }
+
+        let term1 = one_minus_exp_neg(a * self.beta) / denom_main;
+        let term2 = alpha * (1.0 + chi) / denom_ratio;
+        Some(term1 * term2 - 1.0)
+    }
+
+    /// [`Self::chi_exp_with_mu_f`] over the detector acceptance. The detected
+    /// intensity at each exit angle is ∝ μ_a(1+χ)(1 − exp(−A_j β))/A_j with
+    /// A_j = μ_T + g_j μ_f + μ_a χ; the weighted intensities are summed and
+    /// normalized by the χ = 0 sum.
+    fn chi_exp_acceptance(&self, i: usize, chi: f64, mu_f: f64) -> Option<f64> {
+        let mut num = 0.0;
+        let mut den = 0.0;
+        for &(g, w) in &self.acceptance {
+            let alpha = self.alpha(i, g, mu_f);
+            let a = alpha + self.mu_a[i] * chi;
+            if alpha.abs() < 1e-300 || a.abs() < 1e-300 {
+                return None;
+            }
+            num += w * one_minus_exp_neg(a * self.beta) / a;
+            den += w * one_minus_exp_neg(alpha * self.beta) / alpha;
+        }
+        if den.abs() < 1e-300 {
+            return None;
+        }
+        Some((1.0 + chi) * num / den - 1.0)
+    }
+
+    /// Exact R(E, χ) at grid point `i` for non-zero χ.
+    fn suppression(&self, i: usize, chi: f64) -> Result<f64, SelfAbsError> {
+        self.suppression_with_mu_f(i, chi, self.mu_f)
+    }
+
+    fn suppression_with_mu_f(&self, i: usize, chi: f64, mu_f: f64) -> Result<f64, SelfAbsError> {
+        let ri = self.chi_exp_with_mu_f(i, chi, mu_f).ok_or_else(|| {
+            SelfAbsError::InsufficientData(format!("unstable denominator at index {i}"))
+        })? / chi;
+        if !ri.is_finite() {
+            return Err(SelfAbsError::InsufficientData(format!(
+                "non-finite suppression factor at index {i}"
+            )));
+        }
+        Ok(ri)
+    }
+
+    /// R(E, χ) at grid point `i`, using the analytic χ → 0 limit
+    ///
+    /// ```text
+    /// R(E, 0) = 1 − μ_a/α + μ_a β / (exp(αβ) − 1)
+    /// ```
+    ///
+    /// for χ = 0.
+    fn suppression_or_limit(&self, i: usize, chi: f64) -> Result<f64, SelfAbsError> {
+        if chi != 0.0 {
+            return self.suppression(i, chi);
+        }
+        if !self.acceptance.is_empty() {
+            return self.suppression_limit_acceptance(i);
+        }
+        let alpha = self.alpha(i, self.geometry_g, self.mu_f);
+        let ab = alpha * self.beta;
+        let thin_term = if ab > 700.0 {
+            0.0
+        } else {
+            self.mu_a[i] * self.beta / ab.exp_m1()
+        };
+        let ri = 1.0 - self.mu_a[i] / alpha + thin_term;
+        if !ri.is_finite() {
+            return Err(SelfAbsError::InsufficientData(format!(
+                "non-finite suppression factor at index {i}"
+            )));
+        }
+        Ok(ri)
+    }
+
+    /// χ → 0 limit over the detector acceptance:
+    /// R(E, 0) = 1 + μ_a Σ w_j h'(α_j) / Σ w_j h(α_j) with
+    /// h(x) = (1 − exp(−xβ))/x.
+    fn suppression_limit_acceptance(&self, i: usize) -> Result<f64, SelfAbsError> {
+        let mut h_sum = 0.0;
+        let mut dh_sum = 0.0;
+        for &(g, w) in &self.acceptance {
+            let alpha = self.alpha(i, g, self.mu_f);
+            let h = one_minus_exp_neg(alpha * self.beta) / alpha;
+            let dh = self.beta * (-alpha * self.beta).exp() / alpha - h / alpha;
+            h_sum += w * h;
+            dh_sum += w * dh;
+        }
+        let ri = 1.0 + self.mu_a[i] * dh_sum / h_sum;
+        if !ri.is_finite() {
+            return Err(SelfAbsError::InsufficientData(format!(
+                "non-finite suppression factor at index {i}"
+            )));
+        }
+        Ok(ri)
+    }
+
+    /// Exact R(E, χ) over the whole grid for attenuation `mu_f`. With the
+    /// `parallel` feature the points are spread over the rayon thread pool;
+    /// the output is identical to the sequential loop.
+    fn suppression_curve(&self, chi: f64, mu_f: f64) -> Result<Vec<f64>, SelfAbsError> {
+        let point = |i: usize| self.suppression_with_mu_f(i, chi, mu_f);
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            (0..self.mu_total.len())
+                .into_par_iter()
+                .map(point)
+                .collect()
+        }
+        #[cfg(not(feature = "parallel"))]
+        {
+            (0..self.mu_total.len()).map(point).collect()
+        }
+    }
+
+    /// Solve χ_exp(E, χ_true) = `chi_measured` for χ_true at grid point `i`.
+    fn invert(&self, i: usize, chi_measured: f64) -> Result<f64, SelfAbsError> {
+        if !chi_measured.is_finite() {
+            return Err(SelfAbsError::InsufficientData(format!(
+                "measured chi must be finite at index {i}"
+            )));
+        }
+        if chi_measured == 0.0 {
+            return Ok(0.0);
+        }
+        if chi_measured <= -1.0 {
+            return Err(SelfAbsError::InsufficientData(format!(
+                "measured chi {chi_measured} at index {i} is below -1"
+            )));
+        }
+
+        let f = |x: f64| self.chi_exp(i, x).map_or(f64::NAN, |c| c - chi_measured);
+
+        // χ_exp(χ) is increasing with χ_exp(−1) = −1 and χ_exp(0) = 0, so
+        // negative values are bracketed by [−1, 0]. Positive ones saturate
+        // as χ → ∞; widen the upper end until the bracket closes.
+        let (lo, hi) = if chi_measured < 0.0 {
+            (-1.0, 0.0)
+        } else {
+            let mut hi = 2.0 * (chi_measured + 1.0);
+            let mut fhi = f(hi);
+            while fhi.is_nan() || fhi < 0.0 {
+                hi *= 2.0;
+                if hi > INVERT_UPPER_LIMIT {
+                    return Err(SelfAbsError::InsufficientData(format!(
+                        "measured chi {chi_measured} at index {i} exceeds the saturation limit of the sample"
+                    )));
+                }
+                fhi = f(hi);
+            }
+            (0.0, hi)
+        };
+
+        brent_root(f, lo, hi, INVERT_XTOL, INVERT_MAX_ITER)
+            .map(|sol| sol.root)
+            .ok_or_else(|| {
+                SelfAbsError::InsufficientData(format!(
+                    "Ameyanagi inversion did not converge at index {i}"
+                ))
+            })
+    }
+
+    fn into_result(self, energies_ev: &[f64], r: Vec<f64>) -> AmeyanagiSuppressionResult {
+        let (r_min, r_mean, r_max) = summarize(&r);
+        let alpha: Vec<f64> = (0..self.mu_total.len())
+            .map(|i| self.alpha(i, self.geometry_g, self.mu_f))
+            .collect();
+        let s = self
+            .mu_a
+            .iter()
+            .zip(&alpha)
+            .map(|(&a, &al)| a / al)
+            .collect();
+
+        AmeyanagiSuppressionResult {
+            energies: energies_ev.to_vec(),
+            suppression_factor: r,
+            r_min,
+            r_max,
+            r_mean,
+            mu_f: self.mu_f,
+            thickness_cm: self.thickness_cm,
+            geometry_g: self.geometry_g,
+            beta: self.beta,
+            edge_energy: self.edge_energy,
+            fluorescence_energy_weighted: self.fluorescence_energy_weighted,
+            pre_edge_baseline: self.pre_edge_baseline,
+            warnings: self.warnings,
+            density_g_cm3: self.density_g_cm3,
+            density_source: self.density_source,
+            effective_density_g_cm3: self.effective_density_g_cm3,
+            sample_mass_fraction: self.sample_mass_fraction,
+            s,
+            alpha,
+            per_line: Vec::new(),
+        }
+    }
+}
+
+/// Compute exact self-absorption suppression factor:
+///
+/// ```text
+/// R(E, χ) = (1/χ) * [ F(E, χ) - 1 ]
+/// ```
+///
+/// with
+///
+/// ```text
+/// F(E, χ) =
+///   [ (1 - exp(-A(E,χ)β)) / (1 - exp(-α(E)β)) ]
+///   * [ α(E)(1+χ) / A(E,χ) ]
+///
+/// A(E,χ) = α(E) + μ_a(E)χ
+/// α(E)   = μ_T(E) + g μ_f
+/// g      = sin(phi)/sin(theta)
+/// β      = d/sin(phi)
+/// ```
+///
+/// With the `parallel` feature the per-point and per-line evaluations run on
+/// the rayon thread pool with results identical to the serial path.
+pub fn ameyanagi_suppression_exact(
+    formula: &str,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies_ev: &[f64],
+    settings: AmeyanagiSuppressionSettings,
+) -> Result<AmeyanagiSuppressionResult, SelfAbsError> {
+    SelfAbsContext::new().ameyanagi(formula, central_element, edge, energies_ev, settings)
+}
+
+/// [`ameyanagi_suppression_exact`] for a prebuilt [`SampleInfo`]. A sample
+/// built with [`SampleInfo::from_composition`] has no formula to look the
+/// density up by, so `density_g_cm3` must then be set.
+pub fn ameyanagi_suppression_exact_with_sample_info(
+    ctx: &SelfAbsContext,
+    info: &SampleInfo,
+    energies_ev: &[f64],
+    settings: AmeyanagiSuppressionSettings,
+) -> Result<AmeyanagiSuppressionResult, SelfAbsError> {
+    validate_exact_inputs(energies_ev, settings.chi_assumed)?;
+    let model = AmeyanagiModel::from_info(ctx, info, energies_ev, &settings.sample())?;
+    exact_from_model(model, energies_ev, settings.chi_assumed, settings.per_line)
+}
+
+fn validate_exact_inputs(energies_ev: &[f64], chi_assumed: f64) -> Result<(), SelfAbsError> {
+    if energies_ev.is_empty() {
+        return Err(SelfAbsError::InsufficientData(
+            "energy grid must not be empty".to_string(),
+        ));
+    }
+    if chi_assumed == 0.0 || !chi_assumed.is_finite() {
+        return Err(SelfAbsError::InsufficientData(
+            "chi must be finite and non-zero".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// The exact suppression at `chi_assumed` of an already built `model`.
+fn exact_from_model(
+    model: AmeyanagiModel,
+    energies_ev: &[f64],
+    chi_assumed: f64,
+    per_line: bool,
+) -> Result<AmeyanagiSuppressionResult, SelfAbsError> {
+    // Step 5 and final exact suppression formula.
+    let r = model.suppression_curve(chi_assumed, model.mu_f)?;
+
+    let per_line = if per_line {
+        model
+            .lines
+            .iter()
+            .map(|line| {
+                let r_line = model.suppression_curve(chi_assumed, line.mu_f)?;
+                Ok((line.label.clone(), r_line))
+            })
+            .collect::<Result<Vec<_>, SelfAbsError>>()?
+    } else {
+        Vec::new()
+    };
+
+    Ok(AmeyanagiSuppressionResult {
+        per_line,
+        ..model.into_result(energies_ev, r)
+    })
+}
+
+/// [`ameyanagi_suppression_exact`] for any [`SampleSpec`]. Only a
+/// [`SampleSpec::Formula`] can have its density looked up.
+pub fn ameyanagi_suppression_exact_spec(
+    spec: &SampleSpec,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies_ev: &[f64],
+    settings: AmeyanagiSuppressionSettings,
+) -> Result<AmeyanagiSuppressionResult, SelfAbsError> {
+    let ctx = SelfAbsContext::new();
+    let info = SampleInfo::from_spec(&ctx, spec, central_element, edge)?;
+    ameyanagi_suppression_exact_with_sample_info(&ctx, &info, energies_ev, settings)
+}
+
+/// Result of [`ameyanagi_layered`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayeredAmeyanagiResult {
+    /// Exact suppression of the absorbing layer under the caps.
+    pub suppression: AmeyanagiSuppressionResult,
+    /// Transmission of the incident beam through the caps, per energy point.
+    pub incident_transmission: Vec<f64>,
+    /// Transmission of the fluorescence through the caps at the weighted
+    /// fluorescence energy, averaged over the detector acceptance.
+    pub fluorescence_transmission: f64,
+    /// Detected intensity at χ = 0 per unit incident flux, fluorescence
+    /// yield and solid angle: T_in(E) T_f μ̄_a (1 − exp(−αβ)) / α.
+    pub intensity: Vec<f64>,
+}
+
+/// Exact suppression of the absorbing layer of a [`LayeredSample`].
+///
+/// The caps multiply the detected intensity by
+/// T_in(E) = exp(−Σ μ_c(E) d_c / sin φ) and T_f = exp(−Σ μ_c(E_f) d_c / sin θ).
+/// Both factors are the same with and without the χ modulation, so R(E)
+/// is that of the bare layer and the caps only appear in
+/// [`LayeredAmeyanagiResult::intensity`]. With a detector acceptance T_f
+/// differs between exit angles and reweights them, which does change R.
+///
+/// The density and thickness of `settings` are replaced by those of
+/// `sample.absorber`. Every layer is validated and a substrate does not
+/// enter. [`LayerIncidence::ThroughSubstrate`] is rejected; see
+/// [`booth_layered`](crate::booth::booth_layered) for that geometry.
+pub fn ameyanagi_layered(
+    sample: &LayeredSample,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies_ev: &[f64],
+    settings: AmeyanagiSuppressionSettings,
+) -> Result<LayeredAmeyanagiResult, SelfAbsError> {
+    SelfAbsContext::new().ameyanagi_layered(sample, central_element, edge, energies_ev, settings)
+}
+
+/// [`ameyanagi_layered`] on `ctx`.
+pub(crate) fn ameyanagi_layered_on(
+    ctx: &SelfAbsContext,
+    sample: &LayeredSample,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies_ev: &[f64],
+    mut settings: AmeyanagiSuppressionSettings,
+) -> Result<LayeredAmeyanagiResult, SelfAbsError> {
+    sample.validate()?;
+    if sample.incidence == LayerIncidence::ThroughSubstrate {
+        return Err(SelfAbsError::InsufficientData(
+            "a beam entering through the substrate (transmission-fluorescence geometry) is not supported by the Ameyanagi layered model; use booth_layered".to_string(),
+        ));
+    }
+    let info = ctx.sample_info(&sample.absorber.formula, central_element, edge)?;
+    settings.density_g_cm3 = Some(sample.absorber.density);
+    settings.thickness_input =
+        AmeyanagiThicknessInput::ThicknessCm(sample.absorber.thickness_um * 1e-4);
+
+    validate_exact_inputs(energies_ev, settings.chi_assumed)?;
+
+    // One model serves the bare and the capped result: the caps only scale
+    // the signal and, over a detector acceptance, reweight the exit angles.
+    let mut model = AmeyanagiModel::from_info(ctx, &info, energies_ev, &settings.sample())?;
+    let fluorescence_energy = model.fluorescence_energy_weighted;
+    let backend = settings.mu_backend;
+    let incident_transmission =
+        sample.cap_transmission(ctx, energies_ev, settings.phi_rad.sin(), backend)?;
+    let fluorescence_transmission = match &settings.exit_angles {
+        None => sample.cap_transmission_single(
+            ctx,
+            fluorescence_energy,
+            settings.theta_rad.sin(),
+            backend,
+        )?,
+        Some(exit_angles) => {
+            let transmissions = exit_angles
+                .iter()
+                .map(|&(theta, _)| {
+                    sample.cap_transmission_single(ctx, fluorescence_energy, theta.sin(), backend)
+                })
+                .collect::<Result<Vec<_>, SelfAbsError>>()?;
+            model.reweight_acceptance(&transmissions);
+            let total: f64 = exit_angles.iter().map(|&(_, w)| w).sum();
+            let transmitted: f64 = exit_angles
+                .iter()
+                .zip(&transmissions)
+                .map(|(&(_, w), t)| w * t)
+                .sum();
+            transmitted / total
+        }
+    };
+
+    let intensity = incident_transmission
+        .iter()
+        .enumerate()
+        .map(|(i, t_in)| t_in * fluorescence_transmission * model.intensity(i))
+        .collect();
+    Ok(LayeredAmeyanagiResult {
+        suppression: exact_from_model(model, energies_ev, settings.chi_assumed, settings.per_line)?,
+        incident_transmission,
+        fluorescence_transmission,
+        intensity,
+    })
+}
+
+/// One thickness of an [`ameyanagi_thickness_scan`].
+#[derive(Debug, Clone)]
+pub struct AmeyanagiThicknessScanPoint {
+    /// Sample thickness in cm.
+    pub thickness_cm: f64,
+    /// Minimum R over the grid.
+    pub r_min: f64,
+    /// Mean R over the grid.
+    pub r_mean: f64,
+    /// Maximum R over the grid.
+    pub r_max: f64,
+    /// Full R(E), if requested.
+    pub suppression_factor: Option<Vec<f64>>,
+}
+
+/// Evaluate the exact suppression for several thicknesses.
+///
+/// μ_total, μ_a and μ_f are computed once from `base_settings`; only the
+/// thickness (and thus β) changes between points. `base_settings.thickness_input`
+/// is validated but otherwise ignored. Set `include_curves` to keep R(E) for
+/// every thickness.
+pub fn ameyanagi_thickness_scan(
+    formula: &str,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies_ev: &[f64],
+    base_settings: AmeyanagiSuppressionSettings,
+    thicknesses_cm: &[f64],
+    include_curves: bool,
+) -> Result<Vec<AmeyanagiThicknessScanPoint>, SelfAbsError> {
+    SelfAbsContext::new().ameyanagi_thickness_scan(
+        formula,
+        central_element,
+        edge,
+        energies_ev,
+        base_settings,
+        thicknesses_cm,
+        include_curves,
+    )
+}
+
+/// [`ameyanagi_thickness_scan`] for a prebuilt [`SampleInfo`].
+pub fn ameyanagi_thickness_scan_with_sample_info(
+    ctx: &SelfAbsContext,
+    info: &SampleInfo,
+    energies_ev: &[f64],
+    base_settings: AmeyanagiSuppressionSettings,
+    thicknesses_cm: &[f64],
+    include_curves: bool,
+) -> Result<Vec<AmeyanagiThicknessScanPoint>, SelfAbsError> {
+    let chi_assumed = base_settings.chi_assumed;
+    if chi_assumed == 0.0 || !chi_assumed.is_finite() {
+        return Err(SelfAbsError::InsufficientData(
+            "chi must be finite and non-zero".to_string(),
+        ));
+    }
+
+    let mut model = AmeyanagiModel::from_info(ctx, info, energies_ev, &base_settings.sample())?;
+
+    thicknesses_cm
+        .iter()
+        .map(|&thickness_cm| {
+            model.set_thickness(thickness_cm)?;
+            let r = model.suppression_curve(chi_assumed, model.mu_f)?;
+            let (r_min, r_mean, r_max) = summarize(&r);
+            Ok(AmeyanagiThicknessScanPoint {
+                thickness_cm,
+                r_min,
+                r_mean,
+                r_max,
+                suppression_factor: include_curves.then_some(r),
+            })
+        })
+        .collect()
+}
+
+/// Result of [`ameyanagi_max_thickness`].
+#[derive(Debug, Clone)]
+pub struct ThicknessRecommendation {
+    /// Largest thickness (cm) with r_min ≥ target over the EXAFS region, or
+    /// `None` if even the thick limit meets the target.
+    pub max_thickness_cm: Option<f64>,
+    /// r_min over the EXAFS region at that thickness (at the thickest
+    /// sample considered when unlimited).
+    pub r_min: f64,
+    /// Total pellet mass (g, sample plus binder) of that thickness for the
+    /// requested diameter.
+    pub pellet_mass_g: Option<f64>,
+}
+
+/// Largest sample thickness that keeps the suppression above `r_min_target`
+/// over the EXAFS region (E > E₀ + 30 eV).
+///
+/// R_min decreases monotonically with thickness, so the limit is found by
+/// bisection in log-thickness. `base_settings.thickness_input` is validated
+/// but otherwise ignored. Pass `pellet_diameter_cm` to also get the
+/// equivalent pellet mass. Errors if even a 1 nm sample violates the target.
+pub fn ameyanagi_max_thickness(
+    formula: &str,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies_ev: &[f64],
+    base_settings: AmeyanagiSuppressionSettings,
+    r_min_target: f64,
+    pellet_diameter_cm: Option<f64>,
+) -> Result<ThicknessRecommendation, SelfAbsError> {
+    SelfAbsContext::new().ameyanagi_max_thickness(
+        formula,
+        central_element,
+        edge,
+        energies_ev,
+        base_settings,
+        r_min_target,
+        pellet_diameter_cm,
+    )
+}
+
+/// [`ameyanagi_max_thickness`] for a prebuilt [`SampleInfo`].
+pub fn ameyanagi_max_thickness_with_sample_info(
+    ctx: &SelfAbsContext,
+    info: &SampleInfo,
+    energies_ev: &[f64],
+    base_settings: AmeyanagiSuppressionSettings,
+    r_min_target: f64,
+    pellet_diameter_cm: Option<f64>,
+) -> Result<ThicknessRecommendation, SelfAbsError> {
+    let chi_assumed = base_settings.chi_assumed;
+    if chi_assumed == 0.0 || !chi_assumed.is_finite() {
+        return Err(SelfAbsError::InsufficientData(
+            "chi must be finite and non-zero".to_string(),
+        ));
+    }
+    if !r_min_target.is_finite() {
+        return Err(SelfAbsError::InsufficientData(
+            "target r_min must be finite".to_string(),
+        ));
+    }
+    if let Some(diameter) = pellet_diameter_cm
+        && !(diameter.is_finite() && diameter > 0.0)
+    {
+        return Err(SelfAbsError::InsufficientData(
+            "pellet diameter must be finite and > 0".to_string(),
+        ));
+    }
+
+    let mut model = AmeyanagiModel::from_info(ctx, info, energies_ev, &base_settings.sample())?;
+    let exafs: Vec<usize> = energies_ev
+        .iter()
+        .enumerate()
+        .filter(|&(_, &e)| e > model.edge_energy + EXAFS_OFFSET_EV)
+        .map(|(i, _)| i)
+        .collect();
+    if exafs.is_empty() {
+        return Err(SelfAbsError::InsufficientData(format!(
+            "no grid points above E0 + {EXAFS_OFFSET_EV} eV"
+        )));
+    }
+
+    let mut r_min_at = |thickness_cm: f64| -> Result<f64, SelfAbsError> {
+        model.set_thickness(thickness_cm)?;
+        exafs.iter().try_fold(f64::INFINITY, |acc, &i| {
+            Ok(acc.min(model.suppression(i, chi_assumed)?))
+        })
+    };
+
+    let r_thin = r_min_at(MAX_THICKNESS_LOWER_CM)?;
+    if r_thin < r_min_target {
+        return Err(SelfAbsError::TargetUnreachable {
+            target_r_min: r_min_target,
+            best_r_min: r_thin,
+            reason: format!(
+                "target r_min {r_min_target} is not reached even at {MAX_THICKNESS_LOWER_CM} cm (r_min = {r_thin})"
+            ),
+        });
+    }
+    let r_thick = r_min_at(MAX_THICKNESS_UPPER_CM)?;
+    if r_thick >= r_min_target {
+        return Ok(ThicknessRecommendation {
+            max_thickness_cm: None,
+            r_min: r_thick,
+            pellet_mass_g: None,
+        });
+    }
+
+    let (mut lo, mut hi) = (MAX_THICKNESS_LOWER_CM, MAX_THICKNESS_UPPER_CM);
+    let mut r_lo = r_thin;
+    while hi / lo - 1.0 > MAX_THICKNESS_RTOL {
+        let mid = (lo * hi).sqrt();
+        let r_mid = r_min_at(mid)?;
+        if r_mid >= r_min_target {
+            lo = mid;
+            r_lo = r_mid;
+        } else {
+            hi = mid;
+        }
+    }
+
+    let pellet_mass_g = pellet_diameter_cm
+        .map(|diameter| lo * model.effective_density_g_cm3 * PI * (diameter * 0.5).powi(2));
+    Ok(ThicknessRecommendation {
+        max_thickness_cm: Some(lo),
+        r_min: r_lo,
+        pellet_mass_g,
+    })
+}
+
+/// Exact suppression factor for a χ profile aligned with `energies_ev`,
+/// evaluating R(E, χ(E)) point by point instead of with one constant χ.
+///
+/// Zero entries use the analytic χ → 0 limit of R, so a profile that passes
+/// through nodes is fine. A constant profile reproduces
+/// [`ameyanagi_suppression_exact`].
+pub fn ameyanagi_suppression_profile(
+    formula: &str,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies_ev: &[f64],
+    sample: AmeyanagiSampleSettings,
+    chi_profile: &[f64],
+) -> Result<AmeyanagiSuppressionResult, SelfAbsError> {
+    SelfAbsContext::new().ameyanagi_profile(
+        formula,
+        central_element,
+        edge,
+        energies_ev,
+        sample,
+        chi_profile,
+    )
+}
+
+/// [`ameyanagi_suppression_profile`] for a prebuilt [`SampleInfo`].
+pub fn ameyanagi_suppression_profile_with_sample_info(
+    ctx: &SelfAbsContext,
+    info: &SampleInfo,
+    energies_ev: &[f64],
+    sample: AmeyanagiSampleSettings,
+    chi_profile: &[f64],
+) -> Result<AmeyanagiSuppressionResult, SelfAbsError> {
+    if chi_profile.len() != energies_ev.len() {
+        return Err(SelfAbsError::InsufficientData(format!(
+            "chi profile has {} points but the energy grid has {}",
+            chi_profile.len(),
+            energies_ev.len()
+        )));
+    }
+    if let Some(i) = chi_profile.iter().position(|c| !c.is_finite()) {
+        return Err(SelfAbsError::InsufficientData(format!(
+            "chi must be finite (index {i})"
+        )));
+    }
+
+    let model = AmeyanagiModel::from_info(ctx, info, energies_ev, &sample)?;
+    let r = chi_profile
+        .iter()
+        .enumerate()
+        .map(|(i, &chi)| model.suppression_or_limit(i, chi))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(model.into_result(energies_ev, r))
+}
+
+/// Exact R(E, χ) over a range of χ at a few energies, to show how
+/// nonlinear the suppression is.
+///
+/// Returns `R[e][j]` for `energies_ev[e]` and `chi_values[j]`. The
+/// attenuation terms are computed once and χ = 0 uses the analytic limit.
+pub fn ameyanagi_chi_scan(
+    formula: &str,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies_ev: &[f64],
+    sample: AmeyanagiSampleSettings,
+    chi_values: &[f64],
+) -> Result<Vec<Vec<f64>>, SelfAbsError> {
+    SelfAbsContext::new().ameyanagi_chi_scan(
+        formula,
+        central_element,
+        edge,
+        energies_ev,
+        sample,
+        chi_values,
+    )
+}
+
+/// [`ameyanagi_chi_scan`] for a prebuilt [`SampleInfo`].
+pub fn ameyanagi_chi_scan_with_sample_info(
+    ctx: &SelfAbsContext,
+    info: &SampleInfo,
+    energies_ev: &[f64],
+    sample: AmeyanagiSampleSettings,
+    chi_values: &[f64],
+) -> Result<Vec<Vec<f64>>, SelfAbsError> {
+    if let Some(j) = chi_values.iter().position(|c| !c.is_finite()) {
+        return Err(SelfAbsError::InsufficientData(format!(
+            "chi must be finite (index {j})"
+        )));
+    }
+
+    let model = AmeyanagiModel::from_info(ctx, info, energies_ev, &sample)?;
+    (0..energies_ev.len())
+        .map(|i| {
+            chi_values
+                .iter()
+                .map(|&chi| model.suppression_or_limit(i, chi))
+                .collect()
+        })
+        .collect()
+}
+
+/// Correct measured χ with the exact model, i.e. solve
+///
+/// ```text
+/// R(E, χ_true) · χ_true = χ_measured
+/// ```
+///
+/// for χ_true at every energy point with a bracketed root finder.
+/// `chi_measured` must be aligned with `energies_ev`. χ_measured = 0 maps to
+/// 0; values at or below −1, or above the saturation limit of a thick
+/// sample, have no solution and are reported as errors with their index.
+pub fn ameyanagi_correct_chi(
+    formula: &str,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies_ev: &[f64],
+    sample: AmeyanagiSampleSettings,
+    chi_measured: &[f64],
+) -> Result<Vec<f64>, SelfAbsError> {
+    SelfAbsContext::new().ameyanagi_correct_chi(
+        formula,
+        central_element,
+        edge,
+        energies_ev,
+        sample,
+        chi_measured,
+    )
+}
+
+/// [`ameyanagi_correct_chi`] for a prebuilt [`SampleInfo`].
+pub fn ameyanagi_correct_chi_with_sample_info(
+    ctx: &SelfAbsContext,
+    info: &SampleInfo,
+    energies_ev: &[f64],
+    sample: AmeyanagiSampleSettings,
+    chi_measured: &[f64],
+) -> Result<Vec<f64>, SelfAbsError> {
+    if chi_measured.len() != energies_ev.len() {
+        return Err(SelfAbsError::InsufficientData(format!(
+            "chi has {} points but the energy grid has {}",
+            chi_measured.len(),
+            energies_ev.len()
+        )));
+    }
+
+    let model = AmeyanagiModel::from_info(ctx, info, energies_ev, &sample)?;
+    chi_measured
+        .iter()
+        .enumerate()
+        .map(|(i, &c)| model.invert(i, c))
+        .collect()
+}
+
+/// The exact model on one grid as a [`SelfAbsCorrection`]: χ is corrected
+/// by the point-wise inversion of [`ameyanagi_correct_chi`].
+pub struct AmeyanagiCorrection {
+    model: AmeyanagiModel,
+    k: Vec<f64>,
+    result: AmeyanagiSuppressionResult,
+}
+
+impl AmeyanagiCorrection {
+    /// Build the model once; `settings.chi_assumed` sets the χ of the
+    /// suppression in [`result`](Self::result) and the summary.
+    pub fn new(
+        ctx: &SelfAbsContext,
+        info: &SampleInfo,
+        energies_ev: &[f64],
+        settings: AmeyanagiSuppressionSettings,
+    ) -> Result<Self, SelfAbsError> {
+        let result =
+            ameyanagi_suppression_exact_with_sample_info(ctx, info, energies_ev, settings.clone())?;
+        let model = AmeyanagiModel::from_info(ctx, info, energies_ev, &settings.sample())?;
+        Ok(Self {
+            k: energies_to_k(energies_ev, model.edge_energy),
+            model,
+            result,
+        })
+    }
+
+    /// The suppression at `settings.chi_assumed`.
+    pub fn result(&self) -> &AmeyanagiSuppressionResult {
+        &self.result
+    }
+
+    /// Write the corrected `chi` into `out` without allocating, returning
+    /// the number of points written.
+    ///
+    /// `chi` holds the first `chi.len()` points of the grid, so a spectrum
+    /// can be corrected while it is acquired; `out` may be longer than
+    /// `chi`. Values are those of [`SelfAbsCorrection::correct_chi`].
+    pub fn correct_chi_into(&self, chi: &[f64], out: &mut [f64]) -> Result<usize, SelfAbsError> {
+        let n = check_chi_into(chi, out, self.k.len())?;
+        for (i, (o, &c)) in out.iter_mut().zip(chi).enumerate() {
+            *o = self.model.invert(i, c)?;
+        }
+        Ok(n)
+    }
+
+    /// Lazy form of [`correct_chi_into`](Self::correct_chi_into); a point
+    /// whose inversion fails yields its error.
+    pub fn corrected_iter<'a>(
+        &'a self,
+        chi: &'a [f64],
+    ) -> Result<impl Iterator<Item = Result<f64, SelfAbsError>> + 'a, SelfAbsError> {
+        check_chi_prefix(chi, self.k.len())?;
+        Ok(chi
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| self.model.invert(i, c)))
+    }
+}
+
+impl SelfAbsCorrection for AmeyanagiCorrection {
+    fn energies(&self) -> &[f64] {
+        &self.result.energies
+    }
+
+    fn k(&self) -> &[f64] {
+        &self.k
+    }
+
+    fn correct_chi(&self, chi: &[f64]) -> Result<Vec<f64>, SelfAbsError> {
+        check_chi_len(chi, &self.result.energies)?;
+        chi.iter()
+            .enumerate()
+            .map(|(i, &c)| self.model.invert(i, c))
+            .collect()
+    }
+
+    fn summary(&self) -> CorrectionSummary {
+        self.result.summarize()
+    }
+}
+
+impl AmeyanagiSuppressionResult {
+    /// Suppression summary at the χ the result was computed for.
+    pub fn summarize(&self) -> CorrectionSummary {
+        CorrectionSummary::from_suppression(
+            Algorithm::Ameyanagi,
+            &energies_to_k(&self.energies, self.edge_energy),
+            self.suppression_factor.iter().copied(),
+        )
+    }
+}
+
+/// Positive-intensity emission line with its matrix attenuation.
+#[derive(Debug, Clone)]
+struct FluorescenceLine {
+    label: String,
+    intensity: f64,
+    energy: f64,
+    mu_f: f64,
+}
+
+/// `lines` sorted by label, each with μ at its energy.
+fn fluorescence_lines(
+    ctx: &SelfAbsContext,
+    mass_fractions: &[(String, f64)],
+    density_g_cm3: f64,
+    lines: &[EmissionLine],
+    kind: CrossSectionKind,
+    backend: MuBackend,
+) -> Result<Vec<FluorescenceLine>, SelfAbsError> {
+    let mut sorted: Vec<&EmissionLine> = lines.iter().collect();
+    sorted.sort_by(|a, b| a.label.cmp(&b.label));
+
+    let tables = ctx.tables(backend);
+    let line_mu = |line: &&EmissionLine| {
+        let mu_f =
+            compound_mu_linear_single(&tables, mass_fractions, density_g_cm3, line.energy, kind)?;
+        Ok(FluorescenceLine {
+            label: line.label.clone(),
+            intensity: line.intensity,
+            energy: line.energy,
+            mu_f,
+        })
+    };
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        sorted.par_iter().map(line_mu).collect()
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        sorted.iter().map(line_mu).collect()
+    }
+}
+
+/// Branching-weighted (μ_f, fluorescence energy) over `lines`.
+fn weight_fluorescence_lines(lines: &[FluorescenceLine]) -> (f64, f64) {
+    let mut weighted_mu_f = 0.0;
+    let mut weighted_energy = 0.0;
+    let mut weight_sum = 0.0;
+    for line in lines {
+        weighted_mu_f += line.intensity * line.mu_f;
+        weighted_energy += line.intensity * line.energy;
+        weight_sum += line.intensity;
+    }
+    (weighted_mu_f / weight_sum, weighted_energy / weight_sum)
+}
+
+/// (min, mean, max) of a non-empty R array.
+fn summarize(r: &[f64]) -> (f64, f64, f64) {
+    let r_min = r.iter().copied().fold(f64::INFINITY, f64::min);
+    let r_max = r.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let r_mean = r.iter().sum::<f64>() / r.len() as f64;
+    (r_min, r_mean, r_max)
+}
+
+fn one_minus_exp_neg(x: f64) -> f64 {
+    if x <= 0.0 {
+        0.0
+    } else if x > 700.0 {
+        1.0
+    } else {
+        -(-x).exp_m1()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{LayerSpec, absorber_edge_mu_linear_trendline};
+    use xraydb::XrayDb;
+
+    fn energies() -> Vec<f64> {
+        (7000..=8000).step_by(5).map(|e| e as f64).collect()
+    }
+
+    /// Fe2O3 at 5.24 g/cm³, 45°/45° and χ = 0.2, everything else default.
+    fn fe2o3_settings(thickness_input: AmeyanagiThicknessInput) -> AmeyanagiSuppressionSettings {
+        AmeyanagiSuppressionSettings {
+            density_g_cm3: Some(5.24),
+            phi_rad: std::f64::consts::FRAC_PI_4,
+            theta_rad: std::f64::consts::FRAC_PI_4,
+            thickness_input,
+            chi_assumed: 0.2,
+            binder: None,
+            packing_fraction: None,
+            cross_section: CrossSectionKind::Photo,
+            per_line: false,
+            exit_angles: None,
+            mu_a_method: AmeyanagiMuAMethod::default(),
+            e0_override: None,
+            pre_edge_window: PreEdgeOptions::default(),
+            detection_mode: DetectionMode::default(),
+            mu_backend: MuBackend::default(),
+            detector_filter: None,
+        }
+    }
+
+    fn fe2o3_model(energies: &[f64], sample: &AmeyanagiSampleSettings) -> AmeyanagiModel {
+        let ctx = SelfAbsContext::new();
+        let info = ctx.sample_info("Fe2O3", "Fe", Edge::K).unwrap();
+        AmeyanagiModel::from_info(&ctx, &info, energies, sample).unwrap()
+    }
+
+    #[test]
+    fn test_ameyanagi_exact_fe2o3() {
+        let r = ameyanagi_suppression_exact(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies(),
+            fe2o3_settings(AmeyanagiThicknessInput::ThicknessCm(0.01)),
+        )
+        .unwrap();
+
+        assert_eq!(r.energies.len(), r.suppression_factor.len());
+        assert!(r.suppression_factor.iter().all(|v| v.is_finite()));
+        assert!(r.r_min <= r.r_mean);
+        assert!(r.r_mean <= r.r_max);
+    }
+
+    #[test]
+    fn test_mass_diameter_matches_thickness() {
+        let density: f64 = 5.24;
+        let diameter: f64 = 1.0;
+        let mass: f64 = 0.05;
+        let d = mass / (density * PI * (diameter * 0.5).powi(2));
+
+        let direct = ameyanagi_suppression_exact(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies(),
+            AmeyanagiSuppressionSettings {
+                density_g_cm3: Some(density),
+                ..fe2o3_settings(AmeyanagiThicknessInput::ThicknessCm(d))
+            },
+        )
+        .unwrap();
+
+        let pellet = ameyanagi_suppression_exact(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies(),
+            AmeyanagiSuppressionSettings {
+                density_g_cm3: Some(density),
+                ..fe2o3_settings(AmeyanagiThicknessInput::PelletMassDiameter {
+                    mass_g: mass,
+                    diameter_cm: diameter,
+                })
+            },
+        )
+        .unwrap();
+
+        assert!((direct.thickness_cm - pellet.thickness_cm).abs() < 1e-14);
+        assert!((direct.r_mean - pellet.r_mean).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_thicker_sample_has_smaller_mean_r() {
+        let thin = ameyanagi_suppression_exact(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies(),
+            fe2o3_settings(AmeyanagiThicknessInput::ThicknessCm(1e-4)),
+        )
+        .unwrap();
+
+        let thick = ameyanagi_suppression_exact(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies(),
+            fe2o3_settings(AmeyanagiThicknessInput::ThicknessCm(0.2)),
+        )
+        .unwrap();
+
+        assert!(thick.r_mean < thin.r_mean);
+    }
+
+    #[test]
+    fn test_positive_chi_gives_positive_suppression_factor() {
+        let r = ameyanagi_suppression_exact(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies(),
+            fe2o3_settings(AmeyanagiThicknessInput::ThicknessCm(0.01)),
+        )
+        .unwrap();
+
+        assert!(
+            r.suppression_factor
+                .iter()
+                .all(|&v| v.is_finite() && v > 0.0),
+            "expected all R(E,chi)>0 for positive chi"
+        );
+    }
+
+    #[test]
+    fn test_mu_a_trendline_is_nonnegative_and_preedge_small() {
+        let ctx = SelfAbsContext::new();
+        let info = SampleInfo::new(&ctx, "Fe2O3", "Fe", Edge::K).unwrap();
+        let e0 = info.edge_energy;
+        let energies: Vec<f64> = (0..=300).map(|i| e0 - 250.0 + 2.0 * i as f64).collect();
+        let (mu_a, baseline) = absorber_edge_mu_linear_trendline(
+            &ctx,
+            &info,
+            &energies,
+            5.24,
+            &PreEdgeOptions::default(),
+            MuBackend::Elam,
+        )
+        .unwrap();
+
+        assert_eq!(baseline, PreEdgeBaseline::Trendline);
+        assert_eq!(mu_a.len(), energies.len());
+        assert!(mu_a.iter().all(|v| v.is_finite() && *v >= 0.0));
+
+        let mut pre_sum = 0.0;
+        let mut pre_n = 0usize;
+        let mut post_sum = 0.0;
+        let mut post_n = 0usize;
+        for (&e, &m) in energies.iter().zip(mu_a.iter()) {
+            if e <= e0 - 40.0 {
+                pre_sum += m;
+                pre_n += 1;
+            }
+            if e >= e0 + 40.0 {
+                post_sum += m;
+                post_n += 1;
+            }
+        }
+
+        assert!(pre_n > 0 && post_n > 0);
+        let pre_mean = pre_sum / pre_n as f64;
+        let post_mean = post_sum / post_n as f64;
+        assert!(pre_mean < 0.25 * post_mean.max(1e-12));
+    }
+
+    #[test]
+    fn test_mu_a_trendline_window_and_victoreen() {
+        let ctx = SelfAbsContext::new();
+        let info = SampleInfo::new(&ctx, "Au", "Au", Edge::L3).unwrap();
+        let e0 = info.edge_energy;
+        let energies: Vec<f64> = (0..=300).map(|i| e0 - 300.0 + 4.0 * i as f64).collect();
+        let trendline = |pre_edge: PreEdgeOptions| {
+            absorber_edge_mu_linear_trendline(
+                &ctx,
+                &info,
+                &energies,
+                19.3,
+                &pre_edge,
+                MuBackend::Elam,
+            )
+            .unwrap()
+        };
+
+        let (linear, baseline) = trendline(PreEdgeOptions::default());
+        assert_eq!(baseline, PreEdgeBaseline::Trendline);
+        let (victoreen, baseline) = trendline(PreEdgeOptions {
+            victoreen_n: 3,
+            ..Default::default()
+        });
+        assert_eq!(baseline, PreEdgeBaseline::Trendline);
+        // A straight line keeps falling while the E^-3 tail flattens, so the
+        // Victoreen baseline sits higher far above E0 and the step shrinks.
+        let last = energies.len() - 1;
+        let rel = victoreen[last] / linear[last] - 1.0;
+        assert!((-0.1..-1e-3).contains(&rel), "rel={rel}");
+
+        // Requiring more points than the window holds forces the fallback.
+        let (_, baseline) = trendline(PreEdgeOptions {
+            min_points: 1000,
+            ..Default::default()
+        });
+        assert_eq!(baseline, PreEdgeBaseline::Fallback);
+
+        let invalid = PreEdgeOptions {
+            min_points: 1,
+            ..Default::default()
+        };
+        assert!(
+            absorber_edge_mu_linear_trendline(
+                &ctx,
+                &info,
+                &energies,
+                19.3,
+                &invalid,
+                MuBackend::Elam
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_pre_edge_methods_agree_near_edge() {
+        let ctx = SelfAbsContext::new();
+        let info = SampleInfo::new(&ctx, "Fe2O3", "Fe", Edge::K).unwrap();
+        let e0 = info.edge_energy;
+        let energies: Vec<f64> = (0..=700).map(|i| e0 - 300.0 + 5.0 * i as f64).collect();
+        let methods = [
+            PreEdgeMethod::Constant,
+            PreEdgeMethod::Trendline,
+            PreEdgeMethod::JumpRatio,
+        ];
+        let curves: Vec<Vec<f64>> = methods
+            .iter()
+            .map(|&method| {
+                let (mu, baseline) = absorber_edge_mu_linear(
+                    &ctx,
+                    &info,
+                    &energies,
+                    5.24,
+                    method,
+                    &PreEdgeOptions::default(),
+                    MuBackend::Elam,
+                )
+                .unwrap();
+                assert_eq!(
+                    matches!(baseline, PreEdgeBaseline::JumpRatio),
+                    method == PreEdgeMethod::JumpRatio
+                );
+                mu
+            })
+            .collect();
+        // Largest relative spread of the three methods at point `i`.
+        let spread = |i: usize| {
+            let values = curves.iter().map(|c| c[i]);
+            let max = values.clone().fold(f64::MIN, f64::max);
+            let min = values.fold(f64::MAX, f64::min);
+            max / min - 1.0
+        };
+        let index = |e: f64| energies.iter().position(|&x| x >= e).unwrap();
+
+        let near = (index(e0 + 5.0)..=index(e0 + 50.0))
+            .map(spread)
+            .fold(0.0, f64::max);
+        let far = spread(energies.len() - 1);
+        assert!(near < 0.1, "near={near}");
+        assert!(far > 2.0 * near, "far={far}, near={near}");
+        assert!(curves[2][..index(e0)].iter().all(|&m| m == 0.0));
+    }
+
+    #[test]
+    fn test_thick_limit_matches_booth_eq6_ratio() {
+        let energies = energies();
+        let chi = 0.2;
+        let density = 5.24;
         let phi = std::f64::consts::FRAC_PI_4;
         let theta = std::f64::consts::FRAC_PI_4;
         let thickness_cm = 0.5;
 
-        let exact = ameyanagi_suppression_exact(
+        let exact = ameyanagi_suppression_exact(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies,
+            AmeyanagiSuppressionSettings {
+                density_g_cm3: Some(density),
+                phi_rad: phi,
+                theta_rad: theta,
+                chi_assumed: chi,
+                ..fe2o3_settings(AmeyanagiThicknessInput::ThicknessCm(thickness_cm))
+            },
+        )
+        .unwrap();
+
+        assert_eq!(exact.s.len(), energies.len());
+        assert_eq!(exact.alpha.len(), energies.len());
+
+        let mut max_abs_err = 0.0f64;
+        for i in 0..energies.len() {
+            let s = exact.s[i];
+            let thick_ratio = (1.0 - s) / (1.0 + s * chi);
+            let err = (exact.suppression_factor[i] - thick_ratio).abs();
+            if err > max_abs_err {
+                max_abs_err = err;
+            }
+        }
+
+        assert!(
+            max_abs_err < 1e-6,
+            "thick-limit mismatch too large: {max_abs_err}"
+        );
+    }
+
+    #[test]
+    fn test_zero_chi_is_error() {
+        let e = ameyanagi_suppression_exact(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies(),
+            AmeyanagiSuppressionSettings {
+                chi_assumed: 0.0,
+                ..fe2o3_settings(AmeyanagiThicknessInput::ThicknessCm(0.01))
+            },
+        )
+        .unwrap_err();
+        assert!(format!("{e}").contains("chi"));
+    }
+
+    #[test]
+    fn test_correct_chi_round_trip() {
+        let energies = energies();
+        for thickness_cm in [1e-4, 0.5] {
+            for chi_true in [0.2, -0.15] {
+                let settings = AmeyanagiSuppressionSettings {
+                    chi_assumed: chi_true,
+                    ..fe2o3_settings(AmeyanagiThicknessInput::ThicknessCm(thickness_cm))
+                };
+                let forward = ameyanagi_suppression_exact(
+                    "Fe2O3",
+                    "Fe",
+                    Edge::K,
+                    &energies,
+                    settings.clone(),
+                )
+                .unwrap();
+                let measured: Vec<f64> = forward
+                    .suppression_factor
+                    .iter()
+                    .map(|r| r * chi_true)
+                    .collect();
+
+                let corrected = ameyanagi_correct_chi(
+                    "Fe2O3",
+                    "Fe",
+                    Edge::K,
+                    &energies,
+                    settings.sample(),
+                    &measured,
+                )
+                .unwrap();
+                for &c in &corrected {
+                    assert!(
+                        (c - chi_true).abs() < 1e-8,
+                        "d={thickness_cm} chi={chi_true}: got {c}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_correct_chi_edge_cases() {
+        let energies = energies();
+        let sample = fe2o3_settings(AmeyanagiThicknessInput::ThicknessCm(0.5)).sample();
+
+        let zeros = vec![0.0; energies.len()];
+        let corrected =
+            ameyanagi_correct_chi("Fe2O3", "Fe", Edge::K, &energies, sample.clone(), &zeros)
+                .unwrap();
+        assert!(corrected.iter().all(|&c| c == 0.0));
+
+        // Thick Fe2O3 saturates well below χ_exp = 10.
+        let mut unreachable = zeros.clone();
+        unreachable[100] = 10.0;
+        let err = ameyanagi_correct_chi(
             "Fe2O3",
             "Fe",
-            "K",
+            Edge::K,
             &energies,
-            AmeyanagiSuppressionSettings {
-                density_g_cm3: density,
-                phi_rad: phi,
-                theta_rad: theta,
-                thickness_input: AmeyanagiThicknessInput::ThicknessCm(thickness_cm),
-                chi_assumed: chi,
-            },
+            sample.clone(),
+            &unreachable,
+        )
+        .unwrap_err();
+        assert!(format!("{err}").contains("index 100"), "{err}");
+
+        assert!(ameyanagi_correct_chi("Fe2O3", "Fe", Edge::K, &energies, sample, &[0.1]).is_err());
+    }
+
+    #[test]
+    fn test_suppression_profile() {
+        let energies = energies();
+        let settings = fe2o3_settings(AmeyanagiThicknessInput::ThicknessCm(0.01));
+        let scalar =
+            ameyanagi_suppression_exact("Fe2O3", "Fe", Edge::K, &energies, settings.clone())
+                .unwrap();
+
+        let constant = vec![0.2; energies.len()];
+        let profile = ameyanagi_suppression_profile(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies,
+            settings.sample(),
+            &constant,
+        )
+        .unwrap();
+        assert_eq!(profile.suppression_factor, scalar.suppression_factor);
+
+        let decaying: Vec<f64> = energies
+            .iter()
+            .map(|&e| 0.3 * (-(e - energies[0]) / 300.0).exp())
+            .collect();
+        let decayed = ameyanagi_suppression_profile(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies,
+            settings.sample(),
+            &decaying,
+        )
+        .unwrap();
+        assert!((decayed.r_mean - scalar.r_mean).abs() > 1e-4);
+    }
+
+    #[test]
+    fn test_suppression_profile_zero_chi_limit() {
+        let energies = energies();
+        let sample = fe2o3_settings(AmeyanagiThicknessInput::ThicknessCm(0.01)).sample();
+        let zero = ameyanagi_suppression_profile(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies,
+            sample.clone(),
+            &vec![0.0; energies.len()],
+        )
+        .unwrap();
+        let small = ameyanagi_suppression_profile(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies,
+            sample,
+            &vec![1e-7; energies.len()],
+        )
+        .unwrap();
+        for (a, b) in zero
+            .suppression_factor
+            .iter()
+            .zip(&small.suppression_factor)
+        {
+            assert!((a - b).abs() < 1e-6, "limit {a} vs small chi {b}");
+        }
+    }
+
+    #[test]
+    fn test_thickness_scan_monotonic() {
+        let energies = energies();
+        let settings = fe2o3_settings(AmeyanagiThicknessInput::ThicknessCm(0.01));
+        let thicknesses = [1e-5, 1e-4, 1e-3, 0.01, 0.05, 0.2, 1.0];
+        let scan = ameyanagi_thickness_scan(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies,
+            settings.clone(),
+            &thicknesses,
+            true,
         )
         .unwrap();
 
+        assert_eq!(scan.len(), thicknesses.len());
+        for pair in scan.windows(2) {
+            assert!(pair[1].r_mean <= pair[0].r_mean);
+        }
+
+        // Matches the single-thickness call.
+        let single =
+            ameyanagi_suppression_exact("Fe2O3", "Fe", Edge::K, &energies, settings.clone())
+                .unwrap();
+        let at_base = &scan[3];
+        assert_eq!(at_base.r_mean, single.r_mean);
+        assert_eq!(
+            at_base.suppression_factor.as_ref().unwrap().len(),
+            energies.len()
+        );
+
+        assert!(
+            ameyanagi_thickness_scan("Fe2O3", "Fe", Edge::K, &energies, settings, &[0.0], false)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_chi_scan_decreasing_for_thick_sample() {
         let db = XrayDb::new();
-        let info = SampleInfo::new(&db, "Fe2O3", "Fe", "K").unwrap();
-        let mass_fractions = composition_mass_fractions(&db, &info.composition).unwrap();
-        let mu_total = compound_mu_linear(&db, &mass_fractions, density, &energies).unwrap();
-        let mu_a = absorber_edge_mu_linear_trendline(&db, &info, &energies, density).unwrap();
-        let (mu_f, _) =
-            weighted_fluorescence_mu(&db, &mass_fractions, density, &info.central_symbol, "K")
+        let e0 = SampleInfo::new(&db, "Fe2O3", "Fe", Edge::K)
+            .unwrap()
+            .edge_energy;
+        let energies = [e0 + 50.0, e0 + 200.0, e0 + 600.0];
+        let chi_values: Vec<f64> = (-6..=6).map(|j| 0.05 * j as f64).collect();
+        let sample = fe2o3_settings(AmeyanagiThicknessInput::ThicknessCm(0.5)).sample();
+        let grid =
+            ameyanagi_chi_scan("Fe2O3", "Fe", Edge::K, &energies, sample, &chi_values).unwrap();
+
+        assert_eq!(grid.len(), energies.len());
+        for row in &grid {
+            assert_eq!(row.len(), chi_values.len());
+            assert!(row.iter().all(|r| r.is_finite()));
+            for pair in row.windows(2) {
+                assert!(pair[1] < pair[0], "{row:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_binder_dilution_raises_r_mean() {
+        let energies = energies();
+        let pure = AmeyanagiSuppressionSettings {
+            packing_fraction: Some(0.6),
+            ..fe2o3_settings(AmeyanagiThicknessInput::PelletMassDiameter {
+                mass_g: 0.1,
+                diameter_cm: 1.3,
+            })
+        };
+        let diluted = AmeyanagiSuppressionSettings {
+            thickness_input: AmeyanagiThicknessInput::PelletMassDiameter {
+                mass_g: 0.01,
+                diameter_cm: 1.3,
+            },
+            binder: Some(BinderSpec {
+                formula: "BN".to_string(),
+                mass_g: 0.1,
+                density_g_cm3: 2.1,
+            }),
+            ..pure.clone()
+        };
+
+        let r_pure = ameyanagi_suppression_exact("Fe2O3", "Fe", Edge::K, &energies, pure).unwrap();
+        let r_diluted =
+            ameyanagi_suppression_exact("Fe2O3", "Fe", Edge::K, &energies, diluted.clone())
                 .unwrap();
-        let g = phi.sin() / theta.sin();
 
-        let mut max_abs_err = 0.0f64;
+        assert!((r_pure.effective_density_g_cm3 - 0.6 * 5.24).abs() < 1e-12);
+        assert_eq!(r_pure.sample_mass_fraction, 1.0);
+
+        let w = 0.01 / 0.11;
+        let rho = 0.6 / (w / 5.24 + (1.0 - w) / 2.1);
+        assert!((r_diluted.sample_mass_fraction - w).abs() < 1e-12);
+        assert!((r_diluted.effective_density_g_cm3 - rho).abs() < 1e-12);
+        let area = PI * 0.65f64.powi(2);
+        assert!((r_diluted.thickness_cm - 0.11 / (rho * area)).abs() < 1e-12);
+
+        assert!(
+            r_diluted.r_mean > r_pure.r_mean + 0.2,
+            "pure {} vs diluted {}",
+            r_pure.r_mean,
+            r_diluted.r_mean
+        );
+
+        let direct = AmeyanagiSuppressionSettings {
+            thickness_input: AmeyanagiThicknessInput::ThicknessCm(0.05),
+            ..diluted
+        };
+        assert!(ameyanagi_suppression_exact("Fe2O3", "Fe", Edge::K, &energies, direct).is_err());
+    }
+
+    #[test]
+    fn test_per_line_combines_to_weighted() {
+        let energies = energies();
+        let settings = AmeyanagiSuppressionSettings {
+            per_line: true,
+            ..fe2o3_settings(AmeyanagiThicknessInput::ThicknessCm(0.01))
+        };
+        let result =
+            ameyanagi_suppression_exact("Fe2O3", "Fe", Edge::K, &energies, settings.clone())
+                .unwrap();
+        let model = fe2o3_model(&energies, &settings.sample());
+
+        assert_eq!(result.per_line.len(), model.lines.len());
+        assert!(result.per_line.len() > 1);
+        assert!(
+            result
+                .per_line
+                .iter()
+                .any(|(label, _)| label.starts_with("Ka"))
+        );
+
+        // Detected intensities add: weight each line by its χ = 0 yield
+        // I_l ∝ w_l (1 − exp(−α_l β)) / α_l.
         for i in 0..energies.len() {
-            let alpha = mu_total[i] + g * mu_f;
-            let s = mu_a[i] / alpha;
-            let thick_ratio = (1.0 - s) / (1.0 + s * chi);
-            let err = (exact.suppression_factor[i] - thick_ratio).abs();
-            if err > max_abs_err {
-                max_abs_err = err;
+            let mut weighted = 0.0;
+            let mut norm = 0.0;
+            for (line, (label, r_line)) in model.lines.iter().zip(&result.per_line) {
+                assert_eq!(&line.label, label);
+                let alpha = model.mu_total[i] + model.geometry_g * line.mu_f;
+                let yield_l = line.intensity * one_minus_exp_neg(alpha * model.beta) / alpha;
+                weighted += yield_l * r_line[i];
+                norm += yield_l;
             }
+            let combined = weighted / norm;
+            let r = result.suppression_factor[i];
+            assert!(
+                ((combined - r) / r).abs() < 1e-2,
+                "E={}: combined {combined} vs weighted {r}",
+                energies[i]
+            );
+        }
+
+        let plain = AmeyanagiSuppressionSettings {
+            per_line: false,
+            ..settings
+        };
+        let result = ameyanagi_suppression_exact("Fe2O3", "Fe", Edge::K, &energies, plain).unwrap();
+        assert!(result.per_line.is_empty());
+    }
+
+    #[test]
+    fn test_total_cross_section_for_dilute_aqueous_zn() {
+        let energies: Vec<f64> = (9700..=10500).step_by(5).map(|e| e as f64).collect();
+        let photo = AmeyanagiSuppressionSettings {
+            density_g_cm3: Some(1.0),
+            ..fe2o3_settings(AmeyanagiThicknessInput::ThicknessCm(0.5))
+        };
+        let total = AmeyanagiSuppressionSettings {
+            cross_section: CrossSectionKind::Total,
+            ..photo.clone()
+        };
+
+        let r_photo =
+            ameyanagi_suppression_exact("Zn0.001(H2O)55", "Zn", Edge::K, &energies, photo).unwrap();
+        let r_total =
+            ameyanagi_suppression_exact("Zn0.001(H2O)55", "Zn", Edge::K, &energies, total).unwrap();
+
+        // Scattering adds to α, so the (already small) suppression shrinks.
+        let dev_photo = 1.0 - r_photo.r_mean;
+        let dev_total = 1.0 - r_total.r_mean;
+        assert!(dev_total < dev_photo);
+        let rel = (dev_photo - dev_total) / dev_photo;
+        assert!(rel > 1e-3, "relative change={rel}");
+        assert!(r_total.mu_f > r_photo.mu_f);
+    }
+
+    #[test]
+    fn test_ameyanagi_mixture() {
+        let formula = |f: &str| SampleSpec::Formula(f.to_string());
+        let settings = |density| {
+            AmeyanagiSuppressionSettings::builder()
+                .density(density)
+                .thickness_cm(0.1)
+                .chi(0.1)
+                .build()
+                .unwrap()
+        };
+        let run = |spec: &SampleSpec, density, energies: &[f64]| {
+            ameyanagi_suppression_exact_spec(spec, "Fe", Edge::K, energies, settings(density))
+                .unwrap()
+        };
+
+        // A 50/50 "mixture" of one compound is that compound.
+        let pure = run(&formula("Fe2O3"), 5.24, &energies());
+        let halves = SampleSpec::Mixture(vec![(formula("Fe2O3"), 0.5), (formula("Fe2O3"), 0.5)]);
+        let mixed = run(&halves, 5.24, &energies());
+        assert_eq!(pure.suppression_factor, mixed.suppression_factor);
+        assert_eq!(mixed.warnings, pure.warnings);
+
+        // 30 wt% NiO on alumina is less suppressed than pure NiO: 1 − R_mean
+        // drops from about 0.65 to 0.44 in the thick limit.
+        let ni: Vec<f64> = (8200..=9300).step_by(5).map(|e| e as f64).collect();
+        let run_ni = |spec: &SampleSpec, density| {
+            ameyanagi_suppression_exact_spec(spec, "Ni", Edge::K, &ni, settings(density)).unwrap()
+        };
+        let nio = run_ni(&formula("NiO"), 6.67);
+        let supported = SampleSpec::Mixture(vec![(formula("NiO"), 30.0), (formula("Al2O3"), 70.0)]);
+        let on_alumina = run_ni(&supported, 4.5);
+        assert!(on_alumina.warnings.is_empty(), "{:?}", on_alumina.warnings);
+        let (dev_pure, dev_mix) = (1.0 - nio.r_mean, 1.0 - on_alumina.r_mean);
+        assert!(dev_mix < 0.75 * dev_pure, "{dev_mix} vs {dev_pure}");
+
+        // Weights summing to neither 1 nor 100 are normalized with a warning.
+        let loose = SampleSpec::Mixture(vec![(formula("NiO"), 0.3), (formula("Al2O3"), 0.6)]);
+        let normalized = run_ni(&loose, 4.5);
+        assert!(matches!(
+            normalized.warnings.as_slice(),
+            [SelfAbsWarning::MixtureNormalized { weight_sum }] if (weight_sum - 0.9).abs() < 1e-12
+        ));
+        let expected = SampleSpec::Mixture(vec![
+            (formula("NiO"), 1.0 / 3.0),
+            (formula("Al2O3"), 2.0 / 3.0),
+        ]);
+        let expected = run_ni(&expected, 4.5);
+        assert!((normalized.r_mean - expected.r_mean).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_settings_builder() {
+        let from_deg = AmeyanagiSuppressionSettings::builder()
+            .density(5.24)
+            .angles_deg(45.0, 30.0)
+            .thickness_cm(0.01)
+            .chi(0.2)
+            .build()
+            .unwrap();
+        let from_rad = AmeyanagiSuppressionSettings::builder()
+            .density(5.24)
+            .angles_rad(std::f64::consts::FRAC_PI_4, std::f64::consts::FRAC_PI_6)
+            .thickness_cm(0.01)
+            .chi(0.2)
+            .build()
+            .unwrap();
+        assert!((from_deg.phi_rad - from_rad.phi_rad).abs() < 1e-15);
+        assert!((from_deg.theta_rad - from_rad.theta_rad).abs() < 1e-15);
+        assert_eq!(from_deg.cross_section, CrossSectionKind::Photo);
+
+        let energies = energies();
+        let a = ameyanagi_suppression_exact("Fe2O3", "Fe", Edge::K, &energies, from_deg).unwrap();
+        let b = ameyanagi_suppression_exact("Fe2O3", "Fe", Edge::K, &energies, from_rad).unwrap();
+        assert!((a.r_mean - b.r_mean).abs() < 1e-12);
+
+        let areal = AmeyanagiSuppressionSettings::builder()
+            .density(5.24)
+            .areal_density_mg_cm2(52.4)
+            .chi(0.2)
+            .build()
+            .unwrap();
+        assert!(matches!(
+            areal.thickness_input,
+            AmeyanagiThicknessInput::ArealDensityMgCm2(a) if a == 52.4
+        ));
+    }
+
+    #[test]
+    fn test_settings_builder_names_invalid_field() {
+        let valid = || {
+            AmeyanagiSuppressionSettings::builder()
+                .density(5.24)
+                .thickness_cm(0.01)
+                .chi(0.2)
+        };
+        assert!(valid().build().is_ok());
+
+        let cases = [
+            (valid().density(-1.0), "density_g_cm3"),
+            (valid().angles_deg(0.0, 45.0), "phi_rad"),
+            (valid().angles_deg(45.0, 180.0), "theta_rad"),
+            (valid().angles_rad(f64::NAN, 0.5), "phi_rad"),
+            (
+                AmeyanagiSuppressionSettings::builder()
+                    .density(5.24)
+                    .chi(0.2),
+                "thickness",
+            ),
+            (valid().thickness_cm(0.0), "thickness_cm"),
+            (valid().pellet(-0.1, 1.3), "mass_g"),
+            (valid().pellet(0.1, 0.0), "diameter_cm"),
+            (
+                valid().areal_density_mg_cm2(f64::INFINITY),
+                "areal_density_mg_cm2",
+            ),
+            (valid().chi(0.0), "chi"),
+            (
+                AmeyanagiSuppressionSettings::builder()
+                    .density(5.24)
+                    .thickness_cm(0.01),
+                "chi",
+            ),
+            (valid().packing_fraction(1.5), "packing_fraction"),
+            (
+                valid().binder(BinderSpec {
+                    formula: "BN".to_string(),
+                    mass_g: 0.1,
+                    density_g_cm3: 2.1,
+                }),
+                "binder",
+            ),
+            (
+                valid().pellet(0.01, 1.3).binder(BinderSpec {
+                    formula: "BN".to_string(),
+                    mass_g: 0.1,
+                    density_g_cm3: 0.0,
+                }),
+                "binder density_g_cm3",
+            ),
+        ];
+        for (builder, field) in cases {
+            let err = builder.build().unwrap_err();
+            assert!(format!("{err}").contains(field), "{field}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_density_lookup() {
+        let energies = energies();
+        let settings = AmeyanagiSuppressionSettings::builder()
+            .thickness_cm(0.01)
+            .chi(0.2)
+            .build()
+            .unwrap();
+        assert_eq!(settings.density_g_cm3, None);
+
+        let auto = ameyanagi_suppression_exact("Fe2O3", "Fe", Edge::K, &energies, settings.clone())
+            .unwrap();
+        assert_eq!(auto.density_g_cm3, 5.24);
+        assert_eq!(auto.density_source, DensitySource::BuiltinTable);
+        assert_eq!(
+            crate::common::lookup_density(&XrayDb::new(), "SiO2").unwrap(),
+            (2.2, DensitySource::MaterialsTable)
+        );
+
+        let supplied = AmeyanagiSuppressionSettings {
+            density_g_cm3: Some(5.24),
+            ..settings.clone()
+        };
+        let supplied =
+            ameyanagi_suppression_exact("Fe2O3", "Fe", Edge::K, &energies, supplied).unwrap();
+        assert_eq!(supplied.density_source, DensitySource::Supplied);
+        assert_eq!(supplied.r_mean, auto.r_mean);
+
+        let element =
+            ameyanagi_suppression_exact("Fe", "Fe", Edge::K, &energies, settings.clone()).unwrap();
+        assert_eq!(element.density_source, DensitySource::Element);
+        assert!((element.density_g_cm3 - 7.87).abs() < 0.1);
+
+        let err =
+            ameyanagi_suppression_exact("Fe7O11", "Fe", Edge::K, &energies, settings).unwrap_err();
+        assert!(format!("{err}").contains("density"), "{err}");
+    }
+
+    #[test]
+    fn test_areal_density_matches_thickness() {
+        let energies = energies();
+        let direct = AmeyanagiSuppressionSettings::builder()
+            .density(5.24)
+            .thickness_cm(0.01)
+            .chi(0.2)
+            .build()
+            .unwrap();
+        let areal = AmeyanagiSuppressionSettings {
+            thickness_input: AmeyanagiThicknessInput::ArealDensityMgCm2(52.4),
+            ..direct.clone()
+        };
+
+        let direct =
+            ameyanagi_suppression_exact("Fe2O3", "Fe", Edge::K, &energies, direct).unwrap();
+        let areal = ameyanagi_suppression_exact("Fe2O3", "Fe", Edge::K, &energies, areal).unwrap();
+        assert!((areal.thickness_cm - 0.01).abs() < 1e-15);
+        assert!((areal.r_mean - direct.r_mean).abs() < 1e-12);
+
+        let bad = AmeyanagiThicknessInput::ArealDensityMgCm2(-1.0);
+        assert!(bad.resolve_cm(5.24, 0.0).is_err());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_matches_serial() {
+        let energies: Vec<f64> = (0..40_000).map(|i| 6900.0 + 0.05 * i as f64).collect();
+        let settings = AmeyanagiSuppressionSettings::builder()
+            .density(5.24)
+            .thickness_cm(0.01)
+            .chi(0.2)
+            .build()
+            .unwrap();
+        let model = fe2o3_model(&energies, &settings.sample());
+
+        let parallel = model.suppression_curve(0.2, model.mu_f).unwrap();
+        let serial: Vec<f64> = (0..energies.len())
+            .map(|i| model.suppression(i, 0.2).unwrap())
+            .collect();
+        assert_eq!(parallel, serial);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_settings_and_result_serde_roundtrip() {
+        let energies = energies();
+        let settings = AmeyanagiSuppressionSettings::builder()
+            .density(5.24)
+            .pellet(0.01, 1.3)
+            .binder(BinderSpec {
+                formula: "BN".to_string(),
+                mass_g: 0.1,
+                density_g_cm3: 2.1,
+            })
+            .cross_section(CrossSectionKind::Total)
+            .chi(0.2)
+            .build()
+            .unwrap();
+
+        let json = serde_json::to_string(&settings).unwrap();
+        let back: AmeyanagiSuppressionSettings = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+        assert_eq!(back.cross_section, CrossSectionKind::Total);
+
+        let original =
+            ameyanagi_suppression_exact("Fe2O3", "Fe", Edge::K, &energies, settings).unwrap();
+        let rerun = ameyanagi_suppression_exact("Fe2O3", "Fe", Edge::K, &energies, back).unwrap();
+        assert_eq!(rerun.thickness_cm, original.thickness_cm);
+        assert_eq!(rerun.suppression_factor, original.suppression_factor);
+
+        let json = serde_json::to_string(&original).unwrap();
+        let back: AmeyanagiSuppressionResult = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+        assert_eq!(back.suppression_factor, original.suppression_factor);
+    }
+
+    #[test]
+    fn test_max_thickness() {
+        let energies = energies();
+        let settings = AmeyanagiSuppressionSettings::builder()
+            .density(5.24)
+            .thickness_cm(0.01)
+            .chi(0.2)
+            .build()
+            .unwrap();
+
+        let rec = ameyanagi_max_thickness(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies,
+            settings.clone(),
+            0.95,
+            Some(1.3),
+        )
+        .unwrap();
+        let d = rec.max_thickness_cm.unwrap();
+        assert!(d > 0.0 && d < 1e-3, "d={d}");
+        assert!(rec.r_min >= 0.95);
+        let mass = rec.pellet_mass_g.unwrap();
+        assert!((mass - d * 5.24 * PI * 0.65f64.powi(2)).abs() < 1e-15);
+
+        // Slightly thicker samples violate the target.
+        let thicker = ameyanagi_thickness_scan(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies,
+            settings.clone(),
+            &[d * 1.01],
+            true,
+        )
+        .unwrap();
+        let db = XrayDb::new();
+        let e0 = SampleInfo::new(&db, "Fe2O3", "Fe", Edge::K)
+            .unwrap()
+            .edge_energy;
+        let r_exafs_min = energies
+            .iter()
+            .zip(thicker[0].suppression_factor.as_ref().unwrap())
+            .filter(|&(&e, _)| e > e0 + 30.0)
+            .map(|(_, &r)| r)
+            .fold(f64::INFINITY, f64::min);
+        assert!(r_exafs_min < 0.95);
+
+        // R → 1 in the thin limit, so even pure Fe meets 0.999 at a few tens
+        // of nm; a target of 1 can never be met.
+        let pure = AmeyanagiSuppressionSettings {
+            density_g_cm3: None,
+            ..settings.clone()
+        };
+        let rec =
+            ameyanagi_max_thickness("Fe", "Fe", Edge::K, &energies, pure.clone(), 0.999, None)
+                .unwrap();
+        assert!(rec.max_thickness_cm.unwrap() < 1e-5);
+        assert!(ameyanagi_max_thickness("Fe", "Fe", Edge::K, &energies, pure, 1.0, None).is_err());
+
+        // A dilute sample meets a loose target at any thickness.
+        let dilute = ameyanagi_max_thickness(
+            "Fe0.001Si0.999O2",
+            "Fe",
+            Edge::K,
+            &energies,
+            AmeyanagiSuppressionSettings {
+                density_g_cm3: Some(2.2),
+                ..settings
+            },
+            0.9,
+            None,
+        )
+        .unwrap();
+        assert!(dilute.max_thickness_cm.is_none());
+    }
+
+    #[test]
+    fn test_exit_angle_acceptance() {
+        let energies = energies();
+        let base = AmeyanagiSuppressionSettings::builder()
+            .density(5.24)
+            .angles_deg(45.0, 45.0)
+            .thickness_cm(0.01)
+            .chi(0.2)
+            .build()
+            .unwrap();
+        let run = |exit_angles: Option<Vec<(f64, f64)>>| {
+            ameyanagi_suppression_exact(
+                "Fe2O3",
+                "Fe",
+                Edge::K,
+                &energies,
+                AmeyanagiSuppressionSettings {
+                    exit_angles,
+                    ..base.clone()
+                },
+            )
+            .unwrap()
+        };
+
+        // One entry with weight 1 is the single-angle model.
+        let single = run(None);
+        let one = run(Some(vec![(base.theta_rad, 1.0)]));
+        assert_eq!(one.geometry_g, single.geometry_g);
+        assert_eq!(one.suppression_factor, single.suppression_factor);
+
+        // A broad acceptance lies between its extreme single angles.
+        let (lo, hi) = (20f64.to_radians(), 70f64.to_radians());
+        let at_lo = run(Some(vec![(lo, 1.0)]));
+        let at_hi = run(Some(vec![(hi, 1.0)]));
+        let thetas: Vec<(f64, f64)> = (0..=10)
+            .map(|j| {
+                let theta = lo + (hi - lo) * j as f64 / 10.0;
+                (theta, theta.cos())
+            })
+            .collect();
+        let broad = run(Some(thetas.clone()));
+        for i in 0..energies.len() {
+            let (a, b) = (at_lo.suppression_factor[i], at_hi.suppression_factor[i]);
+            let r = broad.suppression_factor[i];
+            assert!(
+                r >= a.min(b) - 1e-12 && r <= a.max(b) + 1e-12,
+                "R={r} outside [{a}, {b}] at index {i}"
+            );
+        }
+        assert!(broad.r_mean > at_lo.r_mean.min(at_hi.r_mean));
+        assert!(broad.r_mean < at_lo.r_mean.max(at_hi.r_mean));
+
+        // The χ → 0 limit matches a small χ over the same acceptance.
+        let sample = AmeyanagiSampleSettings {
+            exit_angles: Some(thetas),
+            ..base.sample()
+        };
+        let profile = ameyanagi_suppression_profile(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies,
+            sample.clone(),
+            &vec![0.0; energies.len()],
+        )
+        .unwrap();
+        let small = ameyanagi_suppression_profile(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies,
+            sample,
+            &vec![1e-7; energies.len()],
+        )
+        .unwrap();
+        for (a, b) in profile
+            .suppression_factor
+            .iter()
+            .zip(&small.suppression_factor)
+        {
+            assert!((a - b).abs() < 1e-5, "{a} vs {b}");
         }
 
         assert!(
-            max_abs_err < 1e-6,
-            "thick-limit mismatch too large: {max_abs_err}"
+            AmeyanagiSuppressionSettings::builder()
+                .thickness_cm(0.01)
+                .chi(0.2)
+                .exit_angles(vec![(0.5, 0.0)])
+                .build()
+                .is_err()
         );
     }
 
     #[test]
-    fn test_zero_chi_is_error() {
-        let e = ameyanagi_suppression_exact(
+    fn test_suppression_exact_with_sample_info() {
+        let energies = energies();
+        let settings = AmeyanagiSuppressionSettings::builder()
+            .thickness_cm(0.01)
+            .chi(0.2)
+            .build()
+            .unwrap();
+        let ctx = SelfAbsContext::new();
+        let info = SampleInfo::new(&ctx, "Fe2O3", "Fe", Edge::K).unwrap();
+        let direct =
+            ameyanagi_suppression_exact("Fe2O3", "Fe", Edge::K, &energies, settings.clone())
+                .unwrap();
+        let with_info =
+            ameyanagi_suppression_exact_with_sample_info(&ctx, &info, &energies, settings.clone())
+                .unwrap();
+        assert_eq!(direct.density_g_cm3, with_info.density_g_cm3);
+        assert_eq!(direct.suppression_factor, with_info.suppression_factor);
+
+        // Without a formula the density cannot be looked up.
+        let from_composition =
+            SampleInfo::from_composition(&ctx, info.composition().clone(), "Fe", Edge::K).unwrap();
+        assert!(from_composition.formula().is_none());
+        assert!(
+            ameyanagi_suppression_exact_with_sample_info(
+                &ctx,
+                &from_composition,
+                &energies,
+                settings.clone()
+            )
+            .is_err()
+        );
+        let supplied = ameyanagi_suppression_exact_with_sample_info(
+            &ctx,
+            &from_composition,
+            &energies,
+            AmeyanagiSuppressionSettings {
+                density_g_cm3: Some(direct.density_g_cm3),
+                ..settings
+            },
+        )
+        .unwrap();
+        assert_eq!(supplied.r_mean, direct.r_mean);
+    }
+
+    #[test]
+    fn test_layered_cap_only_attenuates() {
+        let energies = energies();
+        let film = LayeredSample::new(LayerSpec {
+            formula: "Fe2O3".to_string(),
+            density: 5.24,
+            thickness_um: 20.0,
+        });
+        let kapton = |thickness_um: f64| LayerSpec {
+            formula: "C22H10N2O5".to_string(),
+            density: 1.42,
+            thickness_um,
+        };
+        let settings = AmeyanagiSuppressionSettings::builder()
+            .angles_deg(30.0, 60.0)
+            .thickness_cm(1.0)
+            .chi(0.1)
+            .build()
+            .unwrap();
+        let single = ameyanagi_suppression_exact(
             "Fe2O3",
             "Fe",
-            "K",
-            &energies(),
+            Edge::K,
+            &energies,
             AmeyanagiSuppressionSettings {
-                density_g_cm3: 5.24,
-                phi_rad: std::f64::consts::FRAC_PI_4,
-                theta_rad: std::f64::consts::FRAC_PI_4,
-                thickness_input: AmeyanagiThicknessInput::ThicknessCm(0.01),
-                chi_assumed: 0.0,
+                density_g_cm3: Some(5.24),
+                thickness_input: AmeyanagiThicknessInput::ThicknessCm(20e-4),
+                ..settings.clone()
             },
         )
-        .unwrap_err();
-        assert!(format!("{e}").contains("chi"));
+        .unwrap();
+
+        let bare = ameyanagi_layered(&film, "Fe", Edge::K, &energies, settings.clone()).unwrap();
+        assert_eq!(
+            bare.suppression.suppression_factor,
+            single.suppression_factor
+        );
+        // The layered run builds its model once, as the exact run does.
+        let exact_ctx = SelfAbsContext::new();
+        exact_ctx
+            .ameyanagi(
+                "Fe2O3",
+                "Fe",
+                Edge::K,
+                &energies,
+                AmeyanagiSuppressionSettings {
+                    density_g_cm3: Some(5.24),
+                    thickness_input: AmeyanagiThicknessInput::ThicknessCm(20e-4),
+                    ..settings.clone()
+                },
+            )
+            .unwrap();
+        let layered_ctx = SelfAbsContext::new();
+        layered_ctx
+            .ameyanagi_layered(&film, "Fe", Edge::K, &energies, settings.clone())
+            .unwrap();
+        assert!(exact_ctx.mu_cache_stats().misses > 0);
+        assert_eq!(layered_ctx.mu_cache_stats(), exact_ctx.mu_cache_stats());
+        assert!(bare.incident_transmission.iter().all(|&t| t == 1.0));
+        assert_eq!(bare.fluorescence_transmission, 1.0);
+
+        let capped = ameyanagi_layered(
+            &film.clone().with_cap(kapton(7.5)),
+            "Fe",
+            Edge::K,
+            &energies,
+            settings.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            capped.suppression.suppression_factor,
+            single.suppression_factor
+        );
+        assert!(capped.fluorescence_transmission < 1.0);
+        // Kapton is more transparent at higher energy.
+        let t_in = &capped.incident_transmission;
+        assert!(t_in.windows(2).all(|w| w[1] > w[0]));
+        // μ̄_a, and with it the intensity, is zero below the edge.
+        assert!(bare.intensity.iter().sum::<f64>() > 0.0);
+        for ((c, b), t) in capped.intensity.iter().zip(&bare.intensity).zip(t_in) {
+            assert!(c < b || *b == 0.0);
+            let expected = b * t * capped.fluorescence_transmission;
+            assert!((c - expected).abs() <= 1e-12 * expected);
+        }
+
+        // The caps follow the selected μ backend.
+        let chantler = ameyanagi_layered(
+            &film.clone().with_cap(kapton(7.5)),
+            "Fe",
+            Edge::K,
+            &energies,
+            AmeyanagiSuppressionSettings {
+                mu_backend: MuBackend::Chantler,
+                ..settings.clone()
+            },
+        )
+        .unwrap();
+        assert_ne!(chantler.incident_transmission, capped.incident_transmission);
+        assert_ne!(
+            chantler.fluorescence_transmission,
+            capped.fluorescence_transmission
+        );
+
+        // Over a detector acceptance the cap reweights the exit angles.
+        let spread = AmeyanagiSuppressionSettings {
+            exit_angles: Some(vec![(10f64.to_radians(), 1.0), (80f64.to_radians(), 1.0)]),
+            ..settings
+        };
+        let bare = ameyanagi_layered(&film, "Fe", Edge::K, &energies, spread.clone()).unwrap();
+        let capped = ameyanagi_layered(
+            &film.clone().with_cap(kapton(7.5)),
+            "Fe",
+            Edge::K,
+            &energies,
+            spread.clone(),
+        )
+        .unwrap();
+        assert_ne!(
+            bare.suppression.suppression_factor,
+            capped.suppression.suppression_factor
+        );
+        for (b, c) in bare
+            .suppression
+            .suppression_factor
+            .iter()
+            .zip(&capped.suppression.suppression_factor)
+        {
+            assert!((b - c).abs() < 0.05, "{b} {c}");
+        }
+
+        // Entering through the substrate is left to booth_layered.
+        let through = LayeredSample {
+            incidence: LayerIncidence::ThroughSubstrate,
+            ..film
+        };
+        let err = ameyanagi_layered(&through, "Fe", Edge::K, &energies, spread)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("booth_layered"), "{err}");
     }
 }
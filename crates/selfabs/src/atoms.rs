@@ -7,14 +7,109 @@
 //! χ_corrected(k) = amplitude × χ_measured(k) × exp(σ²_net × k²)
 //! ```
 
-use xraydb::{CrossSectionKind, XrayDb};
+use std::collections::BTreeMap;
+
+use xraydb::CrossSectionKind;
 
 use crate::common::{
-    SampleInfo, SelfAbsError, energies_to_k, fit_ln_vs_x, weighted_mu_background,
+    Edge, ElementRef, FluorescenceGeometry, MuBackend, PreEdgeBaseline, PreEdgeMethod,
+    PreEdgeOptions, SampleInfo, SampleSpec, SelfAbsError, SelfAbsWarning, energies_to_k,
+    fit_ln_vs_x, fit_ln_vs_x_weighted, parse_composition, resolve_e0, sample_warnings,
+    validate_energy_grid, weighted_mu_absorber_edge, weighted_mu_background,
     weighted_mu_total_single,
 };
+use crate::context::SelfAbsContext;
+use crate::correction::{check_chi_into, check_chi_prefix};
+
+/// Default near-edge exclusion (eV) for L edges, whose white lines distort
+/// the log-linear fits.
+const L_EDGE_XANES_EXCLUSION_EV: f64 = 20.0;
+
+/// Pre-edge convention for the absorber μ in the Atoms correction ratio.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AtomsPreEdge {
+    /// Use the full absorber μ (Athena's convention).
+    #[default]
+    None,
+    /// Subtract the absorber μ at E₀ − 200 eV. This point is fixed and does
+    /// not follow [`AtomsOptions::pre_edge_window`].
+    Constant,
+    /// Subtract the pre-edge trendline fitted over the configured
+    /// [`AtomsOptions::pre_edge_window`]. Too few points in the window fall
+    /// back to the μ at its start.
+    Trendline,
+}
+
+/// Least-squares weighting of the Atoms log-linear fits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FitWeight {
+    /// Every fitted point counts equally.
+    #[default]
+    Uniform,
+    /// Weight each point by kʷ.
+    KPower(u8),
+}
+
+/// Options for the Atoms correction.
+///
+/// The defaults reproduce the plain [`atoms`] call.
+#[derive(Debug, Clone)]
+pub struct AtomsOptions {
+    /// I₀ chamber fill gas as `(element or molecule, molar fraction)` pairs,
+    /// e.g. `[("He", 0.7), ("N2", 0.3)]`. Fractions are normalized.
+    pub i0_gas: Vec<(String, f64)>,
+    /// Lower k bound (Å⁻¹) of the points entering the log-linear fits.
+    pub k_min: f64,
+    /// Optional upper k bound (Å⁻¹) of the points entering the fits.
+    pub k_max: Option<f64>,
+    /// Populate [`AtomsResult::diagnostics`] with the fitted curves.
+    pub diagnostics: bool,
+    /// Pre-edge convention for the absorber μ in the correction ratio.
+    /// The McMaster σ²_norm fit always uses the full absorber μ.
+    pub pre_edge: AtomsPreEdge,
+    /// Measurement geometry; μ_f is scaled by g = sin φ / sin θ in both
+    /// terms of the correction ratio. `None` is the 45°/45° default (g = 1).
+    pub geometry: Option<FluorescenceGeometry>,
+    /// Points with E₀ < E ≤ E₀ + `xanes_exclusion_ev` are left out of all
+    /// three fits. `None` picks 20 eV for L edges and 0 otherwise.
+    pub xanes_exclusion_ev: Option<f64>,
+    /// Weighting of the three log-linear fits.
+    pub fit_weight: FitWeight,
+    /// Optional Iₜ chamber fill gas, in the same form as `i0_gas`. When set,
+    /// a fourth σ² term is fitted and added to the net σ².
+    pub it_gas: Option<Vec<(String, f64)>>,
+    /// E₀ (eV) of the k grid and the XANES exclusion instead of the
+    /// tabulated edge energy, e.g. a calibrated E₀.
+    pub e0_override: Option<f64>,
+    /// Trendline window for [`AtomsPreEdge::Trendline`].
+    pub pre_edge_window: PreEdgeOptions,
+    /// Cross-section tables of every μ, the chamber gases included.
+    pub mu_backend: MuBackend,
+}
+
+impl Default for AtomsOptions {
+    fn default() -> Self {
+        Self {
+            i0_gas: vec![("N2".to_string(), 1.0)],
+            k_min: 0.0,
+            k_max: None,
+            diagnostics: false,
+            pre_edge: AtomsPreEdge::None,
+            geometry: None,
+            xanes_exclusion_ev: None,
+            fit_weight: FitWeight::Uniform,
+            it_gas: None,
+            e0_override: None,
+            pre_edge_window: PreEdgeOptions::default(),
+            mu_backend: MuBackend::default(),
+        }
+    }
+}
 
 /// Result of the Atoms correction calculation.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AtomsResult {
     /// Energy grid used (eV).
     pub energies: Vec<f64>,
@@ -28,14 +123,76 @@ pub struct AtomsResult {
     pub sigma_squared_self: f64,
     /// Normalization (McMaster) σ² (Å²).
     pub sigma_squared_norm: f64,
-    /// I₀ fill gas σ² (Å²) — assumes N₂ gas.
+    /// I₀ fill gas σ² (Å²) — N₂ unless [`AtomsOptions::i0_gas`] says otherwise.
     pub sigma_squared_i0: f64,
-    /// Net σ² = self + norm + i0 (Å²).
+    /// Iₜ fill gas σ² (Å²), if [`AtomsOptions::it_gas`] was given.
+    pub sigma_squared_it: Option<f64>,
+    /// Net σ² = self + norm + i0 (+ it) (Å²).
     pub sigma_squared_net: f64,
-    /// Edge energy (eV).
+    /// E₀ (eV) of the k grid.
     pub edge_energy: f64,
     /// Fluorescence energy (eV).
     pub fluorescence_energy: f64,
+    /// k range (Å⁻¹) of the fits; the upper end is the grid maximum unless
+    /// [`AtomsOptions::k_max`] is set.
+    pub fit_k_range: (f64, f64),
+    /// Number of grid points that entered the fits.
+    pub n_fit_points: usize,
+    /// Fit diagnostics, if requested via [`AtomsOptions::diagnostics`].
+    pub diagnostics: Option<AtomsFitDiagnostics>,
+    /// Pre-edge convention used for the absorber μ.
+    pub pre_edge: AtomsPreEdge,
+    /// Fit or fallback for [`AtomsPreEdge::Trendline`]; `None` otherwise.
+    pub pre_edge_baseline: Option<PreEdgeBaseline>,
+    /// Near-edge exclusion (eV above E₀) applied to the fits.
+    pub xanes_exclusion_ev: f64,
+    /// Other absorption edges inside the grid. An extra absorber edge makes
+    /// the σ²_norm fit meaningless.
+    pub warnings: Vec<SelfAbsWarning>,
+}
+
+/// Per-component correction curves on the Atoms k grid, see
+/// [`AtomsResult::component_curves`].
+#[derive(Debug, Clone)]
+pub struct AtomsComponentCurves {
+    /// `amplitude × exp(σ²_self k²)`.
+    pub self_correction: Vec<f64>,
+    /// `exp(σ²_norm k²)`.
+    pub norm_correction: Vec<f64>,
+    /// `exp(σ²_i0 k²)`.
+    pub i0_correction: Vec<f64>,
+    /// `exp(σ²_it k²)`; all ones without an Iₜ term.
+    pub it_correction: Vec<f64>,
+}
+
+/// One `ln(y) = intercept + slope × k²` fit of the Atoms model.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LogLinearFit {
+    /// Fitted intercept of ln(y).
+    pub intercept: f64,
+    /// Fitted slope of ln(y) against k² (Å²).
+    pub slope: f64,
+    /// Fitted model `exp(intercept + slope × k²)` on the k grid.
+    pub fitted: Vec<f64>,
+    /// `ln(y) − (intercept + slope × k²)` at fitted points; `None` elsewhere.
+    pub residuals: Vec<Option<f64>>,
+    /// Coefficient of determination of the fit in ln space.
+    pub r_squared: f64,
+}
+
+/// Diagnostics for the three Atoms fits.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AtomsFitDiagnostics {
+    /// Self-absorption fit of ln σ(E).
+    pub self_fit: LogLinearFit,
+    /// McMaster normalization fit of ln μ_absorber(E).
+    pub norm_fit: LogLinearFit,
+    /// I₀ fill-gas fit of ln μ_gas(E).
+    pub i0_fit: LogLinearFit,
+    /// Iₜ fill-gas fit, if requested.
+    pub it_fit: Option<LogLinearFit>,
 }
 
 impl AtomsResult {
@@ -44,15 +201,83 @@ impl AtomsResult {
     /// ```text
     /// χ_corrected(k) = amplitude × χ(k) × exp(σ²_net × k²)
     /// ```
+    ///
+    /// `chi` must be on this result's k grid; use
+    /// [`correct_chi_on_k`](Self::correct_chi_on_k) for any other grid.
     pub fn correct_chi(&self, chi: &[f64]) -> Vec<f64> {
+        debug_assert_eq!(
+            chi.len(),
+            self.k.len(),
+            "chi must be sampled on the Atoms k grid"
+        );
         chi.iter()
-            .enumerate()
-            .map(|(i, &c)| {
-                let ki = self.k.get(i).copied().unwrap_or(0.0);
-                self.amplitude * c * (self.sigma_squared_net * ki * ki).exp()
-            })
+            .zip(&self.k)
+            .map(|(&c, &ki)| self.correct_single(ki, c))
             .collect()
     }
+
+    /// Write [`correct_chi`](Self::correct_chi) of the first `chi.len()`
+    /// points of the k grid into `out` without allocating, returning the
+    /// number of points written. `out` may be longer than `chi`.
+    pub fn correct_chi_into(&self, chi: &[f64], out: &mut [f64]) -> Result<usize, SelfAbsError> {
+        let n = check_chi_into(chi, out, self.k.len())?;
+        for (o, (&c, &ki)) in out.iter_mut().zip(chi.iter().zip(&self.k)) {
+            *o = self.correct_single(ki, c);
+        }
+        Ok(n)
+    }
+
+    /// Lazy form of [`correct_chi_into`](Self::correct_chi_into), yielding
+    /// one corrected point per point of `chi`.
+    pub fn corrected_iter<'a>(
+        &'a self,
+        chi: &'a [f64],
+    ) -> Result<impl Iterator<Item = f64> + 'a, SelfAbsError> {
+        check_chi_prefix(chi, self.k.len())?;
+        Ok(chi
+            .iter()
+            .zip(&self.k)
+            .map(|(&c, &ki)| self.correct_single(ki, c)))
+    }
+
+    /// Apply the correction to χ sampled on an arbitrary k grid (Å⁻¹), e.g.
+    /// Athena's uniform k grid. The correction is analytic in k, so no
+    /// interpolation is needed.
+    pub fn correct_chi_on_k(&self, k: &[f64], chi: &[f64]) -> Result<Vec<f64>, SelfAbsError> {
+        if k.len() != chi.len() {
+            return Err(SelfAbsError::InsufficientData(format!(
+                "k has {} points but chi has {}",
+                k.len(),
+                chi.len()
+            )));
+        }
+        Ok(k.iter()
+            .zip(chi)
+            .map(|(&ki, &c)| self.correct_single(ki, c))
+            .collect())
+    }
+
+    /// Split the correction applied by [`correct_chi`](Self::correct_chi)
+    /// into its self-absorption, normalization, I₀ and Iₜ factors. Their
+    /// product is the total correction for a unit χ.
+    pub fn component_curves(&self) -> AtomsComponentCurves {
+        let curve = |scale: f64, sigma_squared: f64| -> Vec<f64> {
+            self.k
+                .iter()
+                .map(|&ki| scale * (sigma_squared * ki * ki).exp())
+                .collect()
+        };
+        AtomsComponentCurves {
+            self_correction: curve(self.amplitude, self.sigma_squared_self),
+            norm_correction: curve(1.0, self.sigma_squared_norm),
+            i0_correction: curve(1.0, self.sigma_squared_i0),
+            it_correction: curve(1.0, self.sigma_squared_it.unwrap_or(0.0)),
+        }
+    }
+
+    fn correct_single(&self, k: f64, chi: f64) -> f64 {
+        self.amplitude * chi * (self.sigma_squared_net * k * k).exp()
+    }
 }
 
 /// Compute the Atoms self-absorption correction.
@@ -67,33 +292,151 @@ impl AtomsResult {
 /// - `energies` — energy grid in eV
 pub fn atoms(
     formula: &str,
-    central_element: &str,
-    edge: &str,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
     energies: &[f64],
 ) -> Result<AtomsResult, SelfAbsError> {
-    let db = XrayDb::new();
-    let info = SampleInfo::new(&db, formula, central_element, edge)?;
+    atoms_with_options(
+        formula,
+        central_element,
+        edge,
+        energies,
+        &AtomsOptions::default(),
+    )
+}
 
-    let k = energies_to_k(energies, info.edge_energy);
+/// [`atoms`] with explicit [`AtomsOptions`].
+pub fn atoms_with_options(
+    formula: &str,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies: &[f64],
+    options: &AtomsOptions,
+) -> Result<AtomsResult, SelfAbsError> {
+    SelfAbsContext::new().atoms(formula, central_element, edge, energies, options)
+}
+
+/// [`atoms_with_options`] for an explicit element → stoichiometric count
+/// map instead of a formula string, e.g. `{"Fe": 0.02, "Zn": 0.98, "O": 1}`.
+pub fn atoms_from_composition(
+    composition: &BTreeMap<String, f64>,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies: &[f64],
+    options: &AtomsOptions,
+) -> Result<AtomsResult, SelfAbsError> {
+    let ctx = SelfAbsContext::new();
+    let info = SampleInfo::from_composition(&ctx, composition.clone(), central_element, edge)?;
+    atoms_with_sample_info(&ctx, &info, energies, options)
+}
+
+/// [`atoms_with_options`] for a prebuilt [`SampleInfo`].
+pub fn atoms_with_sample_info(
+    ctx: &SelfAbsContext,
+    info: &SampleInfo,
+    energies: &[f64],
+    options: &AtomsOptions,
+) -> Result<AtomsResult, SelfAbsError> {
+    let e0 = resolve_e0(info, options.e0_override)?;
+    let grid = validate_energy_grid(energies, e0)?;
+    let k = energies_to_k(energies, e0);
+
+    let xanes_exclusion_ev = options.xanes_exclusion_ev.unwrap_or(
+        if matches!(info.edge, Edge::L1 | Edge::L2 | Edge::L3) {
+            L_EDGE_XANES_EXCLUSION_EV
+        } else {
+            0.0
+        },
+    );
+    if !(xanes_exclusion_ev.is_finite() && xanes_exclusion_ev >= 0.0) {
+        return Err(SelfAbsError::InsufficientData(format!(
+            "XANES exclusion must be a non-negative energy, got {xanes_exclusion_ev} eV"
+        )));
+    }
+    let e_fit_min = e0 + xanes_exclusion_ev;
+
+    // All three fits are linear in k². Points outside the fit window get
+    // x = 0, which the fits skip.
+    let k_max = options.k_max.unwrap_or(f64::INFINITY);
+    let k2_fit: Vec<f64> = k
+        .iter()
+        .zip(energies)
+        .map(|(&ki, &e)| {
+            if ki >= options.k_min && ki <= k_max && e > e_fit_min {
+                ki * ki
+            } else {
+                0.0
+            }
+        })
+        .collect();
+    let n_fit_points = k2_fit.iter().filter(|&&x| x > 0.0).count();
+    if n_fit_points < 2 {
+        return Err(SelfAbsError::InsufficientData(format!(
+            "Atoms fit window k = [{}, {k_max}] Å⁻¹ contains {n_fit_points} grid points; need at least 2",
+            options.k_min
+        )));
+    }
+
+    let fit = |y: &[f64]| match options.fit_weight {
+        FitWeight::Uniform => fit_ln_vs_x(&k2_fit, y),
+        FitWeight::KPower(w) => {
+            let weights: Vec<f64> = k.iter().map(|&ki| ki.powi(i32::from(w))).collect();
+            fit_ln_vs_x_weighted(&k2_fit, y, &weights)
+        }
+    };
 
     // --- Self-absorption correction ---
-    // σ(E) = (μ_f + μ_total(E)) / (μ_f + μ_background(E))
+    // σ(E) = (g μ_f + μ_total(E)) / (g μ_f + μ_background(E))
     // where μ_f = total absorption at fluorescence energy
-    let mu_f = weighted_mu_total_single(&db, &info.composition, info.fluor_energy)?;
-    let mu_bg = weighted_mu_background(&db, &info, energies)?;
+    let g = options.geometry.unwrap_or_default().effective_ratio()?;
+    let backend = options.mu_backend;
+    let mu_f = g * weighted_mu_total_single(
+        ctx,
+        &info.composition,
+        info.strongest_line_energy(),
+        CrossSectionKind::Photo,
+        backend,
+    )?;
+    let mu_bg = weighted_mu_background(ctx, info, energies, backend)?;
 
     // Full mu of central element (no pre-edge subtraction for the Atoms formula)
     let mu_central = {
-        let mu = db.mu_elam(&info.central_symbol, energies, CrossSectionKind::Photo)?;
+        let mu = ctx.mu(
+            &info.central_symbol,
+            energies,
+            CrossSectionKind::Photo,
+            backend,
+        )?;
         mu.iter()
             .map(|&m| info.central_count * m)
             .collect::<Vec<_>>()
     };
 
+    // Absorber μ entering the correction ratio.
+    let (mu_edge, pre_edge_baseline) = match options.pre_edge {
+        AtomsPreEdge::None => (mu_central.clone(), None),
+        AtomsPreEdge::Constant => weighted_mu_absorber_edge(
+            ctx,
+            info,
+            energies,
+            PreEdgeMethod::Constant,
+            &options.pre_edge_window,
+            backend,
+        )?,
+        AtomsPreEdge::Trendline => weighted_mu_absorber_edge(
+            ctx,
+            info,
+            energies,
+            PreEdgeMethod::Trendline,
+            &options.pre_edge_window,
+            backend,
+        )?,
+    };
+
     let n = energies.len();
     let mut correction = Vec::with_capacity(n);
     for i in 0..n {
-        let mu_total_i = mu_central[i] + mu_bg[i];
+        let mu_total_i = mu_edge[i] + mu_bg[i];
         let denom = mu_f + mu_bg[i];
         let sigma = if denom > 0.0 {
             (mu_f + mu_total_i) / denom
@@ -103,8 +446,8 @@ pub fn atoms(
         correction.push(sigma);
     }
 
-    // Fit ln(σ) vs k → amplitude = exp(intercept), σ²_self = -slope/2
-    let (intercept_self, slope_self) = fit_ln_vs_x(&k, &correction);
+    // Fit ln(σ) vs k² → amplitude = exp(intercept), σ²_self = -slope/2
+    let (intercept_self, slope_self) = fit(&correction);
     let amplitude = intercept_self.exp();
     let sigma_squared_self = -slope_self / 2.0;
 
@@ -113,22 +456,50 @@ pub fn atoms(
     let mu_central_above: Vec<f64> = (0..n)
         .map(|i| if k[i] > 0.0 { mu_central[i] } else { 0.0 })
         .collect();
-    let (_, slope_norm) = fit_ln_vs_x(&k, &mu_central_above);
+    let (intercept_norm, slope_norm) = fit(&mu_central_above);
     let sigma_squared_norm = -slope_norm / 2.0;
 
     // --- I₀ fill gas correction ---
-    // Assumes 100% N₂ in the ionization chamber
-    let mu_n2: Vec<f64> = {
-        let mu = db.mu_elam("N", energies, CrossSectionKind::Photo)?;
-        mu.iter().map(|&m| 2.0 * m).collect() // N₂
-    };
-    let mu_n2_above: Vec<f64> = (0..n)
-        .map(|i| if k[i] > 0.0 { mu_n2[i] } else { 0.0 })
+    let mu_gas = gas_mu(ctx, &options.i0_gas, energies, backend)?;
+    let mu_gas_above: Vec<f64> = (0..n)
+        .map(|i| if k[i] > 0.0 { mu_gas[i] } else { 0.0 })
         .collect();
-    let (_, slope_i0) = fit_ln_vs_x(&k, &mu_n2_above);
+    let (intercept_i0, slope_i0) = fit(&mu_gas_above);
     let sigma_squared_i0 = -slope_i0 / 2.0;
 
-    let sigma_squared_net = sigma_squared_self + sigma_squared_norm + sigma_squared_i0;
+    // --- Optional Iₜ fill gas correction ---
+    let it = match &options.it_gas {
+        Some(gas) => {
+            let mu_it = gas_mu(ctx, gas, energies, backend)?;
+            let mu_it_above: Vec<f64> = (0..n)
+                .map(|i| if k[i] > 0.0 { mu_it[i] } else { 0.0 })
+                .collect();
+            let (intercept, slope) = fit(&mu_it_above);
+            Some((mu_it_above, intercept, slope))
+        }
+        None => None,
+    };
+    let sigma_squared_it = it.as_ref().map(|&(_, _, slope)| -slope / 2.0);
+
+    let sigma_squared_net = sigma_squared_self
+        + sigma_squared_norm
+        + sigma_squared_i0
+        + sigma_squared_it.unwrap_or(0.0);
+
+    let fit_k_range = (
+        options.k_min,
+        options
+            .k_max
+            .unwrap_or_else(|| k.iter().copied().fold(0.0, f64::max)),
+    );
+    let diagnostics = options.diagnostics.then(|| AtomsFitDiagnostics {
+        self_fit: log_linear_fit(&k, &k2_fit, &correction, intercept_self, slope_self),
+        norm_fit: log_linear_fit(&k, &k2_fit, &mu_central_above, intercept_norm, slope_norm),
+        i0_fit: log_linear_fit(&k, &k2_fit, &mu_gas_above, intercept_i0, slope_i0),
+        it_fit: it
+            .as_ref()
+            .map(|(y, intercept, slope)| log_linear_fit(&k, &k2_fit, y, *intercept, *slope)),
+    });
 
     Ok(AtomsResult {
         energies: energies.to_vec(),
@@ -138,20 +509,118 @@ pub fn atoms(
         sigma_squared_self,
         sigma_squared_norm,
         sigma_squared_i0,
+        sigma_squared_it,
         sigma_squared_net,
-        edge_energy: info.edge_energy,
-        fluorescence_energy: info.fluor_energy,
+        edge_energy: e0,
+        fluorescence_energy: info.strongest_line_energy(),
+        fit_k_range,
+        n_fit_points,
+        diagnostics,
+        pre_edge: options.pre_edge,
+        pre_edge_baseline,
+        xanes_exclusion_ev,
+        warnings: grid
+            .warning()
+            .into_iter()
+            .chain(sample_warnings(ctx, info, energies, options.mu_backend)?)
+            .collect(),
     })
 }
 
+/// [`atoms_with_options`] for any [`SampleSpec`].
+pub fn atoms_spec(
+    spec: &SampleSpec,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies: &[f64],
+    options: &AtomsOptions,
+) -> Result<AtomsResult, SelfAbsError> {
+    let ctx = SelfAbsContext::new();
+    let info = SampleInfo::from_spec(&ctx, spec, central_element, edge)?;
+    atoms_with_sample_info(&ctx, &info, energies, options)
+}
+
+/// Evaluate a fit from [`fit_ln_vs_x`] on the k grid. `x` is the masked k²
+/// that entered the fit.
+fn log_linear_fit(k: &[f64], x: &[f64], y: &[f64], intercept: f64, slope: f64) -> LogLinearFit {
+    let fitted = k
+        .iter()
+        .map(|&ki| (intercept + slope * ki * ki).exp())
+        .collect();
+    let used: Vec<(f64, f64)> = x
+        .iter()
+        .zip(y)
+        .filter(|&(&xi, &yi)| xi > 0.0 && yi > 0.0)
+        .map(|(&xi, &yi)| (xi, yi.ln()))
+        .collect();
+    let residuals = x
+        .iter()
+        .zip(y)
+        .map(|(&xi, &yi)| (xi > 0.0 && yi > 0.0).then(|| yi.ln() - (intercept + slope * xi)))
+        .collect();
+
+    let mean = used.iter().map(|&(_, ly)| ly).sum::<f64>() / used.len() as f64;
+    let ss_tot: f64 = used.iter().map(|&(_, ly)| (ly - mean).powi(2)).sum();
+    let ss_res: f64 = used
+        .iter()
+        .map(|&(xi, ly)| (ly - intercept - slope * xi).powi(2))
+        .sum();
+    // A constant ln(y) is fitted exactly by a flat line.
+    let r_squared = if ss_tot > 0.0 {
+        1.0 - ss_res / ss_tot
+    } else {
+        1.0
+    };
+
+    LogLinearFit {
+        intercept,
+        slope,
+        fitted,
+        residuals,
+        r_squared,
+    }
+}
+
+/// Photoabsorption of a gas mixture, proportional to the cross-section per
+/// molecule averaged over the normalized molar fractions.
+fn gas_mu(
+    ctx: &SelfAbsContext,
+    gas: &[(String, f64)],
+    energies: &[f64],
+    backend: MuBackend,
+) -> Result<Vec<f64>, SelfAbsError> {
+    let valid = gas.iter().all(|(_, f)| f.is_finite() && *f >= 0.0);
+    let f_sum: f64 = gas.iter().map(|(_, f)| f).sum();
+    if gas.is_empty() || !valid || f_sum <= 0.0 {
+        return Err(SelfAbsError::InsufficientData(
+            "i0_gas needs non-negative molar fractions with a positive sum".to_string(),
+        ));
+    }
+
+    let mut total = vec![0.0f64; energies.len()];
+    for (species, fraction) in gas {
+        let weight = fraction / f_sum;
+        for (sym, count) in parse_composition(species)? {
+            let scale = weight * count * ctx.molar_mass(&sym)?;
+            let mu = ctx.mu(&sym, energies, CrossSectionKind::Photo, backend)?;
+            for (t, m) in total.iter_mut().zip(mu) {
+                *t += scale * m;
+            }
+        }
+    }
+    Ok(total)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::ETOK;
+    use xraydb::XrayDb;
 
     #[test]
     fn test_atoms_fe2o3() {
         let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
-        let result = atoms("Fe2O3", "Fe", "K", &energies).unwrap();
+        let result = atoms("Fe2O3", "Fe", Edge::K, &energies).unwrap();
 
         assert!(result.amplitude > 1.0, "amplitude={}", result.amplitude);
         assert_eq!(result.correction.len(), energies.len());
@@ -161,7 +630,7 @@ mod tests {
     #[test]
     fn test_atoms_dilute() {
         let energies: Vec<f64> = (7100..=7500).step_by(10).map(|e| e as f64).collect();
-        let result = atoms("Fe0.001Si0.999O2", "Fe", "K", &energies).unwrap();
+        let result = atoms("Fe0.001Si0.999O2", "Fe", Edge::K, &energies).unwrap();
 
         // Dilute: amplitude close to 1, sigma² close to 0
         assert!(
@@ -174,7 +643,7 @@ mod tests {
     #[test]
     fn test_atoms_correction_components() {
         let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
-        let result = atoms("Fe2O3", "Fe", "K", &energies).unwrap();
+        let result = atoms("Fe2O3", "Fe", Edge::K, &energies).unwrap();
 
         // Net σ² should be the sum of components
         let expected =
@@ -187,10 +656,409 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_atoms_i0_gas() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        let with_gas = |gas: &[(&str, f64)]| {
+            let options = AtomsOptions {
+                i0_gas: gas.iter().map(|&(g, f)| (g.to_string(), f)).collect(),
+                ..Default::default()
+            };
+            atoms_with_options("Fe2O3", "Fe", Edge::K, &energies, &options)
+        };
+
+        let default = atoms("Fe2O3", "Fe", Edge::K, &energies).unwrap();
+        let n2 = with_gas(&[("N2", 2.0)]).unwrap();
+        assert!((default.sigma_squared_i0 - n2.sigma_squared_i0).abs() < 1e-15);
+
+        let he = with_gas(&[("He", 1.0)]).unwrap();
+        let ar = with_gas(&[("Ar", 1.0)]).unwrap();
+        let mix = with_gas(&[("He", 0.5), ("N2", 0.5)]).unwrap();
+        assert!(he.sigma_squared_i0 != n2.sigma_squared_i0);
+        assert!(ar.sigma_squared_i0 != n2.sigma_squared_i0);
+        let (lo, hi) = (
+            he.sigma_squared_i0.min(n2.sigma_squared_i0),
+            he.sigma_squared_i0.max(n2.sigma_squared_i0),
+        );
+        assert!(mix.sigma_squared_i0 >= lo && mix.sigma_squared_i0 <= hi);
+        // The self-absorption terms do not depend on the I₀ gas.
+        assert!((he.sigma_squared_self - n2.sigma_squared_self).abs() < 1e-12);
+
+        assert!(with_gas(&[]).is_err());
+        assert!(with_gas(&[("He", -1.0), ("N2", 2.0)]).is_err());
+    }
+
+    #[test]
+    fn test_atoms_k_window() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        let full = atoms("Fe2O3", "Fe", Edge::K, &energies).unwrap();
+        let above = full.k.iter().filter(|&&k| k > 0.0).count();
+        assert_eq!(full.n_fit_points, above);
+        let k_top = full.k.iter().copied().fold(0.0, f64::max);
+        assert_eq!(full.fit_k_range, (0.0, k_top));
+
+        let options = AtomsOptions {
+            k_min: 6.0,
+            ..Default::default()
+        };
+        let windowed = atoms_with_options("Fe2O3", "Fe", Edge::K, &energies, &options).unwrap();
+        let expected = full.k.iter().filter(|&&k| k >= 6.0).count();
+        assert_eq!(windowed.n_fit_points, expected);
+        assert!(
+            (windowed.amplitude - full.amplitude).abs() > 1e-3,
+            "windowed={} full={}",
+            windowed.amplitude,
+            full.amplitude
+        );
+
+        let empty = AtomsOptions {
+            k_min: 30.0,
+            ..Default::default()
+        };
+        assert!(matches!(
+            atoms_with_options("Fe2O3", "Fe", Edge::K, &energies, &empty),
+            Err(SelfAbsError::InsufficientData(_))
+        ));
+    }
+
+    #[test]
+    fn test_atoms_norm_sigma_squared_matches_power_law() {
+        // Above the edge μ ∝ E^-p. With E = E₀ + k²/ETOK,
+        // ln μ ≈ const − p·k²/(ETOK·E₀), so σ²_norm ≈ p/(2·ETOK·E₀).
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        let result = atoms("Fe2O3", "Fe", Edge::K, &energies).unwrap();
+
+        let (e1, e2) = (result.edge_energy + 50.0, 8000.0);
+        let mu = XrayDb::new()
+            .mu_elam("Fe", &[e1, e2], CrossSectionKind::Photo)
+            .unwrap();
+        let p = -(mu[1] / mu[0]).ln() / (e2 / e1).ln();
+        let expected = p / (2.0 * ETOK * result.edge_energy);
+        let rel = (result.sigma_squared_norm - expected).abs() / expected;
+        assert!(
+            rel < 0.1,
+            "σ²_norm={} expected≈{expected} (p={p})",
+            result.sigma_squared_norm
+        );
+    }
+
+    #[test]
+    fn test_atoms_fit_diagnostics() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        assert!(
+            atoms("Fe2O3", "Fe", Edge::K, &energies)
+                .unwrap()
+                .diagnostics
+                .is_none()
+        );
+
+        let options = AtomsOptions {
+            diagnostics: true,
+            ..Default::default()
+        };
+        let result = atoms_with_options("Fe2O3", "Fe", Edge::K, &energies, &options).unwrap();
+        let diag = result.diagnostics.as_ref().unwrap();
+        for fit in [&diag.self_fit, &diag.norm_fit, &diag.i0_fit] {
+            assert!(fit.r_squared > 0.95, "R²={}", fit.r_squared);
+            assert_eq!(fit.fitted.len(), energies.len());
+            assert_eq!(fit.residuals.len(), energies.len());
+        }
+
+        for (i, &ki) in result.k.iter().enumerate() {
+            let expected = result.amplitude * (-2.0 * result.sigma_squared_self * ki * ki).exp();
+            let fitted = diag.self_fit.fitted[i];
+            assert!(
+                ((fitted - expected) / expected).abs() < 1e-12,
+                "k={ki}: {fitted} vs {expected}"
+            );
+            assert_eq!(diag.self_fit.residuals[i].is_none(), ki <= 0.0);
+        }
+    }
+
+    #[test]
+    fn test_atoms_correct_chi_on_k() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        let result = atoms("Fe2O3", "Fe", Edge::K, &energies).unwrap();
+
+        let chi: Vec<f64> = result.k.iter().map(|&k| 0.1 * (2.0 * k).sin()).collect();
+        let on_grid = result.correct_chi(&chi);
+        let explicit = result.correct_chi_on_k(&result.k, &chi).unwrap();
+        assert_eq!(on_grid, explicit);
+
+        let k_uniform: Vec<f64> = (0..=300).map(|i| 0.05 * i as f64).collect();
+        let chi_uniform: Vec<f64> = k_uniform.iter().map(|&k| 0.1 * (2.0 * k).sin()).collect();
+        let corrected = result.correct_chi_on_k(&k_uniform, &chi_uniform).unwrap();
+        for ((&k, &c), &corr) in k_uniform.iter().zip(&chi_uniform).zip(&corrected) {
+            let expected = result.amplitude * c * (result.sigma_squared_net * k * k).exp();
+            assert_eq!(corr, expected);
+        }
+
+        assert!(
+            result
+                .correct_chi_on_k(&k_uniform, &chi_uniform[1..])
+                .is_err()
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Atoms k grid")]
+    fn test_atoms_correct_chi_rejects_foreign_grid() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        let result = atoms("Fe2O3", "Fe", Edge::K, &energies).unwrap();
+        result.correct_chi(&[0.1; 10]);
+    }
+
+    #[test]
+    fn test_atoms_pre_edge_subtraction() {
+        let energies: Vec<f64> = (6900..=8000).step_by(5).map(|e| e as f64).collect();
+        let full = atoms("Fe", "Fe", Edge::K, &energies).unwrap();
+        assert_eq!(full.pre_edge, AtomsPreEdge::None);
+
+        for pre_edge in [AtomsPreEdge::Constant, AtomsPreEdge::Trendline] {
+            let options = AtomsOptions {
+                pre_edge,
+                ..Default::default()
+            };
+            let sub = atoms_with_options("Fe", "Fe", Edge::K, &energies, &options).unwrap();
+            assert_eq!(sub.pre_edge, pre_edge);
+            let ratio = sub.amplitude / full.amplitude;
+            assert!(ratio < 1.0 && ratio > 0.8, "{pre_edge:?}: ratio={ratio}");
+        }
+    }
+
+    #[test]
+    fn test_atoms_geometry() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        let at_exit = |theta: f64| {
+            let options = AtomsOptions {
+                geometry: Some(FluorescenceGeometry {
+                    theta_incident_deg: 45.0,
+                    theta_fluorescence_deg: theta,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            atoms_with_options("Fe2O3", "Fe", Edge::K, &energies, &options).unwrap()
+        };
+
+        let default = atoms("Fe2O3", "Fe", Edge::K, &energies).unwrap();
+        let at_45 = at_exit(45.0);
+        assert!((at_45.amplitude - default.amplitude).abs() < 1e-12);
+
+        // The amplitude shrinks, not grows, as the exit becomes grazing. With
+        // g = sin φ / sin θ, a glancing exit makes g μ_f dominate both terms
+        // of (g μ_f + μ_T)/(g μ_f + μ_bg), so the ratio tends to 1. This is the
+        // thin-sample limit, in which there is less self-absorption to undo.
+        // The same geometry minimizes s in Booth and Tröger.
+        let amplitudes: Vec<f64> = [60.0, 45.0, 20.0, 5.0]
+            .iter()
+            .map(|&theta| at_exit(theta).amplitude)
+            .collect();
+        assert!(amplitudes.windows(2).all(|w| w[1] < w[0]), "{amplitudes:?}");
+        assert!(amplitudes.iter().all(|&a| a > 1.0));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_atoms_result_serde_roundtrip() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        let options = AtomsOptions {
+            diagnostics: true,
+            ..Default::default()
+        };
+        let result = atoms_with_options("Fe2O3", "Fe", Edge::K, &energies, &options).unwrap();
+
+        let json = serde_json::to_string(&result).unwrap();
+        let back: AtomsResult = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+        assert_eq!(back.amplitude.to_bits(), result.amplitude.to_bits());
+        assert_eq!(
+            back.sigma_squared_net.to_bits(),
+            result.sigma_squared_net.to_bits()
+        );
+        assert_eq!(back.k, result.k);
+        assert_eq!(back.correction, result.correction);
+
+        let chi: Vec<f64> = result.k.iter().map(|&k| 0.1 * (2.0 * k).sin()).collect();
+        let a = result.correct_chi(&chi);
+        let b = back.correct_chi(&chi);
+        assert!(a.iter().zip(&b).all(|(x, y)| x.to_bits() == y.to_bits()));
+    }
+
+    #[test]
+    fn test_atoms_pt_l3() {
+        let energies: Vec<f64> = (11400..=13000).step_by(5).map(|e| e as f64).collect();
+        let result = atoms("PtO2", "Pt", Edge::L3, &energies).unwrap();
+        assert_eq!(result.xanes_exclusion_ev, 20.0);
+        assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+        let in_window = energies
+            .iter()
+            .filter(|&&e| e > result.edge_energy + 20.0)
+            .count();
+        assert_eq!(result.n_fit_points, in_window);
+        assert!(result.amplitude.is_finite() && result.amplitude > 1.0);
+        for s2 in [
+            result.sigma_squared_self,
+            result.sigma_squared_norm,
+            result.sigma_squared_i0,
+        ] {
+            assert!(s2.is_finite() && s2.abs() < 0.1, "σ²={s2}");
+        }
+
+        let fe: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        assert_eq!(
+            atoms("Fe2O3", "Fe", Edge::K, &fe)
+                .unwrap()
+                .xanes_exclusion_ev,
+            0.0
+        );
+
+        let crossing: Vec<f64> = (11400..=13600).step_by(5).map(|e| e as f64).collect();
+        let result = atoms("PtO2", "Pt", Edge::L3, &crossing).unwrap();
+        assert!(result.warnings.iter().any(|w| matches!(
+            w,
+            SelfAbsWarning::InterferingEdge { element, edge, .. } if element == "Pt" && edge == "L2"
+        )));
+    }
+
+    #[test]
+    fn test_atoms_k_weighted_fit() {
+        let energies: Vec<f64> = (7000..=9000).step_by(5).map(|e| e as f64).collect();
+        let with_weight = |fit_weight| {
+            let options = AtomsOptions {
+                fit_weight,
+                ..Default::default()
+            };
+            atoms_with_options("Fe2O3", "Fe", Edge::K, &energies, &options).unwrap()
+        };
+
+        let default = atoms("Fe2O3", "Fe", Edge::K, &energies).unwrap();
+        let uniform = with_weight(FitWeight::Uniform);
+        assert!((uniform.sigma_squared_self - default.sigma_squared_self).abs() < 1e-15);
+        assert!((uniform.amplitude - default.amplitude).abs() < 1e-12);
+
+        let k2 = with_weight(FitWeight::KPower(2));
+        assert!(
+            (k2.sigma_squared_self - uniform.sigma_squared_self).abs()
+                > 1e-3 * uniform.sigma_squared_self.abs(),
+            "k²={} uniform={}",
+            k2.sigma_squared_self,
+            uniform.sigma_squared_self
+        );
+        // k⁰ weighting is the unweighted fit.
+        let k0 = with_weight(FitWeight::KPower(0));
+        assert!((k0.sigma_squared_self - uniform.sigma_squared_self).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_atoms_component_curves() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        let result = atoms("Fe2O3", "Fe", Edge::K, &energies).unwrap();
+        let curves = result.component_curves();
+        let total = result.correct_chi(&vec![1.0; energies.len()]);
+
+        for (i, &t) in total.iter().enumerate() {
+            let product = curves.self_correction[i]
+                * curves.norm_correction[i]
+                * curves.i0_correction[i]
+                * curves.it_correction[i];
+            assert!(((product - t) / t).abs() < 1e-12, "i={i}: {product} vs {t}");
+        }
+    }
+
+    #[test]
+    fn test_atoms_it_gas() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        let plain = atoms("Fe2O3", "Fe", Edge::K, &energies).unwrap();
+        assert!(plain.sigma_squared_it.is_none());
+
+        let options = AtomsOptions {
+            it_gas: Some(vec![("Ar".to_string(), 1.0)]),
+            diagnostics: true,
+            ..Default::default()
+        };
+        let with_it = atoms_with_options("Fe2O3", "Fe", Edge::K, &energies, &options).unwrap();
+        let s2_it = with_it.sigma_squared_it.unwrap();
+        assert!(s2_it.is_finite() && s2_it != 0.0);
+        let expected = with_it.sigma_squared_self
+            + with_it.sigma_squared_norm
+            + with_it.sigma_squared_i0
+            + s2_it;
+        assert!((with_it.sigma_squared_net - expected).abs() < 1e-15);
+        assert!((with_it.sigma_squared_net - plain.sigma_squared_net).abs() > 1e-6);
+        assert!(with_it.diagnostics.as_ref().unwrap().it_fit.is_some());
+
+        let curves = with_it.component_curves();
+        let total = with_it.correct_chi(&vec![1.0; energies.len()]);
+        for (i, &t) in total.iter().enumerate() {
+            let product = curves.self_correction[i]
+                * curves.norm_correction[i]
+                * curves.i0_correction[i]
+                * curves.it_correction[i];
+            assert!(((product - t) / t).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_atoms_from_composition_matches_formula() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        let options = AtomsOptions::default();
+        let from_formula = atoms("Fe2O3", "Fe", Edge::K, &energies).unwrap();
+        let composition = BTreeMap::from([("Fe".to_string(), 2.0), ("O".to_string(), 3.0)]);
+        let from_map =
+            atoms_from_composition(&composition, "Fe", Edge::K, &energies, &options).unwrap();
+
+        assert!((from_map.amplitude - from_formula.amplitude).abs() < 1e-12);
+        assert!((from_map.sigma_squared_net - from_formula.sigma_squared_net).abs() < 1e-12);
+        for (a, b) in from_map.correction.iter().zip(&from_formula.correction) {
+            assert!((a - b).abs() < 1e-12);
+        }
+
+        let dopant = BTreeMap::from([
+            ("Fe".to_string(), 0.02),
+            ("Zn".to_string(), 0.98),
+            ("O".to_string(), 1.0),
+        ]);
+        assert!(atoms_from_composition(&dopant, "Fe", Edge::K, &energies, &options).is_ok());
+        let missing = BTreeMap::from([("Zn".to_string(), 1.0), ("O".to_string(), 1.0)]);
+        assert!(matches!(
+            atoms_from_composition(&missing, "Fe", Edge::K, &energies, &options),
+            Err(SelfAbsError::InvalidFormula(_))
+        ));
+    }
+
+    #[test]
+    fn test_atoms_xanes_exclusion_tio2() {
+        let energies: Vec<f64> = (4900..=5800).step_by(2).map(|e| e as f64).collect();
+        let full = atoms("TiO2", "Ti", Edge::K, &energies).unwrap();
+        assert_eq!(full.xanes_exclusion_ev, 0.0);
+
+        let options = AtomsOptions {
+            xanes_exclusion_ev: Some(50.0),
+            ..Default::default()
+        };
+        let trimmed = atoms_with_options("TiO2", "Ti", Edge::K, &energies, &options).unwrap();
+        let skipped = energies
+            .iter()
+            .filter(|&&e| e > full.edge_energy && e <= full.edge_energy + 50.0)
+            .count();
+        assert_eq!(trimmed.n_fit_points, full.n_fit_points - skipped);
+        assert!((trimmed.amplitude - full.amplitude).abs() > 1e-4);
+        // The correction curve still covers the whole grid.
+        assert_eq!(trimmed.correction.len(), energies.len());
+
+        let negative = AtomsOptions {
+            xanes_exclusion_ev: Some(-5.0),
+            ..Default::default()
+        };
+        assert!(atoms_with_options("TiO2", "Ti", Edge::K, &energies, &negative).is_err());
+    }
+
     #[test]
     fn test_atoms_pure_element() {
         let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
-        let result = atoms("Fe", "Fe", "K", &energies).unwrap();
+        let result = atoms("Fe", "Fe", Edge::K, &energies).unwrap();
 
         // Pure element should have large correction
         assert!(result.amplitude > 1.0);
@@ -201,4 +1069,16 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_atoms_with_sample_info() {
+        let energies: Vec<f64> = (7000..=8000).step_by(2).map(|e| e as f64).collect();
+        let ctx = SelfAbsContext::new();
+        let info = SampleInfo::new(&ctx, "Fe2O3", "Fe", Edge::K).unwrap();
+        let options = AtomsOptions::default();
+        let direct = atoms_with_options("Fe2O3", "Fe", Edge::K, &energies, &options).unwrap();
+        let with_info = atoms_with_sample_info(&ctx, &info, &energies, &options).unwrap();
+        assert!((direct.amplitude - with_info.amplitude).abs() < 1e-10);
+        assert!((direct.sigma_squared_net - with_info.sigma_squared_net).abs() < 1e-10);
+    }
 }
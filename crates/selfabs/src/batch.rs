@@ -0,0 +1,77 @@
+//! Batch correction of many spectra through [`SelfAbsCorrection`].
+
+use crate::common::SelfAbsError;
+use crate::correction::SelfAbsCorrection;
+
+/// Apply `correction` to every spectrum in `spectra`, returning one result
+/// per spectrum in input order.
+///
+/// A spectrum that fails (e.g. of the wrong length) only fails its own
+/// entry. With the `parallel` feature the spectra are distributed over the
+/// rayon thread pool; the output is identical to the sequential loop.
+pub fn correct_many<C: SelfAbsCorrection + ?Sized>(
+    correction: &C,
+    spectra: &[Vec<f64>],
+) -> Vec<Result<Vec<f64>, SelfAbsError>> {
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        spectra
+            .par_iter()
+            .map(|chi| correction.correct_chi(chi))
+            .collect()
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        spectra
+            .iter()
+            .map(|chi| correction.correct_chi(chi))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Edge;
+    use crate::correction::{Algorithm, CorrectionSetup, correction};
+
+    #[test]
+    fn test_correct_many_matches_sequential_and_isolates_failures() {
+        let energies: Vec<f64> = (6900..=8000).step_by(5).map(|e| e as f64).collect();
+        let setup = CorrectionSetup {
+            geometry: None,
+            thickness_um: 10.0,
+            density_g_cm3: None,
+        };
+        for algorithm in [Algorithm::Troger, Algorithm::Booth, Algorithm::Ameyanagi] {
+            let correction =
+                correction(algorithm, "Fe2O3", "Fe", Edge::K, &energies, &setup).unwrap();
+            let mut spectra: Vec<Vec<f64>> = (0..32)
+                .map(|j| {
+                    let amp = 0.02 + 0.003 * j as f64;
+                    correction
+                        .k()
+                        .iter()
+                        .map(|&k| amp * (2.1 * k).sin())
+                        .collect()
+                })
+                .collect();
+            spectra[5].pop();
+
+            let batch = correct_many(correction.as_ref(), &spectra);
+            assert_eq!(batch.len(), spectra.len());
+            for (j, (chi, corrected)) in spectra.iter().zip(&batch).enumerate() {
+                if j == 5 {
+                    assert!(corrected.is_err(), "{algorithm:?}");
+                } else {
+                    assert_eq!(
+                        corrected.as_ref().unwrap(),
+                        &correction.correct_chi(chi).unwrap(),
+                        "{algorithm:?} spectrum {j}"
+                    );
+                }
+            }
+        }
+    }
+}
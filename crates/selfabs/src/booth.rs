@@ -4,19 +4,116 @@
 //! samples. In the thick limit, includes a nonlinear `s × (χ+1)` term that
 //! Tröger omits.
 
-use xraydb::XrayDb;
+use xraydb::CrossSectionKind;
 
 use crate::common::{
-    FluorescenceGeometry, SampleInfo, SelfAbsError, absorber_edge_mu_linear_trendline,
-    composition_mass_fractions, compound_mu_linear, compound_mu_linear_single, energies_to_k,
-    weighted_mu_absorber, weighted_mu_total, weighted_mu_total_single,
+    DetectionMode, EXAFS_OFFSET_EV, Edge, ElementRef, FluorescenceGeometry, FluorescenceLineMode,
+    LayerIncidence, LayeredSample, MuBackend, PreEdgeBaseline, PreEdgeMethod, PreEdgeOptions,
+    SampleInfo, SampleSpec, SelfAbsError, SelfAbsWarning, absorber_edge_mu_linear, brent_root,
+    energies_to_k, filter_transmission, path_transmission, resolve_e0, sample_warnings,
+    validate_energy_grid, weighted_mu_absorber_edge, weighted_mu_total, weighted_mu_total_single,
+    weighted_over_lines,
 };
+use crate::context::SelfAbsContext;
+use crate::correction::{
+    Algorithm, CorrectionSummary, NOMINAL_CHI, SelfAbsCorrection, check_chi_into, check_chi_len,
+    check_chi_prefix,
+};
+use crate::mu::{composition_mass_fractions, compound_mu_linear, compound_mu_linear_single};
 
 /// Thickness threshold (μm) for thin vs. thick determination.
 /// Path length = thickness / sin(θ_in). If > this value, use thick formula.
 const THICK_LIMIT_UM: f64 = 90.0;
 
+/// Lower end of the thin-inversion bracket (χ_exp > −1 is required for a
+/// positive fluorescence signal).
+const THIN_SOLVE_LOWER: f64 = -0.999_999;
+/// Absolute tolerance on χ_exp for the thin-inversion root finder.
+const THIN_SOLVE_XTOL: f64 = 1e-14;
+/// Iteration cap for the thin-inversion root finder.
+const THIN_SOLVE_MAX_ITER: usize = 100;
+
+/// Relative step of the symmetric finite difference used to propagate σ
+/// through the thin correction.
+const THIN_SIGMA_STEP: f64 = 1e-6;
+
+/// Options for the Booth μ pipeline.
+///
+/// The defaults reproduce the plain [`booth`] and
+/// [`booth_suppression_reference`] calls.
+#[derive(Debug, Clone)]
+pub struct BoothOptions {
+    /// Cross-section kind used for μ_total(E) and μ_f.
+    ///
+    /// The absorber edge term μ̄_a always uses the photoelectric cross-section,
+    /// since only photoabsorption by the absorber produces fluorescence.
+    pub cross_section: CrossSectionKind,
+    /// EXAFS-region statistics in [`BoothSuppressionResult`] only use points
+    /// with E > E₀ + `exafs_offset_ev`.
+    pub exafs_offset_ev: f64,
+    /// Optional additional k threshold (Å⁻¹) for the EXAFS-region statistics.
+    pub exafs_k_min: Option<f64>,
+    /// Optional detector acceptance as `(exit_angle_deg, weight)` pairs.
+    ///
+    /// When set, s(E) and α(E) are the weight-averaged values over these exit
+    /// angles and the geometry's own exit angle is ignored. Each angle goes
+    /// through [`FluorescenceGeometry::effective_ratio`], so an incident
+    /// spread still applies; an exit spread would describe the acceptance a
+    /// second time and is an error. See [`exit_acceptance_range`] for a
+    /// uniform angular range.
+    pub exit_acceptance: Option<Vec<(f64, f64)>>,
+    /// Absorbers between sample and detector as `(formula, density g/cm³,
+    /// thickness cm)` layers (air/He path, detector window).
+    ///
+    /// Their transmission at each emission line re-weights the line
+    /// intensities before μ_f and the fluorescence energy are averaged.
+    pub detection_path: Vec<(String, f64, f64)>,
+    /// Emission lines μ_f is averaged over in [`booth_with_options`].
+    ///
+    /// The reference calculations always average over all lines.
+    pub fluorescence_lines: FluorescenceLineMode,
+    /// E₀ (eV) of the k grid and the EXAFS-region statistics instead of the
+    /// tabulated edge energy, e.g. a calibrated E₀.
+    pub e0_override: Option<f64>,
+    /// Pre-edge trendline under μ̄_a(E) in the reference calculations.
+    pub pre_edge_window: PreEdgeOptions,
+    /// How μ̄_a(E) is separated from the absorber's pre-edge μ. `None`
+    /// keeps each path's convention: [`PreEdgeMethod::Constant`] for
+    /// [`booth`] and [`PreEdgeMethod::Trendline`] for the reference
+    /// calculations.
+    pub pre_edge_method: Option<PreEdgeMethod>,
+    /// Detector type; a total-yield detector mixes scatter into α.
+    pub detection_mode: DetectionMode,
+    /// Cross-section tables of every μ, the detection path included.
+    pub mu_backend: MuBackend,
+    /// Filter in front of the detector, see
+    /// [`TrogerOptions::detector_filter`](crate::troger::TrogerOptions::detector_filter);
+    /// unlike `detection_path` its thickness is in μm.
+    pub detector_filter: Option<(String, f64, f64)>,
+}
+
+impl Default for BoothOptions {
+    fn default() -> Self {
+        Self {
+            cross_section: CrossSectionKind::Photo,
+            exafs_offset_ev: EXAFS_OFFSET_EV,
+            exafs_k_min: None,
+            exit_acceptance: None,
+            detection_path: Vec::new(),
+            fluorescence_lines: FluorescenceLineMode::default(),
+            e0_override: None,
+            pre_edge_window: PreEdgeOptions::default(),
+            pre_edge_method: None,
+            detection_mode: DetectionMode::default(),
+            mu_backend: MuBackend::default(),
+            detector_filter: None,
+        }
+    }
+}
+
 /// Result of the Booth correction calculation.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BoothResult {
     /// Energy grid (eV).
     pub energies: Vec<f64>,
@@ -30,13 +127,33 @@ pub struct BoothResult {
     pub alpha: Vec<f64>,
     /// sin(θ_incident) — stored for correct_chi thin-sample correction.
     pub sin_phi: f64,
-    /// Edge energy (eV).
+    /// Sample thickness (μm) the result was computed for.
+    pub thickness_um: f64,
+    /// E₀ (eV) of the k grid.
     pub edge_energy: f64,
     /// Fluorescence energy (eV).
     pub fluorescence_energy: f64,
+    /// Interfering edges and other unreliable regions of the grid.
+    pub warnings: Vec<SelfAbsWarning>,
+    /// Whether s(E) and α(E) were averaged over a detector acceptance.
+    pub acceptance_averaged: bool,
+}
+
+/// Per-point diagnostics of the thin-sample inversion in
+/// [`BoothResult::suppression_factor_with_diagnostics`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SolverDiagnostics {
+    /// Root-finder iterations used at this point.
+    pub iterations: usize,
+    /// |f(χ_exp)| at the returned root.
+    pub residual: f64,
+    /// Whether the initial bracket missed the root and had to be widened.
+    pub used_fallback: bool,
 }
 
 /// Booth suppression-ratio result for reference plotting.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BoothSuppressionResult {
     /// Energy grid (eV).
     pub energies: Vec<f64>,
@@ -48,12 +165,87 @@ pub struct BoothSuppressionResult {
     pub r_max: f64,
     /// Mean R over grid.
     pub r_mean: f64,
+    /// Minimum R over the EXAFS region (NaN if the region is empty).
+    pub r_min_exafs: f64,
+    /// Maximum R over the EXAFS region (NaN if the region is empty).
+    pub r_max_exafs: f64,
+    /// Mean R over the EXAFS region (NaN if the region is empty).
+    pub r_mean_exafs: f64,
+    /// Number of grid points in the EXAFS region.
+    pub n_exafs: usize,
     /// Whether thick branch was used by Booth.
     pub is_thick: bool,
-    /// Edge energy (eV).
+    /// E₀ (eV) of the k grid.
     pub edge_energy: f64,
     /// Fluorescence energy (eV).
     pub fluorescence_energy: f64,
+    /// Whether μ̄_a(E) came from the pre-edge fit or its fallback.
+    pub pre_edge_baseline: PreEdgeBaseline,
+}
+
+/// [`BoothResult`] bound to a density and thickness as a
+/// [`SelfAbsCorrection`].
+pub struct BoothCorrection {
+    result: BoothResult,
+    density: f64,
+    thickness_um: f64,
+    suppression: Vec<f64>,
+}
+
+impl BoothCorrection {
+    /// Bind `result` to the sample density (g/cm³) and thickness (μm) used
+    /// by its thin-sample branch.
+    pub fn new(result: BoothResult, density: f64, thickness_um: f64) -> Result<Self, SelfAbsError> {
+        let suppression = result.suppression_factor(NOMINAL_CHI, density, thickness_um)?;
+        Ok(Self {
+            result,
+            density,
+            thickness_um,
+            suppression,
+        })
+    }
+
+    /// The underlying Booth result.
+    pub fn result(&self) -> &BoothResult {
+        &self.result
+    }
+}
+
+impl SelfAbsCorrection for BoothCorrection {
+    fn energies(&self) -> &[f64] {
+        &self.result.energies
+    }
+
+    fn k(&self) -> &[f64] {
+        &self.result.k
+    }
+
+    fn correct_chi(&self, chi: &[f64]) -> Result<Vec<f64>, SelfAbsError> {
+        check_chi_len(chi, &self.result.energies)?;
+        Ok(self
+            .result
+            .correct_chi(chi, self.density, self.thickness_um))
+    }
+
+    /// R at χ_true = [`NOMINAL_CHI`].
+    fn summary(&self) -> CorrectionSummary {
+        CorrectionSummary::from_suppression(
+            Algorithm::Booth,
+            &self.result.k,
+            self.suppression.iter().copied(),
+        )
+    }
+}
+
+impl BoothSuppressionResult {
+    /// Suppression summary at the χ the reference was computed for.
+    pub fn summarize(&self) -> CorrectionSummary {
+        CorrectionSummary::from_suppression(
+            Algorithm::Booth,
+            &energies_to_k(&self.energies, self.edge_energy),
+            self.suppression_factor.iter().copied(),
+        )
+    }
 }
 
 impl BoothResult {
@@ -76,6 +268,200 @@ impl BoothResult {
         }
     }
 
+    /// Apply [`correct_chi`](Self::correct_chi) to many spectra sharing this
+    /// result, returning the corrected spectra in input order.
+    ///
+    /// With the `parallel` feature the spectra are distributed over the rayon
+    /// thread pool; the output is identical to the sequential loop.
+    pub fn correct_chi_batch(
+        &self,
+        spectra: &[&[f64]],
+        density: f64,
+        thickness_um: f64,
+    ) -> Vec<Vec<f64>> {
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            spectra
+                .par_iter()
+                .map(|chi| self.correct_chi(chi, density, thickness_um))
+                .collect()
+        }
+        #[cfg(not(feature = "parallel"))]
+        {
+            spectra
+                .iter()
+                .map(|chi| self.correct_chi(chi, density, thickness_um))
+                .collect()
+        }
+    }
+
+    /// Write the corrected `chi` into `out` without allocating, returning
+    /// the number of points written.
+    ///
+    /// `chi` holds the first `chi.len()` points of the grid, so a spectrum
+    /// can be corrected while it is acquired; `out` may be longer than
+    /// `chi`. Values are those of [`correct_chi`](Self::correct_chi).
+    pub fn correct_chi_into(
+        &self,
+        chi: &[f64],
+        out: &mut [f64],
+        density: f64,
+        thickness_um: f64,
+    ) -> Result<usize, SelfAbsError> {
+        let n = check_chi_into(chi, out, self.s.len())?;
+        for (o, c) in out
+            .iter_mut()
+            .zip(self.corrected_points(chi, density, thickness_um))
+        {
+            *o = c;
+        }
+        Ok(n)
+    }
+
+    /// Lazy form of [`correct_chi_into`](Self::correct_chi_into), yielding
+    /// one corrected point per point of `chi`.
+    pub fn corrected_iter<'a>(
+        &'a self,
+        chi: &'a [f64],
+        density: f64,
+        thickness_um: f64,
+    ) -> Result<impl Iterator<Item = f64> + 'a, SelfAbsError> {
+        check_chi_prefix(chi, self.s.len())?;
+        Ok(self.corrected_points(chi, density, thickness_um))
+    }
+
+    fn corrected_points<'a>(
+        &'a self,
+        chi: &'a [f64],
+        density: f64,
+        thickness_um: f64,
+    ) -> impl Iterator<Item = f64> + 'a {
+        chi.iter().enumerate().map(move |(i, &c)| {
+            if self.is_thick {
+                self.correct_single_thick(i, c)
+            } else {
+                self.correct_single_thin(i, c, density, thickness_um)
+            }
+        })
+    }
+
+    /// [`correct_chi`](Self::correct_chi) plus first-order propagation of
+    /// the measurement uncertainty `sigma` on χ.
+    ///
+    /// `σ_corr = |dχ_corr/dχ| × σ`. The thick branch uses the analytic
+    /// derivative `(1 − s) / (1 − s(χ + 1))²`; the thin branch uses a
+    /// symmetric finite difference of the quadratic solution.
+    pub fn correct_chi_with_sigma(
+        &self,
+        chi: &[f64],
+        sigma: &[f64],
+        density: f64,
+        thickness_um: f64,
+    ) -> Result<(Vec<f64>, Vec<f64>), SelfAbsError> {
+        if sigma.len() != chi.len() {
+            return Err(SelfAbsError::InsufficientData(format!(
+                "sigma has {} points but chi has {}",
+                sigma.len(),
+                chi.len()
+            )));
+        }
+        let corrected = self.correct_chi(chi, density, thickness_um);
+        let sigma_corr = chi
+            .iter()
+            .zip(sigma)
+            .enumerate()
+            .map(|(i, (&c, &sig))| {
+                let slope = if self.is_thick {
+                    self.thick_derivative(i, c)
+                } else {
+                    let h = THIN_SIGMA_STEP * c.abs().max(1.0);
+                    (self.correct_single_thin(i, c + h, density, thickness_um)
+                        - self.correct_single_thin(i, c - h, density, thickness_um))
+                        / (2.0 * h)
+                };
+                slope.abs() * sig
+            })
+            .collect();
+        Ok((corrected, sigma_corr))
+    }
+
+    /// Effective probing depth z* (μm) at each energy point: the depth,
+    /// from the surface the beam enters, above which 63% (1 − 1/e) of the
+    /// fluorescence originates.
+    ///
+    /// The fluorescence from depth z is weighted by `exp(−a z)` with
+    /// a = α ρ / sin φ, so over the stored thickness d
+    /// ```text
+    /// z* = −ln(1 − (1 − 1/e)(1 − exp(−a d))) / a
+    /// ```
+    /// which is sin φ / (α ρ) for a semi-infinite sample and approaches
+    /// (1 − 1/e) d for a film much thinner than 1/a, so z* never exceeds the
+    /// thickness. Below the edge α is set by the matrix alone and z* is
+    /// correspondingly large. A non-positive density gives `f64::INFINITY`.
+    pub fn probing_depth_um(&self, density: f64) -> Vec<f64> {
+        let fraction = 1.0 - (-1.0f64).exp();
+        let thickness_cm = self.thickness_um * 1e-4;
+        self.alpha
+            .iter()
+            .map(|&a| {
+                let a = a * density / self.sin_phi;
+                if !(density.is_finite() && density > 0.0 && a.is_finite()) {
+                    f64::INFINITY
+                } else if a * thickness_cm == 0.0 {
+                    1e4 * fraction * thickness_cm
+                } else {
+                    let depth_cm = -(fraction * (-a * thickness_cm).exp_m1()).ln_1p() / a;
+                    if depth_cm.is_finite() {
+                        1e4 * depth_cm
+                    } else {
+                        f64::INFINITY
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Correct a normalized μ(E) spectrum with the same s(E) and α(E) used
+    /// by [`correct_chi`](Self::correct_chi).
+    ///
+    /// The absorber contribution is taken as μ_a(E) = μ̄_a(E) × μ_norm(E), so
+    /// `μ_norm − 1` plays the role of χ in both the thick and thin formulas:
+    /// ```text
+    /// μ_corr = 1 + χ_corr(μ_norm − 1)
+    /// ```
+    /// This is an approximation in the XANES region, where μ̄_a(E) is the
+    /// smooth post-edge trend rather than the true edge shape; it is exact in
+    /// the same sense as the χ correction far above the edge.
+    pub fn correct_mu_norm(
+        &self,
+        mu_norm: &[f64],
+        density: f64,
+        thickness_um: f64,
+    ) -> Result<Vec<f64>, SelfAbsError> {
+        if mu_norm.len() != self.s.len() {
+            return Err(SelfAbsError::InsufficientData(format!(
+                "mu_norm has {} points but the Booth grid has {}",
+                mu_norm.len(),
+                self.s.len()
+            )));
+        }
+        let valid_sample =
+            density.is_finite() && density > 0.0 && thickness_um.is_finite() && thickness_um > 0.0;
+        if !self.is_thick && !valid_sample {
+            return Err(SelfAbsError::InsufficientData(
+                "thin-sample correction needs finite density and thickness > 0".to_string(),
+            ));
+        }
+
+        let oscillation: Vec<f64> = mu_norm.iter().map(|&m| m - 1.0).collect();
+        Ok(self
+            .correct_chi(&oscillation, density, thickness_um)
+            .into_iter()
+            .map(|c| c + 1.0)
+            .collect())
+    }
+
     /// Compute suppression ratio `R(E, χ) = χ_exp / χ_true` point-by-point.
     ///
     /// For thick samples this is closed-form:
@@ -89,6 +475,21 @@ impl BoothResult {
         density: f64,
         thickness_um: f64,
     ) -> Result<Vec<f64>, SelfAbsError> {
+        self.suppression_factor_with_diagnostics(chi_true, density, thickness_um)
+            .map(|(r, _)| r)
+    }
+
+    /// [`suppression_factor`](Self::suppression_factor) plus per-point
+    /// solver diagnostics.
+    ///
+    /// For the thick branch no solve is needed and every entry reports zero
+    /// iterations and zero residual.
+    pub fn suppression_factor_with_diagnostics(
+        &self,
+        chi_true: f64,
+        density: f64,
+        thickness_um: f64,
+    ) -> Result<(Vec<f64>, Vec<SolverDiagnostics>), SelfAbsError> {
         if !chi_true.is_finite() || chi_true == 0.0 {
             return Err(SelfAbsError::InsufficientData(
                 "chi_true must be finite and non-zero".to_string(),
@@ -106,15 +507,18 @@ impl BoothResult {
                 }
                 out.push((1.0 - si) / denom);
             }
-            return Ok(out);
+            let diagnostics = vec![SolverDiagnostics::default(); out.len()];
+            return Ok((out, diagnostics));
         }
 
         let mut out = Vec::with_capacity(self.s.len());
+        let mut diagnostics = Vec::with_capacity(self.s.len());
         for i in 0..self.s.len() {
-            let chi_exp = self.solve_chi_exp_thin(i, chi_true, density, thickness_um)?;
+            let (chi_exp, diag) = self.solve_chi_exp_thin(i, chi_true, density, thickness_um)?;
             out.push(chi_exp / chi_true);
+            diagnostics.push(diag);
         }
-        Ok(out)
+        Ok((out, diagnostics))
     }
 
     fn correct_thick(&self, chi: &[f64]) -> Vec<f64> {
@@ -141,6 +545,17 @@ impl BoothResult {
         }
     }
 
+    /// dχ_corr/dχ_exp of [`correct_single_thick`](Self::correct_single_thick).
+    fn thick_derivative(&self, i: usize, chi_exp: f64) -> f64 {
+        let si = self.s[i];
+        let denom = 1.0 - si * (chi_exp + 1.0);
+        if denom.abs() > 1e-10 {
+            (1.0 - si) / (denom * denom)
+        } else {
+            1.0
+        }
+    }
+
     fn correct_single_thin(&self, i: usize, chi_exp: f64, density: f64, thickness_um: f64) -> f64 {
         let thickness_cm = thickness_um * 1e-4;
         let alpha_i = self.alpha[i] * density;
@@ -162,7 +577,11 @@ impl BoothResult {
         if discriminant < 0.0 {
             chi_exp
         } else {
-            (-term1 + discriminant.sqrt()) / (2.0 * beta)
+            // The physical root is the one that vanishes with χ_exp. That
+            // is the + root unless μ_a > α (beam from below) makes term1
+            // negative at χ_exp = 0.
+            let sign = (gamma * (alpha_i - mu_a_i) + beta).signum();
+            (-term1 + sign * discriminant.sqrt()) / (2.0 * beta)
         }
     }
 
@@ -172,81 +591,42 @@ impl BoothResult {
         chi_true: f64,
         density: f64,
         thickness_um: f64,
-    ) -> Result<f64, SelfAbsError> {
+    ) -> Result<(f64, SolverDiagnostics), SelfAbsError> {
         let f = |x: f64| self.correct_single_thin(i, x, density, thickness_um) - chi_true;
 
-        // Fast local solve near the physical branch.
-        let mut x = chi_true;
-        for _ in 0..20 {
-            let fx = f(x);
-            if !fx.is_finite() {
-                break;
-            }
-            if fx.abs() < 1e-12 {
-                return Ok(x);
-            }
-            let h = 1e-6 * x.abs().max(1.0);
-            let df = (f(x + h) - f(x - h)) / (2.0 * h);
-            if !df.is_finite() || df.abs() < 1e-12 {
-                break;
-            }
-            let x_next = (x - fx / df).clamp(-0.999_999, 10.0);
-            if !x_next.is_finite() {
-                break;
-            }
-            if (x_next - x).abs() < 1e-12 {
-                return Ok(x_next);
-            }
-            x = x_next;
-        }
-
-        // Robust fallback: bracket + bisection.
-        let mut lo = -0.999_999;
+        // The physical branch lies in (-1, ~2(χ+1)); widen the upper end only
+        // if that initial bracket does not contain a sign change.
+        let lo = THIN_SOLVE_LOWER;
         let mut hi = (chi_true.max(0.0) + 1.0) * 2.0;
-        let mut flo = f(lo);
+        let flo = f(lo);
         let mut fhi = f(hi);
+        let mut used_fallback = false;
 
-        let mut bracketed = flo.is_finite() && fhi.is_finite() && flo * fhi <= 0.0;
-        if !bracketed {
-            for _ in 0..40 {
-                hi *= 2.0;
-                if hi > 1e6 {
-                    break;
-                }
-                fhi = f(hi);
-                bracketed = flo.is_finite() && fhi.is_finite() && flo * fhi <= 0.0;
-                if bracketed {
-                    break;
-                }
-            }
-        }
-
-        if !bracketed {
-            return Err(SelfAbsError::InsufficientData(format!(
-                "failed to bracket thin Booth inversion at index {i}"
-            )));
-        }
-
-        for _ in 0..80 {
-            let mid = 0.5 * (lo + hi);
-            let fmid = f(mid);
-            if !fmid.is_finite() {
+        while !(flo.is_finite() && fhi.is_finite() && flo * fhi <= 0.0) {
+            hi *= 2.0;
+            if hi > 1e6 {
                 return Err(SelfAbsError::InsufficientData(format!(
-                    "non-finite thin Booth inversion function at index {i}"
+                    "failed to bracket thin Booth inversion at index {i}"
                 )));
             }
-            if fmid.abs() < 1e-12 || (hi - lo).abs() < 1e-10 {
-                return Ok(mid);
-            }
-            if flo * fmid <= 0.0 {
-                hi = mid;
-            } else {
-                lo = mid;
-                flo = fmid;
-            }
+            fhi = f(hi);
+            used_fallback = true;
         }
 
-        Ok(0.5 * (lo + hi))
+        let sol = brent_root(f, lo, hi, THIN_SOLVE_XTOL, THIN_SOLVE_MAX_ITER).ok_or_else(|| {
+            SelfAbsError::InsufficientData(format!(
+                "thin Booth inversion did not converge at index {i}"
+            ))
+        })?;
+
+        Ok((
+            sol.root,
+            SolverDiagnostics {
+                iterations: sol.iterations,
+                residual: sol.residual,
+                used_fallback,
+            },
+        ))
     }
 }
 
@@ -261,43 +641,122 @@ impl BoothResult {
 /// - `thickness_um` — sample thickness in μm (large value = thick limit)
 pub fn booth(
     formula: &str,
-    central_element: &str,
-    edge: &str,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
     energies: &[f64],
     geometry: Option<FluorescenceGeometry>,
     thickness_um: f64,
 ) -> Result<BoothResult, SelfAbsError> {
-    let db = XrayDb::new();
-    let geo = geometry.unwrap_or_default();
-    let info = SampleInfo::new(&db, formula, central_element, edge)?;
-    let ratio = geo.ratio();
-
-    let k = energies_to_k(energies, info.edge_energy);
+    booth_with_options(
+        formula,
+        central_element,
+        edge,
+        energies,
+        geometry,
+        thickness_um,
+        &BoothOptions::default(),
+    )
+}
 
-    // μ quantities (weighted by stoichiometric count, in cm²/g-equivalent)
-    let mu_t = weighted_mu_total(&db, &info.composition, energies)?;
-    let mu_a = weighted_mu_absorber(&db, &info, energies, true)?;
-    let mu_f = weighted_mu_total_single(&db, &info.composition, info.fluor_energy)?;
+/// [`booth`] with explicit [`BoothOptions`].
+pub fn booth_with_options(
+    formula: &str,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies: &[f64],
+    geometry: Option<FluorescenceGeometry>,
+    thickness_um: f64,
+    options: &BoothOptions,
+) -> Result<BoothResult, SelfAbsError> {
+    SelfAbsContext::new().booth(
+        formula,
+        central_element,
+        edge,
+        energies,
+        geometry,
+        thickness_um,
+        options,
+    )
+}
 
-    let n = energies.len();
-    let mut s = Vec::with_capacity(n);
-    let mut alpha = Vec::with_capacity(n);
+/// [`booth_with_options`] for a prebuilt [`SampleInfo`].
+pub fn booth_with_sample_info(
+    ctx: &SelfAbsContext,
+    info: &SampleInfo,
+    energies: &[f64],
+    geometry: Option<FluorescenceGeometry>,
+    thickness_um: f64,
+    options: &BoothOptions,
+) -> Result<BoothResult, SelfAbsError> {
+    booth_on_info(
+        ctx,
+        info,
+        energies,
+        geometry.unwrap_or_default(),
+        thickness_um,
+        options,
+        LayerIncidence::Front,
+    )
+}
 
-    for i in 0..n {
-        let alpha_i = mu_t[i] + ratio * mu_f;
-        let si = if alpha_i > 0.0 {
-            mu_a[i] / alpha_i
-        } else {
-            0.0
-        };
-        alpha.push(alpha_i);
-        s.push(si);
+/// [`booth_with_sample_info`] for a beam entering from `incidence`. From
+/// below the exit term of α changes sign and the thin branch is forced.
+fn booth_on_info(
+    ctx: &SelfAbsContext,
+    info: &SampleInfo,
+    energies: &[f64],
+    geo: FluorescenceGeometry,
+    thickness_um: f64,
+    options: &BoothOptions,
+    incidence: LayerIncidence,
+) -> Result<BoothResult, SelfAbsError> {
+    let mut ratios = acceptance_ratios(&geo, options)?;
+    let from_below = incidence == LayerIncidence::ThroughSubstrate;
+    if from_below {
+        for (ratio, _) in &mut ratios {
+            *ratio = -*ratio;
+        }
     }
 
+    let e0 = resolve_e0(info, options.e0_override)?;
+    let grid = validate_energy_grid(energies, e0)?;
+    let k = energies_to_k(energies, e0);
+
+    // μ quantities (weighted by stoichiometric count, in cm²/g-equivalent)
+    let backend = options.mu_backend;
+    let mu_t = weighted_mu_total(
+        ctx,
+        &info.composition,
+        energies,
+        options.cross_section,
+        backend,
+    )?;
+    let (mu_a, _) = weighted_mu_absorber_edge(
+        ctx,
+        info,
+        energies,
+        options.pre_edge_method.unwrap_or(PreEdgeMethod::Constant),
+        &options.pre_edge_window,
+        backend,
+    )?;
+    let (mu_f, fluorescence_energy) = weighted_over_lines(
+        info,
+        info.lines_for(options.fluorescence_lines),
+        |e| {
+            Ok(path_transmission(ctx, &options.detection_path, e, backend)?
+                * filter_transmission(ctx, options.detector_filter.as_ref(), e, backend)?)
+        },
+        |e| weighted_mu_total_single(ctx, &info.composition, e, options.cross_section, backend),
+    )?;
+
+    let (s, alpha) = averaged_s_alpha(&mu_t, &mu_a, mu_f, &ratios, options.detection_mode);
+
     // Determine thick vs thin: effective path = thickness / sin(φ)
     let sin_phi = geo.theta_incident_deg.to_radians().sin();
     let effective_path = thickness_um / sin_phi;
-    let is_thick = effective_path >= THICK_LIMIT_UM;
+    let is_thick = !from_below && effective_path >= THICK_LIMIT_UM;
+    let mut warnings: Vec<SelfAbsWarning> = grid.warning().into_iter().collect();
+    warnings.extend(sample_warnings(ctx, info, energies, options.mu_backend)?);
 
     Ok(BoothResult {
         energies: energies.to_vec(),
@@ -306,130 +765,704 @@ pub fn booth(
         s,
         alpha,
         sin_phi,
-        edge_energy: info.edge_energy,
-        fluorescence_energy: info.fluor_energy,
+        thickness_um,
+        edge_energy: e0,
+        fluorescence_energy,
+        warnings,
+        acceptance_averaged: options.exit_acceptance.is_some(),
     })
 }
 
+/// [`booth_with_options`] for any [`SampleSpec`].
+pub fn booth_spec(
+    spec: &SampleSpec,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies: &[f64],
+    geometry: Option<FluorescenceGeometry>,
+    thickness_um: f64,
+    options: &BoothOptions,
+) -> Result<BoothResult, SelfAbsError> {
+    let ctx = SelfAbsContext::new();
+    let info = SampleInfo::from_spec(&ctx, spec, central_element, edge)?;
+    booth_with_sample_info(&ctx, &info, energies, geometry, thickness_um, options)
+}
+
 /// Compute Booth reference suppression ratio `R(E, χ) = χ_exp/χ_true`.
 #[allow(clippy::too_many_arguments)]
 pub fn booth_suppression_reference(
     formula: &str,
-    central_element: &str,
-    edge: &str,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
     energies: &[f64],
     geometry: Option<FluorescenceGeometry>,
     thickness_um: f64,
     density_g_cm3: f64,
     chi_true: f64,
 ) -> Result<BoothSuppressionResult, SelfAbsError> {
-    if !density_g_cm3.is_finite() || density_g_cm3 <= 0.0 {
-        return Err(SelfAbsError::InsufficientData(
-            "density must be finite and > 0".to_string(),
-        ));
-    }
-    if !thickness_um.is_finite() || thickness_um <= 0.0 {
-        return Err(SelfAbsError::InsufficientData(
-            "thickness_um must be finite and > 0".to_string(),
-        ));
-    }
-    if !chi_true.is_finite() || chi_true == 0.0 {
-        return Err(SelfAbsError::InsufficientData(
-            "chi_true must be finite and non-zero".to_string(),
-        ));
-    }
+    booth_suppression_reference_with_options(
+        formula,
+        central_element,
+        edge,
+        energies,
+        geometry,
+        thickness_um,
+        density_g_cm3,
+        chi_true,
+        &BoothOptions::default(),
+    )
+}
+
+/// [`booth_suppression_reference`] with explicit [`BoothOptions`].
+#[allow(clippy::too_many_arguments)]
+pub fn booth_suppression_reference_with_options(
+    formula: &str,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies: &[f64],
+    geometry: Option<FluorescenceGeometry>,
+    thickness_um: f64,
+    density_g_cm3: f64,
+    chi_true: f64,
+    options: &BoothOptions,
+) -> Result<BoothSuppressionResult, SelfAbsError> {
+    let edge: Edge = edge.try_into().map_err(Into::into)?;
+    booth_suppression_reference_on(
+        &SelfAbsContext::new(),
+        formula,
+        central_element,
+        edge,
+        energies,
+        geometry,
+        thickness_um,
+        density_g_cm3,
+        chi_true,
+        options,
+        None,
+    )
+}
+
+/// [`booth_suppression_reference_with_options`] on `ctx`. `thick` forces the
+/// thick or thin branch instead of choosing it from the effective path
+/// length.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn booth_suppression_reference_on(
+    ctx: &SelfAbsContext,
+    formula: &str,
+    central_element: impl Into<ElementRef>,
+    edge: Edge,
+    energies: &[f64],
+    geometry: Option<FluorescenceGeometry>,
+    thickness_um: f64,
+    density_g_cm3: f64,
+    chi_true: f64,
+    options: &BoothOptions,
+    thick: Option<bool>,
+) -> Result<BoothSuppressionResult, SelfAbsError> {
+    validate_reference_inputs(thickness_um, density_g_cm3, chi_true)?;
 
-    let db = XrayDb::new();
     let geo = geometry.unwrap_or_default();
-    let info = SampleInfo::new(&db, formula, central_element, edge)?;
-    let ratio = geo.ratio();
-
-    let k = energies_to_k(energies, info.edge_energy);
-    let mass_fractions = composition_mass_fractions(&db, &info.composition)?;
-    let mu_t = compound_mu_linear(&db, &mass_fractions, density_g_cm3, energies)?;
-    let mu_a = absorber_edge_mu_linear_trendline(&db, &info, energies, density_g_cm3)?;
-
-    let lines = db.xray_lines(central_element, Some(edge), None)?;
-    let mut mu_f_weighted = 0.0;
-    let mut ef_weighted = 0.0;
-    let mut w_sum = 0.0;
-    for line in lines.values() {
-        if !line.intensity.is_finite() || line.intensity <= 0.0 {
-            continue;
-        }
-        let w = line.intensity;
-        let mu_line = compound_mu_linear_single(&db, &mass_fractions, density_g_cm3, line.energy)?;
-        mu_f_weighted += w * mu_line;
-        ef_weighted += w * line.energy;
-        w_sum += w;
-    }
-    if w_sum <= 0.0 {
-        return Err(SelfAbsError::NoEmissionLines(format!(
-            "{central_element} {edge} has no positive-intensity lines"
-        )));
-    }
-    let mu_f = mu_f_weighted / w_sum;
-    let fluorescence_energy = ef_weighted / w_sum;
-
-    let mut s = Vec::with_capacity(energies.len());
-    let mut alpha = Vec::with_capacity(energies.len());
-    for i in 0..energies.len() {
-        let alpha_linear = mu_t[i] + ratio * mu_f;
-        let si = if alpha_linear > 0.0 {
-            mu_a[i] / alpha_linear
-        } else {
-            0.0
-        };
-        alpha.push(alpha_linear / density_g_cm3);
-        s.push(si);
+    let mu = ReferenceMu::new(
+        ctx,
+        formula,
+        central_element,
+        edge,
+        energies,
+        density_g_cm3,
+        options,
+    )?;
+    let mut base = mu.booth_result(energies, &geo, thickness_um, density_g_cm3, options)?;
+    if let Some(thick) = thick {
+        base.is_thick = thick;
     }
+    summarize_suppression(
+        base,
+        mu.pre_edge_baseline,
+        chi_true,
+        density_g_cm3,
+        thickness_um,
+        options,
+    )
+}
 
-    let sin_phi = geo.theta_incident_deg.to_radians().sin();
-    let effective_path = thickness_um / sin_phi;
-    let is_thick = effective_path >= THICK_LIMIT_UM;
+/// Result of [`booth_layered`].
+pub struct LayeredBoothResult {
+    /// Booth parameters of the absorbing layer.
+    pub booth: BoothResult,
+    /// Transmission of the incident beam before it reaches the absorbing
+    /// layer, per energy point: through the caps, or through the substrate
+    /// with [`LayerIncidence::ThroughSubstrate`].
+    pub incident_transmission: Vec<f64>,
+    /// Transmission of the fluorescence through the caps.
+    pub fluorescence_transmission: f64,
+}
 
-    let base = BoothResult {
-        energies: energies.to_vec(),
-        k,
-        is_thick,
-        s,
-        alpha,
-        sin_phi,
-        edge_energy: info.edge_energy,
-        fluorescence_energy,
-    };
+/// Booth correction for the absorbing layer of a [`LayeredSample`].
+pub fn booth_layered(
+    sample: &LayeredSample,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies: &[f64],
+    geometry: Option<FluorescenceGeometry>,
+) -> Result<LayeredBoothResult, SelfAbsError> {
+    booth_layered_with_options(
+        sample,
+        central_element,
+        edge,
+        energies,
+        geometry,
+        &BoothOptions::default(),
+    )
+}
 
-    let r = base.suppression_factor(chi_true, density_g_cm3, thickness_um)?;
-    let r_min = r.iter().fold(f64::INFINITY, |m, &v| m.min(v));
-    let r_max = r.iter().fold(f64::NEG_INFINITY, |m, &v| m.max(v));
-    let r_mean = r.iter().sum::<f64>() / r.len() as f64;
+/// [`booth_layered`] with explicit [`BoothOptions`].
+///
+/// The layers in front of the absorbing one scale the whole fluorescence
+/// signal smoothly, so their attenuation is reported as transmissions and
+/// leaves s(E), α(E) and the suppression ratio of the absorbing layer
+/// unchanged. The layer's finite thickness is its lower boundary (the thin
+/// branch when the layer is thin). Every layer is validated first.
+///
+/// From the front a substrate is never reached. With
+/// [`LayerIncidence::ThroughSubstrate`] the substrate attenuates the
+/// incident beam instead of the caps, and the beam enters the absorbing
+/// layer from below. Fluorescence from depth z then sees an incident path
+/// (d − z)/sin φ and an exit path z/sin θ. Up to a χ-independent factor
+/// this is the front-side slab with the exit term reversed,
+/// α(E) = μ_T − g μ_f, so the thin branch is always used. Where that α
+/// falls below μ_a the thin-film χ_exp is not single-valued in χ, and the
+/// suppression solvers report an error instead of picking a branch.
+pub fn booth_layered_with_options(
+    sample: &LayeredSample,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies: &[f64],
+    geometry: Option<FluorescenceGeometry>,
+    options: &BoothOptions,
+) -> Result<LayeredBoothResult, SelfAbsError> {
+    SelfAbsContext::new().booth_layered(sample, central_element, edge, energies, geometry, options)
+}
 
-    Ok(BoothSuppressionResult {
-        energies: base.energies,
-        suppression_factor: r,
-        r_min,
-        r_max,
-        r_mean,
-        is_thick: base.is_thick,
-        edge_energy: base.edge_energy,
-        fluorescence_energy: base.fluorescence_energy,
+/// [`booth_layered_with_options`] on `ctx`.
+pub(crate) fn booth_layered_on(
+    ctx: &SelfAbsContext,
+    sample: &LayeredSample,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies: &[f64],
+    geometry: Option<FluorescenceGeometry>,
+    options: &BoothOptions,
+) -> Result<LayeredBoothResult, SelfAbsError> {
+    sample.validate()?;
+    let geo = geometry.unwrap_or_default();
+    let absorber = &sample.absorber;
+    let info = ctx.sample_info(&absorber.formula, central_element, edge)?;
+    let booth = booth_on_info(
+        ctx,
+        &info,
+        energies,
+        geo,
+        absorber.thickness_um,
+        options,
+        sample.incidence,
+    )?;
+
+    let backend = options.mu_backend;
+    let incident_transmission = sample.incident_transmission(
+        ctx,
+        energies,
+        geo.theta_incident_deg.to_radians().sin(),
+        backend,
+    )?;
+    let fluorescence_transmission = sample.cap_transmission_single(
+        ctx,
+        booth.fluorescence_energy,
+        geo.theta_fluorescence_deg.to_radians().sin(),
+        backend,
+    )?;
+
+    Ok(LayeredBoothResult {
+        booth,
+        incident_transmission,
+        fluorescence_transmission,
     })
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::ameyanagi::{
-        AmeyanagiSuppressionSettings, AmeyanagiThicknessInput, ameyanagi_suppression_exact,
-    };
+/// Bounds and grid resolution for [`booth_optimize_geometry`].
+#[derive(Debug, Clone, Copy)]
+pub struct GeometryBounds {
+    /// Smallest allowed incident angle φ (degrees from the surface).
+    pub incident_min_deg: f64,
+    /// Largest allowed incident angle φ (degrees from the surface).
+    pub incident_max_deg: f64,
+    /// Smallest allowed exit angle θ (degrees from the surface).
+    pub exit_min_deg: f64,
+    /// Largest allowed exit angle θ (degrees from the surface).
+    pub exit_max_deg: f64,
+    /// Coarse grid points per angle (≥ 2).
+    pub grid_points: usize,
+}
 
-    #[test]
-    fn test_booth_thick_fe2o3() {
-        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
-        // 100 mm = effectively infinite thickness
-        let result = booth("Fe2O3", "Fe", "K", &energies, None, 100_000.0).unwrap();
+impl Default for GeometryBounds {
+    fn default() -> Self {
+        Self {
+            incident_min_deg: 5.0,
+            incident_max_deg: 85.0,
+            exit_min_deg: 10.0,
+            exit_max_deg: 85.0,
+            grid_points: 17,
+        }
+    }
+}
 
-        assert!(result.is_thick);
+/// One evaluated geometry of the coarse scan in [`BoothGeometryOptimum`].
+#[derive(Debug, Clone, Copy)]
+pub struct GeometryScanPoint {
+    /// Incident angle φ (degrees).
+    pub theta_incident_deg: f64,
+    /// Exit angle θ (degrees).
+    pub theta_fluorescence_deg: f64,
+    /// Mean suppression ratio R over the grid at this geometry.
+    pub r_mean: f64,
+}
+
+/// Result of [`booth_optimize_geometry`].
+pub struct BoothGeometryOptimum {
+    /// Geometry with the largest mean suppression ratio.
+    pub geometry: FluorescenceGeometry,
+    /// Mean suppression ratio R at the optimum.
+    pub r_mean: f64,
+    /// Coarse scan, incident angle major (`grid_points²` entries).
+    pub scan: Vec<GeometryScanPoint>,
+}
+
+/// Step (degrees) below which the local refinement stops.
+const GEOMETRY_REFINE_TOL_DEG: f64 = 1e-3;
+
+/// Find the (φ, θ) pair within `bounds` that maximizes the mean Booth
+/// suppression ratio `r_mean`, i.e. minimizes the amplitude loss.
+///
+/// The sample cross-sections are computed once; a coarse grid search over
+/// both angles is followed by a compass-search refinement around the best
+/// grid point.
+#[allow(clippy::too_many_arguments)]
+pub fn booth_optimize_geometry(
+    formula: &str,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies: &[f64],
+    thickness_um: f64,
+    density_g_cm3: f64,
+    chi_true: f64,
+    bounds: &GeometryBounds,
+) -> Result<BoothGeometryOptimum, SelfAbsError> {
+    SelfAbsContext::new().booth_optimize_geometry(
+        formula,
+        central_element,
+        edge,
+        energies,
+        thickness_um,
+        density_g_cm3,
+        chi_true,
+        bounds,
+    )
+}
+
+/// [`booth_optimize_geometry`] on `ctx`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn booth_optimize_geometry_on(
+    ctx: &SelfAbsContext,
+    formula: &str,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies: &[f64],
+    thickness_um: f64,
+    density_g_cm3: f64,
+    chi_true: f64,
+    bounds: &GeometryBounds,
+) -> Result<BoothGeometryOptimum, SelfAbsError> {
+    let edge: Edge = edge.try_into().map_err(Into::into)?;
+    validate_reference_inputs(thickness_um, density_g_cm3, chi_true)?;
+    let valid_range =
+        |lo: f64, hi: f64| lo.is_finite() && hi.is_finite() && 0.0 < lo && lo <= hi && hi <= 90.0;
+    if !valid_range(bounds.incident_min_deg, bounds.incident_max_deg)
+        || !valid_range(bounds.exit_min_deg, bounds.exit_max_deg)
+    {
+        return Err(SelfAbsError::InsufficientData(
+            "geometry bounds must satisfy 0 < min <= max <= 90 degrees".to_string(),
+        ));
+    }
+    if bounds.grid_points < 2 {
+        return Err(SelfAbsError::InsufficientData(
+            "geometry scan needs at least 2 grid points per angle".to_string(),
+        ));
+    }
+
+    let options = BoothOptions::default();
+    let mu = ReferenceMu::new(
+        ctx,
+        formula,
+        central_element,
+        edge,
+        energies,
+        density_g_cm3,
+        &options,
+    )?;
+    let evaluate = |phi: f64, theta: f64| -> Result<f64, SelfAbsError> {
+        let geo = FluorescenceGeometry {
+            theta_incident_deg: phi,
+            theta_fluorescence_deg: theta,
+            ..Default::default()
+        };
+        let base = mu.booth_result(energies, &geo, thickness_um, density_g_cm3, &options)?;
+        let r = base.suppression_factor(chi_true, density_g_cm3, thickness_um)?;
+        Ok(r.iter().sum::<f64>() / r.len() as f64)
+    };
+
+    let n = bounds.grid_points;
+    let step_phi = (bounds.incident_max_deg - bounds.incident_min_deg) / (n - 1) as f64;
+    let step_theta = (bounds.exit_max_deg - bounds.exit_min_deg) / (n - 1) as f64;
+
+    let mut scan = Vec::with_capacity(n * n);
+    let mut best = (
+        bounds.incident_min_deg,
+        bounds.exit_min_deg,
+        f64::NEG_INFINITY,
+    );
+    for i in 0..n {
+        let phi = bounds.incident_min_deg + step_phi * i as f64;
+        for j in 0..n {
+            let theta = bounds.exit_min_deg + step_theta * j as f64;
+            let r_mean = evaluate(phi, theta)?;
+            if r_mean > best.2 {
+                best = (phi, theta, r_mean);
+            }
+            scan.push(GeometryScanPoint {
+                theta_incident_deg: phi,
+                theta_fluorescence_deg: theta,
+                r_mean,
+            });
+        }
+    }
+
+    // Compass search: try ± steps along each angle, halving on failure.
+    let (mut dphi, mut dtheta) = (step_phi * 0.5, step_theta * 0.5);
+    while dphi.max(dtheta) > GEOMETRY_REFINE_TOL_DEG {
+        let (phi, theta, _) = best;
+        let mut improved = false;
+        for (p, t) in [
+            (phi + dphi, theta),
+            (phi - dphi, theta),
+            (phi, theta + dtheta),
+            (phi, theta - dtheta),
+        ] {
+            let p = p.clamp(bounds.incident_min_deg, bounds.incident_max_deg);
+            let t = t.clamp(bounds.exit_min_deg, bounds.exit_max_deg);
+            if p == phi && t == theta {
+                continue;
+            }
+            let r_mean = evaluate(p, t)?;
+            if r_mean > best.2 {
+                best = (p, t, r_mean);
+                improved = true;
+            }
+        }
+        if !improved {
+            dphi *= 0.5;
+            dtheta *= 0.5;
+        }
+    }
+
+    Ok(BoothGeometryOptimum {
+        geometry: FluorescenceGeometry {
+            theta_incident_deg: best.0,
+            theta_fluorescence_deg: best.1,
+            ..Default::default()
+        },
+        r_mean: best.2,
+        scan,
+    })
+}
+
+/// Uniform exit-angle acceptance: `n` midpoint-rule angles spanning
+/// `[min_deg, max_deg]`, each with weight 1, for
+/// [`BoothOptions::exit_acceptance`].
+pub fn exit_acceptance_range(min_deg: f64, max_deg: f64, n: usize) -> Vec<(f64, f64)> {
+    let step = (max_deg - min_deg) / n as f64;
+    (0..n)
+        .map(|i| (min_deg + step * (i as f64 + 0.5), 1.0))
+        .collect()
+}
+
+/// (geometry ratio, normalized weight) pairs for the exit-angle average.
+fn acceptance_ratios(
+    geo: &FluorescenceGeometry,
+    options: &BoothOptions,
+) -> Result<Vec<(f64, f64)>, SelfAbsError> {
+    options.detection_mode.validate()?;
+    let Some(acceptance) = &options.exit_acceptance else {
+        return Ok(vec![(geo.effective_ratio()?, 1.0)]);
+    };
+    if geo.exit_spread_deg != 0.0 {
+        return Err(SelfAbsError::InsufficientData(
+            "exit acceptance and an exit spread of the geometry both describe the detector acceptance; set only one"
+                .to_string(),
+        ));
+    }
+    let valid = acceptance.iter().all(|&(angle, w)| {
+        angle.is_finite() && angle > 0.0 && angle <= 90.0 && w.is_finite() && w >= 0.0
+    });
+    let w_sum: f64 = acceptance.iter().map(|&(_, w)| w).sum();
+    if !valid || w_sum <= 0.0 {
+        return Err(SelfAbsError::InsufficientData(
+            "exit acceptance needs angles in (0, 90] and non-negative weights with a positive sum"
+                .to_string(),
+        ));
+    }
+    acceptance
+        .iter()
+        .map(|&(angle, w)| {
+            let geo = FluorescenceGeometry {
+                theta_fluorescence_deg: angle,
+                exit_spread_deg: 0.0,
+                ..*geo
+            };
+            Ok((geo.effective_ratio()?, w / w_sum))
+        })
+        .collect()
+}
+
+/// Weight-averaged s(E) and α(E) over the given geometry ratios.
+fn averaged_s_alpha(
+    mu_t: &[f64],
+    mu_a: &[f64],
+    mu_f: f64,
+    ratios: &[(f64, f64)],
+    detection_mode: DetectionMode,
+) -> (Vec<f64>, Vec<f64>) {
+    let n = mu_t.len();
+    let mut s = vec![0.0; n];
+    let mut alpha = vec![0.0; n];
+    for &(ratio, w) in ratios {
+        for i in 0..n {
+            let alpha_i = mu_t[i] + ratio * detection_mode.exit_mu(mu_f, mu_t[i]);
+            // α < 0 only with the exit term reversed (beam from below).
+            let si = if alpha_i != 0.0 {
+                mu_a[i] / alpha_i
+            } else {
+                0.0
+            };
+            alpha[i] += w * alpha_i;
+            s[i] += w * si;
+        }
+    }
+    (s, alpha)
+}
+
+fn validate_reference_inputs(
+    thickness_um: f64,
+    density_g_cm3: f64,
+    chi_true: f64,
+) -> Result<(), SelfAbsError> {
+    if !density_g_cm3.is_finite() || density_g_cm3 <= 0.0 {
+        return Err(SelfAbsError::InsufficientData(
+            "density must be finite and > 0".to_string(),
+        ));
+    }
+    if !thickness_um.is_finite() || thickness_um <= 0.0 {
+        return Err(SelfAbsError::InsufficientData(
+            "thickness_um must be finite and > 0".to_string(),
+        ));
+    }
+    if !chi_true.is_finite() || chi_true == 0.0 {
+        return Err(SelfAbsError::InsufficientData(
+            "chi_true must be finite and non-zero".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Geometry-independent linear attenuation coefficients (1/cm) of a
+/// reference calculation.
+struct ReferenceMu {
+    edge_energy: f64,
+    k: Vec<f64>,
+    mu_t: Vec<f64>,
+    mu_a: Vec<f64>,
+    mu_f: f64,
+    fluorescence_energy: f64,
+    pre_edge_baseline: PreEdgeBaseline,
+}
+
+impl ReferenceMu {
+    fn new(
+        ctx: &SelfAbsContext,
+        formula: &str,
+        central_element: impl Into<ElementRef>,
+        edge: Edge,
+        energies: &[f64],
+        density_g_cm3: f64,
+        options: &BoothOptions,
+    ) -> Result<Self, SelfAbsError> {
+        let info = ctx.sample_info(formula, central_element, edge)?;
+        let e0 = resolve_e0(&info, options.e0_override)?;
+        validate_energy_grid(energies, e0)?;
+        let k = energies_to_k(energies, e0);
+        let mass_fractions = composition_mass_fractions(ctx, &info.composition)?;
+        let backend = options.mu_backend;
+        let tables = ctx.tables(backend);
+        let mu_t = compound_mu_linear(
+            &tables,
+            &mass_fractions,
+            density_g_cm3,
+            energies,
+            options.cross_section,
+        )?;
+        let (mu_a, pre_edge_baseline) = absorber_edge_mu_linear(
+            ctx,
+            &info,
+            energies,
+            density_g_cm3,
+            options.pre_edge_method.unwrap_or(PreEdgeMethod::Trendline),
+            &options.pre_edge_window,
+            backend,
+        )?;
+
+        let (mu_f, fluorescence_energy) = weighted_over_lines(
+            &info,
+            info.emission_lines(),
+            |e| {
+                Ok(path_transmission(ctx, &options.detection_path, e, backend)?
+                    * filter_transmission(ctx, options.detector_filter.as_ref(), e, backend)?)
+            },
+            |e| {
+                compound_mu_linear_single(
+                    &tables,
+                    &mass_fractions,
+                    density_g_cm3,
+                    e,
+                    options.cross_section,
+                )
+            },
+        )?;
+
+        Ok(Self {
+            edge_energy: e0,
+            k,
+            mu_t,
+            mu_a,
+            mu_f,
+            fluorescence_energy,
+            pre_edge_baseline,
+        })
+    }
+
+    fn booth_result(
+        &self,
+        energies: &[f64],
+        geo: &FluorescenceGeometry,
+        thickness_um: f64,
+        density_g_cm3: f64,
+        options: &BoothOptions,
+    ) -> Result<BoothResult, SelfAbsError> {
+        let ratios = acceptance_ratios(geo, options)?;
+        let (s, mut alpha) = averaged_s_alpha(
+            &self.mu_t,
+            &self.mu_a,
+            self.mu_f,
+            &ratios,
+            options.detection_mode,
+        );
+        for a in &mut alpha {
+            *a /= density_g_cm3;
+        }
+
+        let sin_phi = geo.theta_incident_deg.to_radians().sin();
+        let effective_path = thickness_um / sin_phi;
+        let is_thick = effective_path >= THICK_LIMIT_UM;
+
+        Ok(BoothResult {
+            energies: energies.to_vec(),
+            k: self.k.clone(),
+            is_thick,
+            s,
+            alpha,
+            sin_phi,
+            thickness_um,
+            edge_energy: self.edge_energy,
+            fluorescence_energy: self.fluorescence_energy,
+            warnings: Vec::new(),
+            acceptance_averaged: options.exit_acceptance.is_some(),
+        })
+    }
+}
+
+fn summarize_suppression(
+    base: BoothResult,
+    pre_edge_baseline: PreEdgeBaseline,
+    chi_true: f64,
+    density_g_cm3: f64,
+    thickness_um: f64,
+    options: &BoothOptions,
+) -> Result<BoothSuppressionResult, SelfAbsError> {
+    let r = base.suppression_factor(chi_true, density_g_cm3, thickness_um)?;
+    let r_min = r.iter().fold(f64::INFINITY, |m, &v| m.min(v));
+    let r_max = r.iter().fold(f64::NEG_INFINITY, |m, &v| m.max(v));
+    let r_mean = r.iter().sum::<f64>() / r.len() as f64;
+
+    let e_exafs = base.edge_energy + options.exafs_offset_ev;
+    let k_min = options.exafs_k_min.unwrap_or(f64::NEG_INFINITY);
+    let exafs: Vec<f64> = r
+        .iter()
+        .zip(base.energies.iter().zip(base.k.iter()))
+        .filter(|&(_, (&e, &k))| e > e_exafs && k > k_min)
+        .map(|(&v, _)| v)
+        .collect();
+    let n_exafs = exafs.len();
+    let (r_min_exafs, r_max_exafs, r_mean_exafs) = if exafs.is_empty() {
+        (f64::NAN, f64::NAN, f64::NAN)
+    } else {
+        (
+            exafs.iter().fold(f64::INFINITY, |m, &v| m.min(v)),
+            exafs.iter().fold(f64::NEG_INFINITY, |m, &v| m.max(v)),
+            exafs.iter().sum::<f64>() / n_exafs as f64,
+        )
+    };
+
+    Ok(BoothSuppressionResult {
+        energies: base.energies,
+        suppression_factor: r,
+        r_min,
+        r_max,
+        r_mean,
+        r_min_exafs,
+        r_max_exafs,
+        r_mean_exafs,
+        n_exafs,
+        is_thick: base.is_thick,
+        edge_energy: base.edge_energy,
+        fluorescence_energy: base.fluorescence_energy,
+        pre_edge_baseline,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ameyanagi::{
+        AmeyanagiMuAMethod, AmeyanagiSuppressionSettings, AmeyanagiThicknessInput,
+        ameyanagi_suppression_exact,
+    };
+    use crate::common::{LayerIncidence, LayerSpec, SelfAbsErrorKind};
+    use xraydb::XrayDb;
+
+    #[test]
+    fn test_booth_thick_fe2o3() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        // 100 mm = effectively infinite thickness
+        let result = booth("Fe2O3", "Fe", Edge::K, &energies, None, 100_000.0).unwrap();
+
+        assert!(result.is_thick);
 
         // s(k) should be 0..1 above edge
         for (i, &si) in result.s.iter().enumerate() {
@@ -443,14 +1476,14 @@ mod tests {
     fn test_booth_thin_sample() {
         let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
         // 10 μm = thin
-        let result = booth("Fe2O3", "Fe", "K", &energies, None, 10.0).unwrap();
+        let result = booth("Fe2O3", "Fe", Edge::K, &energies, None, 10.0).unwrap();
         assert!(!result.is_thick);
     }
 
     #[test]
     fn test_booth_thick_correction() {
         let energies: Vec<f64> = (7100..=8000).step_by(5).map(|e| e as f64).collect();
-        let result = booth("Fe2O3", "Fe", "K", &energies, None, 100_000.0).unwrap();
+        let result = booth("Fe2O3", "Fe", Edge::K, &energies, None, 100_000.0).unwrap();
 
         // Simulate chi data
         let chi: Vec<f64> = result.k.iter().map(|&ki| 0.1 * (-0.5 * ki).exp()).collect();
@@ -464,10 +1497,724 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_booth_correct_chi_batch_matches_sequential() {
+        let energies: Vec<f64> = (7100..=7600).step_by(5).map(|e| e as f64).collect();
+        for thickness_um in [10.0, 100_000.0] {
+            let result = booth("Fe2O3", "Fe", Edge::K, &energies, None, thickness_um).unwrap();
+            let spectra: Vec<Vec<f64>> = (0..64)
+                .map(|j| {
+                    let amp = 0.02 + 0.005 * j as f64;
+                    result.k.iter().map(|&k| amp * (2.1 * k).sin()).collect()
+                })
+                .collect();
+            let refs: Vec<&[f64]> = spectra.iter().map(Vec::as_slice).collect();
+
+            let batch = result.correct_chi_batch(&refs, 5.24, thickness_um);
+            assert_eq!(batch.len(), spectra.len());
+            for (chi, corrected) in spectra.iter().zip(batch.iter()) {
+                assert_eq!(corrected, &result.correct_chi(chi, 5.24, thickness_um));
+            }
+        }
+    }
+
+    #[test]
+    fn test_booth_correct_mu_norm_matches_chi_far_above_edge() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        let result = booth("Fe2O3", "Fe", Edge::K, &energies, None, 100_000.0).unwrap();
+        assert!(result.is_thick);
+
+        let mu_norm: Vec<f64> = result
+            .k
+            .iter()
+            .map(|&k| {
+                if k > 0.0 {
+                    1.0 + 0.1 * (2.0 * k).sin() * (-0.05 * k * k).exp()
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        let corrected = result.correct_mu_norm(&mu_norm, 5.24, 100_000.0).unwrap();
+
+        // Solving χ_exp = χ (1 − s) / (1 + s χ) for χ with χ_exp = m − 1
+        // gives χ = (m − 1) / (1 − s m) by hand.
+        for (i, &c) in corrected.iter().enumerate() {
+            if result.k[i] > 4.0 {
+                let m = mu_norm[i];
+                let expected = 1.0 + (m - 1.0) / (1.0 - result.s[i] * m);
+                assert!((c - expected).abs() < 1e-12, "i={i}: {c} vs {expected}");
+            }
+        }
+
+        // Round trip through the forward model: a flat μ_norm = 1.1 above
+        // the edge, suppressed by R(E, 0.1), is corrected back to 1.1.
+        let r = result.suppression_factor(0.1, 5.24, 100_000.0).unwrap();
+        let measured: Vec<f64> = r.iter().map(|r| 1.0 + 0.1 * r).collect();
+        let recovered = result.correct_mu_norm(&measured, 5.24, 100_000.0).unwrap();
+        for (i, &m) in recovered.iter().enumerate() {
+            if result.k[i] > 4.0 {
+                assert!((m - 1.1).abs() < 1e-12, "i={i}: {m}");
+            }
+        }
+
+        let thin = booth("Fe2O3", "Fe", Edge::K, &energies, None, 5.0).unwrap();
+        let thin_corr = thin.correct_mu_norm(&mu_norm, 5.24, 5.0).unwrap();
+        assert!(thin_corr.iter().all(|v| v.is_finite()));
+        assert!(thin.correct_mu_norm(&mu_norm[1..], 5.24, 5.0).is_err());
+    }
+
+    #[test]
+    fn test_booth_exit_acceptance_average() {
+        let energies: Vec<f64> = (7100..=7600).step_by(5).map(|e| e as f64).collect();
+        let at = |theta: f64| {
+            let geo = FluorescenceGeometry {
+                theta_incident_deg: 45.0,
+                theta_fluorescence_deg: theta,
+                ..Default::default()
+            };
+            booth("Fe2O3", "Fe", Edge::K, &energies, Some(geo), 100_000.0).unwrap()
+        };
+        let options = |acceptance: Vec<(f64, f64)>| BoothOptions {
+            exit_acceptance: Some(acceptance),
+            ..Default::default()
+        };
+        let with_acceptance = |acceptance: Vec<(f64, f64)>| {
+            booth_with_options(
+                "Fe2O3",
+                "Fe",
+                Edge::K,
+                &energies,
+                None,
+                100_000.0,
+                &options(acceptance),
+            )
+            .unwrap()
+        };
+
+        let single = with_acceptance(vec![(45.0, 3.0)]);
+        let plain = at(45.0);
+        assert!(single.acceptance_averaged && !plain.acceptance_averaged);
+        assert_eq!(single.s, plain.s);
+        assert_eq!(single.alpha, plain.alpha);
+
+        let low = at(30.0);
+        let high = at(60.0);
+        let averaged = with_acceptance(exit_acceptance_range(30.0, 60.0, 7));
+        for i in 0..energies.len() {
+            let (lo, hi) = (low.s[i].min(high.s[i]), low.s[i].max(high.s[i]));
+            assert!(
+                averaged.s[i] >= lo && averaged.s[i] <= hi,
+                "i={i}: {} not in [{lo}, {hi}]",
+                averaged.s[i]
+            );
+        }
+
+        // Each acceptance angle is averaged over the geometry's incident
+        // spread; an exit spread on top is rejected.
+        let spread = |incident_spread_deg: f64, exit_spread_deg: f64| FluorescenceGeometry {
+            incident_spread_deg,
+            exit_spread_deg,
+            ..Default::default()
+        };
+        let spread_plain = booth(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies,
+            Some(spread(10.0, 0.0)),
+            100_000.0,
+        )
+        .unwrap();
+        let spread_single = booth_with_options(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies,
+            Some(spread(10.0, 0.0)),
+            100_000.0,
+            &options(vec![(45.0, 1.0)]),
+        )
+        .unwrap();
+        assert_eq!(spread_single.alpha, spread_plain.alpha);
+        assert_ne!(spread_single.alpha, plain.alpha);
+        let err = booth_with_options(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies,
+            Some(spread(0.0, 10.0)),
+            100_000.0,
+            &options(vec![(45.0, 1.0)]),
+        )
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("exit spread"), "{err}");
+    }
+
+    #[test]
+    fn test_booth_layered_window_only_attenuates() {
+        let energies: Vec<f64> = (7100..=7600).step_by(5).map(|e| e as f64).collect();
+        let film = LayeredSample::new(LayerSpec {
+            formula: "Fe2O3".to_string(),
+            density: 5.24,
+            thickness_um: 10.0,
+        });
+        let kapton = LayerSpec {
+            formula: "C22H10N2O5".to_string(),
+            density: 1.42,
+            thickness_um: 25.0,
+        };
+
+        let plain = booth("Fe2O3", "Fe", Edge::K, &energies, None, 10.0).unwrap();
+        let single = booth_layered(&film, "Fe", Edge::K, &energies, None).unwrap();
+        assert_eq!(single.booth.s, plain.s);
+        assert_eq!(single.booth.alpha, plain.alpha);
+        assert_eq!(single.booth.is_thick, plain.is_thick);
+        assert!(single.incident_transmission.iter().all(|&t| t == 1.0));
+        assert_eq!(single.fluorescence_transmission, 1.0);
+
+        let windowed = booth_layered(
+            &film.clone().with_cap(kapton),
+            "Fe",
+            Edge::K,
+            &energies,
+            None,
+        )
+        .unwrap();
+        let r_plain = plain.suppression_factor(0.2, 5.24, 10.0).unwrap();
+        let r_windowed = windowed.booth.suppression_factor(0.2, 5.24, 10.0).unwrap();
+        assert_eq!(r_plain, r_windowed);
+        assert!(
+            windowed
+                .incident_transmission
+                .iter()
+                .all(|&t| t > 0.5 && t < 1.0)
+        );
+        assert!(windowed.fluorescence_transmission < 1.0);
+        let mut bad_cap = film.clone().with_cap(LayerSpec {
+            formula: "C22H10N2O5".to_string(),
+            density: -1.0,
+            thickness_um: 25.0,
+        });
+        assert!(booth_layered(&bad_cap, "Fe", Edge::K, &energies, None).is_err());
+        bad_cap.caps.clear();
+        assert!(booth_layered(&bad_cap, "Fe", Edge::K, &energies, None).is_ok());
+
+        // The absorber itself is validated too.
+        for (density, thickness_um) in [(0.0, 10.0), (f64::NAN, 10.0), (5.24, 0.0)] {
+            let mut bad = film.clone();
+            bad.absorber.density = density;
+            bad.absorber.thickness_um = thickness_um;
+            assert!(booth_layered(&bad, "Fe", Edge::K, &energies, None).is_err());
+        }
+
+        // A substrate below the film is never reached in reflection
+        // geometry.
+        let silicon = LayerSpec {
+            formula: "Si".to_string(),
+            density: 2.33,
+            thickness_um: 500.0,
+        };
+        let on_wafer = film.clone().with_substrate(silicon);
+        let supported = booth_layered(&on_wafer, "Fe", Edge::K, &energies, None).unwrap();
+        assert_eq!(supported.booth.s, plain.s);
+        assert!(supported.incident_transmission.iter().all(|&t| t == 1.0));
+        let through = LayeredSample {
+            incidence: LayerIncidence::ThroughSubstrate,
+            ..on_wafer.clone()
+        };
+        let capped_through = LayeredSample {
+            caps: vec![LayerSpec {
+                formula: "C22H10N2O5".to_string(),
+                density: 1.42,
+                thickness_um: 25.0,
+            }],
+            ..through.clone()
+        };
+        let from_below = booth_layered(&through, "Fe", Edge::K, &energies, None).unwrap();
+        let capped_below = booth_layered(&capped_through, "Fe", Edge::K, &energies, None).unwrap();
+
+        // The substrate attenuates the incident beam, the caps only the
+        // fluorescence.
+        assert!(
+            from_below
+                .incident_transmission
+                .iter()
+                .all(|&t| t > 0.0 && t < 1.0)
+        );
+        assert_eq!(
+            capped_below.incident_transmission,
+            from_below.incident_transmission
+        );
+        assert_eq!(from_below.fluorescence_transmission, 1.0);
+        assert!(capped_below.fluorescence_transmission < 1.0);
+
+        // The exit term of α is reversed and the thin branch is forced.
+        assert!(!from_below.booth.is_thick);
+        for (below, front) in from_below.booth.alpha.iter().zip(&plain.alpha) {
+            assert!(below < front && below + front > 0.0);
+        }
+
+        // With a steep exit the reversed α stays above μ_a, so χ_exp is
+        // single-valued in χ. Check it against Booth's thin-slab intensity,
+        // with exp(−μ_a χ d′) linearised in χ as in the forward model.
+        let steep = FluorescenceGeometry {
+            theta_incident_deg: 20.0,
+            theta_fluorescence_deg: 70.0,
+            ..FluorescenceGeometry::default()
+        };
+        let steep_front = booth_layered(&on_wafer, "Fe", Edge::K, &energies, Some(steep)).unwrap();
+        let steep_below = booth_layered(&through, "Fe", Edge::K, &energies, Some(steep))
+            .unwrap()
+            .booth;
+        let chi_true = 0.2;
+        let r_front = steep_front
+            .booth
+            .suppression_factor(chi_true, 5.24, 10.0)
+            .unwrap();
+        let r_below = steep_below
+            .suppression_factor(chi_true, 5.24, 10.0)
+            .unwrap();
+        assert_ne!(r_below, r_front);
+        let depth = 10.0e-4 / steep_below.sin_phi;
+        for (i, &r) in r_below.iter().enumerate() {
+            let alpha = steep_below.alpha[i] * 5.24;
+            let mu_a = steep_below.s[i] * alpha;
+            let eta = alpha * depth;
+            let ratio = (1.0 + chi_true) * alpha / (alpha + mu_a * chi_true)
+                * (1.0 - (-eta).exp() * (1.0 - mu_a * chi_true * depth))
+                / (-(-eta).exp_m1());
+            let expected = (ratio - 1.0) / chi_true;
+            assert!((r - expected).abs() < 1e-9, "i={i}: {r} vs {expected}");
+        }
+        let chi_exp: Vec<f64> = r_below.iter().map(|r| r * chi_true).collect();
+        for c in steep_below.correct_chi(&chi_exp, 5.24, 10.0) {
+            assert!((c - chi_true).abs() < 1e-9, "{c}");
+        }
+
+        // Options reach the layered path, including the μ backend of the
+        // substrate transmission.
+        let chantler = booth_layered_with_options(
+            &through,
+            "Fe",
+            Edge::K,
+            &energies,
+            None,
+            &BoothOptions {
+                mu_backend: MuBackend::Chantler,
+                ..BoothOptions::default()
+            },
+        )
+        .unwrap();
+        assert_ne!(
+            chantler.incident_transmission,
+            from_below.incident_transmission
+        );
+    }
+
+    #[test]
+    fn test_booth_probing_depth_drops_across_edge() {
+        let energies: Vec<f64> = (7000..=7300).step_by(5).map(|e| e as f64).collect();
+        let result = booth("Fe2O3", "Fe", Edge::K, &energies, None, 100_000.0).unwrap();
+        let depth = result.probing_depth_um(5.24);
+        assert_eq!(depth.len(), energies.len());
+
+        let below = energies
+            .iter()
+            .rposition(|&e| e < result.edge_energy)
+            .unwrap();
+        let above = energies
+            .iter()
+            .position(|&e| e > result.edge_energy + 20.0)
+            .unwrap();
+        assert!(depth.iter().all(|d| d.is_finite() && *d > 0.0));
+        assert!(
+            depth[above] < 0.5 * depth[below],
+            "below={} above={}",
+            depth[below],
+            depth[above]
+        );
+        assert!(result.probing_depth_um(0.0).iter().all(|d| d.is_infinite()));
+        // Semi-infinite: the 1/e depth sin φ / (α ρ).
+        for (d, a) in depth.iter().zip(&result.alpha) {
+            let expected = 1e4 * result.sin_phi / (a * 5.24);
+            assert!((d - expected).abs() <= 1e-9 * expected, "{d} vs {expected}");
+        }
+    }
+
+    #[test]
+    fn test_booth_probing_depth_thin_film() {
+        let energies: Vec<f64> = (7000..=7300).step_by(5).map(|e| e as f64).collect();
+        let thick = booth("Fe2O3", "Fe", Edge::K, &energies, None, 100_000.0).unwrap();
+        let film = booth("Fe2O3", "Fe", Edge::K, &energies, None, 2.0).unwrap();
+        assert!(!film.is_thick);
+        assert_eq!(film.thickness_um, 2.0);
+        let semi_infinite = thick.probing_depth_um(5.24);
+        let depth = film.probing_depth_um(5.24);
+
+        // The film bounds the depth; it stays below (1 − 1/e) d and below
+        // the semi-infinite depth.
+        let fraction = 1.0 - (-1.0f64).exp();
+        for (d, d_inf) in depth.iter().zip(&semi_infinite) {
+            assert!(*d > 0.0 && *d < fraction * 2.0, "{d}");
+            assert!(d < d_inf, "{d} vs {d_inf}");
+        }
+        // Below the edge the film is fairly transparent and the signal
+        // close to uniform in depth.
+        let last = energies.len() - 1;
+        assert!(depth[0] > depth[last]);
+        assert!(
+            (depth[0] / (fraction * 2.0) - 1.0).abs() < 0.15,
+            "{}",
+            depth[0]
+        );
+
+        // 63% of the film's signal comes from above z*.
+        let a = film.alpha[last] * 5.24 / film.sin_phi;
+        let signal = |z_um: f64| -(-a * z_um * 1e-4).exp_m1();
+        let share = signal(depth[last]) / signal(2.0);
+        assert!((share - fraction).abs() < 1e-12, "{share}");
+    }
+
+    #[test]
+    fn test_booth_sigma_propagation() {
+        let energies: Vec<f64> = (7100..=7600).step_by(5).map(|e| e as f64).collect();
+        let thick = booth("Fe2O3", "Fe", Edge::K, &energies, None, 100_000.0).unwrap();
+        let chi: Vec<f64> = thick.k.iter().map(|&k| 0.15 * (2.3 * k).sin()).collect();
+
+        let h = 1e-6;
+        for (i, &c) in chi.iter().enumerate() {
+            let analytic = thick.thick_derivative(i, c);
+            let numeric = (thick.correct_single_thick(i, c + h)
+                - thick.correct_single_thick(i, c - h))
+                / (2.0 * h);
+            assert!(
+                (analytic - numeric).abs() < 1e-6,
+                "i={i}: analytic={analytic} numeric={numeric}"
+            );
+        }
+
+        let zeros = vec![0.0; chi.len()];
+        let sigma = vec![0.01; chi.len()];
+        let thin = booth("Fe2O3", "Fe", Edge::K, &energies, None, 10.0).unwrap();
+        for (result, thickness) in [(&thick, 100_000.0), (&thin, 10.0)] {
+            let (corr, sig0) = result
+                .correct_chi_with_sigma(&chi, &zeros, 5.24, thickness)
+                .unwrap();
+            assert_eq!(corr, result.correct_chi(&chi, 5.24, thickness));
+            assert!(sig0.iter().all(|&v| v == 0.0));
+
+            let (_, sig) = result
+                .correct_chi_with_sigma(&chi, &sigma, 5.24, thickness)
+                .unwrap();
+            // Self-absorption damps χ, so undoing it inflates the error bars.
+            assert!(sig.iter().all(|&v| v.is_finite() && v >= 0.01));
+        }
+        assert!(
+            thick
+                .correct_chi_with_sigma(&chi, &sigma[1..], 5.24, 100_000.0)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_booth_detection_path_reweights_lines() {
+        let energies: Vec<f64> = (4000..=4400).step_by(5).map(|e| e as f64).collect();
+        let empty = BoothOptions {
+            detection_path: Vec::new(),
+            ..Default::default()
+        };
+        let plain =
+            booth_suppression_reference("CaCO3", "Ca", Edge::K, &energies, None, 100.0, 2.71, 0.2)
+                .unwrap();
+        let unchanged = booth_suppression_reference_with_options(
+            "CaCO3",
+            "Ca",
+            Edge::K,
+            &energies,
+            None,
+            100.0,
+            2.71,
+            0.2,
+            &empty,
+        )
+        .unwrap();
+        for (a, b) in plain
+            .suppression_factor
+            .iter()
+            .zip(unchanged.suppression_factor.iter())
+        {
+            assert!((a - b).abs() < 1e-12, "{a} vs {b}");
+        }
+        assert!((plain.fluorescence_energy - unchanged.fluorescence_energy).abs() < 1e-9);
+
+        // A thick Be window transmits Ca Kβ better than Kα.
+        let windowed = BoothOptions {
+            detection_path: vec![("Be".to_string(), 1.85, 0.05)],
+            ..Default::default()
+        };
+        let filtered = booth_suppression_reference_with_options(
+            "CaCO3",
+            "Ca",
+            Edge::K,
+            &energies,
+            None,
+            100.0,
+            2.71,
+            0.2,
+            &windowed,
+        )
+        .unwrap();
+        let shift = filtered.fluorescence_energy - plain.fluorescence_energy;
+        assert!(shift > 0.5 && shift < 50.0, "shift={shift}");
+
+        let booth_filtered =
+            booth_with_options("CaCO3", "Ca", Edge::K, &energies, None, 100.0, &windowed).unwrap();
+        let booth_plain = booth("CaCO3", "Ca", Edge::K, &energies, None, 100.0).unwrap();
+        // The window moves μ_f's energy further toward Kβ.
+        assert!(booth_filtered.fluorescence_energy > booth_plain.fluorescence_energy);
+    }
+
+    #[test]
+    fn test_detector_filter_reweights_lines() {
+        use crate::ameyanagi::ameyanagi_suppression_profile;
+        use crate::fluo::{FluoOptions, fluo_params_with_options};
+        use crate::troger::{TrogerOptions, troger_with_options};
+
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        // 20 μm of Mn is about six absorption lengths at Fe Kβ (above the
+        // Mn K edge at 6539 eV) and under one at Fe Kα (below it).
+        let mn = Some(("Mn".to_string(), 7.43, 20.0));
+
+        let unfiltered = booth_with_options(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies,
+            None,
+            100.0,
+            &BoothOptions {
+                detector_filter: None,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let plain = booth("Fe2O3", "Fe", Edge::K, &energies, None, 100.0).unwrap();
+        assert_eq!(unfiltered.fluorescence_energy, plain.fluorescence_energy);
+        assert_eq!(unfiltered.s, plain.s);
+
+        // With Kβ absorbed the weighted line energy falls toward Kα.
+        let booth_options = BoothOptions {
+            detector_filter: mn.clone(),
+            ..Default::default()
+        };
+        let filtered = booth_with_options(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies,
+            None,
+            100.0,
+            &booth_options,
+        )
+        .unwrap();
+        let shift = filtered.fluorescence_energy - plain.fluorescence_energy;
+        assert!(shift < -10.0 && shift > -100.0, "shift={shift}");
+
+        let troger_options = TrogerOptions {
+            detector_filter: mn.clone(),
+            ..Default::default()
+        };
+        let troger_plain =
+            troger_with_options("Fe2O3", "Fe", Edge::K, &energies, None, &Default::default())
+                .unwrap();
+        let troger_filtered =
+            troger_with_options("Fe2O3", "Fe", Edge::K, &energies, None, &troger_options).unwrap();
+        assert!(troger_filtered.fluorescence_energy < troger_plain.fluorescence_energy - 10.0);
+
+        let fluo_options = FluoOptions {
+            detector_filter: mn.clone(),
+            ..Default::default()
+        };
+        let fluo_plain =
+            fluo_params_with_options("Fe2O3", "Fe", Edge::K, &energies, None, &Default::default())
+                .unwrap();
+        let fluo_filtered =
+            fluo_params_with_options("Fe2O3", "Fe", Edge::K, &energies, None, &fluo_options)
+                .unwrap();
+        assert!(fluo_filtered.fluorescence_energy < fluo_plain.fluorescence_energy - 10.0);
+
+        let ameyanagi = |filter: Option<(String, f64, f64)>| {
+            let mut builder = AmeyanagiSuppressionSettings::builder()
+                .density(5.24)
+                .thickness_cm(1e-2)
+                .chi(0.1);
+            if let Some((formula, density, thickness_um)) = filter {
+                builder = builder.detector_filter(&formula, density, thickness_um);
+            }
+            let settings = builder.build().unwrap();
+            ameyanagi_suppression_profile(
+                "Fe2O3",
+                "Fe",
+                Edge::K,
+                &energies,
+                settings.sample(),
+                &vec![0.0; energies.len()],
+            )
+            .unwrap()
+        };
+        let amey_plain = ameyanagi(None);
+        let amey_filtered = ameyanagi(mn);
+        assert!(
+            amey_filtered.fluorescence_energy_weighted
+                < amey_plain.fluorescence_energy_weighted - 10.0
+        );
+    }
+
+    #[test]
+    fn test_booth_chantler_backend() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        let run = |mu_backend| {
+            let options = BoothOptions {
+                mu_backend,
+                ..Default::default()
+            };
+            booth_with_options("Fe2O3", "Fe", Edge::K, &energies, None, 100_000.0, &options)
+                .unwrap()
+        };
+        let elam = run(MuBackend::Elam);
+        let chantler = run(MuBackend::Chantler);
+
+        // Above the edge the tabulations differ by a few percent of s ≈ 0.66
+        // (about 0.02 for Fe K); 0.05 bounds the spread between the tables.
+        let above = elam.energies.iter().position(|&e| e > 7150.0).unwrap();
+        let max_diff = elam.s[above..]
+            .iter()
+            .zip(&chantler.s[above..])
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f64::max);
+        assert!(max_diff > 1e-4, "max_diff={max_diff}");
+        assert!(max_diff < 0.05, "max_diff={max_diff}");
+    }
+
+    #[test]
+    fn test_booth_accepts_atomic_number() {
+        let energies: Vec<f64> = (7000..=8000).step_by(10).map(|e| e as f64).collect();
+        let by_symbol = booth("Fe2O3", "Fe", Edge::K, &energies, None, 10.0).unwrap();
+        let by_z = booth("Fe2O3", 26u16, Edge::K, &energies, None, 10.0).unwrap();
+        assert_eq!(by_symbol.s, by_z.s);
+        assert_eq!(by_symbol.alpha, by_z.alpha);
+        assert_eq!(by_symbol.edge_energy, by_z.edge_energy);
+
+        // Resolution failures name the input.
+        let err = booth("Fe2O3", "Xx", Edge::K, &energies, None, 10.0).unwrap_err();
+        assert_eq!(err.kind(), SelfAbsErrorKind::InvalidElement);
+        assert!(err.to_string().contains("'Xx'"), "{err}");
+        let err = booth("Fe2O3", 200u16, Edge::K, &energies, None, 10.0).unwrap_err();
+        assert!(err.to_string().contains("Z = 200"), "{err}");
+    }
+
+    #[test]
+    fn test_booth_hydrate_notation() {
+        // The water dilutes the absorber, so s = μ̄_a/α drops.
+        let energies: Vec<f64> = (8800..=9800).step_by(10).map(|e| e as f64).collect();
+        let hydrate = booth("CuSO4·5H2O", "Cu", Edge::K, &energies, None, 100_000.0).unwrap();
+        let anhydrous = booth("CuSO4", "Cu", Edge::K, &energies, None, 100_000.0).unwrap();
+        let above = energies.iter().position(|&e| e > 9030.0).unwrap();
+        for (h, a) in hydrate.s[above..].iter().zip(&anhydrous.s[above..]) {
+            assert!(*h < 0.9 * a, "{h} vs {a}");
+        }
+    }
+
+    #[test]
+    fn test_booth_reference_reports_pre_edge_fallback() {
+        let reference = |energies: &[f64]| {
+            booth_suppression_reference("Fe2O3", "Fe", Edge::K, energies, None, 10.0, 5.24, 0.2)
+                .unwrap()
+        };
+        // E0 - 200 .. E0 - 30 eV lies inside the first grid only.
+        let covered: Vec<f64> = (6800..=8000).step_by(5).map(|e| e as f64).collect();
+        let missed: Vec<f64> = (7100..=8000).step_by(5).map(|e| e as f64).collect();
+        assert_eq!(
+            reference(&covered).pre_edge_baseline,
+            PreEdgeBaseline::Trendline
+        );
+        assert_eq!(
+            reference(&missed).pre_edge_baseline,
+            PreEdgeBaseline::Fallback
+        );
+    }
+
+    #[test]
+    fn test_booth_validates_energy_grid() {
+        let invalid: [&[f64]; 5] = [
+            &[],
+            &[7000.0, f64::NAN, 7200.0],
+            &[7000.0, f64::NEG_INFINITY],
+            &[7200.0, 7100.0, 7300.0],
+            &[7000.0, 7100.0, 7100.0],
+        ];
+        for energies in invalid {
+            assert!(
+                booth("Fe2O3", "Fe", Edge::K, energies, None, 10.0).is_err(),
+                "{energies:?}"
+            );
+            assert!(
+                booth_suppression_reference(
+                    "Fe2O3",
+                    "Fe",
+                    Edge::K,
+                    energies,
+                    None,
+                    10.0,
+                    5.24,
+                    0.2
+                )
+                .is_err(),
+                "{energies:?}"
+            );
+        }
+
+        let summary = validate_energy_grid(&[6900.0, 7000.0, 7100.0], 7112.0).unwrap();
+        assert_eq!((summary.n_below_edge, summary.n_above_edge), (3, 0));
+        assert!(!summary.spans_edge());
+
+        let below: Vec<f64> = (6900..=7100).step_by(10).map(|e| e as f64).collect();
+        let result = booth("Fe2O3", "Fe", Edge::K, &below, None, 10.0).unwrap();
+        assert!(
+            result
+                .warnings
+                .iter()
+                .any(|w| matches!(w, SelfAbsWarning::GridMissesEdge { .. })),
+            "{:?}",
+            result.warnings
+        );
+    }
+
+    #[test]
+    fn test_booth_weighted_lines_cu_k() {
+        let energies: Vec<f64> = (9000..=9900).step_by(10).map(|e| e as f64).collect();
+        let strongest = BoothOptions {
+            fluorescence_lines: FluorescenceLineMode::Strongest,
+            ..Default::default()
+        };
+        let single =
+            booth_with_options("CuO", "Cu", Edge::K, &energies, None, 100.0, &strongest).unwrap();
+        let weighted = booth("CuO", "Cu", Edge::K, &energies, None, 100.0).unwrap();
+        assert!(weighted.fluorescence_energy > single.fluorescence_energy);
+
+        // Kβ is absorbed less than Kα, so s rises by well under a percent.
+        for (a, b) in single.s.iter().zip(&weighted.s) {
+            let rel = b / a - 1.0;
+            assert!((0.0005..0.02).contains(&rel), "rel={rel}");
+        }
+    }
+
     #[test]
     fn test_booth_thick_suppression_matches_closed_form() {
         let energies: Vec<f64> = (7100..=8000).step_by(5).map(|e| e as f64).collect();
-        let result = booth("Fe2O3", "Fe", "K", &energies, None, 100_000.0).unwrap();
+        let result = booth("Fe2O3", "Fe", Edge::K, &energies, None, 100_000.0).unwrap();
         assert!(result.is_thick);
 
         let chi_true = 0.2;
@@ -492,7 +2239,7 @@ mod tests {
         let density = 5.24;
         let chi_true = 0.2;
 
-        let result = booth("Fe2O3", "Fe", "K", &energies, None, thickness_um).unwrap();
+        let result = booth("Fe2O3", "Fe", Edge::K, &energies, None, thickness_um).unwrap();
         assert!(!result.is_thick);
 
         let r = result
@@ -510,6 +2257,193 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_booth_thin_inversion_stress_grid() {
+        let energies: Vec<f64> = (7000..=8000).step_by(10).map(|e| e as f64).collect();
+        let mut max_iterations = 0;
+        for formula in ["Fe2O3", "Fe0.02Si0.98O2"] {
+            for &thickness_um in &[0.5, 2.0, 5.0, 10.0] {
+                let result = booth(formula, "Fe", Edge::K, &energies, None, thickness_um).unwrap();
+                assert!(!result.is_thick);
+                for &density in &[1.0, 3.0, 5.24] {
+                    for &chi_true in &[-0.3, -0.05, 0.05, 0.2, 0.5] {
+                        let (r, diagnostics) = result
+                            .suppression_factor_with_diagnostics(chi_true, density, thickness_um)
+                            .unwrap();
+                        let chi_exp: Vec<f64> = r.iter().map(|ri| ri * chi_true).collect();
+                        let chi_corr = result.correct_chi(&chi_exp, density, thickness_um);
+                        for (i, (&c, d)) in chi_corr.iter().zip(diagnostics.iter()).enumerate() {
+                            assert!(
+                                (c - chi_true).abs() < 1e-10,
+                                "{formula}, d={thickness_um}, rho={density}, chi={chi_true}, i={i}: {c}"
+                            );
+                            assert!(d.residual < 1e-10, "residual={}", d.residual);
+                            max_iterations = max_iterations.max(d.iterations);
+                        }
+                    }
+                }
+            }
+        }
+        assert!(max_iterations <= 40, "max iterations={max_iterations}");
+    }
+
+    #[test]
+    fn test_booth_total_cross_section_for_dilute_aqueous_zn() {
+        let energies: Vec<f64> = (9700..=10500).step_by(5).map(|e| e as f64).collect();
+        let total = BoothOptions {
+            cross_section: CrossSectionKind::Total,
+            ..Default::default()
+        };
+
+        let photo_ref = booth_suppression_reference(
+            "Zn0.01(H2O)55",
+            "Zn",
+            Edge::K,
+            &energies,
+            None,
+            1000.0,
+            1.0,
+            0.2,
+        )
+        .unwrap();
+        let total_ref = booth_suppression_reference_with_options(
+            "Zn0.01(H2O)55",
+            "Zn",
+            Edge::K,
+            &energies,
+            None,
+            1000.0,
+            1.0,
+            0.2,
+            &total,
+        )
+        .unwrap();
+
+        // Scattering adds to α, so the suppression deviation 1 − R shrinks by
+        // a few percent for a water matrix.
+        let dev_photo = 1.0 - photo_ref.r_mean;
+        let dev_total = 1.0 - total_ref.r_mean;
+        let rel = (dev_photo - dev_total) / dev_photo;
+        assert!((0.01..0.2).contains(&rel), "relative change={rel}");
+
+        let default_booth = booth("Zn0.01(H2O)55", "Zn", Edge::K, &energies, None, 1000.0).unwrap();
+        let total_booth = booth_with_options(
+            "Zn0.01(H2O)55",
+            "Zn",
+            Edge::K,
+            &energies,
+            None,
+            1000.0,
+            &total,
+        )
+        .unwrap();
+        for (i, (&sp, &st)) in default_booth.s.iter().zip(total_booth.s.iter()).enumerate() {
+            if default_booth.k[i] > 0.0 {
+                assert!(st < sp, "s_total={st} >= s_photo={sp} at {i}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_booth_warns_about_interfering_edges() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        let clean = booth("Fe2O3", "Fe", Edge::K, &energies, None, 100_000.0).unwrap();
+        assert!(clean.warnings.is_empty(), "{:?}", clean.warnings);
+
+        let energies: Vec<f64> = (11400..=13600).step_by(5).map(|e| e as f64).collect();
+        let crossing = booth("PtO2", "Pt", Edge::L3, &energies, None, 100_000.0).unwrap();
+        assert!(
+            crossing.warnings.iter().any(|w| matches!(
+                w,
+                SelfAbsWarning::InterferingEdge { element, edge, energy }
+                    if element == "Pt" && edge == "L2" && *energy > 13000.0
+            )),
+            "{:?}",
+            crossing.warnings
+        );
+    }
+
+    #[test]
+    fn test_booth_reference_exafs_statistics() {
+        let db = XrayDb::new();
+        let e0 = db.xray_edge("Fe", "K").unwrap().energy;
+        let energies: Vec<f64> = (0..=700).map(|i| e0 - 100.0 + 2.0 * i as f64).collect();
+
+        let r =
+            booth_suppression_reference("Fe2O3", "Fe", Edge::K, &energies, None, 100.0, 5.24, 0.2)
+                .unwrap();
+        let expected_n = energies.iter().filter(|&&e| e > e0 + 30.0).count();
+        assert_eq!(r.n_exafs, expected_n);
+        assert!(r.r_min_exafs >= r.r_min && r.r_max_exafs <= r.r_max);
+        assert!(
+            (r.r_mean_exafs - r.r_mean).abs() > 1e-3,
+            "exafs mean={} full mean={}",
+            r.r_mean_exafs,
+            r.r_mean
+        );
+
+        let with_k = booth_suppression_reference_with_options(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies,
+            None,
+            100.0,
+            5.24,
+            0.2,
+            &BoothOptions {
+                exafs_k_min: Some(8.0),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(with_k.n_exafs < r.n_exafs && with_k.n_exafs > 0);
+        assert!((with_k.r_mean - r.r_mean).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_booth_optimize_geometry_thick_concentrated() {
+        let energies: Vec<f64> = (7150..=7800).step_by(10).map(|e| e as f64).collect();
+        let bounds = GeometryBounds::default();
+        let opt = booth_optimize_geometry(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies,
+            1000.0,
+            5.24,
+            0.2,
+            &bounds,
+        )
+        .unwrap();
+
+        assert_eq!(opt.scan.len(), bounds.grid_points * bounds.grid_points);
+        assert!(opt.scan.iter().all(|p| p.r_mean <= opt.r_mean));
+
+        // In the thick limit s = μ̄_a / (μ_t + μ_f sin φ / sin θ), so the
+        // suppression is smallest for steep incidence and glancing exit:
+        // a large sin φ / sin θ limits the probed depth by the exit path,
+        // the grazing-exit geometry of Tröger et al. The grazing incidence
+        // and normal exit one might expect instead drives sin φ / sin θ to
+        // 0, where s reaches its largest value μ̄_a / μ_t, so the optimum
+        // lands on the opposite corner of the bounds.
+        assert!(
+            (opt.geometry.theta_incident_deg - bounds.incident_max_deg).abs() < 1e-9,
+            "phi={}",
+            opt.geometry.theta_incident_deg
+        );
+        assert!(
+            (opt.geometry.theta_fluorescence_deg - bounds.exit_min_deg).abs() < 1e-9,
+            "theta={}",
+            opt.geometry.theta_fluorescence_deg
+        );
+
+        let at_45 =
+            booth_suppression_reference("Fe2O3", "Fe", Edge::K, &energies, None, 1000.0, 5.24, 0.2)
+                .unwrap();
+        assert!(opt.r_mean > at_45.r_mean);
+    }
+
     #[test]
     fn test_booth_reference_is_close_to_ameyanagi_after_mu_unification() {
         let energies: Vec<f64> = (7000..=8000).step_by(2).map(|e| e as f64).collect();
@@ -522,14 +2456,25 @@ mod tests {
         let ameyanagi = ameyanagi_suppression_exact(
             "Fe2O3",
             "Fe",
-            "K",
+            Edge::K,
             &energies,
             AmeyanagiSuppressionSettings {
-                density_g_cm3: density,
+                density_g_cm3: Some(density),
                 phi_rad: phi,
                 theta_rad: theta,
                 thickness_input: AmeyanagiThicknessInput::ThicknessCm(thickness_cm),
                 chi_assumed: chi,
+                binder: None,
+                packing_fraction: None,
+                cross_section: CrossSectionKind::Photo,
+                per_line: false,
+                exit_angles: None,
+                mu_a_method: AmeyanagiMuAMethod::EdgeOnlyTrendline,
+                e0_override: None,
+                pre_edge_window: PreEdgeOptions::default(),
+                detection_mode: DetectionMode::default(),
+                mu_backend: MuBackend::default(),
+                detector_filter: None,
             },
         )
         .unwrap();
@@ -537,7 +2482,7 @@ mod tests {
         let booth_ref = booth_suppression_reference(
             "Fe2O3",
             "Fe",
-            "K",
+            Edge::K,
             &energies,
             None,
             thickness_cm * 1.0e4,
@@ -559,4 +2504,100 @@ mod tests {
             "unexpectedly large A-vs-Booth-ref gap: {mean_abs_diff}"
         );
     }
+
+    #[test]
+    fn test_booth_reference_matches_ameyanagi_edge_only_trendline() {
+        let energies: Vec<f64> = (7000..=8000).step_by(2).map(|e| e as f64).collect();
+        let density = 5.24;
+        let chi = 0.2;
+        let thickness_cm = 0.01;
+        let mean_abs_diff_from_reference = |mu_a_method| {
+            let ameyanagi = ameyanagi_suppression_exact(
+                "Fe2O3",
+                "Fe",
+                Edge::K,
+                &energies,
+                AmeyanagiSuppressionSettings::builder()
+                    .density(density)
+                    .thickness_cm(thickness_cm)
+                    .chi(chi)
+                    .mu_a_method(mu_a_method)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+            let booth_ref = booth_suppression_reference(
+                "Fe2O3",
+                "Fe",
+                Edge::K,
+                &energies,
+                None,
+                thickness_cm * 1.0e4,
+                density,
+                chi,
+            )
+            .unwrap();
+            ameyanagi
+                .suppression_factor
+                .iter()
+                .zip(booth_ref.suppression_factor.iter())
+                .map(|(a, b)| (a - b).abs())
+                .sum::<f64>()
+                / energies.len() as f64
+        };
+
+        let trendline = mean_abs_diff_from_reference(AmeyanagiMuAMethod::EdgeOnlyTrendline);
+        assert!(trendline < 0.02, "A-vs-Booth-ref gap: {trendline}");
+
+        // The raw μ_a keeps the absorber pre-edge part, which the reference
+        // removes.
+        let raw = mean_abs_diff_from_reference(AmeyanagiMuAMethod::Raw);
+        assert!(raw > trendline, "raw {raw} vs trendline {trendline}");
+    }
+
+    #[test]
+    fn test_booth_with_sample_info() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        let ctx = SelfAbsContext::new();
+        let info = SampleInfo::new(&ctx, "Fe2O3", "Fe", Edge::K).unwrap();
+        let options = BoothOptions::default();
+        let direct =
+            booth_with_options("Fe2O3", "Fe", Edge::K, &energies, None, 10.0, &options).unwrap();
+        let with_info =
+            booth_with_sample_info(&ctx, &info, &energies, None, 10.0, &options).unwrap();
+        assert_eq!(direct.is_thick, with_info.is_thick);
+        assert_eq!(direct.fluorescence_energy, with_info.fluorescence_energy);
+        for (a, b) in direct.s.iter().zip(&with_info.s) {
+            assert!((a - b).abs() < 1e-12);
+        }
+        for (a, b) in direct.alpha.iter().zip(&with_info.alpha) {
+            assert!((a - b).abs() <= 1e-12 * a.abs());
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_booth_results_serde_roundtrip() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        let result = booth("Fe2O3", "Fe", Edge::K, &energies, None, 10.0).unwrap();
+        let json = serde_json::to_string(&result).unwrap();
+        let back: BoothResult = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+        assert_eq!(back.is_thick, result.is_thick);
+        assert_eq!(back.alpha, result.alpha);
+
+        let chi: Vec<f64> = result.k.iter().map(|&k| 0.1 * (2.0 * k).sin()).collect();
+        let a = result.correct_chi(&chi, 5.24, 10.0);
+        let b = back.correct_chi(&chi, 5.24, 10.0);
+        assert_eq!(a, b);
+
+        let reference =
+            booth_suppression_reference("Fe2O3", "Fe", Edge::K, &energies, None, 10.0, 5.24, 0.2)
+                .unwrap();
+        let json = serde_json::to_string(&reference).unwrap();
+        let back: BoothSuppressionResult = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+        assert_eq!(back.suppression_factor, reference.suppression_factor);
+        assert_eq!(back.r_mean_exafs, reference.r_mean_exafs);
+    }
 }
@@ -1,20 +1,45 @@
 //! Shared types and utilities for self-absorption algorithms.
 
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
 use std::fmt;
+use std::str::FromStr;
 
-use chemical_formula::prelude::parse_formula;
+use chemical_formula::prelude::{ChemicalFormula, parse_formula};
 use xraydb::{CrossSectionKind, XrayDb};
 
+use crate::context::SelfAbsContext;
+use crate::formula::expand_hydrates;
+use crate::mu::{composition_mass_fractions, compound_mu_linear, compound_mu_linear_single};
+
 /// Energy-to-k conversion: k (Å⁻¹) = sqrt(ETOK × (E - E₀) [eV]).
 pub const ETOK: f64 = 0.2624682917;
+/// Start of the EXAFS region above E₀ (eV) for the statistics and searches
+/// that skip the edge itself.
+pub(crate) const EXAFS_OFFSET_EV: f64 = 30.0;
+/// Relative tolerance within which mixture weights read as mass fractions
+/// (summing to 1) or weight percentages (summing to 100).
+const MIXTURE_SUM_RTOL: f64 = 0.01;
+/// Default window (eV) below each absorber emission line searched for matrix
+/// edges when the algorithms report [`SelfAbsWarning::LineReabsorbed`]; see
+/// [`SelfAbsContext::with_reabsorption_window`].
+pub const REABSORPTION_WINDOW_EV: f64 = 1000.0;
 
 /// Measurement geometry for fluorescence XAS.
 ///
-/// Default is 45° incident / 45° exit (geometry ratio = 1.0).
+/// Default is 45° incident / 45° exit (geometry ratio = 1.0) with no angular
+/// spread.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FluorescenceGeometry {
     pub theta_incident_deg: f64,
     pub theta_fluorescence_deg: f64,
+    /// Half-width (degrees) of a uniform spread of incident angles around
+    /// `theta_incident_deg`, e.g. a divergent or focused beam.
+    pub incident_spread_deg: f64,
+    /// Half-width (degrees) of a uniform spread of exit angles around
+    /// `theta_fluorescence_deg`, e.g. the detector acceptance.
+    pub exit_spread_deg: f64,
 }
 
 impl FluorescenceGeometry {
@@ -22,6 +47,98 @@ impl FluorescenceGeometry {
     pub fn ratio(&self) -> f64 {
         self.theta_incident_deg.to_radians().sin() / self.theta_fluorescence_deg.to_radians().sin()
     }
+
+    /// sin φ / sin θ averaged uniformly over the incident and exit spreads.
+    ///
+    /// Both averages have closed forms: ⟨sin φ⟩ = (cos φ₁ − cos φ₂)/(φ₂ − φ₁)
+    /// and ⟨1/sin θ⟩ = ln(tan(θ₂/2)/tan(θ₁/2))/(θ₂ − θ₁). Without spread this
+    /// is exactly [`ratio`](Self::ratio). A spread must keep every angle in
+    /// (0°, 90°).
+    pub fn effective_ratio(&self) -> Result<f64, SelfAbsError> {
+        let incident = spread_range(
+            "incident",
+            self.theta_incident_deg,
+            self.incident_spread_deg,
+        )?;
+        let exit = spread_range("exit", self.theta_fluorescence_deg, self.exit_spread_deg)?;
+        let mean_sin = match incident {
+            Some((lo, hi)) => (lo.cos() - hi.cos()) / (hi - lo),
+            None => self.theta_incident_deg.to_radians().sin(),
+        };
+        Ok(match exit {
+            Some((lo, hi)) => mean_sin * ((hi / 2.0).tan() / (lo / 2.0).tan()).ln() / (hi - lo),
+            None => mean_sin / self.theta_fluorescence_deg.to_radians().sin(),
+        })
+    }
+
+    /// Geometry from a beam direction, the outward normal of the front face
+    /// and the direction from the sample to the detector.
+    ///
+    /// The angles are grazing angles from the surface:
+    /// sin φ = −b̂·n̂ and sin θ = d̂·n̂. The vectors need not be normalized.
+    /// Errors unless the beam hits the front face and the detector sees it.
+    pub fn from_vectors(
+        beam_dir: [f64; 3],
+        surface_normal: [f64; 3],
+        detector_dir: [f64; 3],
+    ) -> Result<Self, SelfAbsError> {
+        let unit = |name: &str, v: [f64; 3]| {
+            let norm = v.iter().map(|c| c * c).sum::<f64>().sqrt();
+            if norm.is_finite() && norm > 0.0 {
+                Ok(v.map(|c| c / norm))
+            } else {
+                Err(SelfAbsError::InsufficientData(format!(
+                    "{name} must be a finite non-zero vector"
+                )))
+            }
+        };
+        let beam = unit("beam direction", beam_dir)?;
+        let normal = unit("surface normal", surface_normal)?;
+        let detector = unit("detector direction", detector_dir)?;
+        let dot = |a: [f64; 3], b: [f64; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+
+        let sin_phi = -dot(beam, normal);
+        let sin_theta = dot(detector, normal);
+        if sin_phi <= 0.0 {
+            return Err(SelfAbsError::InsufficientData(
+                "beam does not hit the front face of the sample".to_string(),
+            ));
+        }
+        if sin_theta <= 0.0 {
+            return Err(SelfAbsError::InsufficientData(
+                "detector does not see the front face of the sample".to_string(),
+            ));
+        }
+        Ok(Self {
+            theta_incident_deg: sin_phi.min(1.0).asin().to_degrees(),
+            theta_fluorescence_deg: sin_theta.min(1.0).asin().to_degrees(),
+            ..Default::default()
+        })
+    }
+}
+
+/// Angle range in radians covered by `center_deg ± spread_deg`, or `None`
+/// without spread.
+fn spread_range(
+    which: &str,
+    center_deg: f64,
+    spread_deg: f64,
+) -> Result<Option<(f64, f64)>, SelfAbsError> {
+    if !spread_deg.is_finite() || spread_deg < 0.0 {
+        return Err(SelfAbsError::InsufficientData(format!(
+            "{which} angle spread must be finite and non-negative"
+        )));
+    }
+    if spread_deg == 0.0 {
+        return Ok(None);
+    }
+    let (lo, hi) = (center_deg - spread_deg, center_deg + spread_deg);
+    if !(lo > 0.0 && hi < 90.0) {
+        return Err(SelfAbsError::InsufficientData(format!(
+            "{which} angle {center_deg}° ± {spread_deg}° leaves (0°, 90°)"
+        )));
+    }
+    Ok(Some((lo.to_radians(), hi.to_radians())))
 }
 
 impl Default for FluorescenceGeometry {
@@ -29,6 +146,8 @@ impl Default for FluorescenceGeometry {
         Self {
             theta_incident_deg: 45.0,
             theta_fluorescence_deg: 45.0,
+            incident_spread_deg: 0.0,
+            exit_spread_deg: 0.0,
         }
     }
 }
@@ -39,6 +158,19 @@ pub enum SelfAbsError {
     NoEmissionLines(String),
     InvalidFormula(String),
     InsufficientData(String),
+    /// Reading or writing a file, or a malformed file.
+    Io(String),
+    /// An edge name that does not parse, or an edge the element lacks.
+    InvalidEdge(String),
+    /// An element reference that matches no element.
+    InvalidElement(String),
+    /// A suppression target no sample preparation within the searched range
+    /// reaches; `best_r_min` is the closest r_min found.
+    TargetUnreachable {
+        target_r_min: f64,
+        best_r_min: f64,
+        reason: String,
+    },
 }
 
 impl fmt::Display for SelfAbsError {
@@ -48,6 +180,10 @@ impl fmt::Display for SelfAbsError {
             Self::NoEmissionLines(s) => write!(f, "no emission lines found for {s}"),
             Self::InvalidFormula(s) => write!(f, "invalid formula: {s}"),
             Self::InsufficientData(s) => write!(f, "insufficient data: {s}"),
+            Self::Io(s) => write!(f, "io error: {s}"),
+            Self::InvalidEdge(s) => write!(f, "invalid edge: {s}"),
+            Self::InvalidElement(s) => write!(f, "invalid element: {s}"),
+            Self::TargetUnreachable { reason, .. } => write!(f, "target unreachable: {reason}"),
         }
     }
 }
@@ -60,69 +196,1175 @@ impl From<xraydb::XrayDbError> for SelfAbsError {
     }
 }
 
-/// Precomputed sample information shared across algorithms.
-pub(crate) struct SampleInfo {
-    pub composition: HashMap<String, f64>,
-    pub central_symbol: String,
-    pub central_z: u16,
-    pub central_count: f64,
+/// Lets an [`Edge`] stand where `impl TryInto<Edge, Error: Into<SelfAbsError>>`
+/// is accepted.
+impl From<Infallible> for SelfAbsError {
+    fn from(e: Infallible) -> Self {
+        match e {}
+    }
+}
+
+/// Variant of a [`SelfAbsError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SelfAbsErrorKind {
+    Xraydb,
+    NoEmissionLines,
+    InvalidFormula,
+    InsufficientData,
+    Io,
+    InvalidEdge,
+    InvalidElement,
+    TargetUnreachable,
+}
+
+/// Plain-data form of a [`SelfAbsError`], e.g. to send to a front end.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SelfAbsErrorReport {
+    pub kind: SelfAbsErrorKind,
+    /// The [`Display`](fmt::Display) message of the error.
+    pub message: String,
+}
+
+impl SelfAbsError {
+    /// Which variant this is.
+    pub fn kind(&self) -> SelfAbsErrorKind {
+        match self {
+            Self::Xraydb(_) => SelfAbsErrorKind::Xraydb,
+            Self::NoEmissionLines(_) => SelfAbsErrorKind::NoEmissionLines,
+            Self::InvalidFormula(_) => SelfAbsErrorKind::InvalidFormula,
+            Self::InsufficientData(_) => SelfAbsErrorKind::InsufficientData,
+            Self::Io(_) => SelfAbsErrorKind::Io,
+            Self::InvalidEdge(_) => SelfAbsErrorKind::InvalidEdge,
+            Self::InvalidElement(_) => SelfAbsErrorKind::InvalidElement,
+            Self::TargetUnreachable { .. } => SelfAbsErrorKind::TargetUnreachable,
+        }
+    }
+
+    /// Kind and message of this error as plain data.
+    pub fn report(&self) -> SelfAbsErrorReport {
+        SelfAbsErrorReport {
+            kind: self.kind(),
+            message: self.to_string(),
+        }
+    }
+}
+
+/// Serializes as its [`SelfAbsErrorReport`]; deserialize the report, as
+/// the wrapped [`xraydb::XrayDbError`] cannot be rebuilt.
+#[cfg(feature = "serde")]
+impl serde::Serialize for SelfAbsError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.report().serialize(serializer)
+    }
+}
+
+/// Absorption edge, named by its IUPAC level.
+///
+/// Parses case-insensitively with an optional separator and Roman
+/// subshell numbers, so `"k"`, `"L3"`, `"l_3"` and `"LIII"` are all
+/// accepted; displays as the canonical name (`"L3"`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Edge {
+    K,
+    L1,
+    L2,
+    L3,
+    M1,
+    M2,
+    M3,
+    M4,
+    M5,
+    N1,
+    N2,
+    N3,
+    N4,
+    N5,
+    N6,
+    N7,
+    O1,
+    O2,
+    O3,
+    O4,
+    O5,
+    P1,
+    P2,
+    P3,
+}
+
+impl Edge {
+    /// Every edge, from the innermost level outwards.
+    pub const ALL: [Self; 24] = [
+        Self::K,
+        Self::L1,
+        Self::L2,
+        Self::L3,
+        Self::M1,
+        Self::M2,
+        Self::M3,
+        Self::M4,
+        Self::M5,
+        Self::N1,
+        Self::N2,
+        Self::N3,
+        Self::N4,
+        Self::N5,
+        Self::N6,
+        Self::N7,
+        Self::O1,
+        Self::O2,
+        Self::O3,
+        Self::O4,
+        Self::O5,
+        Self::P1,
+        Self::P2,
+        Self::P3,
+    ];
+
+    /// The canonical name, as used by xraydb.
+    pub fn as_str(self) -> &'static str {
+        const NAMES: [&str; 24] = [
+            "K", "L1", "L2", "L3", "M1", "M2", "M3", "M4", "M5", "N1", "N2", "N3", "N4", "N5",
+            "N6", "N7", "O1", "O2", "O3", "O4", "O5", "P1", "P2", "P3",
+        ];
+        NAMES[self as usize]
+    }
+
+    /// Comma-separated canonical names of `edges`.
+    fn list(edges: impl IntoIterator<Item = Self>) -> String {
+        edges
+            .into_iter()
+            .map(Self::as_str)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl fmt::Display for Edge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl TryFrom<&str> for Edge {
+    type Error = SelfAbsError;
+
+    fn try_from(s: &str) -> Result<Self, SelfAbsError> {
+        s.parse()
+    }
+}
+
+impl TryFrom<&String> for Edge {
+    type Error = SelfAbsError;
+
+    fn try_from(s: &String) -> Result<Self, SelfAbsError> {
+        s.parse()
+    }
+}
+
+impl FromStr for Edge {
+    type Err = SelfAbsError;
+
+    fn from_str(s: &str) -> Result<Self, SelfAbsError> {
+        let invalid = || {
+            SelfAbsError::InvalidEdge(format!(
+                "'{s}' is not an edge; expected one of {}",
+                Self::list(Self::ALL)
+            ))
+        };
+        let name: String = s
+            .chars()
+            .filter(|c| !matches!(c, '_' | '-' | ' '))
+            .collect::<String>()
+            .to_ascii_uppercase();
+        let mut chars = name.chars();
+        let shell = chars.next().ok_or_else(invalid)?;
+        let subshell = chars.as_str();
+        let index = match subshell {
+            "" => 1,
+            "1" | "I" => 1,
+            "2" | "II" => 2,
+            "3" | "III" => 3,
+            "4" | "IV" => 4,
+            "5" | "V" => 5,
+            "6" | "VI" => 6,
+            "7" | "VII" => 7,
+            _ => return Err(invalid()),
+        };
+        let (first, count) = match shell {
+            'K' if subshell.is_empty() => (Self::K, 1),
+            'L' => (Self::L1, 3),
+            'M' => (Self::M1, 5),
+            'N' => (Self::N1, 7),
+            'O' => (Self::O1, 5),
+            'P' => (Self::P1, 3),
+            _ => return Err(invalid()),
+        };
+        if (shell != 'K' && subshell.is_empty()) || index > count {
+            return Err(invalid());
+        }
+        Ok(Self::ALL[first as usize + index - 1])
+    }
+}
+
+/// An element by symbol, name or atomic number, e.g. the absorber of
+/// [`SampleInfo::new`]. Every entry point taking a `central_element`
+/// accepts `"Fe"`, `"iron"`, `"26"` or `26u16`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ElementRef {
+    /// Symbol or name, matched case-insensitively; a numeric string is read
+    /// as Z.
+    Symbol(String),
+    /// Atomic number.
+    Z(u16),
+}
+
+impl ElementRef {
+    /// Atomic number and symbol of the element.
+    pub fn resolve(&self, db: &XrayDb) -> Result<(u16, String), SelfAbsError> {
+        let z = match self {
+            Self::Symbol(s) => db.resolve_element(s).map_err(|_| {
+                SelfAbsError::InvalidElement(format!(
+                    "'{s}' is not an element symbol, name or atomic number"
+                ))
+            })?,
+            Self::Z(z) => *z,
+        };
+        let symbol = db
+            .symbol(&z.to_string())
+            .map_err(|_| SelfAbsError::InvalidElement(format!("no element with Z = {z}")))?;
+        Ok((z, symbol.to_string()))
+    }
+}
+
+impl fmt::Display for ElementRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Symbol(s) => f.write_str(s),
+            Self::Z(z) => write!(f, "Z = {z}"),
+        }
+    }
+}
+
+impl From<u16> for ElementRef {
+    fn from(z: u16) -> Self {
+        Self::Z(z)
+    }
+}
+
+impl From<&str> for ElementRef {
+    fn from(s: &str) -> Self {
+        Self::Symbol(s.to_string())
+    }
+}
+
+impl From<String> for ElementRef {
+    fn from(s: String) -> Self {
+        Self::Symbol(s)
+    }
+}
+
+impl From<&String> for ElementRef {
+    fn from(s: &String) -> Self {
+        Self::Symbol(s.clone())
+    }
+}
+
+/// Non-fatal condition detected while computing a correction.
+///
+/// Warnings never change the computed numbers; they flag regions of the
+/// result that should not be trusted.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SelfAbsWarning {
+    /// An absorption edge of a sample element (other than the selected
+    /// absorber edge) lies inside the energy grid.
+    InterferingEdge {
+        element: String,
+        edge: String,
+        energy: f64,
+    },
+    /// The energy grid lacks points below or above the absorber edge.
+    GridMissesEdge {
+        edge_energy: f64,
+        e_min: f64,
+        e_max: f64,
+    },
+    /// The weights of a [`SampleSpec::Mixture`] summed to neither 1 nor 100
+    /// and were rescaled to mass fractions.
+    MixtureNormalized { weight_sum: f64 },
+    /// A matrix-element edge lies just below an absorber emission line, so
+    /// the matrix absorbs the fluorescence strongly (and may re-emit it as
+    /// secondary fluorescence). See [`detect_line_reabsorption`].
+    LineReabsorbed {
+        line: String,
+        line_energy: f64,
+        element: String,
+        edge: String,
+        edge_energy: f64,
+        extra_mu_f: f64,
+        mu_f_fraction: f64,
+    },
+}
+
+impl fmt::Display for SelfAbsWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InterferingEdge {
+                element,
+                edge,
+                energy,
+            } => write!(
+                f,
+                "{element} {edge} edge at {energy:.1} eV lies inside the grid"
+            ),
+            Self::GridMissesEdge {
+                edge_energy,
+                e_min,
+                e_max,
+            } => write!(
+                f,
+                "grid {e_min:.1}-{e_max:.1} eV does not span the edge at {edge_energy:.1} eV"
+            ),
+            Self::MixtureNormalized { weight_sum } => write!(
+                f,
+                "mixture weights sum to {weight_sum}, not 1 or 100; normalized to mass fractions"
+            ),
+            Self::LineReabsorbed {
+                line,
+                line_energy,
+                element,
+                edge,
+                edge_energy,
+                mu_f_fraction,
+                ..
+            } => write!(
+                f,
+                "{element} {edge} edge at {edge_energy:.1} eV lies below the {line} line at {line_energy:.1} eV and adds {:.0}% to mu_f",
+                100.0 * mu_f_fraction
+            ),
+        }
+    }
+}
+
+/// Extent of a validated energy grid, see [`validate_energy_grid`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GridSummary {
+    /// Number of grid points.
+    pub n_points: usize,
+    /// First (lowest) energy in eV.
+    pub e_min: f64,
+    /// Last (highest) energy in eV.
+    pub e_max: f64,
+    /// Edge energy the grid was checked against, in eV.
     pub edge_energy: f64,
-    pub fluor_energy: f64,
+    /// Points at or below the edge.
+    pub n_below_edge: usize,
+    /// Points above the edge.
+    pub n_above_edge: usize,
+}
+
+impl GridSummary {
+    /// Whether the grid has points on both sides of the edge.
+    pub fn spans_edge(&self) -> bool {
+        self.n_below_edge > 0 && self.n_above_edge > 0
+    }
+
+    /// [`SelfAbsWarning::GridMissesEdge`] unless the grid spans the edge.
+    pub fn warning(&self) -> Option<SelfAbsWarning> {
+        (!self.spans_edge()).then_some(SelfAbsWarning::GridMissesEdge {
+            edge_energy: self.edge_energy,
+            e_min: self.e_min,
+            e_max: self.e_max,
+        })
+    }
+}
+
+/// Check that `energies` is non-empty, finite and strictly increasing, and
+/// count the points on either side of `edge_energy`.
+///
+/// A grid on one side of the edge is allowed but flagged through
+/// [`GridSummary::warning`].
+pub fn validate_energy_grid(
+    energies: &[f64],
+    edge_energy: f64,
+) -> Result<GridSummary, SelfAbsError> {
+    if energies.is_empty() {
+        return Err(SelfAbsError::InsufficientData(
+            "energy grid must not be empty".to_string(),
+        ));
+    }
+    if let Some(i) = energies.iter().position(|e| !e.is_finite()) {
+        return Err(SelfAbsError::InsufficientData(format!(
+            "energy grid must be finite, got {} at index {i}",
+            energies[i]
+        )));
+    }
+    if let Some(i) = energies.windows(2).position(|w| w[1] <= w[0]) {
+        return Err(SelfAbsError::InsufficientData(format!(
+            "energy grid must be strictly increasing, got {} after {} at index {}",
+            energies[i + 1],
+            energies[i],
+            i + 1
+        )));
+    }
+    let n_below_edge = energies.partition_point(|&e| e <= edge_energy);
+    Ok(GridSummary {
+        n_points: energies.len(),
+        e_min: energies[0],
+        e_max: energies[energies.len() - 1],
+        edge_energy,
+        n_below_edge,
+        n_above_edge: energies.len() - n_below_edge,
+    })
+}
+
+/// serde mirror of xraydb's [`CrossSectionKind`], for
+/// `#[serde(with = "crate::common::CrossSectionKindDef")]` fields.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(remote = "CrossSectionKind")]
+pub(crate) enum CrossSectionKindDef {
+    Photo,
+    Coherent,
+    Incoherent,
+    Total,
+}
+
+/// Which emission lines of the absorber edge set the fluorescence energy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FluorescenceLineMode {
+    /// Only the strongest line, e.g. Kα1.
+    Strongest,
+    /// All lines, μ_f averaged with their relative intensities.
+    #[default]
+    Weighted,
+}
+
+/// Pre-edge window and Victoreen exponent of the μ̄_a(E) trendline.
+///
+/// The line is fit to μ(E)·E^n over `[E₀ + start_rel_ev, E₀ + end_rel_ev]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PreEdgeOptions {
+    /// Window start relative to E₀ (eV).
+    pub start_rel_ev: f64,
+    /// Window end relative to E₀ (eV).
+    pub end_rel_ev: f64,
+    /// Victoreen exponent n; 0 fits μ(E) itself.
+    pub victoreen_n: i32,
+    /// Fewer grid points than this in the window (at least 2) fall back to
+    /// the scalar μ at `E₀ + start_rel_ev`.
+    pub min_points: usize,
+}
+
+impl Default for PreEdgeOptions {
+    fn default() -> Self {
+        Self {
+            start_rel_ev: -200.0,
+            end_rel_ev: -30.0,
+            victoreen_n: 0,
+            min_points: 2,
+        }
+    }
+}
+
+/// How the edge-only absorber μ̄_a(E) is separated from the absorber μ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PreEdgeMethod {
+    /// Subtract the absorber μ at E₀ − 200 eV.
+    #[default]
+    Constant,
+    /// Subtract the pre-edge trendline fitted over the [`PreEdgeOptions`]
+    /// window.
+    Trendline,
+    /// μ̄_a(E) = μ_abs(E)·(1 − 1/J) above the edge and 0 below, with J the
+    /// tabulated edge jump ratio, as in Athena's classic implementation.
+    JumpRatio,
+}
+
+/// Tabulation of the elemental cross-sections behind every μ(E).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MuBackend {
+    /// Elam, Ravel and Sieber (2002), as used by Athena and Larch.
+    #[default]
+    Elam,
+    /// Chantler (FFAST), finer-grained around the absorption edges.
+    Chantler,
+}
+
+/// How the pre-edge baseline under μ̄_a(E) was obtained.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PreEdgeBaseline {
+    /// Line fit over the [`PreEdgeOptions`] window.
+    Trendline,
+    /// Too few usable points in the window: scalar μ at the window start.
+    Fallback,
+    /// Scalar μ at E₀ − 200 eV, by [`PreEdgeMethod::Constant`].
+    Constant,
+    /// No baseline: the edge share of μ from the jump ratio, by
+    /// [`PreEdgeMethod::JumpRatio`].
+    JumpRatio,
+}
+
+/// Where a sample density came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DensitySource {
+    /// Given by the caller.
+    Supplied,
+    /// Looked up by formula in the xraydb materials table.
+    MaterialsTable,
+    /// Looked up in this crate's table of common reference compounds.
+    BuiltinTable,
+    /// Elemental density of a single-element sample.
+    Element,
+}
+
+/// Densities (g/cm³) of common XAS reference compounds missing from the
+/// xraydb materials table. Room-temperature values for the stable polymorph,
+/// after the CRC Handbook of Chemistry and Physics, "Physical Constants of
+/// Inorganic Compounds".
+const COMPOUND_DENSITIES: &[(&str, f64)] = &[
+    ("Fe2O3", 5.24),
+    ("Fe3O4", 5.17),
+    ("CuO", 6.31),
+    ("Cu2O", 6.0),
+    ("ZnO", 5.61),
+    ("NiO", 6.67),
+    ("CoO", 6.44),
+    ("Co3O4", 6.11),
+    ("MnO", 5.37),
+    ("MnO2", 5.03),
+    ("Cr2O3", 5.22),
+    ("V2O5", 3.36),
+    ("CeO2", 7.22),
+    ("PtO2", 10.2),
+    ("PdO", 8.3),
+    ("WO3", 7.16),
+    ("MoO3", 4.69),
+    ("Ga2O3", 5.88),
+    ("GeO2", 4.23),
+    ("SnO2", 6.95),
+    ("In2O3", 7.18),
+    ("ZrO2", 5.68),
+    ("Y2O3", 5.01),
+    ("La2O3", 6.51),
+    ("Bi2O3", 8.9),
+];
+
+/// Tabulated density (g/cm³) for `formula`: the elemental density for a
+/// single element, otherwise the xraydb materials table followed by a small
+/// table of common reference compounds.
+pub fn lookup_density(db: &XrayDb, formula: &str) -> Result<(f64, DensitySource), SelfAbsError> {
+    let formula = formula.trim();
+    let composition = parse_composition(formula)?;
+    if composition.len() == 1
+        && let Some(element) = composition.keys().next()
+    {
+        return Ok((db.density(element)?, DensitySource::Element));
+    }
+    if let Some((_, density)) = db.find_material(formula) {
+        return Ok((density, DensitySource::MaterialsTable));
+    }
+    COMPOUND_DENSITIES
+        .iter()
+        .find(|(f, _)| *f == formula)
+        .map(|&(_, density)| (density, DensitySource::BuiltinTable))
+        .ok_or_else(|| {
+            SelfAbsError::InsufficientData(format!(
+                "no tabulated density for {formula}; supply density_g_cm3"
+            ))
+        })
+}
+
+/// One emission line of the absorber edge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmissionLine {
+    /// Siegbahn/IUPAC label as in xraydb, e.g. `"Ka1"`.
+    pub label: String,
+    /// Line energy in eV.
+    pub energy: f64,
+    /// Relative intensity within the edge.
+    pub intensity: f64,
+}
+
+/// Parsed sample and absorber data shared across algorithms.
+///
+/// Build it once with [`SampleInfo::new`] to inspect the edge and
+/// fluorescence line before a correction, then pass it to the
+/// `*_with_sample_info` entry points to skip the repeated parsing and
+/// database lookups.
+#[derive(Debug, Clone)]
+pub struct SampleInfo {
+    pub(crate) formula: Option<String>,
+    pub(crate) composition: BTreeMap<String, f64>,
+    pub(crate) central_symbol: String,
+    pub(crate) central_z: u16,
+    pub(crate) central_count: f64,
+    pub(crate) edge: Edge,
+    pub(crate) edge_energy: f64,
+    /// Positive-intensity emission lines of the edge, strongest first.
+    pub(crate) lines: Vec<EmissionLine>,
+    /// Weight sum of a mixture that had to be normalized, see
+    /// [`SampleSpec::unnormalized_weight_sum`].
+    pub(crate) mixture_weight_sum: Option<f64>,
 }
 
 impl SampleInfo {
+    /// Parse `formula` and look up the `edge` of `central_element` and its
+    /// emission lines.
     pub fn new(
         db: &XrayDb,
         formula: &str,
-        central_element: &str,
-        edge: &str,
+        central_element: impl Into<ElementRef>,
+        edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
     ) -> Result<Self, SelfAbsError> {
-        let parsed =
-            parse_formula(formula).map_err(|e| SelfAbsError::InvalidFormula(e.to_string()))?;
-        let molecular = parsed
-            .to_molecular_formula()
-            .map_err(|e| SelfAbsError::InvalidFormula(e.to_string()))?;
-        let composition: HashMap<String, f64> = molecular
-            .stoichiometry
-            .iter()
-            .map(|(sym, &count)| (format!("{sym:?}"), count))
-            .collect();
+        let central_element = central_element.into();
+        let composition = parse_composition(formula)?;
+        let info = Self::from_composition(db, composition, central_element.clone(), edge).map_err(
+            |e| match e {
+                SelfAbsError::InvalidFormula(_) => SelfAbsError::InvalidFormula(format!(
+                    "{central_element} not found in formula {formula}"
+                )),
+                other => other,
+            },
+        )?;
+        Ok(Self {
+            formula: Some(formula.to_string()),
+            ..info
+        })
+    }
 
-        let central_z = db.resolve_element(central_element)?;
-        let central_symbol = db.symbol(&central_z.to_string())?.to_string();
+    /// Build from an explicit element → stoichiometric count map.
+    ///
+    /// Keys may be any element identifier accepted by xraydb (symbol, name
+    /// or Z); duplicates after resolution are summed.
+    pub fn from_composition(
+        db: &XrayDb,
+        composition: BTreeMap<String, f64>,
+        central_element: impl Into<ElementRef>,
+        edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    ) -> Result<Self, SelfAbsError> {
+        let edge: Edge = edge.try_into().map_err(Into::into)?;
+        let central_element = central_element.into();
+        let mut resolved: BTreeMap<String, f64> = BTreeMap::new();
+        for (sym, count) in composition {
+            if !count.is_finite() || count < 0.0 {
+                return Err(SelfAbsError::InsufficientData(format!(
+                    "element count for {sym} must be finite and >= 0"
+                )));
+            }
+            let symbol = db.symbol(&sym)?.to_string();
+            *resolved.entry(symbol).or_insert(0.0) += count;
+        }
+        let composition = resolved;
 
-        let central_count = find_element_count(&composition, db, central_z).ok_or_else(|| {
-            SelfAbsError::InvalidFormula(format!(
-                "{central_element} not found in formula {formula}"
-            ))
-        })?;
+        let (central_z, central_symbol) = central_element.resolve(db)?;
 
-        let edge_energy = db.xray_edge(central_element, edge)?.energy;
+        let central_count = find_element_count(&composition, db, central_z)
+            .filter(|&c| c > 0.0)
+            .ok_or_else(|| {
+                SelfAbsError::InvalidFormula(format!("{central_element} not found in composition"))
+            })?;
 
-        let lines = db.xray_lines(central_element, Some(edge), None)?;
-        let fluor_energy = lines
-            .values()
-            .max_by(|a, b| {
-                a.intensity
-                    .partial_cmp(&b.intensity)
-                    .unwrap_or(std::cmp::Ordering::Equal)
+        let edge_energy = match db.xray_edge(&central_symbol, edge.as_str()) {
+            Ok(xray_edge) => xray_edge.energy,
+            Err(xraydb::XrayDbError::UnknownEdge { .. }) => {
+                let mut available: Vec<Edge> = db
+                    .xray_edges(&central_symbol)?
+                    .keys()
+                    .filter_map(|label| label.parse().ok())
+                    .collect();
+                available.sort();
+                return Err(SelfAbsError::InvalidEdge(format!(
+                    "{central_symbol} has no {edge} edge; available: {}",
+                    Edge::list(available)
+                )));
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut lines: Vec<EmissionLine> = db
+            .xray_lines(&central_symbol, Some(edge.as_str()), None)?
+            .into_iter()
+            .filter(|(_, line)| line.intensity.is_finite() && line.intensity > 0.0)
+            .map(|(label, line)| EmissionLine {
+                label,
+                energy: line.energy,
+                intensity: line.intensity,
             })
-            .map(|l| l.energy)
-            .ok_or_else(|| SelfAbsError::NoEmissionLines(format!("{central_element} {edge}")))?;
+            .collect();
+        if lines.is_empty() {
+            return Err(SelfAbsError::NoEmissionLines(format!(
+                "{central_symbol} {edge}"
+            )));
+        }
+        lines.sort_by(|a, b| {
+            b.intensity
+                .total_cmp(&a.intensity)
+                .then_with(|| a.label.cmp(&b.label))
+        });
 
         Ok(Self {
+            formula: None,
             composition,
             central_symbol,
             central_z,
             central_count,
+            edge,
             edge_energy,
-            fluor_energy,
+            lines,
+            mixture_weight_sum: None,
         })
     }
+
+    /// Build from any [`SampleSpec`]. A formula goes through [`Self::new`];
+    /// everything else through [`Self::from_composition`]. A mixture whose
+    /// weights needed normalizing is reported by every algorithm as
+    /// [`SelfAbsWarning::MixtureNormalized`].
+    pub fn from_spec(
+        db: &XrayDb,
+        spec: &SampleSpec,
+        central_element: impl Into<ElementRef>,
+        edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    ) -> Result<Self, SelfAbsError> {
+        match spec {
+            SampleSpec::Formula(formula) => Self::new(db, formula, central_element, edge),
+            other => Ok(Self {
+                mixture_weight_sum: other.unnormalized_weight_sum(),
+                ..Self::from_composition(db, other.composition(db)?, central_element, edge)?
+            }),
+        }
+    }
+
+    /// The formula this was parsed from, if built with [`Self::new`].
+    pub fn formula(&self) -> Option<&str> {
+        self.formula.as_deref()
+    }
+
+    /// Element symbol → stoichiometric count.
+    pub fn composition(&self) -> &BTreeMap<String, f64> {
+        &self.composition
+    }
+
+    /// Absorber element symbol.
+    pub fn central_symbol(&self) -> &str {
+        &self.central_symbol
+    }
+
+    /// Absorber atomic number.
+    pub fn central_z(&self) -> u16 {
+        self.central_z
+    }
+
+    /// Absorber atoms per formula unit.
+    pub fn central_count(&self) -> f64 {
+        self.central_count
+    }
+
+    /// Absorption edge.
+    pub fn edge(&self) -> Edge {
+        self.edge
+    }
+
+    /// Edge energy E₀ in eV.
+    pub fn edge_energy(&self) -> f64 {
+        self.edge_energy
+    }
+
+    /// Label of the strongest emission line.
+    pub fn fluorescence_line(&self) -> &str {
+        &self.lines[0].label
+    }
+
+    /// Energy of [`Self::fluorescence_line`] in eV.
+    pub fn strongest_line_energy(&self) -> f64 {
+        self.lines[0].energy
+    }
+
+    /// Intensity-weighted mean energy of [`Self::emission_lines`] in eV.
+    pub fn weighted_line_energy(&self) -> f64 {
+        let weight: f64 = self.lines.iter().map(|l| l.intensity).sum();
+        self.lines
+            .iter()
+            .map(|l| l.intensity * l.energy)
+            .sum::<f64>()
+            / weight
+    }
+
+    /// Positive-intensity emission lines of the edge, strongest first.
+    pub fn emission_lines(&self) -> &[EmissionLine] {
+        &self.lines
+    }
+
+    /// Lines averaged over by `mode`: the strongest alone or all of them.
+    pub(crate) fn lines_for(&self, mode: FluorescenceLineMode) -> &[EmissionLine] {
+        match mode {
+            FluorescenceLineMode::Strongest => &self.lines[..1],
+            FluorescenceLineMode::Weighted => &self.lines,
+        }
+    }
+}
+
+/// Sample composition in any of the forms samples are usually described in.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SampleSpec {
+    /// Chemical formula, e.g. `"Fe2O3"`.
+    Formula(String),
+    /// Element → stoichiometric count.
+    Composition(BTreeMap<String, f64>),
+    /// Element → mass fraction; normalized to sum to 1.
+    MassFractions(Vec<(String, f64)>),
+    /// Components mixed by mass, e.g. 30 wt% NiO on Al2O3 as
+    /// `[(NiO, 30.0), (Al2O3, 70.0)]`; the weights are normalized to sum to
+    /// 1, with a warning unless they already sum to 1 or 100. Components
+    /// may be mixtures themselves.
+    Mixture(Vec<(SampleSpec, f64)>),
+}
+
+impl SampleSpec {
+    /// Element symbol → mass fraction, summing to 1.
+    pub fn mass_fractions(&self, db: &XrayDb) -> Result<Vec<(String, f64)>, SelfAbsError> {
+        let fractions = match self {
+            Self::Formula(formula) => composition_mass_fractions(db, &parse_composition(formula)?)?,
+            Self::Composition(composition) => composition_mass_fractions(db, composition)?,
+            Self::MassFractions(fractions) => fractions.clone(),
+            Self::Mixture(components) => {
+                let mut mixed = Vec::new();
+                for (component, weight) in components {
+                    if !weight.is_finite() || *weight < 0.0 {
+                        return Err(SelfAbsError::InsufficientData(format!(
+                            "mixture weight must be finite and >= 0, got {weight}"
+                        )));
+                    }
+                    mixed.extend(
+                        component
+                            .mass_fractions(db)?
+                            .into_iter()
+                            .map(|(sym, w)| (sym, w * weight)),
+                    );
+                }
+                mixed
+            }
+        };
+
+        let mut merged: Vec<(String, f64)> = Vec::with_capacity(fractions.len());
+        for (sym, w) in fractions {
+            if !w.is_finite() || w < 0.0 {
+                return Err(SelfAbsError::InsufficientData(format!(
+                    "mass fraction for {sym} must be finite and >= 0"
+                )));
+            }
+            let symbol = db.symbol(&sym)?.to_string();
+            match merged.iter_mut().find(|(s, _)| *s == symbol) {
+                Some((_, total)) => *total += w,
+                None => merged.push((symbol, w)),
+            }
+        }
+        let total: f64 = merged.iter().map(|(_, w)| w).sum();
+        if total <= 0.0 || !total.is_finite() {
+            return Err(SelfAbsError::InsufficientData(
+                "sample has no mass".to_string(),
+            ));
+        }
+        Ok(merged
+            .into_iter()
+            .map(|(sym, w)| (sym, w / total))
+            .collect())
+    }
+
+    /// Weight sum of the first mixture, at any depth, whose weights sum to
+    /// neither 1 nor 100 within 1 %; those are normalized with a
+    /// [`SelfAbsWarning::MixtureNormalized`].
+    pub fn unnormalized_weight_sum(&self) -> Option<f64> {
+        let Self::Mixture(components) = self else {
+            return None;
+        };
+        let sum: f64 = components.iter().map(|(_, weight)| weight).sum();
+        let normalized = [1.0, 100.0]
+            .iter()
+            .any(|&target| (sum - target).abs() <= MIXTURE_SUM_RTOL * target);
+        if !normalized {
+            return Some(sum);
+        }
+        components
+            .iter()
+            .find_map(|(component, _)| component.unnormalized_weight_sum())
+    }
+
+    /// Element symbol → stoichiometric count. Formulas and compositions
+    /// keep their counts; mass fractions become moles per gram, which only
+    /// changes the overall scale.
+    pub fn composition(&self, db: &XrayDb) -> Result<BTreeMap<String, f64>, SelfAbsError> {
+        match self {
+            Self::Formula(formula) => parse_composition(formula),
+            Self::Composition(composition) => Ok(composition.clone()),
+            Self::MassFractions(_) | Self::Mixture(_) => self
+                .mass_fractions(db)?
+                .into_iter()
+                .map(|(sym, w)| Ok((sym.clone(), w / db.molar_mass(&sym)?)))
+                .collect(),
+        }
+    }
+}
+
+/// Parse a chemical formula into element symbol → stoichiometric count.
+///
+/// Hydrates are expanded first, see [`expand_hydrates`]. Weight-percent
+/// composites such as `Pt5wt%/SiO2` are resolved through their mass
+/// fractions, see [`wt_percent_composition`].
+pub(crate) fn parse_composition(formula: &str) -> Result<BTreeMap<String, f64>, SelfAbsError> {
+    let expanded = expand_hydrates(formula);
+    let parsed =
+        parse_formula(&expanded).map_err(|e| SelfAbsError::InvalidFormula(e.to_string()))?;
+    if !parsed.wt_percent.is_empty() {
+        return wt_percent_composition(formula, &parsed);
+    }
+    let molecular = parsed
+        .to_molecular_formula()
+        .map_err(|e| SelfAbsError::InvalidFormula(e.to_string()))?;
+    Ok(molecular
+        .stoichiometry
+        .iter()
+        .map(|(sym, &count)| (format!("{sym:?}"), count))
+        .collect())
+}
+
+/// Composition of a weight-percent composite from its mass fractions: each
+/// wt% phase keeps its mass share and the molecular remainder (the matrix)
+/// fills the rest. Counts are moles per 100 g, so only their ratios matter.
+fn wt_percent_composition(
+    formula: &str,
+    parsed: &ChemicalFormula,
+) -> Result<BTreeMap<String, f64>, SelfAbsError> {
+    let wt_sum: f64 = parsed.wt_percent.values().sum();
+    if !(wt_sum.is_finite() && wt_sum > 0.0 && wt_sum <= 100.0) {
+        return Err(SelfAbsError::InvalidFormula(format!(
+            "weight percentages in {formula} must sum to (0, 100], got {wt_sum}"
+        )));
+    }
+    let matrix_wt = 100.0 - wt_sum;
+    let matrix_mass: f64 = parsed
+        .stoichiometry
+        .iter()
+        .map(|(element, &count)| count * element.atomic_weight())
+        .sum();
+    if matrix_wt > 0.0 && matrix_mass <= 0.0 {
+        return Err(SelfAbsError::InvalidFormula(format!(
+            "{formula} leaves {matrix_wt} wt% without a matrix formula"
+        )));
+    }
+
+    let mut composition: BTreeMap<String, f64> = BTreeMap::new();
+    for (element, &wt) in &parsed.wt_percent {
+        *composition.entry(format!("{element:?}")).or_insert(0.0) += wt / element.atomic_weight();
+    }
+    if matrix_wt > 0.0 {
+        for (element, &count) in &parsed.stoichiometry {
+            *composition.entry(format!("{element:?}")).or_insert(0.0) +=
+                matrix_wt * count / matrix_mass;
+        }
+    }
+    Ok(composition)
+}
+
+/// An absorption edge inside an energy range, see [`detect_edge_overlaps`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EdgeOverlapWarning {
+    /// Element symbol.
+    pub element: String,
+    /// Edge label, e.g. `"K"` or `"L3"`.
+    pub edge: String,
+    /// Edge energy in eV.
+    pub energy: f64,
+}
+
+impl From<EdgeOverlapWarning> for SelfAbsWarning {
+    fn from(w: EdgeOverlapWarning) -> Self {
+        Self::InterferingEdge {
+            element: w.element,
+            edge: w.edge,
+            energy: w.energy,
+        }
+    }
+}
+
+/// A matrix-element edge just below an absorber emission line, see
+/// [`detect_line_reabsorption`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReabsorptionWarning {
+    /// Emission line label, e.g. `"Ka1"`.
+    pub line: String,
+    /// Line energy in eV.
+    pub line_energy: f64,
+    /// Matrix element symbol.
+    pub element: String,
+    /// Edge label, e.g. `"K"`.
+    pub edge: String,
+    /// Edge energy in eV.
+    pub edge_energy: f64,
+    /// Estimated μ/ρ (cm²/g of sample) the edge jump adds at the line
+    /// energy.
+    pub extra_mu_f: f64,
+    /// `extra_mu_f` as a fraction of the sample μ/ρ at the line energy.
+    pub mu_f_fraction: f64,
+}
+
+impl From<ReabsorptionWarning> for SelfAbsWarning {
+    fn from(w: ReabsorptionWarning) -> Self {
+        Self::LineReabsorbed {
+            line: w.line,
+            line_energy: w.line_energy,
+            element: w.element,
+            edge: w.edge,
+            edge_energy: w.edge_energy,
+            extra_mu_f: w.extra_mu_f,
+            mu_f_fraction: w.mu_f_fraction,
+        }
+    }
+}
+
+/// Edges of matrix elements (every element but the absorber) within
+/// `window_ev` below an emission line of `info`, one entry per line and
+/// edge: strongest line first, then by edge energy.
+///
+/// The extra μ_f is the element's mass fraction times the rise of its total
+/// μ/ρ across the edge at the line energy: μ(E_line) less μ just below the
+/// edge carried to E_line as E⁻³. It is an estimate of how much the edge
+/// raises μ_f, and with it the share of the fluorescence the matrix absorbs
+/// and may re-emit. μ/ρ comes from `ctx` on the `backend` tables.
+pub fn detect_line_reabsorption(
+    ctx: &SelfAbsContext,
+    info: &SampleInfo,
+    window_ev: f64,
+    backend: MuBackend,
+) -> Result<Vec<ReabsorptionWarning>, SelfAbsError> {
+    if !window_ev.is_finite() || window_ev < 0.0 {
+        return Err(SelfAbsError::InsufficientData(
+            "reabsorption window must be finite and >= 0".to_string(),
+        ));
+    }
+    let db = ctx.db();
+    let fractions = composition_mass_fractions(db, &info.composition)?;
+    let mu_total = |sym: &str, e: f64| -> Result<f64, SelfAbsError> {
+        Ok(ctx.mu(sym, &[e], CrossSectionKind::Total, backend)?[0])
+    };
+    let total = |e: f64| -> Result<f64, SelfAbsError> {
+        fractions
+            .iter()
+            .try_fold(0.0, |acc, (sym, w)| Ok(acc + w * mu_total(sym, e)?))
+    };
+    let mut hits = Vec::new();
+    for line in &info.lines {
+        let first = hits.len();
+        let mu_f = total(line.energy)?;
+        for (sym, w) in &fractions {
+            let symbol = db.symbol(sym)?.to_string();
+            if symbol == info.central_symbol {
+                continue;
+            }
+            for (label, xe) in db.xray_edges(sym)? {
+                if !(xe.energy < line.energy && xe.energy >= line.energy - window_ev) {
+                    continue;
+                }
+                let above = mu_total(sym, line.energy)?;
+                let e_below = xe.energy - 1.0;
+                let below = mu_total(sym, e_below)? * (e_below / line.energy).powi(3);
+                let extra_mu_f = w * (above - below).max(0.0);
+                hits.push(ReabsorptionWarning {
+                    line: line.label.clone(),
+                    line_energy: line.energy,
+                    element: symbol.clone(),
+                    edge: label,
+                    edge_energy: xe.energy,
+                    extra_mu_f,
+                    mu_f_fraction: extra_mu_f / mu_f,
+                });
+            }
+        }
+        hits[first..].sort_by(|a: &ReabsorptionWarning, b| a.edge_energy.total_cmp(&b.edge_energy));
+    }
+    Ok(hits)
+}
+
+/// Every absorption edge of every element in `composition` with
+/// `e_min < E ≤ e_max`, sorted by energy.
+///
+/// The selected absorber edge is included; the algorithms drop it before
+/// reporting the rest as [`SelfAbsWarning::InterferingEdge`].
+pub fn detect_edge_overlaps(
+    db: &XrayDb,
+    composition: &BTreeMap<String, f64>,
+    energy_range: (f64, f64),
+) -> Result<Vec<EdgeOverlapWarning>, SelfAbsError> {
+    let (e_min, e_max) = energy_range;
+    let mut hits = Vec::new();
+    for sym in composition.keys() {
+        let symbol = db.symbol(sym)?.to_string();
+        for (label, xe) in db.xray_edges(sym)? {
+            if xe.energy > e_min && xe.energy <= e_max {
+                hits.push(EdgeOverlapWarning {
+                    element: symbol.clone(),
+                    edge: label,
+                    energy: xe.energy,
+                });
+            }
+        }
+    }
+    hits.sort_by(|a, b| {
+        a.energy
+            .total_cmp(&b.energy)
+            .then_with(|| a.element.cmp(&b.element))
+    });
+    Ok(hits)
+}
+
+/// Warnings about the sample itself: a normalized mixture,
+/// [`detect_edge_overlaps`] over the span of `energies` without the
+/// selected absorber edge, and [`detect_line_reabsorption`] within the
+/// window of `ctx` on the `backend` tables.
+pub(crate) fn sample_warnings(
+    ctx: &SelfAbsContext,
+    info: &SampleInfo,
+    energies: &[f64],
+    backend: MuBackend,
+) -> Result<Vec<SelfAbsWarning>, SelfAbsError> {
+    let mut warnings: Vec<SelfAbsWarning> = info
+        .mixture_weight_sum
+        .map(|weight_sum| SelfAbsWarning::MixtureNormalized { weight_sum })
+        .into_iter()
+        .collect();
+    let e_min = energies.iter().copied().fold(f64::INFINITY, f64::min);
+    let e_max = energies.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if e_min.is_finite() && e_max.is_finite() {
+        warnings.extend(
+            detect_edge_overlaps(ctx, &info.composition, (e_min, e_max))?
+                .into_iter()
+                .filter(|w| !(w.element == info.central_symbol && w.edge == info.edge.as_str()))
+                .map(SelfAbsWarning::from),
+        );
+    }
+    warnings.extend(
+        detect_line_reabsorption(ctx, info, ctx.reabsorption_window_ev(), backend)?
+            .into_iter()
+            .map(SelfAbsWarning::from),
+    );
+    Ok(warnings)
 }
 
 fn find_element_count(
-    composition: &HashMap<String, f64>,
+    composition: &BTreeMap<String, f64>,
     db: &XrayDb,
     target_z: u16,
 ) -> Option<f64> {
@@ -138,17 +1380,19 @@ fn find_element_count(
 
 /// Compute stoichiometry-weighted mu at given energies for all atoms.
 ///
-/// Returns Σ(count_i × μ_elam_i(E)) in cm²/g-equivalent units.
+/// Returns Σ(count_i × μ_i(E)) in cm²/g-equivalent units.
 /// (For ratios between similar quantities the units cancel.)
 pub(crate) fn weighted_mu_total(
-    db: &XrayDb,
-    composition: &HashMap<String, f64>,
+    ctx: &SelfAbsContext,
+    composition: &BTreeMap<String, f64>,
     energies: &[f64],
+    kind: CrossSectionKind,
+    backend: MuBackend,
 ) -> Result<Vec<f64>, SelfAbsError> {
     let n = energies.len();
     let mut total = vec![0.0f64; n];
     for (sym, &count) in composition {
-        let mu = db.mu_elam(sym, energies, CrossSectionKind::Photo)?;
+        let mu = ctx.mu(sym, energies, kind, backend)?;
         for (i, &m) in mu.iter().enumerate() {
             total[i] += count * m;
         }
@@ -156,66 +1400,36 @@ pub(crate) fn weighted_mu_total(
     Ok(total)
 }
 
-/// Convert formula stoichiometry to mass fractions for each element.
-pub(crate) fn composition_mass_fractions(
-    db: &XrayDb,
-    composition: &HashMap<String, f64>,
-) -> Result<Vec<(String, f64)>, SelfAbsError> {
-    let mut masses = Vec::with_capacity(composition.len());
-    let mut total = 0.0;
-
-    for (sym, &count) in composition {
-        let mm = db.molar_mass(sym)?;
-        let mass = count * mm;
-        masses.push((sym.clone(), mass));
-        total += mass;
-    }
-
-    if total <= 0.0 || !total.is_finite() {
-        return Err(SelfAbsError::InsufficientData(
-            "formula produced non-positive total mass".to_string(),
-        ));
-    }
-
-    Ok(masses
-        .into_iter()
-        .map(|(sym, m)| (sym, m / total))
-        .collect())
-}
-
-/// Compute compound linear attenuation μ(E) in cm^-1 from mass fractions.
-pub(crate) fn compound_mu_linear(
-    db: &XrayDb,
-    mass_fractions: &[(String, f64)],
-    density_g_cm3: f64,
+/// Raw absorber photoelectric attenuation in cm^-1,
+/// `μ_abs_raw(E) = ρ * w_a * (μ/ρ)_absorber(E)`, including its pre-edge part.
+pub(crate) fn absorber_mu_linear(
+    ctx: &SelfAbsContext,
+    info: &SampleInfo,
     energies_ev: &[f64],
+    density_g_cm3: f64,
+    backend: MuBackend,
 ) -> Result<Vec<f64>, SelfAbsError> {
-    let mut mu_comp_mass = vec![0.0f64; energies_ev.len()];
-    for (sym, &w) in mass_fractions.iter().map(|(s, w)| (s, w)) {
-        let mu = db.mu_elam(sym, energies_ev, CrossSectionKind::Photo)?;
-        for (i, &v) in mu.iter().enumerate() {
-            mu_comp_mass[i] += w * v;
-        }
-    }
-    Ok(mu_comp_mass
-        .into_iter()
-        .map(|mu_rho| density_g_cm3 * mu_rho)
-        .collect())
-}
+    let mass_fractions = composition_mass_fractions(ctx, &info.composition)?;
+    let w_absorber = mass_fractions
+        .iter()
+        .find_map(|(sym, w)| (sym == &info.central_symbol).then_some(*w))
+        .ok_or_else(|| {
+            SelfAbsError::InsufficientData(format!(
+                "absorber {} not found in mass fractions",
+                info.central_symbol
+            ))
+        })?;
 
-/// Compute compound linear attenuation μ(E) at one energy in cm^-1.
-pub(crate) fn compound_mu_linear_single(
-    db: &XrayDb,
-    mass_fractions: &[(String, f64)],
-    density_g_cm3: f64,
-    energy_ev: f64,
-) -> Result<f64, SelfAbsError> {
-    let mut mu_comp_mass = 0.0;
-    for (sym, &w) in mass_fractions.iter().map(|(s, w)| (s, w)) {
-        let mu = db.mu_elam(sym, &[energy_ev], CrossSectionKind::Photo)?;
-        mu_comp_mass += w * mu[0];
-    }
-    Ok(density_g_cm3 * mu_comp_mass)
+    let mu_abs_mass = ctx.mu(
+        &info.central_symbol,
+        energies_ev,
+        CrossSectionKind::Photo,
+        backend,
+    )?;
+    Ok(mu_abs_mass
+        .iter()
+        .map(|&mu_rho| density_g_cm3 * w_absorber * mu_rho)
+        .collect())
 }
 
 /// Compute absorber edge contribution μ̄_a(E) in cm^-1 using a pre-edge trendline.
@@ -226,15 +1440,18 @@ pub(crate) fn compound_mu_linear_single(
 /// with:
 /// `μ_abs_raw(E) = ρ * w_a * (μ/ρ)_absorber(E)`.
 ///
-/// The pre-edge trendline is fit over `[E0 - 200 eV, E0 - 30 eV]`.
-/// If fitting is unstable or there are insufficient points, a scalar baseline
-/// at `E0 - 200 eV` is used.
+/// The pre-edge trendline is fit over the [`PreEdgeOptions`] window. If the
+/// window holds fewer than `min_points` usable points or the fit is unstable,
+/// a scalar baseline at the window start is used; the returned
+/// [`PreEdgeBaseline`] says which.
 pub(crate) fn absorber_edge_mu_linear_trendline(
-    db: &XrayDb,
+    ctx: &SelfAbsContext,
     info: &SampleInfo,
     energies_ev: &[f64],
     density_g_cm3: f64,
-) -> Result<Vec<f64>, SelfAbsError> {
+    pre_edge: &PreEdgeOptions,
+    backend: MuBackend,
+) -> Result<(Vec<f64>, PreEdgeBaseline), SelfAbsError> {
     if !density_g_cm3.is_finite() || density_g_cm3 <= 0.0 {
         return Err(SelfAbsError::InsufficientData(
             "density must be finite and > 0".to_string(),
@@ -245,31 +1462,23 @@ pub(crate) fn absorber_edge_mu_linear_trendline(
             "energy grid must not be empty".to_string(),
         ));
     }
+    if !(pre_edge.start_rel_ev.is_finite() && pre_edge.end_rel_ev.is_finite()) {
+        return Err(SelfAbsError::InsufficientData(
+            "pre-edge window bounds must be finite".to_string(),
+        ));
+    }
+    if pre_edge.min_points < 2 {
+        return Err(SelfAbsError::InsufficientData(format!(
+            "pre-edge min_points must be >= 2, got {}",
+            pre_edge.min_points
+        )));
+    }
 
-    let mass_fractions = composition_mass_fractions(db, &info.composition)?;
-    let w_absorber = mass_fractions
-        .iter()
-        .find_map(|(sym, w)| (sym == &info.central_symbol).then_some(*w))
-        .ok_or_else(|| {
-            SelfAbsError::InsufficientData(format!(
-                "absorber {} not found in mass fractions",
-                info.central_symbol
-            ))
-        })?;
-
-    let mu_abs_mass = db.mu_elam(&info.central_symbol, energies_ev, CrossSectionKind::Photo)?;
-    let mu_abs_raw: Vec<f64> = mu_abs_mass
-        .iter()
-        .map(|&mu_rho| density_g_cm3 * w_absorber * mu_rho)
-        .collect();
-
-    const PRE_EDGE_START_REL_EV: f64 = -200.0;
-    const PRE_EDGE_END_REL_EV: f64 = -30.0;
-    const PRE_EDGE_FALLBACK_REL_EV: f64 = -200.0;
-    const N_VICTOREEN: i32 = 0;
+    let mu_abs_raw = absorber_mu_linear(ctx, info, energies_ev, density_g_cm3, backend)?;
+    let n_victoreen = pre_edge.victoreen_n;
 
-    let pre_start = info.edge_energy + PRE_EDGE_START_REL_EV;
-    let pre_end = info.edge_energy + PRE_EDGE_END_REL_EV;
+    let pre_start = info.edge_energy + pre_edge.start_rel_ev;
+    let pre_end = info.edge_energy + pre_edge.end_rel_ev;
     let (fit_min, fit_max) = if pre_start <= pre_end {
         (pre_start, pre_end)
     } else {
@@ -280,7 +1489,7 @@ pub(crate) fn absorber_edge_mu_linear_trendline(
     let mut fit_y = Vec::new();
     for (&e, &mu_raw) in energies_ev.iter().zip(mu_abs_raw.iter()) {
         if e >= fit_min && e <= fit_max && e.is_finite() && mu_raw.is_finite() {
-            let y = mu_raw * e.powi(N_VICTOREEN);
+            let y = mu_raw * e.powi(n_victoreen);
             if y.is_finite() {
                 fit_x.push(e);
                 fit_y.push(y);
@@ -288,25 +1497,139 @@ pub(crate) fn absorber_edge_mu_linear_trendline(
         }
     }
 
-    let baseline: Vec<f64> = if let Some((intercept, slope)) = fit_line(&fit_x, &fit_y) {
-        energies_ev
+    let fit = if fit_x.len() >= pre_edge.min_points {
+        fit_line(&fit_x, &fit_y)
+    } else {
+        None
+    };
+    let (baseline, kind) = if let Some((intercept, slope)) = fit {
+        let baseline = energies_ev
             .iter()
             .map(|&e| {
-                let y = (intercept + slope * e) * e.powi(-N_VICTOREEN);
+                let y = (intercept + slope * e) * e.powi(-n_victoreen);
                 if y.is_finite() { y.max(0.0) } else { 0.0 }
             })
-            .collect()
+            .collect();
+        (baseline, PreEdgeBaseline::Trendline)
     } else {
-        let e_pre = info.edge_energy + PRE_EDGE_FALLBACK_REL_EV;
-        let mu_pre_mass = db.mu_elam(&info.central_symbol, &[e_pre], CrossSectionKind::Photo)?[0];
-        let mu_pre = (density_g_cm3 * w_absorber * mu_pre_mass).max(0.0);
-        vec![mu_pre; energies_ev.len()]
+        let mu_pre =
+            absorber_mu_linear(ctx, info, &[pre_start], density_g_cm3, backend)?[0].max(0.0);
+        (vec![mu_pre; energies_ev.len()], PreEdgeBaseline::Fallback)
     };
 
-    Ok(mu_abs_raw
+    let mu_a = mu_abs_raw
         .iter()
         .zip(baseline.iter())
         .map(|(&raw, &base)| (raw - base).max(0.0))
+        .collect();
+    Ok((mu_a, kind))
+}
+
+/// Absorber edge contribution μ̄_a(E) in cm^-1 by `method`. `pre_edge` is
+/// the window of [`PreEdgeMethod::Trendline`].
+pub(crate) fn absorber_edge_mu_linear(
+    ctx: &SelfAbsContext,
+    info: &SampleInfo,
+    energies_ev: &[f64],
+    density_g_cm3: f64,
+    method: PreEdgeMethod,
+    pre_edge: &PreEdgeOptions,
+    backend: MuBackend,
+) -> Result<(Vec<f64>, PreEdgeBaseline), SelfAbsError> {
+    match method {
+        PreEdgeMethod::Trendline => absorber_edge_mu_linear_trendline(
+            ctx,
+            info,
+            energies_ev,
+            density_g_cm3,
+            pre_edge,
+            backend,
+        ),
+        PreEdgeMethod::Constant => {
+            let raw = absorber_mu_linear(ctx, info, energies_ev, density_g_cm3, backend)?;
+            let below = info.edge_energy - 200.0;
+            let pre = absorber_mu_linear(ctx, info, &[below], density_g_cm3, backend)?[0];
+            let mu_a = raw.iter().map(|&m| (m - pre).max(0.0)).collect();
+            Ok((mu_a, PreEdgeBaseline::Constant))
+        }
+        PreEdgeMethod::JumpRatio => {
+            let raw = absorber_mu_linear(ctx, info, energies_ev, density_g_cm3, backend)?;
+            Ok((
+                jump_ratio_edge_share(ctx, info, energies_ev, raw)?,
+                PreEdgeBaseline::JumpRatio,
+            ))
+        }
+    }
+}
+
+/// Edge-only absorber μ̄_a(E) by `method` in the stoichiometry-weighted
+/// units of [`weighted_mu_absorber`]. The baseline is `None` for
+/// [`PreEdgeMethod::Constant`].
+pub(crate) fn weighted_mu_absorber_edge(
+    ctx: &SelfAbsContext,
+    info: &SampleInfo,
+    energies: &[f64],
+    method: PreEdgeMethod,
+    pre_edge: &PreEdgeOptions,
+    backend: MuBackend,
+) -> Result<(Vec<f64>, Option<PreEdgeBaseline>), SelfAbsError> {
+    match method {
+        PreEdgeMethod::Constant => Ok((
+            weighted_mu_absorber(ctx, info, energies, true, backend)?,
+            None,
+        )),
+        PreEdgeMethod::Trendline => {
+            // Fit at unit density, then rescale from cm^-1 to the weighted
+            // units.
+            let w_absorber = composition_mass_fractions(ctx, &info.composition)?
+                .into_iter()
+                .find_map(|(sym, w)| (sym == info.central_symbol).then_some(w))
+                .unwrap_or(1.0);
+            let (mu_a, baseline) =
+                absorber_edge_mu_linear_trendline(ctx, info, energies, 1.0, pre_edge, backend)?;
+            let mu_edge = mu_a
+                .iter()
+                .map(|&m| m * info.central_count / w_absorber)
+                .collect();
+            Ok((mu_edge, Some(baseline)))
+        }
+        PreEdgeMethod::JumpRatio => {
+            let raw = weighted_mu_absorber(ctx, info, energies, false, backend)?;
+            Ok((
+                jump_ratio_edge_share(ctx, info, energies, raw)?,
+                Some(PreEdgeBaseline::JumpRatio),
+            ))
+        }
+    }
+}
+
+/// `mu` × (1 − 1/J) at and above the tabulated edge, 0 below.
+fn jump_ratio_edge_share(
+    ctx: &SelfAbsContext,
+    info: &SampleInfo,
+    energies: &[f64],
+    mu: Vec<f64>,
+) -> Result<Vec<f64>, SelfAbsError> {
+    let jump_ratio = ctx
+        .xray_edge(&info.central_symbol, info.edge.as_str())?
+        .jump_ratio;
+    if !(jump_ratio.is_finite() && jump_ratio > 1.0) {
+        return Err(SelfAbsError::InsufficientData(format!(
+            "{} {} edge has no usable jump ratio ({jump_ratio})",
+            info.central_symbol, info.edge
+        )));
+    }
+    let share = 1.0 - 1.0 / jump_ratio;
+    Ok(energies
+        .iter()
+        .zip(mu)
+        .map(|(&e, m)| {
+            if e >= info.edge_energy {
+                m * share
+            } else {
+                0.0
+            }
+        })
         .collect())
 }
 
@@ -315,16 +1638,27 @@ pub(crate) fn absorber_edge_mu_linear_trendline(
 /// `subtract_pre_edge`: if true, subtracts μ(E_edge − 200 eV) to get the
 /// edge-jump contribution only (used by Troger, Booth, Atoms).
 pub(crate) fn weighted_mu_absorber(
-    db: &XrayDb,
+    ctx: &SelfAbsContext,
     info: &SampleInfo,
     energies: &[f64],
     subtract_pre_edge: bool,
+    backend: MuBackend,
 ) -> Result<Vec<f64>, SelfAbsError> {
-    let mu = db.mu_elam(&info.central_symbol, energies, CrossSectionKind::Photo)?;
+    let mu = ctx.mu(
+        &info.central_symbol,
+        energies,
+        CrossSectionKind::Photo,
+        backend,
+    )?;
 
     let pre_edge = if subtract_pre_edge {
         let e_below = info.edge_energy - 200.0;
-        let v = db.mu_elam(&info.central_symbol, &[e_below], CrossSectionKind::Photo)?;
+        let v = ctx.mu(
+            &info.central_symbol,
+            &[e_below],
+            CrossSectionKind::Photo,
+            backend,
+        )?;
         v[0]
     } else {
         0.0
@@ -338,18 +1672,19 @@ pub(crate) fn weighted_mu_absorber(
 
 /// Compute stoichiometry-weighted mu for all non-absorber atoms.
 pub(crate) fn weighted_mu_background(
-    db: &XrayDb,
+    ctx: &SelfAbsContext,
     info: &SampleInfo,
     energies: &[f64],
+    backend: MuBackend,
 ) -> Result<Vec<f64>, SelfAbsError> {
     let n = energies.len();
     let mut total = vec![0.0f64; n];
     for (sym, &count) in &info.composition {
-        let z = db.resolve_element(sym)?;
+        let z = ctx.resolve_element(sym)?;
         if z == info.central_z {
             continue;
         }
-        let mu = db.mu_elam(sym, energies, CrossSectionKind::Photo)?;
+        let mu = ctx.mu(sym, energies, CrossSectionKind::Photo, backend)?;
         for (i, &m) in mu.iter().enumerate() {
             total[i] += count * m;
         }
@@ -359,18 +1694,336 @@ pub(crate) fn weighted_mu_background(
 
 /// Compute stoichiometry-weighted mu at a single energy for all atoms.
 pub(crate) fn weighted_mu_total_single(
-    db: &XrayDb,
-    composition: &HashMap<String, f64>,
+    ctx: &SelfAbsContext,
+    composition: &BTreeMap<String, f64>,
     energy: f64,
+    kind: CrossSectionKind,
+    backend: MuBackend,
 ) -> Result<f64, SelfAbsError> {
     let mut total = 0.0;
     for (sym, &count) in composition {
-        let mu = db.mu_elam(sym, &[energy], CrossSectionKind::Photo)?;
+        let mu = ctx.mu(sym, &[energy], kind, backend)?;
         total += count * mu[0];
     }
     Ok(total)
 }
 
+/// Transmission at `energy` through a stack of `(formula, density g/cm³,
+/// thickness cm)` layers, using the total cross-section.
+pub(crate) fn path_transmission(
+    ctx: &SelfAbsContext,
+    path: &[(String, f64, f64)],
+    energy: f64,
+    backend: MuBackend,
+) -> Result<f64, SelfAbsError> {
+    let mut transmission = 1.0;
+    for (formula, density, thickness_cm) in path {
+        if !(density.is_finite()
+            && *density >= 0.0
+            && thickness_cm.is_finite()
+            && *thickness_cm >= 0.0)
+        {
+            return Err(SelfAbsError::InsufficientData(format!(
+                "path layer {formula} needs finite, non-negative density and thickness"
+            )));
+        }
+        let composition = parse_composition(formula)?;
+        let mass_fractions = composition_mass_fractions(ctx, &composition)?;
+        let mu = compound_mu_linear_single(
+            &ctx.tables(backend),
+            &mass_fractions,
+            *density,
+            energy,
+            CrossSectionKind::Total,
+        )?;
+        transmission *= (-mu * thickness_cm).exp();
+    }
+    Ok(transmission)
+}
+
+/// What the fluorescence detector counts.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DetectionMode {
+    /// An energy-dispersive detector gated on the fluorescence lines.
+    #[default]
+    EnergyResolved,
+    /// A total-yield (Lytle) detector without energy discrimination. A
+    /// fraction `scatter_fraction` in [0, 1] of the detected photons is
+    /// elastic and Compton scatter, which leaves the sample attenuated at
+    /// the incident energy rather than at the fluorescence energy.
+    TotalYield { scatter_fraction: f64 },
+}
+
+impl DetectionMode {
+    /// Outgoing attenuation: `mu_f` at the fluorescence energy mixed with
+    /// `mu_incident` at the incident energy by the scatter fraction.
+    pub fn exit_mu(&self, mu_f: f64, mu_incident: f64) -> f64 {
+        match *self {
+            Self::EnergyResolved => mu_f,
+            Self::TotalYield { scatter_fraction } => {
+                (1.0 - scatter_fraction) * mu_f + scatter_fraction * mu_incident
+            }
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), SelfAbsError> {
+        if let Self::TotalYield { scatter_fraction } = *self
+            && !(0.0..=1.0).contains(&scatter_fraction)
+        {
+            return Err(SelfAbsError::InsufficientData(format!(
+                "scatter fraction must be in [0, 1], got {scatter_fraction}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// One homogeneous layer of a [`LayeredSample`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayerSpec {
+    /// Layer chemical formula.
+    pub formula: String,
+    /// Layer density (g/cm³).
+    pub density: f64,
+    /// Layer thickness (μm).
+    pub thickness_um: f64,
+}
+
+impl LayerSpec {
+    /// Error unless the density and thickness are finite and > 0.
+    pub(crate) fn validate(&self) -> Result<(), SelfAbsError> {
+        if self.density.is_finite()
+            && self.density > 0.0
+            && self.thickness_um.is_finite()
+            && self.thickness_um > 0.0
+        {
+            Ok(())
+        } else {
+            Err(SelfAbsError::InsufficientData(format!(
+                "layer {} needs finite density > 0 and thickness > 0, got {} g/cm3 and {} um",
+                self.formula, self.density, self.thickness_um
+            )))
+        }
+    }
+}
+
+/// Side of a [`LayeredSample`] the incident beam enters from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LayerIncidence {
+    /// Reflection geometry: beam and detector both face the caps.
+    #[default]
+    Front,
+    /// Transmission-fluorescence geometry: the beam crosses the substrate
+    /// and enters the absorbing layer from below, while the detector still
+    /// faces the caps. Supported by
+    /// [`booth_layered`](crate::booth::booth_layered) only.
+    ThroughSubstrate,
+}
+
+/// An absorbing layer under zero or more capping layers, e.g. a film under a
+/// Kapton window or a protective overlayer, for
+/// [`booth_layered`](crate::booth::booth_layered) and
+/// [`ameyanagi_layered`](crate::ameyanagi::ameyanagi_layered).
+///
+/// In reflection geometry both the incident beam and the fluorescence cross
+/// every cap. The absorbing layer's lower boundary is its finite thickness:
+/// the models integrate over that depth only, so a substrate below it is
+/// never reached and does not change the result. Caps use the total
+/// cross-section, since scattering also removes photons from the beam.
+///
+/// With [`LayerIncidence::ThroughSubstrate`] the incident beam crosses the
+/// substrate instead of the caps and enters the absorbing layer from below,
+/// so incident and fluorescence paths run in opposite directions through
+/// it.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayeredSample {
+    /// Capping layers from the beam-facing surface downwards.
+    pub caps: Vec<LayerSpec>,
+    /// The layer containing the absorbing element.
+    pub absorber: LayerSpec,
+    /// Layer below the absorbing one, e.g. a Si wafer.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub substrate: Option<LayerSpec>,
+    /// Side the incident beam enters from.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub incidence: LayerIncidence,
+}
+
+impl LayeredSample {
+    /// `absorber` without caps.
+    pub fn new(absorber: LayerSpec) -> Self {
+        Self {
+            caps: Vec::new(),
+            absorber,
+            substrate: None,
+            incidence: LayerIncidence::Front,
+        }
+    }
+
+    /// Put `substrate` below the absorbing layer.
+    pub fn with_substrate(mut self, substrate: LayerSpec) -> Self {
+        self.substrate = Some(substrate);
+        self
+    }
+
+    /// Check every layer.
+    pub(crate) fn validate(&self) -> Result<(), SelfAbsError> {
+        for layer in self
+            .caps
+            .iter()
+            .chain([&self.absorber])
+            .chain(&self.substrate)
+        {
+            layer.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Transmission of the incident beam at each of `energies` before it
+    /// reaches the absorbing layer: through the caps from the front, through
+    /// the substrate (if any) with [`LayerIncidence::ThroughSubstrate`].
+    pub(crate) fn incident_transmission(
+        &self,
+        ctx: &SelfAbsContext,
+        energies: &[f64],
+        sin_angle: f64,
+        backend: MuBackend,
+    ) -> Result<Vec<f64>, SelfAbsError> {
+        match self.incidence {
+            LayerIncidence::Front => self.cap_transmission(ctx, energies, sin_angle, backend),
+            LayerIncidence::ThroughSubstrate => {
+                layers_transmission(ctx, &self.substrate, energies, sin_angle, backend)
+            }
+        }
+    }
+
+    /// Add `cap` directly above the absorbing layer.
+    pub fn with_cap(mut self, cap: LayerSpec) -> Self {
+        self.caps.push(cap);
+        self
+    }
+
+    /// Transmission of the caps at each of `energies` along a path at
+    /// `sin_angle` to the surface.
+    pub(crate) fn cap_transmission(
+        &self,
+        ctx: &SelfAbsContext,
+        energies: &[f64],
+        sin_angle: f64,
+        backend: MuBackend,
+    ) -> Result<Vec<f64>, SelfAbsError> {
+        layers_transmission(ctx, &self.caps, energies, sin_angle, backend)
+    }
+
+    /// [`Self::cap_transmission`] at a single energy.
+    pub(crate) fn cap_transmission_single(
+        &self,
+        ctx: &SelfAbsContext,
+        energy: f64,
+        sin_angle: f64,
+        backend: MuBackend,
+    ) -> Result<f64, SelfAbsError> {
+        let mut transmission = 1.0;
+        for cap in &self.caps {
+            let mass_fractions = cap_mass_fractions(ctx, cap)?;
+            let mu = compound_mu_linear_single(
+                &ctx.tables(backend),
+                &mass_fractions,
+                cap.density,
+                energy,
+                CrossSectionKind::Total,
+            )?;
+            transmission *= (-mu * cap.thickness_um * 1e-4 / sin_angle).exp();
+        }
+        Ok(transmission)
+    }
+}
+
+/// Transmission of `layers` at each of `energies` along a path at
+/// `sin_angle` to the surface, with the total cross-section.
+fn layers_transmission<'a>(
+    ctx: &SelfAbsContext,
+    layers: impl IntoIterator<Item = &'a LayerSpec>,
+    energies: &[f64],
+    sin_angle: f64,
+    backend: MuBackend,
+) -> Result<Vec<f64>, SelfAbsError> {
+    let mut transmission = vec![1.0; energies.len()];
+    for layer in layers {
+        let mass_fractions = cap_mass_fractions(ctx, layer)?;
+        let mu = compound_mu_linear(
+            &ctx.tables(backend),
+            &mass_fractions,
+            layer.density,
+            energies,
+            CrossSectionKind::Total,
+        )?;
+        let path_cm = layer.thickness_um * 1e-4 / sin_angle;
+        for (t, mu) in transmission.iter_mut().zip(mu) {
+            *t *= (-mu * path_cm).exp();
+        }
+    }
+    Ok(transmission)
+}
+
+fn cap_mass_fractions(
+    ctx: &SelfAbsContext,
+    cap: &LayerSpec,
+) -> Result<Vec<(String, f64)>, SelfAbsError> {
+    cap.validate()?;
+    composition_mass_fractions(ctx, &parse_composition(&cap.formula)?)
+}
+
+/// Transmission at `energy` of an optional `(formula, density g/cm³,
+/// thickness μm)` filter in front of the detector; 1 without one.
+pub(crate) fn filter_transmission(
+    ctx: &SelfAbsContext,
+    filter: Option<&(String, f64, f64)>,
+    energy: f64,
+    backend: MuBackend,
+) -> Result<f64, SelfAbsError> {
+    match filter {
+        None => Ok(1.0),
+        Some((formula, density, thickness_um)) => path_transmission(
+            ctx,
+            &[(formula.clone(), *density, thickness_um * 1e-4)],
+            energy,
+            backend,
+        ),
+    }
+}
+
+/// (μ_f, fluorescence energy) averaged over `lines`, each weighted by its
+/// intensity times `transmission(energy)`, with `mu_at(energy)` giving μ at
+/// one line.
+pub(crate) fn weighted_over_lines(
+    info: &SampleInfo,
+    lines: &[EmissionLine],
+    mut transmission: impl FnMut(f64) -> Result<f64, SelfAbsError>,
+    mut mu_at: impl FnMut(f64) -> Result<f64, SelfAbsError>,
+) -> Result<(f64, f64), SelfAbsError> {
+    let mut mu_f_weighted = 0.0;
+    let mut ef_weighted = 0.0;
+    let mut w_sum = 0.0;
+    for line in lines {
+        let w = line.intensity * transmission(line.energy)?;
+        mu_f_weighted += w * mu_at(line.energy)?;
+        ef_weighted += w * line.energy;
+        w_sum += w;
+    }
+    if w_sum <= 0.0 {
+        return Err(SelfAbsError::NoEmissionLines(format!(
+            "{} {} has no transmitted lines",
+            info.central_symbol, info.edge
+        )));
+    }
+    Ok((mu_f_weighted / w_sum, ef_weighted / w_sum))
+}
+
 /// Linear least-squares fit of ln(y) vs x for points where x > 0 and y > 0.
 ///
 /// Model: ln(y) = intercept + slope × x.
@@ -409,6 +2062,156 @@ pub(crate) fn fit_ln_vs_x(x: &[f64], y: &[f64]) -> (f64, f64) {
     (intercept, slope)
 }
 
+/// Weighted variant of [`fit_ln_vs_x`]: minimizes Σ wᵢ (ln yᵢ − a − b xᵢ)².
+///
+/// Points with x ≤ 0, y ≤ 0 or w ≤ 0 are skipped.
+pub(crate) fn fit_ln_vs_x_weighted(x: &[f64], y: &[f64], w: &[f64]) -> (f64, f64) {
+    let mut sw = 0.0;
+    let mut sx = 0.0;
+    let mut sy = 0.0;
+    let mut sxx = 0.0;
+    let mut sxy = 0.0;
+    let mut n = 0u32;
+
+    for ((&xi, &yi), &wi) in x.iter().zip(y.iter()).zip(w.iter()) {
+        if xi <= 0.0 || yi <= 0.0 || !(wi > 0.0 && wi.is_finite()) {
+            continue;
+        }
+        let ly = yi.ln();
+        sw += wi;
+        sx += wi * xi;
+        sy += wi * ly;
+        sxx += wi * xi * xi;
+        sxy += wi * xi * ly;
+        n += 1;
+    }
+
+    if n < 2 {
+        return (0.0, 0.0);
+    }
+
+    let denom = sw * sxx - sx * sx;
+    if denom.abs() < 1e-30 {
+        return (0.0, 0.0);
+    }
+
+    let slope = (sw * sxy - sx * sy) / denom;
+    let intercept = (sy - slope * sx) / sw;
+    (intercept, slope)
+}
+
+/// Root returned by [`brent_root`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RootSolution {
+    pub root: f64,
+    pub iterations: usize,
+    pub residual: f64,
+}
+
+/// Brent's bracketing root finder (inverse quadratic interpolation with
+/// bisection safeguard).
+///
+/// Requires `f(lo)` and `f(hi)` to be finite with opposite signs (or one of
+/// them zero). Returns `None` if the bracket is invalid, `f` becomes
+/// non-finite, or `max_iter` is exhausted.
+pub(crate) fn brent_root<F>(
+    f: F,
+    lo: f64,
+    hi: f64,
+    xtol: f64,
+    max_iter: usize,
+) -> Option<RootSolution>
+where
+    F: Fn(f64) -> f64,
+{
+    let mut a = lo;
+    let mut b = hi;
+    let mut fa = f(a);
+    let mut fb = f(b);
+    if !fa.is_finite() || !fb.is_finite() || fa * fb > 0.0 {
+        return None;
+    }
+    if fa == 0.0 {
+        return Some(RootSolution {
+            root: a,
+            iterations: 0,
+            residual: 0.0,
+        });
+    }
+
+    let mut c = b;
+    let mut fc = fb;
+    let mut d = b - a;
+    let mut e = d;
+
+    for iteration in 1..=max_iter {
+        if (fb > 0.0) == (fc > 0.0) {
+            c = a;
+            fc = fa;
+            d = b - a;
+            e = d;
+        }
+        if fc.abs() < fb.abs() {
+            a = b;
+            b = c;
+            c = a;
+            fa = fb;
+            fb = fc;
+            fc = fa;
+        }
+
+        let tol1 = 2.0 * f64::EPSILON * b.abs() + 0.5 * xtol;
+        let xm = 0.5 * (c - b);
+        if xm.abs() <= tol1 || fb == 0.0 {
+            return Some(RootSolution {
+                root: b,
+                iterations: iteration,
+                residual: fb.abs(),
+            });
+        }
+
+        if e.abs() >= tol1 && fa.abs() > fb.abs() {
+            let s = fb / fa;
+            let (mut p, mut q) = if a == c {
+                (2.0 * xm * s, 1.0 - s)
+            } else {
+                let q = fa / fc;
+                let r = fb / fc;
+                (
+                    s * (2.0 * xm * q * (q - r) - (b - a) * (r - 1.0)),
+                    (q - 1.0) * (r - 1.0) * (s - 1.0),
+                )
+            };
+            if p > 0.0 {
+                q = -q;
+            }
+            p = p.abs();
+            let min1 = 3.0 * xm * q - (tol1 * q).abs();
+            let min2 = (e * q).abs();
+            if 2.0 * p < min1.min(min2) {
+                e = d;
+                d = p / q;
+            } else {
+                d = xm;
+                e = d;
+            }
+        } else {
+            d = xm;
+            e = d;
+        }
+
+        a = b;
+        fa = fb;
+        b += if d.abs() > tol1 { d } else { tol1.copysign(xm) };
+        fb = f(b);
+        if !fb.is_finite() {
+            return None;
+        }
+    }
+
+    None
+}
+
 fn fit_line(x: &[f64], y: &[f64]) -> Option<(f64, f64)> {
     if x.len() != y.len() || x.len() < 2 {
         return None;
@@ -449,16 +2252,89 @@ fn fit_line(x: &[f64], y: &[f64]) -> Option<(f64, f64)> {
     Some((intercept, slope))
 }
 
-/// Convert energy array to k array. k = 0 for E ≤ E_edge.
-pub(crate) fn energies_to_k(energies: &[f64], e_edge: f64) -> Vec<f64> {
+/// Convert energies (eV) to k (Å⁻¹) relative to `e0`. k = 0 for E ≤ E₀.
+pub fn energies_to_k(energies: &[f64], e0: f64) -> Vec<f64> {
     energies
         .iter()
         .map(|&e| {
-            if e > e_edge {
-                ((e - e_edge) * ETOK).sqrt()
+            if e > e0 {
+                ((e - e0) * ETOK).sqrt()
             } else {
                 0.0
             }
         })
         .collect()
 }
+
+/// Convert k (Å⁻¹) to energies (eV) relative to `e0`: E = E₀ + k²/ETOK.
+///
+/// Inverse of [`energies_to_k`] above the edge; negative k maps to E₀.
+pub fn k_to_energies(k: &[f64], e0: f64) -> Vec<f64> {
+    k.iter()
+        .map(|&ki| e0 + ki.max(0.0).powi(2) / ETOK)
+        .collect()
+}
+
+/// E₀ (eV) for k grids and E₀-relative windows: `e0_override` when given
+/// (e.g. a calibrated E₀), otherwise the tabulated edge energy.
+///
+/// Only the k axis moves; tabulated cross-sections keep their own edge.
+pub(crate) fn resolve_e0(info: &SampleInfo, e0_override: Option<f64>) -> Result<f64, SelfAbsError> {
+    match e0_override {
+        Some(e0) if !(e0.is_finite() && e0 > 0.0) => Err(SelfAbsError::InsufficientData(format!(
+            "E0 override must be finite and > 0, got {e0}"
+        ))),
+        Some(e0) => Ok(e0),
+        None => Ok(info.edge_energy),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edge_parsing_and_unknown_edge_message() {
+        for (text, edge) in [
+            ("K", Edge::K),
+            ("k", Edge::K),
+            ("L3", Edge::L3),
+            ("l3", Edge::L3),
+            ("L_3", Edge::L3),
+            ("l-3", Edge::L3),
+            ("LIII", Edge::L3),
+            ("m_v", Edge::M5),
+            (" N7", Edge::N7),
+        ] {
+            assert_eq!(text.parse::<Edge>().unwrap(), edge, "{text}");
+        }
+        for edge in Edge::ALL {
+            assert_eq!(edge.to_string().parse::<Edge>().unwrap(), edge);
+        }
+
+        for text in ["", "K1", "L", "L4", "M6", "Q1", "KL"] {
+            let err = text.parse::<Edge>().unwrap_err();
+            assert_eq!(err.kind(), SelfAbsErrorKind::InvalidEdge, "{text}");
+            let message = err.to_string();
+            assert!(message.contains("K, L1, L2, L3, M1"), "{message}");
+            assert!(message.contains("P3"), "{message}");
+        }
+
+        // N7 is a valid name, but carbon only has K and L levels.
+        let db = XrayDb::new();
+        let err = SampleInfo::new(&db, "CO2", "C", Edge::N7).unwrap_err();
+        assert_eq!(err.kind(), SelfAbsErrorKind::InvalidEdge);
+        assert_eq!(
+            err.to_string(),
+            "invalid edge: C has no N7 edge; available: K, L1, L2, L3"
+        );
+
+        // Entry points take an Edge or a name.
+        let typed = SampleInfo::new(&db, "Fe2O3", "Fe", Edge::K).unwrap();
+        let named = SampleInfo::new(&db, "Fe2O3", "Fe", "k").unwrap();
+        assert_eq!(named.edge(), typed.edge());
+        assert_eq!(named.edge_energy(), typed.edge_energy());
+        let err = SampleInfo::new(&db, "Fe2O3", "Fe", "L4").unwrap_err();
+        assert_eq!(err.kind(), SelfAbsErrorKind::InvalidEdge);
+    }
+}
@@ -0,0 +1,1330 @@
+//! Shared database and memoized lookups across algorithm calls.
+
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use xraydb::{ChantlerKind, CrossSectionKind, XrayDb};
+
+use crate::ameyanagi::{
+    AmeyanagiSampleSettings, AmeyanagiSuppressionResult, AmeyanagiSuppressionSettings,
+    AmeyanagiThicknessScanPoint, LayeredAmeyanagiResult, ThicknessRecommendation,
+    ameyanagi_chi_scan_with_sample_info, ameyanagi_correct_chi_with_sample_info,
+    ameyanagi_layered_on, ameyanagi_max_thickness_with_sample_info,
+    ameyanagi_suppression_exact_with_sample_info, ameyanagi_suppression_profile_with_sample_info,
+    ameyanagi_thickness_scan_with_sample_info,
+};
+use crate::atoms::{AtomsOptions, AtomsResult, atoms_with_sample_info};
+use crate::booth::{
+    BoothGeometryOptimum, BoothOptions, BoothResult, BoothSuppressionResult, GeometryBounds,
+    LayeredBoothResult, booth_layered_on, booth_optimize_geometry_on,
+    booth_suppression_reference_on, booth_with_sample_info,
+};
+use crate::common::{
+    Edge, ElementRef, FluorescenceGeometry, LayeredSample, MuBackend, REABSORPTION_WINDOW_EV,
+    SampleInfo, SelfAbsError,
+};
+use crate::fluo::{FluoOptions, FluoParams, fluo_params_with_sample_info};
+use crate::troger::{TrogerOptions, TrogerResult, troger_with_sample_info};
+
+/// Elemental μ/ρ tables, either Elam straight from [`XrayDb`] or one
+/// [`MuBackend`] memoized by a [`SelfAbsContext`] through
+/// [`SelfAbsContext::tables`].
+pub trait MuTables {
+    /// μ/ρ (cm²/g) of `element` on `energies_ev`.
+    fn mu_table(
+        &self,
+        element: &str,
+        energies_ev: &[f64],
+        kind: CrossSectionKind,
+    ) -> Result<Vec<f64>, SelfAbsError>;
+}
+
+impl MuTables for XrayDb {
+    fn mu_table(
+        &self,
+        element: &str,
+        energies_ev: &[f64],
+        kind: CrossSectionKind,
+    ) -> Result<Vec<f64>, SelfAbsError> {
+        Ok(self.mu_elam(element, energies_ev, kind)?)
+    }
+}
+
+/// Energy values sampled, end points included, for a grid fingerprint.
+const FINGERPRINT_SAMPLES: usize = 8;
+
+/// Grids shorter than this bypass the μ cache. Lookups at one or a few
+/// energies, such as μ at the fluorescence lines, are cheap to recompute and
+/// would otherwise add an entry for every distinct energy.
+const MIN_CACHED_GRID_LEN: usize = 8;
+
+/// μ tables are keyed by element symbol, cross-section kind, backend and a
+/// grid fingerprint hashing the length and a few sampled energies.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct MuKey {
+    symbol: String,
+    kind: u8,
+    backend: MuBackend,
+    fingerprint: u64,
+}
+
+/// A cached μ table and the exact grid it was computed on, compared before
+/// reuse since fingerprints can collide.
+struct MuEntry {
+    energies: Vec<f64>,
+    mu: Vec<f64>,
+}
+
+/// Hit and miss counts of the μ cache of a [`SelfAbsContext`]. Lookups on
+/// grids too short to be cached are not counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MuCacheStats {
+    /// Lookups served from the cache.
+    pub hits: usize,
+    /// Lookups computed from the [`XrayDb`] tables.
+    pub misses: usize,
+}
+
+/// Cheap hash of the grid length and [`FINGERPRINT_SAMPLES`] evenly spaced
+/// energies.
+fn grid_fingerprint(energies_ev: &[f64]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    let n = energies_ev.len();
+    n.hash(&mut hasher);
+    if n > 0 {
+        for i in 0..FINGERPRINT_SAMPLES {
+            energies_ev[i * (n - 1) / (FINGERPRINT_SAMPLES - 1)]
+                .to_bits()
+                .hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+/// Bitwise equality of two grids.
+fn same_grid(a: &[f64], b: &[f64]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.to_bits() == y.to_bits())
+}
+
+/// Database of a [`SelfAbsContext`], owned or borrowed from the caller.
+enum DbHandle<'db> {
+    Owned(XrayDb),
+    Borrowed(&'db XrayDb),
+}
+
+/// One [`XrayDb`] plus caches of [`SampleInfo`] per (formula, element, edge)
+/// and of elemental μ tables per energy grid, shared by every algorithm run
+/// through it.
+///
+/// The free functions (e.g. [`crate::troger::troger`]) run on a transient
+/// context, so results are identical; a long-lived context only saves the
+/// repeated parsing and μ evaluation, e.g. for a comparison view running all
+/// five algorithms on one grid. Dereferences to the underlying [`XrayDb`],
+/// which is owned ([`new`](Self::new)) or borrowed
+/// ([`with_db`](Self::with_db)).
+pub struct SelfAbsContext<'db> {
+    db: DbHandle<'db>,
+    sample_infos: Mutex<HashMap<(String, String, Edge), SampleInfo>>,
+    mu_tables: Mutex<HashMap<MuKey, Vec<MuEntry>>>,
+    mu_hits: AtomicUsize,
+    mu_misses: AtomicUsize,
+    reabsorption_window_ev: f64,
+}
+
+impl Default for SelfAbsContext<'static> {
+    fn default() -> Self {
+        Self::with_db(None)
+    }
+}
+
+impl SelfAbsContext<'static> {
+    /// A context owning a fresh [`XrayDb`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<'db> SelfAbsContext<'db> {
+    /// A context on `db`, or on a fresh [`XrayDb`] when `None`.
+    pub fn with_db(db: Option<&'db XrayDb>) -> Self {
+        Self {
+            db: match db {
+                Some(db) => DbHandle::Borrowed(db),
+                None => DbHandle::Owned(XrayDb::new()),
+            },
+            sample_infos: Mutex::default(),
+            mu_tables: Mutex::default(),
+            mu_hits: AtomicUsize::default(),
+            mu_misses: AtomicUsize::default(),
+            reabsorption_window_ev: REABSORPTION_WINDOW_EV,
+        }
+    }
+
+    /// Search `window_ev` below each absorber emission line for the matrix
+    /// edges that every algorithm run on this context reports as
+    /// [`LineReabsorbed`](crate::common::SelfAbsWarning::LineReabsorbed),
+    /// instead of [`REABSORPTION_WINDOW_EV`]. A window that is not finite
+    /// and ≥ 0 makes those runs fail.
+    pub fn with_reabsorption_window(mut self, window_ev: f64) -> Self {
+        self.reabsorption_window_ev = window_ev;
+        self
+    }
+}
+
+impl SelfAbsContext<'_> {
+    /// The underlying database.
+    pub fn db(&self) -> &XrayDb {
+        match &self.db {
+            DbHandle::Owned(db) => db,
+            DbHandle::Borrowed(db) => db,
+        }
+    }
+
+    /// Window (eV) of the line-reabsorption warnings, see
+    /// [`with_reabsorption_window`](SelfAbsContext::with_reabsorption_window).
+    pub fn reabsorption_window_ev(&self) -> f64 {
+        self.reabsorption_window_ev
+    }
+
+    /// [`SampleInfo::new`], cached per (formula, resolved element, edge).
+    pub fn sample_info(
+        &self,
+        formula: &str,
+        central_element: impl Into<ElementRef>,
+        edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    ) -> Result<SampleInfo, SelfAbsError> {
+        let edge: Edge = edge.try_into().map_err(Into::into)?;
+        let (_, symbol) = central_element.into().resolve(self.db())?;
+        let key = (formula.to_string(), symbol, edge);
+        if let Some(info) = self.sample_infos.lock().unwrap().get(&key) {
+            return Ok(info.clone());
+        }
+        let info = SampleInfo::new(self.db(), formula, &key.1, edge)?;
+        self.sample_infos.lock().unwrap().insert(key, info.clone());
+        Ok(info)
+    }
+
+    /// [`XrayDb::mu_elam`], memoized per element, kind and energy grid.
+    pub fn mu_elam(
+        &self,
+        element: &str,
+        energies_ev: &[f64],
+        kind: CrossSectionKind,
+    ) -> Result<Vec<f64>, SelfAbsError> {
+        self.mu(element, energies_ev, kind, MuBackend::Elam)
+    }
+
+    /// μ/ρ (cm²/g) of `element` on `energies_ev` from the `backend` tables,
+    /// memoized per element, kind, backend and energy grid. Grids of fewer
+    /// than eight points are computed directly and not stored.
+    ///
+    /// Chantler has no separate coherent table; its coherent part is the
+    /// total less the photoelectric and incoherent parts.
+    pub fn mu(
+        &self,
+        element: &str,
+        energies_ev: &[f64],
+        kind: CrossSectionKind,
+        backend: MuBackend,
+    ) -> Result<Vec<f64>, SelfAbsError> {
+        if energies_ev.len() < MIN_CACHED_GRID_LEN {
+            return self.compute_mu(element, energies_ev, kind, backend);
+        }
+        let key = MuKey {
+            symbol: self.db().symbol(element)?.to_string(),
+            kind: match kind {
+                CrossSectionKind::Photo => 0,
+                CrossSectionKind::Coherent => 1,
+                CrossSectionKind::Incoherent => 2,
+                CrossSectionKind::Total => 3,
+            },
+            backend,
+            fingerprint: grid_fingerprint(energies_ev),
+        };
+        if let Some(entries) = self.mu_tables.lock().unwrap().get(&key)
+            && let Some(entry) = entries
+                .iter()
+                .find(|entry| same_grid(&entry.energies, energies_ev))
+        {
+            self.mu_hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(entry.mu.clone());
+        }
+        let mu = self.compute_mu(element, energies_ev, kind, backend)?;
+        self.mu_misses.fetch_add(1, Ordering::Relaxed);
+        self.mu_tables
+            .lock()
+            .unwrap()
+            .entry(key)
+            .or_default()
+            .push(MuEntry {
+                energies: energies_ev.to_vec(),
+                mu: mu.clone(),
+            });
+        Ok(mu)
+    }
+
+    /// Drop every cached μ table and [`SampleInfo`]. The hit and miss counts
+    /// are kept.
+    pub fn clear_cache(&self) {
+        self.mu_tables.lock().unwrap().clear();
+        self.sample_infos.lock().unwrap().clear();
+    }
+
+    /// Uncached body of [`mu`](Self::mu).
+    fn compute_mu(
+        &self,
+        element: &str,
+        energies_ev: &[f64],
+        kind: CrossSectionKind,
+        backend: MuBackend,
+    ) -> Result<Vec<f64>, SelfAbsError> {
+        Ok(match backend {
+            MuBackend::Elam => self.db().mu_elam(element, energies_ev, kind)?,
+            MuBackend::Chantler => {
+                let chantler = |kind| self.db().mu_chantler(element, energies_ev, kind);
+                match kind {
+                    CrossSectionKind::Photo => chantler(ChantlerKind::Photo)?,
+                    CrossSectionKind::Incoherent => chantler(ChantlerKind::Incoherent)?,
+                    CrossSectionKind::Total => chantler(ChantlerKind::Total)?,
+                    CrossSectionKind::Coherent => {
+                        let photo = chantler(ChantlerKind::Photo)?;
+                        let incoherent = chantler(ChantlerKind::Incoherent)?;
+                        chantler(ChantlerKind::Total)?
+                            .into_iter()
+                            .zip(photo.iter().zip(&incoherent))
+                            .map(|(total, (photo, incoherent))| {
+                                (total - photo - incoherent).max(0.0)
+                            })
+                            .collect()
+                    }
+                }
+            }
+        })
+    }
+
+    /// [`MuTables`] of this context on the `backend` tables.
+    pub fn tables(&self, backend: MuBackend) -> BackendTables<'_> {
+        BackendTables { ctx: self, backend }
+    }
+
+    /// Hits and misses of [`mu`](Self::mu) so far.
+    pub fn mu_cache_stats(&self) -> MuCacheStats {
+        MuCacheStats {
+            hits: self.mu_hits.load(Ordering::Relaxed),
+            misses: self.mu_misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// [`crate::fluo::fluo_params_with_options`] on this context.
+    pub fn fluo(
+        &self,
+        formula: &str,
+        central_element: impl Into<ElementRef>,
+        edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+        energies: &[f64],
+        geometry: Option<FluorescenceGeometry>,
+        options: &FluoOptions,
+    ) -> Result<FluoParams, SelfAbsError> {
+        let info = self.sample_info(formula, central_element, edge)?;
+        fluo_params_with_sample_info(self, &info, energies, geometry, options)
+    }
+
+    /// [`crate::troger::troger_with_options`] on this context.
+    pub fn troger(
+        &self,
+        formula: &str,
+        central_element: impl Into<ElementRef>,
+        edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+        energies: &[f64],
+        geometry: Option<FluorescenceGeometry>,
+        options: &TrogerOptions,
+    ) -> Result<TrogerResult, SelfAbsError> {
+        let info = self.sample_info(formula, central_element, edge)?;
+        troger_with_sample_info(self, &info, energies, geometry, options)
+    }
+
+    /// [`crate::booth::booth_with_options`] on this context.
+    #[allow(clippy::too_many_arguments)]
+    pub fn booth(
+        &self,
+        formula: &str,
+        central_element: impl Into<ElementRef>,
+        edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+        energies: &[f64],
+        geometry: Option<FluorescenceGeometry>,
+        thickness_um: f64,
+        options: &BoothOptions,
+    ) -> Result<BoothResult, SelfAbsError> {
+        let info = self.sample_info(formula, central_element, edge)?;
+        booth_with_sample_info(self, &info, energies, geometry, thickness_um, options)
+    }
+
+    /// [`crate::booth::booth_suppression_reference_with_options`] on this
+    /// context.
+    #[allow(clippy::too_many_arguments)]
+    pub fn booth_reference(
+        &self,
+        formula: &str,
+        central_element: impl Into<ElementRef>,
+        edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+        energies: &[f64],
+        geometry: Option<FluorescenceGeometry>,
+        thickness_um: f64,
+        density_g_cm3: f64,
+        chi_true: f64,
+        options: &BoothOptions,
+    ) -> Result<BoothSuppressionResult, SelfAbsError> {
+        let edge: Edge = edge.try_into().map_err(Into::into)?;
+        booth_suppression_reference_on(
+            self,
+            formula,
+            central_element,
+            edge,
+            energies,
+            geometry,
+            thickness_um,
+            density_g_cm3,
+            chi_true,
+            options,
+            None,
+        )
+    }
+
+    /// [`crate::booth::booth_layered_with_options`] on this context.
+    pub fn booth_layered(
+        &self,
+        sample: &LayeredSample,
+        central_element: impl Into<ElementRef>,
+        edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+        energies: &[f64],
+        geometry: Option<FluorescenceGeometry>,
+        options: &BoothOptions,
+    ) -> Result<LayeredBoothResult, SelfAbsError> {
+        booth_layered_on(
+            self,
+            sample,
+            central_element,
+            edge,
+            energies,
+            geometry,
+            options,
+        )
+    }
+
+    /// [`crate::booth::booth_optimize_geometry`] on this context.
+    #[allow(clippy::too_many_arguments)]
+    pub fn booth_optimize_geometry(
+        &self,
+        formula: &str,
+        central_element: impl Into<ElementRef>,
+        edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+        energies: &[f64],
+        thickness_um: f64,
+        density_g_cm3: f64,
+        chi_true: f64,
+        bounds: &GeometryBounds,
+    ) -> Result<BoothGeometryOptimum, SelfAbsError> {
+        booth_optimize_geometry_on(
+            self,
+            formula,
+            central_element,
+            edge,
+            energies,
+            thickness_um,
+            density_g_cm3,
+            chi_true,
+            bounds,
+        )
+    }
+
+    /// [`crate::atoms::atoms_with_options`] on this context.
+    pub fn atoms(
+        &self,
+        formula: &str,
+        central_element: impl Into<ElementRef>,
+        edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+        energies: &[f64],
+        options: &AtomsOptions,
+    ) -> Result<AtomsResult, SelfAbsError> {
+        let info = self.sample_info(formula, central_element, edge)?;
+        atoms_with_sample_info(self, &info, energies, options)
+    }
+
+    /// [`crate::ameyanagi::ameyanagi_suppression_exact`] on this context.
+    pub fn ameyanagi(
+        &self,
+        formula: &str,
+        central_element: impl Into<ElementRef>,
+        edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+        energies_ev: &[f64],
+        settings: AmeyanagiSuppressionSettings,
+    ) -> Result<AmeyanagiSuppressionResult, SelfAbsError> {
+        let info = self.sample_info(formula, central_element, edge)?;
+        ameyanagi_suppression_exact_with_sample_info(self, &info, energies_ev, settings)
+    }
+
+    /// [`crate::ameyanagi::ameyanagi_thickness_scan`] on this context.
+    #[allow(clippy::too_many_arguments)]
+    pub fn ameyanagi_thickness_scan(
+        &self,
+        formula: &str,
+        central_element: impl Into<ElementRef>,
+        edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+        energies_ev: &[f64],
+        base_settings: AmeyanagiSuppressionSettings,
+        thicknesses_cm: &[f64],
+        include_curves: bool,
+    ) -> Result<Vec<AmeyanagiThicknessScanPoint>, SelfAbsError> {
+        let info = self.sample_info(formula, central_element, edge)?;
+        ameyanagi_thickness_scan_with_sample_info(
+            self,
+            &info,
+            energies_ev,
+            base_settings,
+            thicknesses_cm,
+            include_curves,
+        )
+    }
+
+    /// [`crate::ameyanagi::ameyanagi_suppression_profile`] on this context.
+    pub fn ameyanagi_profile(
+        &self,
+        formula: &str,
+        central_element: impl Into<ElementRef>,
+        edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+        energies_ev: &[f64],
+        sample: AmeyanagiSampleSettings,
+        chi_profile: &[f64],
+    ) -> Result<AmeyanagiSuppressionResult, SelfAbsError> {
+        let info = self.sample_info(formula, central_element, edge)?;
+        ameyanagi_suppression_profile_with_sample_info(
+            self,
+            &info,
+            energies_ev,
+            sample,
+            chi_profile,
+        )
+    }
+
+    /// [`crate::ameyanagi::ameyanagi_max_thickness`] on this context.
+    #[allow(clippy::too_many_arguments)]
+    pub fn ameyanagi_max_thickness(
+        &self,
+        formula: &str,
+        central_element: impl Into<ElementRef>,
+        edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+        energies_ev: &[f64],
+        base_settings: AmeyanagiSuppressionSettings,
+        r_min_target: f64,
+        pellet_diameter_cm: Option<f64>,
+    ) -> Result<ThicknessRecommendation, SelfAbsError> {
+        let info = self.sample_info(formula, central_element, edge)?;
+        ameyanagi_max_thickness_with_sample_info(
+            self,
+            &info,
+            energies_ev,
+            base_settings,
+            r_min_target,
+            pellet_diameter_cm,
+        )
+    }
+
+    /// [`crate::ameyanagi::ameyanagi_chi_scan`] on this context.
+    pub fn ameyanagi_chi_scan(
+        &self,
+        formula: &str,
+        central_element: impl Into<ElementRef>,
+        edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+        energies_ev: &[f64],
+        sample: AmeyanagiSampleSettings,
+        chi_values: &[f64],
+    ) -> Result<Vec<Vec<f64>>, SelfAbsError> {
+        let info = self.sample_info(formula, central_element, edge)?;
+        ameyanagi_chi_scan_with_sample_info(self, &info, energies_ev, sample, chi_values)
+    }
+
+    /// [`crate::ameyanagi::ameyanagi_correct_chi`] on this context.
+    pub fn ameyanagi_correct_chi(
+        &self,
+        formula: &str,
+        central_element: impl Into<ElementRef>,
+        edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+        energies_ev: &[f64],
+        sample: AmeyanagiSampleSettings,
+        chi_measured: &[f64],
+    ) -> Result<Vec<f64>, SelfAbsError> {
+        let info = self.sample_info(formula, central_element, edge)?;
+        ameyanagi_correct_chi_with_sample_info(self, &info, energies_ev, sample, chi_measured)
+    }
+
+    /// [`crate::ameyanagi::ameyanagi_layered`] on this context.
+    pub fn ameyanagi_layered(
+        &self,
+        sample: &LayeredSample,
+        central_element: impl Into<ElementRef>,
+        edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+        energies_ev: &[f64],
+        settings: AmeyanagiSuppressionSettings,
+    ) -> Result<LayeredAmeyanagiResult, SelfAbsError> {
+        ameyanagi_layered_on(self, sample, central_element, edge, energies_ev, settings)
+    }
+}
+
+/// A [`SelfAbsContext`] serving [`MuTables`] from one [`MuBackend`], from
+/// [`SelfAbsContext::tables`].
+#[derive(Clone, Copy)]
+pub struct BackendTables<'a> {
+    ctx: &'a SelfAbsContext<'a>,
+    backend: MuBackend,
+}
+
+impl MuTables for BackendTables<'_> {
+    fn mu_table(
+        &self,
+        element: &str,
+        energies_ev: &[f64],
+        kind: CrossSectionKind,
+    ) -> Result<Vec<f64>, SelfAbsError> {
+        self.ctx.mu(element, energies_ev, kind, self.backend)
+    }
+}
+
+impl Deref for SelfAbsContext<'_> {
+    type Target = XrayDb;
+
+    fn deref(&self) -> &XrayDb {
+        self.db()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::advisor::{
+        ThicknessConstraint, dilution_for_target, dilution_for_target_on, thin_limit_thickness,
+        thin_limit_thickness_on,
+    };
+    use crate::ameyanagi::{
+        ameyanagi_chi_scan, ameyanagi_correct_chi, ameyanagi_layered, ameyanagi_max_thickness,
+        ameyanagi_suppression_exact, ameyanagi_suppression_profile, ameyanagi_thickness_scan,
+    };
+    use crate::atoms::atoms_with_options;
+    use crate::booth::{
+        booth_layered, booth_optimize_geometry, booth_suppression_reference, booth_with_options,
+    };
+    use crate::common::{
+        LayerSpec, REABSORPTION_WINDOW_EV, SelfAbsWarning, detect_edge_overlaps,
+        detect_line_reabsorption,
+    };
+    use crate::compare::{atoms_vs_troger, atoms_vs_troger_on};
+    use crate::fluo::fluo_params;
+    use crate::reference::{synthesize, synthesize_on};
+    use crate::troger::troger;
+
+    fn energies() -> Vec<f64> {
+        (6900..=8000).step_by(5).map(|e| e as f64).collect()
+    }
+
+    fn ameyanagi_settings() -> AmeyanagiSuppressionSettings {
+        AmeyanagiSuppressionSettings::builder()
+            .density(5.24)
+            .thickness_cm(0.01)
+            .chi(0.2)
+            .build()
+            .unwrap()
+    }
+
+    fn run_all(ctx: &SelfAbsContext, energies: &[f64]) {
+        ctx.fluo(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            energies,
+            None,
+            &FluoOptions::default(),
+        )
+        .unwrap();
+        ctx.troger(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            energies,
+            None,
+            &TrogerOptions::default(),
+        )
+        .unwrap();
+        ctx.booth(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            energies,
+            None,
+            10.0,
+            &BoothOptions::default(),
+        )
+        .unwrap();
+        ctx.atoms("Fe2O3", "Fe", Edge::K, energies, &AtomsOptions::default())
+            .unwrap();
+        ctx.ameyanagi("Fe2O3", "Fe", Edge::K, energies, ameyanagi_settings())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_context_matches_free_functions() {
+        let energies = energies();
+        let ctx = SelfAbsContext::new();
+        // Run everything once so the second pass reads from the caches.
+        run_all(&ctx, &energies);
+
+        let fluo = ctx
+            .fluo(
+                "Fe2O3",
+                "Fe",
+                Edge::K,
+                &energies,
+                None,
+                &FluoOptions::default(),
+            )
+            .unwrap();
+        let free = fluo_params("Fe2O3", "Fe", Edge::K, &energies, None).unwrap();
+        assert_eq!(fluo.beta, free.beta);
+        assert_eq!(fluo.gamma_prime, free.gamma_prime);
+        assert_eq!(fluo.mu_background_norm, free.mu_background_norm);
+
+        let troger_ctx = ctx
+            .troger(
+                "Fe2O3",
+                "Fe",
+                Edge::K,
+                &energies,
+                None,
+                &TrogerOptions::default(),
+            )
+            .unwrap();
+        let free = troger("Fe2O3", "Fe", Edge::K, &energies, None).unwrap();
+        assert_eq!(troger_ctx.s, free.s);
+        assert_eq!(troger_ctx.correction_factor, free.correction_factor);
+
+        let options = BoothOptions::default();
+        let booth_ctx = ctx
+            .booth("Fe2O3", "Fe", Edge::K, &energies, None, 10.0, &options)
+            .unwrap();
+        let free =
+            booth_with_options("Fe2O3", "Fe", Edge::K, &energies, None, 10.0, &options).unwrap();
+        assert_eq!(booth_ctx.s, free.s);
+        assert_eq!(booth_ctx.alpha, free.alpha);
+
+        let options = AtomsOptions::default();
+        let atoms_ctx = ctx
+            .atoms("Fe2O3", "Fe", Edge::K, &energies, &options)
+            .unwrap();
+        let free = atoms_with_options("Fe2O3", "Fe", Edge::K, &energies, &options).unwrap();
+        assert_eq!(atoms_ctx.amplitude, free.amplitude);
+        assert_eq!(atoms_ctx.sigma_squared_net, free.sigma_squared_net);
+        assert_eq!(atoms_ctx.correction, free.correction);
+
+        let ameyanagi_ctx = ctx
+            .ameyanagi("Fe2O3", "Fe", Edge::K, &energies, ameyanagi_settings())
+            .unwrap();
+        let free =
+            ameyanagi_suppression_exact("Fe2O3", "Fe", Edge::K, &energies, ameyanagi_settings())
+                .unwrap();
+        assert_eq!(ameyanagi_ctx.suppression_factor, free.suppression_factor);
+        assert_eq!(ameyanagi_ctx.mu_f, free.mu_f);
+    }
+
+    #[test]
+    fn test_context_fetches_each_mu_table_once() {
+        let energies = energies();
+        let ctx = SelfAbsContext::new();
+        run_all(&ctx, &energies);
+        let stats = ctx.mu_cache_stats();
+        let tables = ctx.mu_tables.lock().unwrap();
+        assert_eq!(stats.misses, tables.values().map(Vec::len).sum::<usize>());
+
+        // On the shared grid: Fe and O of the sample plus the N2 I0 gas of
+        // the Atoms correction, all with the photo cross-section.
+        let mut on_grid: Vec<&str> = tables
+            .iter()
+            .filter(|(_, entries)| entries.iter().any(|e| same_grid(&e.energies, &energies)))
+            .map(|(key, _)| key.symbol.as_str())
+            .collect();
+        on_grid.sort_unstable();
+        assert_eq!(on_grid, ["Fe", "N", "O"]);
+        drop(tables);
+
+        run_all(&ctx, &energies);
+        let again = ctx.mu_cache_stats();
+        assert_eq!(again.misses, stats.misses);
+        assert!(again.hits > stats.hits);
+        assert_eq!(ctx.sample_infos.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_troger_and_booth_share_mu_lookups() {
+        let energies = energies();
+        let ctx = SelfAbsContext::new();
+        ctx.troger(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies,
+            None,
+            &TrogerOptions::default(),
+        )
+        .unwrap();
+        ctx.booth(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies,
+            None,
+            10.0,
+            &BoothOptions::default(),
+        )
+        .unwrap();
+
+        // One computed table per element, kind and grid; the rest are hits.
+        let stats = ctx.mu_cache_stats();
+        let tables = ctx.mu_tables.lock().unwrap();
+        assert_eq!(stats.misses, tables.values().map(Vec::len).sum::<usize>());
+        assert!(tables.values().all(|entries| entries.len() == 1));
+        assert!(stats.hits > 0);
+        let mut on_grid: Vec<&str> = tables
+            .iter()
+            .filter(|(_, entries)| same_grid(&entries[0].energies, &energies))
+            .map(|(key, _)| key.symbol.as_str())
+            .collect();
+        on_grid.sort_unstable();
+        assert_eq!(on_grid, ["Fe", "O"]);
+    }
+
+    #[test]
+    fn test_fingerprint_collision_is_not_reused() {
+        let ctx = SelfAbsContext::new();
+        let a: Vec<f64> = (0..15).map(|i| 7000.0 + 10.0 * i as f64).collect();
+        // Index 1 is not among the sampled energies of a 15-point grid.
+        let mut b = a.clone();
+        b[1] += 5.0;
+        assert_eq!(grid_fingerprint(&a), grid_fingerprint(&b));
+
+        let mu_a = ctx.mu_elam("Fe", &a, CrossSectionKind::Photo).unwrap();
+        let mu_b = ctx.mu_elam("Fe", &b, CrossSectionKind::Photo).unwrap();
+        assert_eq!(ctx.mu_cache_stats(), MuCacheStats { hits: 0, misses: 2 });
+        assert_ne!(mu_a[1], mu_b[1]);
+        assert_eq!(
+            mu_b,
+            ctx.db().mu_elam("Fe", &b, CrossSectionKind::Photo).unwrap()
+        );
+
+        assert_eq!(
+            ctx.mu_elam("Fe", &a, CrossSectionKind::Photo).unwrap(),
+            mu_a
+        );
+        assert_eq!(ctx.mu_cache_stats().hits, 1);
+    }
+
+    #[test]
+    fn test_short_grids_bypass_cache_and_clear_cache() {
+        let ctx = SelfAbsContext::new();
+        for e in [6400.0, 7058.0, 7100.0] {
+            let mu = ctx.mu_elam("Fe", &[e], CrossSectionKind::Photo).unwrap();
+            assert_eq!(
+                mu,
+                ctx.db()
+                    .mu_elam("Fe", &[e], CrossSectionKind::Photo)
+                    .unwrap()
+            );
+        }
+        assert!(ctx.mu_tables.lock().unwrap().is_empty());
+        assert_eq!(ctx.mu_cache_stats(), MuCacheStats::default());
+
+        let energies = energies();
+        let mu = ctx
+            .mu_elam("Fe", &energies, CrossSectionKind::Photo)
+            .unwrap();
+        ctx.sample_info("Fe2O3", "Fe", Edge::K).unwrap();
+        assert_eq!(ctx.mu_tables.lock().unwrap().len(), 1);
+
+        ctx.clear_cache();
+        assert!(ctx.mu_tables.lock().unwrap().is_empty());
+        assert!(ctx.sample_infos.lock().unwrap().is_empty());
+        assert_eq!(
+            ctx.mu_elam("Fe", &energies, CrossSectionKind::Photo)
+                .unwrap(),
+            mu
+        );
+        assert_eq!(ctx.mu_cache_stats(), MuCacheStats { hits: 0, misses: 2 });
+    }
+
+    /// Warnings of all five algorithms for `formula` on `energies`.
+    fn all_warnings(
+        ctx: &SelfAbsContext,
+        formula: &str,
+        central_element: &str,
+        energies: &[f64],
+    ) -> Vec<Vec<SelfAbsWarning>> {
+        let (f, c, e) = (formula, central_element, Edge::K);
+        vec![
+            ctx.fluo(f, c, e, energies, None, &FluoOptions::default())
+                .unwrap()
+                .warnings,
+            ctx.troger(f, c, e, energies, None, &TrogerOptions::default())
+                .unwrap()
+                .warnings,
+            ctx.booth(f, c, e, energies, None, 10.0, &BoothOptions::default())
+                .unwrap()
+                .warnings,
+            ctx.atoms(f, c, e, energies, &AtomsOptions::default())
+                .unwrap()
+                .warnings,
+            ctx.ameyanagi(f, c, e, energies, ameyanagi_settings())
+                .unwrap()
+                .warnings,
+        ]
+    }
+
+    #[test]
+    fn test_all_algorithms_report_edge_overlaps() {
+        let energies: Vec<f64> = (6900..=8200).step_by(5).map(|e| e as f64).collect();
+        let ctx = SelfAbsContext::new();
+
+        let composition = ctx
+            .sample_info("FeCoO3", "Co", Edge::K)
+            .unwrap()
+            .composition()
+            .clone();
+        let overlaps = detect_edge_overlaps(&ctx, &composition, (6900.0, 8200.0)).unwrap();
+        let labels: Vec<(&str, &str)> = overlaps
+            .iter()
+            .map(|w| (w.element.as_str(), w.edge.as_str()))
+            .collect();
+        assert_eq!(labels, [("Fe", "K"), ("Co", "K")]);
+
+        for warnings in all_warnings(&ctx, "FeCoO3", "Co", &energies) {
+            // The Fe K edge also sits below Co Kβ, see
+            // test_line_reabsorption_warning.
+            let (interfering, reabsorbed): (Vec<_>, Vec<_>) = warnings
+                .into_iter()
+                .partition(|w| matches!(w, SelfAbsWarning::InterferingEdge { .. }));
+            assert!(reabsorbed.iter().all(|w| matches!(
+                w,
+                SelfAbsWarning::LineReabsorbed { element, edge, .. } if element == "Fe" && edge == "K"
+            )));
+            assert_eq!(
+                interfering,
+                [SelfAbsWarning::InterferingEdge {
+                    element: "Fe".to_string(),
+                    edge: "K".to_string(),
+                    energy: overlaps[0].energy,
+                }]
+            );
+        }
+        for warnings in all_warnings(&ctx, "Fe2O3", "Fe", &energies) {
+            assert!(warnings.is_empty(), "{warnings:?}");
+        }
+    }
+
+    #[test]
+    fn test_line_reabsorption_warning() {
+        let ctx = SelfAbsContext::new();
+
+        // Cr K (5989 eV) lies 415 eV below Fe Kα in chromite.
+        let info = ctx.sample_info("FeCr2O4", "Fe", Edge::K).unwrap();
+        let hits =
+            detect_line_reabsorption(&ctx, &info, REABSORPTION_WINDOW_EV, MuBackend::Elam).unwrap();
+        let ka1 = hits
+            .iter()
+            .find(|w| w.line == "Ka1" && w.element == "Cr" && w.edge == "K")
+            .unwrap();
+        assert!((ka1.edge_energy - 5989.0).abs() < 5.0, "{ka1:?}");
+        assert!(ka1.extra_mu_f > 0.0, "{ka1:?}");
+        assert!(
+            ka1.mu_f_fraction > 0.3 && ka1.mu_f_fraction < 1.0,
+            "{ka1:?}"
+        );
+        assert!(hits.iter().all(|w| w.element != "Fe"));
+
+        // A narrower window misses it; the algorithms report it.
+        assert!(
+            detect_line_reabsorption(&ctx, &info, 300.0, MuBackend::Elam)
+                .unwrap()
+                .iter()
+                .all(|w| w.line != "Ka1")
+        );
+        let energies: Vec<f64> = (7000..=8000).step_by(10).map(|e| e as f64).collect();
+        for warnings in all_warnings(&ctx, "FeCr2O4", "Fe", &energies) {
+            assert!(warnings.iter().any(|w| matches!(
+                w,
+                SelfAbsWarning::LineReabsorbed { line, extra_mu_f, .. } if line == "Ka1" && *extra_mu_f > 0.0
+            )));
+        }
+
+        let fe2o3 = ctx.sample_info("Fe2O3", "Fe", Edge::K).unwrap();
+        assert!(
+            detect_line_reabsorption(&ctx, &fe2o3, REABSORPTION_WINDOW_EV, MuBackend::Elam)
+                .unwrap()
+                .is_empty()
+        );
+
+        // The algorithms search the window of their context.
+        let narrow = SelfAbsContext::new().with_reabsorption_window(300.0);
+        assert_eq!(narrow.reabsorption_window_ev(), 300.0);
+        for warnings in all_warnings(&narrow, "FeCr2O4", "Fe", &energies) {
+            assert!(!warnings.iter().any(|w| matches!(
+                w,
+                SelfAbsWarning::LineReabsorbed { line, .. } if line == "Ka1"
+            )));
+        }
+        let invalid = SelfAbsContext::new().with_reabsorption_window(f64::NAN);
+        assert!(
+            invalid
+                .troger(
+                    "FeCr2O4",
+                    "Fe",
+                    Edge::K,
+                    &energies,
+                    None,
+                    &TrogerOptions::default()
+                )
+                .is_err()
+        );
+
+        // The estimate follows the selected μ backend.
+        let chantler =
+            detect_line_reabsorption(&ctx, &info, REABSORPTION_WINDOW_EV, MuBackend::Chantler)
+                .unwrap();
+        let ka1_chantler = chantler
+            .iter()
+            .find(|w| w.line == "Ka1" && w.element == "Cr" && w.edge == "K")
+            .unwrap();
+        assert_ne!(ka1_chantler.extra_mu_f, ka1.extra_mu_f);
+        assert!(ka1_chantler.extra_mu_f > 0.0, "{ka1_chantler:?}");
+        let options = BoothOptions {
+            mu_backend: MuBackend::Chantler,
+            ..BoothOptions::default()
+        };
+        let booth = ctx
+            .booth("FeCr2O4", "Fe", Edge::K, &energies, None, 1e5, &options)
+            .unwrap();
+        assert!(booth.warnings.iter().any(|w| matches!(
+            w,
+            SelfAbsWarning::LineReabsorbed { line, element, extra_mu_f, .. }
+                if line == "Ka1" && element == "Cr" && *extra_mu_f == ka1_chantler.extra_mu_f
+        )));
+    }
+
+    #[test]
+    fn test_borrowed_db_matches_owned() {
+        let energies = energies();
+        let db = XrayDb::new();
+        for db in [None, Some(&db)] {
+            let ctx = SelfAbsContext::with_db(db);
+            let fluo = ctx
+                .fluo(
+                    "Fe2O3",
+                    "Fe",
+                    Edge::K,
+                    &energies,
+                    None,
+                    &FluoOptions::default(),
+                )
+                .unwrap();
+            let owned = fluo_params("Fe2O3", "Fe", Edge::K, &energies, None).unwrap();
+            assert_eq!(fluo.beta, owned.beta);
+            assert_eq!(fluo.mu_background_norm, owned.mu_background_norm);
+
+            let troger_db = ctx
+                .troger(
+                    "Fe2O3",
+                    "Fe",
+                    Edge::K,
+                    &energies,
+                    None,
+                    &TrogerOptions::default(),
+                )
+                .unwrap();
+            let owned = troger("Fe2O3", "Fe", Edge::K, &energies, None).unwrap();
+            assert_eq!(troger_db.correction_factor, owned.correction_factor);
+
+            let options = BoothOptions::default();
+            let booth_db = ctx
+                .booth("Fe2O3", "Fe", Edge::K, &energies, None, 10.0, &options)
+                .unwrap();
+            let owned = booth_with_options("Fe2O3", "Fe", Edge::K, &energies, None, 10.0, &options)
+                .unwrap();
+            assert_eq!(booth_db.s, owned.s);
+            assert_eq!(booth_db.alpha, owned.alpha);
+
+            let reference_db = ctx
+                .booth_reference(
+                    "Fe2O3",
+                    "Fe",
+                    Edge::K,
+                    &energies,
+                    None,
+                    10.0,
+                    5.24,
+                    0.2,
+                    &options,
+                )
+                .unwrap();
+            let owned = booth_suppression_reference(
+                "Fe2O3",
+                "Fe",
+                Edge::K,
+                &energies,
+                None,
+                10.0,
+                5.24,
+                0.2,
+            )
+            .unwrap();
+            assert_eq!(reference_db.suppression_factor, owned.suppression_factor);
+
+            let options = AtomsOptions::default();
+            let atoms_db = ctx
+                .atoms("Fe2O3", "Fe", Edge::K, &energies, &options)
+                .unwrap();
+            let owned = atoms_with_options("Fe2O3", "Fe", Edge::K, &energies, &options).unwrap();
+            assert_eq!(atoms_db.correction, owned.correction);
+
+            let ameyanagi_db = ctx
+                .ameyanagi("Fe2O3", "Fe", Edge::K, &energies, ameyanagi_settings())
+                .unwrap();
+            let owned = ameyanagi_suppression_exact(
+                "Fe2O3",
+                "Fe",
+                Edge::K,
+                &energies,
+                ameyanagi_settings(),
+            )
+            .unwrap();
+            assert_eq!(ameyanagi_db.suppression_factor, owned.suppression_factor);
+
+            let thicknesses = [0.001, 0.01];
+            let scan_db = ctx
+                .ameyanagi_thickness_scan(
+                    "Fe2O3",
+                    "Fe",
+                    Edge::K,
+                    &energies,
+                    ameyanagi_settings(),
+                    &thicknesses,
+                    false,
+                )
+                .unwrap();
+            let owned = ameyanagi_thickness_scan(
+                "Fe2O3",
+                "Fe",
+                Edge::K,
+                &energies,
+                ameyanagi_settings(),
+                &thicknesses,
+                false,
+            )
+            .unwrap();
+            for (a, b) in scan_db.iter().zip(&owned) {
+                assert_eq!(a.r_mean, b.r_mean);
+            }
+
+            let chi = vec![0.2; energies.len()];
+            let sample = ameyanagi_settings().sample();
+            let profile_db = ctx
+                .ameyanagi_profile("Fe2O3", "Fe", Edge::K, &energies, sample.clone(), &chi)
+                .unwrap();
+            let owned =
+                ameyanagi_suppression_profile("Fe2O3", "Fe", Edge::K, &energies, sample, &chi)
+                    .unwrap();
+            assert_eq!(profile_db.suppression_factor, owned.suppression_factor);
+        }
+
+        // A borrowing context caches like an owning one.
+        let ctx = SelfAbsContext::with_db(Some(&db));
+        run_all(&ctx, &energies);
+        let misses = ctx.mu_cache_stats().misses;
+        run_all(&ctx, &energies);
+        assert_eq!(ctx.mu_cache_stats().misses, misses);
+    }
+
+    #[test]
+    fn test_borrowed_db_matches_owned_for_derived_entry_points() {
+        let energies = energies();
+        let settings = ameyanagi_settings();
+        let sample = settings.sample();
+        let layered = LayeredSample::new(LayerSpec {
+            formula: "Fe2O3".to_string(),
+            density: 5.24,
+            thickness_um: 2.0,
+        });
+        let bounds = GeometryBounds {
+            grid_points: 3,
+            ..Default::default()
+        };
+        let pellet = ThicknessConstraint::Pellet {
+            thickness_cm: 0.05,
+            diameter_cm: 1.3,
+        };
+        let db = XrayDb::new();
+        for db in [None, Some(&db)] {
+            let ctx = SelfAbsContext::with_db(db);
+
+            let max_db = ctx
+                .ameyanagi_max_thickness(
+                    "Fe2O3",
+                    "Fe",
+                    Edge::K,
+                    &energies,
+                    settings.clone(),
+                    0.9,
+                    Some(1.3),
+                )
+                .unwrap();
+            let owned = ameyanagi_max_thickness(
+                "Fe2O3",
+                "Fe",
+                Edge::K,
+                &energies,
+                settings.clone(),
+                0.9,
+                Some(1.3),
+            )
+            .unwrap();
+            assert_eq!(max_db.max_thickness_cm, owned.max_thickness_cm);
+            assert_eq!(max_db.pellet_mass_g, owned.pellet_mass_g);
+
+            let chi = [0.0, 0.1, 0.3];
+            let scan_db = ctx
+                .ameyanagi_chi_scan("Fe2O3", "Fe", Edge::K, &energies, sample.clone(), &chi)
+                .unwrap();
+            let owned = ameyanagi_chi_scan("Fe2O3", "Fe", Edge::K, &energies, sample.clone(), &chi)
+                .unwrap();
+            assert_eq!(scan_db, owned);
+
+            let measured = vec![0.1; energies.len()];
+            let corrected_db = ctx
+                .ameyanagi_correct_chi("Fe2O3", "Fe", Edge::K, &energies, sample.clone(), &measured)
+                .unwrap();
+            let owned =
+                ameyanagi_correct_chi("Fe2O3", "Fe", Edge::K, &energies, sample.clone(), &measured)
+                    .unwrap();
+            assert_eq!(corrected_db, owned);
+
+            let layered_db = ctx
+                .ameyanagi_layered(&layered, "Fe", Edge::K, &energies, settings.clone())
+                .unwrap();
+            let owned =
+                ameyanagi_layered(&layered, "Fe", Edge::K, &energies, settings.clone()).unwrap();
+            assert_eq!(
+                layered_db.suppression.suppression_factor,
+                owned.suppression.suppression_factor
+            );
+            assert_eq!(layered_db.intensity, owned.intensity);
+
+            let layered_db = ctx
+                .booth_layered(
+                    &layered,
+                    "Fe",
+                    Edge::K,
+                    &energies,
+                    None,
+                    &BoothOptions::default(),
+                )
+                .unwrap();
+            let owned = booth_layered(&layered, "Fe", Edge::K, &energies, None).unwrap();
+            assert_eq!(layered_db.booth.s, owned.booth.s);
+            assert_eq!(
+                layered_db.incident_transmission,
+                owned.incident_transmission
+            );
+
+            let optimum_db = ctx
+                .booth_optimize_geometry(
+                    "Fe2O3",
+                    "Fe",
+                    Edge::K,
+                    &energies,
+                    10.0,
+                    5.24,
+                    0.2,
+                    &bounds,
+                )
+                .unwrap();
+            let owned = booth_optimize_geometry(
+                "Fe2O3",
+                "Fe",
+                Edge::K,
+                &energies,
+                10.0,
+                5.24,
+                0.2,
+                &bounds,
+            )
+            .unwrap();
+            assert_eq!(optimum_db.r_mean, owned.r_mean);
+
+            let advice_db = dilution_for_target_on(
+                &ctx,
+                "Fe2O3",
+                "Fe",
+                Edge::K,
+                &energies,
+                None,
+                "BN",
+                0.8,
+                pellet,
+            )
+            .unwrap();
+            let owned =
+                dilution_for_target("Fe2O3", "Fe", Edge::K, &energies, None, "BN", 0.8, pellet)
+                    .unwrap();
+            assert_eq!(advice_db.sample_mass_fraction, owned.sample_mass_fraction);
+
+            let thin_db = thin_limit_thickness_on(
+                &ctx,
+                "Fe2O3",
+                "Fe",
+                Edge::K,
+                &energies,
+                None,
+                None,
+                0.2,
+                0.05,
+            )
+            .unwrap();
+            let owned =
+                thin_limit_thickness("Fe2O3", "Fe", Edge::K, &energies, None, None, 0.2, 0.05)
+                    .unwrap();
+            assert_eq!(thin_db, owned);
+
+            let model = |k: f64| 0.2 * (2.0 * k).sin();
+            let synthetic_db = synthesize_on(
+                &ctx,
+                "Fe2O3",
+                "Fe",
+                Edge::K,
+                &energies,
+                None,
+                5.24,
+                0.01,
+                &model,
+            )
+            .unwrap();
+            let owned =
+                synthesize("Fe2O3", "Fe", Edge::K, &energies, None, 5.24, 0.01, &model).unwrap();
+            assert_eq!(synthetic_db.chi_measured, owned.chi_measured);
+
+            let comparison_db =
+                atoms_vs_troger_on(&ctx, "Fe2O3", "Fe", Edge::K, &energies, None, (3.0, 10.0))
+                    .unwrap();
+            let owned =
+                atoms_vs_troger("Fe2O3", "Fe", Edge::K, &energies, None, (3.0, 10.0)).unwrap();
+            assert_eq!(comparison_db.ratio, owned.ratio);
+        }
+    }
+}
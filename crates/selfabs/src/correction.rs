@@ -0,0 +1,920 @@
+//! Algorithm-independent χ(k) correction interface.
+//!
+//! Each algorithm returns a differently shaped result. [`SelfAbsCorrection`]
+//! hides the differences so a caller can pick the algorithm at run time:
+//!
+//! - [`TrogerResult`] and [`AtomsResult`] implement it directly;
+//! - [`BoothCorrection`] binds a [`BoothResult`](crate::booth::BoothResult)
+//!   to the density and thickness its thin-sample branch needs;
+//! - [`FluoCorrection`] applies the μ(E) correction to 1 + χ;
+//! - [`AmeyanagiCorrection`] inverts the exact model point by point.
+
+use crate::ameyanagi::{
+    AmeyanagiCorrection, AmeyanagiSuppressionResult, AmeyanagiThicknessInput, settings_for_geometry,
+};
+use crate::atoms::{AtomsOptions, AtomsResult, atoms_with_sample_info};
+use crate::booth::{BoothCorrection, BoothOptions, BoothResult, booth_with_sample_info};
+use crate::common::{
+    Edge, ElementRef, FluorescenceGeometry, SampleInfo, SelfAbsError, energies_to_k, lookup_density,
+};
+use crate::context::SelfAbsContext;
+use crate::fluo::{FluoCorrection, FluoOptions, fluo_params_with_sample_info};
+use crate::troger::{TrogerOptions, TrogerResult, troger_with_sample_info};
+
+/// χ at which the χ-dependent suppression of Fluo, Booth and Ameyanagi is
+/// evaluated for [`CorrectionSummary`].
+pub const NOMINAL_CHI: f64 = 0.1;
+
+/// The self-absorption algorithms of this crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Algorithm {
+    Fluo,
+    Troger,
+    Booth,
+    Atoms,
+    Ameyanagi,
+}
+
+impl Algorithm {
+    /// Every algorithm, in the order of the crate documentation.
+    pub const ALL: [Algorithm; 5] = [
+        Algorithm::Fluo,
+        Algorithm::Troger,
+        Algorithm::Booth,
+        Algorithm::Atoms,
+        Algorithm::Ameyanagi,
+    ];
+
+    /// Lower-case name, e.g. `"troger"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Fluo => "fluo",
+            Self::Troger => "troger",
+            Self::Booth => "booth",
+            Self::Atoms => "atoms",
+            Self::Ameyanagi => "ameyanagi",
+        }
+    }
+}
+
+/// How strongly self-absorption distorts the EXAFS amplitude.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Severity {
+    Negligible,
+    Moderate,
+    Severe,
+}
+
+/// What to do about the self-absorption of a [`Severity`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RecommendedAction {
+    /// The distortion is below the usual amplitude uncertainty; use the data
+    /// as measured.
+    None,
+    /// Apply the correction.
+    Correct,
+    /// The correction divides by a small R and amplifies noise and model
+    /// errors; measure a more dilute or thinner sample, or at grazing exit,
+    /// and correct only with care.
+    Remeasure,
+}
+
+impl From<Severity> for RecommendedAction {
+    fn from(severity: Severity) -> Self {
+        match severity {
+            Severity::Negligible => Self::None,
+            Severity::Moderate => Self::Correct,
+            Severity::Severe => Self::Remeasure,
+        }
+    }
+}
+
+/// Limits on [`CorrectionSummary::max_amplitude_error_percent`] between the
+/// [`Severity`] classes.
+///
+/// The defaults put the moderate limit at 5 %, about the accuracy of a
+/// fitted S₀², and the severe limit at 30 %, where R < 0.7 and the
+/// correction amplifies the noise by more than 1/0.7 ≈ 1.4.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SeverityThresholds {
+    /// Smallest amplitude error (%) classified as moderate.
+    pub moderate_percent: f64,
+    /// Smallest amplitude error (%) classified as severe.
+    pub severe_percent: f64,
+}
+
+impl Default for SeverityThresholds {
+    fn default() -> Self {
+        Self {
+            moderate_percent: 5.0,
+            severe_percent: 30.0,
+        }
+    }
+}
+
+impl SeverityThresholds {
+    /// Class of an amplitude error (%). NaN, i.e. no EXAFS region, is
+    /// negligible.
+    pub fn classify(&self, amplitude_error_percent: f64) -> Severity {
+        if amplitude_error_percent >= self.severe_percent {
+            Severity::Severe
+        } else if amplitude_error_percent >= self.moderate_percent {
+            Severity::Moderate
+        } else {
+            Severity::Negligible
+        }
+    }
+}
+
+/// Size of the self-absorption suppression R = χ_exp/χ_true over the EXAFS
+/// region (E > E₀) of a correction, with a verdict under the default
+/// [`SeverityThresholds`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CorrectionSummary {
+    /// Algorithm the correction came from.
+    pub algorithm: Algorithm,
+    /// Mean R (NaN if the grid has no point above E₀).
+    pub r_mean: f64,
+    /// Minimum R (NaN if the grid has no point above E₀).
+    pub r_min: f64,
+    /// Largest |1 − R| × 100: the worst amplitude error of the uncorrected
+    /// χ (NaN if the grid has no point above E₀).
+    pub max_amplitude_error_percent: f64,
+    pub severity: Severity,
+    pub recommended_action: RecommendedAction,
+}
+
+impl CorrectionSummary {
+    /// Summary of the suppression `r` at the points where `k` > 0.
+    pub(crate) fn from_suppression(
+        algorithm: Algorithm,
+        k: &[f64],
+        r: impl IntoIterator<Item = f64>,
+    ) -> Self {
+        let exafs: Vec<f64> = k
+            .iter()
+            .zip(r)
+            .filter(|(ki, _)| **ki > 0.0)
+            .map(|(_, ri)| ri)
+            .collect();
+        let (r_mean, r_min, max_error) = if exafs.is_empty() {
+            (f64::NAN, f64::NAN, f64::NAN)
+        } else {
+            (
+                exafs.iter().sum::<f64>() / exafs.len() as f64,
+                exafs.iter().copied().fold(f64::INFINITY, f64::min),
+                exafs.iter().map(|r| (1.0 - r).abs()).fold(0.0, f64::max) * 100.0,
+            )
+        };
+        let severity = SeverityThresholds::default().classify(max_error);
+        Self {
+            algorithm,
+            r_mean,
+            r_min,
+            max_amplitude_error_percent: max_error,
+            severity,
+            recommended_action: severity.into(),
+        }
+    }
+
+    /// The summary with its verdict under `thresholds`.
+    pub fn with_thresholds(mut self, thresholds: &SeverityThresholds) -> Self {
+        self.severity = thresholds.classify(self.max_amplitude_error_percent);
+        self.recommended_action = self.severity.into();
+        self
+    }
+}
+
+/// The single amplitude factor an EXAFS fit over a k window would absorb
+/// into S₀²·N, from `amplitude_suppression_report` on a result.
+///
+/// The factor is the k^w-weighted mean of R = χ_exp/χ_true over the
+/// window, ∫ R k^w dk / ∫ k^w dk by the trapezoid rule on the result's own
+/// k points, so k-weights that favour high k see the suppression there.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AmplitudeSuppressionReport {
+    pub algorithm: Algorithm,
+    /// k window (Å⁻¹) the factor was averaged over.
+    pub k_range: (f64, f64),
+    /// k-weight w.
+    pub k_weight: f64,
+    /// Effective R: the fitted amplitude comes out this fraction of the
+    /// true one.
+    pub amplitude_factor: f64,
+    /// 1 / `amplitude_factor`, the factor that restores the amplitude.
+    pub correction_factor: f64,
+    /// (1 − `amplitude_factor`) × 100: how many percent low the fitted
+    /// amplitude is.
+    pub amplitude_error_percent: f64,
+    /// Grid points inside the window.
+    pub n_points: usize,
+}
+
+impl AmplitudeSuppressionReport {
+    /// Report of the suppression `r` on `k` over `k_range`.
+    pub(crate) fn from_suppression(
+        algorithm: Algorithm,
+        k: &[f64],
+        r: impl IntoIterator<Item = f64>,
+        k_range: (f64, f64),
+        k_weight: f64,
+    ) -> Result<Self, SelfAbsError> {
+        let (k_min, k_max) = k_range;
+        if !(k_min.is_finite() && k_max.is_finite() && 0.0 <= k_min && k_min < k_max) {
+            return Err(SelfAbsError::InsufficientData(format!(
+                "k range ({k_min}, {k_max}) must be finite with 0 <= k_min < k_max"
+            )));
+        }
+        if !k_weight.is_finite() {
+            return Err(SelfAbsError::InsufficientData(
+                "k-weight must be finite".to_string(),
+            ));
+        }
+        let window: Vec<(f64, f64)> = k
+            .iter()
+            .zip(r)
+            .filter(|&(&ki, _)| ki > 0.0 && ki >= k_min && ki <= k_max)
+            .map(|(&ki, ri)| (ki, ri))
+            .collect();
+        if window.len() < 2 {
+            return Err(SelfAbsError::InsufficientData(format!(
+                "fewer than 2 grid points in k range ({k_min}, {k_max})"
+            )));
+        }
+        let (mut weighted, mut norm) = (0.0, 0.0);
+        for pair in window.windows(2) {
+            let [(k0, r0), (k1, r1)] = [pair[0], pair[1]];
+            let (w0, w1) = (k0.powf(k_weight), k1.powf(k_weight));
+            weighted += 0.5 * (k1 - k0) * (w0 * r0 + w1 * r1);
+            norm += 0.5 * (k1 - k0) * (w0 + w1);
+        }
+        let amplitude_factor = weighted / norm;
+        Ok(Self {
+            algorithm,
+            k_range,
+            k_weight,
+            amplitude_factor,
+            correction_factor: 1.0 / amplitude_factor,
+            amplitude_error_percent: (1.0 - amplitude_factor) * 100.0,
+            n_points: window.len(),
+        })
+    }
+}
+
+/// A self-absorption correction of χ on a fixed energy grid. Corrections are
+/// shareable across threads, e.g. by [`correct_many`](crate::batch::correct_many).
+pub trait SelfAbsCorrection: Send + Sync {
+    /// Energy grid (eV).
+    fn energies(&self) -> &[f64];
+    /// k grid (Å⁻¹); 0 for E ≤ E₀.
+    fn k(&self) -> &[f64];
+    /// Correct measured χ sampled on [`energies`](Self::energies).
+    fn correct_chi(&self, chi: &[f64]) -> Result<Vec<f64>, SelfAbsError>;
+    /// Suppression summary over the EXAFS region.
+    fn summary(&self) -> CorrectionSummary;
+}
+
+/// Sample and geometry inputs shared by [`correction`] for every algorithm.
+#[derive(Debug, Clone)]
+pub struct CorrectionSetup {
+    /// Measurement geometry (default 45°/45°). Ameyanagi rejects angular
+    /// spreads.
+    pub geometry: Option<FluorescenceGeometry>,
+    /// Sample thickness (μm) for Booth and Ameyanagi.
+    pub thickness_um: f64,
+    /// Sample density (g/cm³) for Booth and Ameyanagi; `None` looks the
+    /// formula up.
+    pub density_g_cm3: Option<f64>,
+}
+
+/// Build the `algorithm` correction of `formula` with default options.
+pub fn correction(
+    algorithm: Algorithm,
+    formula: &str,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies: &[f64],
+    setup: &CorrectionSetup,
+) -> Result<Box<dyn SelfAbsCorrection>, SelfAbsError> {
+    let ctx = SelfAbsContext::new();
+    let info = ctx.sample_info(formula, central_element, edge)?;
+    correction_with_sample_info(&ctx, &info, algorithm, energies, setup)
+}
+
+/// [`correction`] for a prebuilt [`SampleInfo`].
+pub fn correction_with_sample_info(
+    ctx: &SelfAbsContext,
+    info: &SampleInfo,
+    algorithm: Algorithm,
+    energies: &[f64],
+    setup: &CorrectionSetup,
+) -> Result<Box<dyn SelfAbsCorrection>, SelfAbsError> {
+    let geometry = setup.geometry;
+    let density = || match (setup.density_g_cm3, info.formula()) {
+        (Some(density), _) => Ok(density),
+        (None, Some(formula)) => lookup_density(ctx, formula).map(|(density, _)| density),
+        (None, None) => Err(SelfAbsError::InsufficientData(
+            "a sample without formula needs density_g_cm3".to_string(),
+        )),
+    };
+
+    Ok(match algorithm {
+        Algorithm::Fluo => {
+            let params = fluo_params_with_sample_info(
+                ctx,
+                info,
+                energies,
+                geometry,
+                &FluoOptions::default(),
+            )?;
+            Box::new(FluoCorrection::new(params, energies)?)
+        }
+        Algorithm::Troger => Box::new(troger_with_sample_info(
+            ctx,
+            info,
+            energies,
+            geometry,
+            &TrogerOptions::default(),
+        )?),
+        Algorithm::Booth => {
+            let result = booth_with_sample_info(
+                ctx,
+                info,
+                energies,
+                geometry,
+                setup.thickness_um,
+                &BoothOptions::default(),
+            )?;
+            Box::new(BoothCorrection::new(
+                result,
+                density()?,
+                setup.thickness_um,
+            )?)
+        }
+        Algorithm::Atoms => {
+            let options = AtomsOptions {
+                geometry,
+                ..Default::default()
+            };
+            Box::new(atoms_with_sample_info(ctx, info, energies, &options)?)
+        }
+        Algorithm::Ameyanagi => {
+            let settings = settings_for_geometry(
+                geometry,
+                density()?,
+                AmeyanagiThicknessInput::ThicknessCm(setup.thickness_um * 1e-4),
+                None,
+                NOMINAL_CHI,
+            )?;
+            Box::new(AmeyanagiCorrection::new(ctx, info, energies, settings)?)
+        }
+    })
+}
+
+/// Error unless `chi` has one point per energy.
+pub(crate) fn check_chi_len(chi: &[f64], energies: &[f64]) -> Result<(), SelfAbsError> {
+    if chi.len() != energies.len() {
+        return Err(SelfAbsError::InsufficientData(format!(
+            "chi has {} points but the energy grid has {}",
+            chi.len(),
+            energies.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Number of points a `correct_chi_into` writes: all of `chi`, which must not
+/// run past a grid of `grid_len` points and must fit in `out`.
+pub(crate) fn check_chi_into(
+    chi: &[f64],
+    out: &[f64],
+    grid_len: usize,
+) -> Result<usize, SelfAbsError> {
+    check_chi_prefix(chi, grid_len)?;
+    if out.len() < chi.len() {
+        return Err(SelfAbsError::InsufficientData(format!(
+            "output has room for {} points but chi has {}",
+            out.len(),
+            chi.len()
+        )));
+    }
+    Ok(chi.len())
+}
+
+/// Error if `chi` has more points than a grid of `grid_len`; a shorter `chi`
+/// is the leading part of a spectrum still being acquired.
+pub(crate) fn check_chi_prefix(chi: &[f64], grid_len: usize) -> Result<(), SelfAbsError> {
+    if chi.len() > grid_len {
+        return Err(SelfAbsError::InsufficientData(format!(
+            "chi has {} points but the grid has only {grid_len}",
+            chi.len()
+        )));
+    }
+    Ok(())
+}
+
+impl SelfAbsCorrection for TrogerResult {
+    fn energies(&self) -> &[f64] {
+        &self.energies
+    }
+
+    fn k(&self) -> &[f64] {
+        &self.k
+    }
+
+    fn correct_chi(&self, chi: &[f64]) -> Result<Vec<f64>, SelfAbsError> {
+        check_chi_len(chi, &self.energies)?;
+        Ok(chi
+            .iter()
+            .zip(&self.correction_factor)
+            .map(|(c, f)| c * f)
+            .collect())
+    }
+
+    fn summary(&self) -> CorrectionSummary {
+        self.summarize()
+    }
+}
+
+impl TrogerResult {
+    /// Write the corrected `chi` into `out` without allocating, returning
+    /// the number of points written.
+    ///
+    /// `chi` holds the first `chi.len()` points of the grid, so a spectrum
+    /// can be corrected while it is acquired; `out` may be longer than
+    /// `chi`. Values are those of [`SelfAbsCorrection::correct_chi`].
+    pub fn correct_chi_into(&self, chi: &[f64], out: &mut [f64]) -> Result<usize, SelfAbsError> {
+        let n = check_chi_into(chi, out, self.correction_factor.len())?;
+        for ((o, c), f) in out.iter_mut().zip(chi).zip(&self.correction_factor) {
+            *o = c * f;
+        }
+        Ok(n)
+    }
+
+    /// Lazy form of [`correct_chi_into`](Self::correct_chi_into), yielding
+    /// one corrected point per point of `chi`.
+    pub fn corrected_iter<'a>(
+        &'a self,
+        chi: &'a [f64],
+    ) -> Result<impl Iterator<Item = f64> + 'a, SelfAbsError> {
+        check_chi_prefix(chi, self.correction_factor.len())?;
+        Ok(chi.iter().zip(&self.correction_factor).map(|(c, f)| c * f))
+    }
+
+    /// Suppression summary, with R = 1 / correction factor.
+    pub fn summarize(&self) -> CorrectionSummary {
+        CorrectionSummary::from_suppression(
+            Algorithm::Troger,
+            &self.k,
+            self.correction_factor.iter().map(|f| 1.0 / f),
+        )
+    }
+
+    /// Effective amplitude suppression over `k_range` (Å⁻¹) with k-weight
+    /// `k_weight`, with R = 1 / correction factor.
+    pub fn amplitude_suppression_report(
+        &self,
+        k_range: (f64, f64),
+        k_weight: f64,
+    ) -> Result<AmplitudeSuppressionReport, SelfAbsError> {
+        AmplitudeSuppressionReport::from_suppression(
+            Algorithm::Troger,
+            &self.k,
+            self.correction_factor.iter().map(|f| 1.0 / f),
+            k_range,
+            k_weight,
+        )
+    }
+}
+
+impl BoothResult {
+    /// Effective amplitude suppression over `k_range` (Å⁻¹) with k-weight
+    /// `k_weight`, from [`suppression_factor`](Self::suppression_factor) at
+    /// `chi_true` for a sample of `density` and `thickness_um`.
+    pub fn amplitude_suppression_report(
+        &self,
+        chi_true: f64,
+        density: f64,
+        thickness_um: f64,
+        k_range: (f64, f64),
+        k_weight: f64,
+    ) -> Result<AmplitudeSuppressionReport, SelfAbsError> {
+        AmplitudeSuppressionReport::from_suppression(
+            Algorithm::Booth,
+            &self.k,
+            self.suppression_factor(chi_true, density, thickness_um)?,
+            k_range,
+            k_weight,
+        )
+    }
+}
+
+impl AmeyanagiSuppressionResult {
+    /// Effective amplitude suppression over `k_range` (Å⁻¹) with k-weight
+    /// `k_weight`, at the χ the result was computed for.
+    pub fn amplitude_suppression_report(
+        &self,
+        k_range: (f64, f64),
+        k_weight: f64,
+    ) -> Result<AmplitudeSuppressionReport, SelfAbsError> {
+        AmplitudeSuppressionReport::from_suppression(
+            Algorithm::Ameyanagi,
+            &energies_to_k(&self.energies, self.edge_energy),
+            self.suppression_factor.iter().copied(),
+            k_range,
+            k_weight,
+        )
+    }
+}
+
+impl SelfAbsCorrection for AtomsResult {
+    fn energies(&self) -> &[f64] {
+        &self.energies
+    }
+
+    fn k(&self) -> &[f64] {
+        &self.k
+    }
+
+    fn correct_chi(&self, chi: &[f64]) -> Result<Vec<f64>, SelfAbsError> {
+        self.correct_chi_on_k(&self.k, chi)
+    }
+
+    fn summary(&self) -> CorrectionSummary {
+        self.summarize()
+    }
+}
+
+impl AtomsResult {
+    /// Suppression summary. R is the inverse of the self-absorption part of
+    /// the correction only, without the normalization and fill-gas terms.
+    pub fn summarize(&self) -> CorrectionSummary {
+        CorrectionSummary::from_suppression(
+            Algorithm::Atoms,
+            &self.k,
+            self.component_curves()
+                .self_correction
+                .into_iter()
+                .map(|f| 1.0 / f),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_algorithms_through_trait_object() {
+        let energies: Vec<f64> = (6900..=8000).step_by(5).map(|e| e as f64).collect();
+        let setup = CorrectionSetup {
+            geometry: None,
+            thickness_um: 100_000.0,
+            density_g_cm3: None,
+        };
+        let corrections: Vec<Box<dyn SelfAbsCorrection>> = Algorithm::ALL
+            .iter()
+            .map(|&algorithm| {
+                correction(algorithm, "Fe2O3", "Fe", Edge::K, &energies, &setup).unwrap()
+            })
+            .collect();
+
+        let k = corrections[0].k().to_vec();
+        let chi: Vec<f64> = k.iter().map(|&ki| 0.05 * (2.0 * 2.0 * ki).sin()).collect();
+        for (algorithm, correction) in Algorithm::ALL.iter().zip(&corrections) {
+            assert_eq!(correction.energies(), energies.as_slice());
+            assert_eq!(correction.k(), k.as_slice());
+            let summary = correction.summary();
+            assert_eq!(summary.algorithm, *algorithm);
+            // Concentrated Fe2O3 is strongly suppressed.
+            assert!(
+                summary.r_mean > 0.1 && summary.r_mean < 0.9,
+                "{algorithm:?}: {summary:?}"
+            );
+            assert!(summary.r_min <= summary.r_mean);
+
+            let corrected = correction.correct_chi(&chi).unwrap();
+            assert_eq!(corrected.len(), chi.len());
+            // Every correction restores amplitude in the EXAFS region.
+            let gain = |range: &[f64]| range.iter().map(|c| c.abs()).sum::<f64>();
+            let above = k.iter().position(|&ki| ki > 3.0).unwrap();
+            assert!(
+                gain(&corrected[above..]) > 1.1 * gain(&chi[above..]),
+                "{algorithm:?}"
+            );
+            assert!(correction.correct_chi(&chi[1..]).is_err());
+        }
+
+        // The thick-sample Booth correction at small χ reduces to Tröger's.
+        let small: Vec<f64> = k.iter().map(|&ki| 1e-4 * (4.0 * ki).sin()).collect();
+        let troger = corrections[1].correct_chi(&small).unwrap();
+        let booth = corrections[2].correct_chi(&small).unwrap();
+        for (t, b) in troger.iter().zip(&booth) {
+            assert!((t - b).abs() <= 2e-3 * t.abs() + 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_severity_of_dilute_and_pure_fe() {
+        use crate::ameyanagi::{AmeyanagiSuppressionSettings, ameyanagi_suppression_exact};
+        use crate::booth::booth_suppression_reference;
+        use crate::troger::troger;
+
+        let energies: Vec<f64> = (6900..=8000).step_by(5).map(|e| e as f64).collect();
+        // 0.5 wt% Fe in quartz, and Fe foil, both thick.
+        let samples = [
+            ("Si0.995Fe0.0054O1.99", 2.65, Severity::Negligible),
+            ("Fe", 7.87, Severity::Severe),
+        ];
+        for (formula, density, expected) in samples {
+            let summaries = [
+                troger(formula, "Fe", Edge::K, &energies, None)
+                    .unwrap()
+                    .summarize(),
+                booth_suppression_reference(
+                    formula,
+                    "Fe",
+                    Edge::K,
+                    &energies,
+                    None,
+                    1e5,
+                    density,
+                    NOMINAL_CHI,
+                )
+                .unwrap()
+                .summarize(),
+                ameyanagi_suppression_exact(
+                    formula,
+                    "Fe",
+                    Edge::K,
+                    &energies,
+                    AmeyanagiSuppressionSettings::builder()
+                        .density(density)
+                        .thickness_cm(10.0)
+                        .chi(NOMINAL_CHI)
+                        .build()
+                        .unwrap(),
+                )
+                .unwrap()
+                .summarize(),
+            ];
+            for summary in summaries {
+                assert_eq!(summary.severity, expected, "{formula}: {summary:?}");
+                assert_eq!(summary.recommended_action, expected.into());
+                let error = (1.0 - summary.r_min) * 100.0;
+                assert!((summary.max_amplitude_error_percent - error).abs() < 1e-9);
+            }
+        }
+
+        // Overriding the thresholds reclassifies the same numbers.
+        let summary = troger("Si0.995Fe0.0054O1.99", "Fe", Edge::K, &energies, None)
+            .unwrap()
+            .summarize();
+        let strict = SeverityThresholds {
+            moderate_percent: summary.max_amplitude_error_percent / 2.0,
+            severe_percent: 100.0,
+        };
+        let summary = summary.with_thresholds(&strict);
+        assert_eq!(summary.severity, Severity::Moderate);
+        assert_eq!(summary.recommended_action, RecommendedAction::Correct);
+        assert_eq!(
+            SeverityThresholds::default().classify(f64::NAN),
+            Severity::Negligible
+        );
+    }
+
+    #[test]
+    fn test_total_yield_scatter_weakens_suppression() {
+        use crate::ameyanagi::{AmeyanagiSuppressionSettings, ameyanagi_suppression_exact};
+        use crate::booth::booth_suppression_reference_with_options;
+        use crate::common::DetectionMode;
+        use crate::troger::troger_with_options;
+
+        let energies: Vec<f64> = (6900..=8000).step_by(5).map(|e| e as f64).collect();
+        let r_mean = |detection_mode: DetectionMode| {
+            let troger = troger_with_options(
+                "Fe2O3",
+                "Fe",
+                Edge::K,
+                &energies,
+                None,
+                &TrogerOptions {
+                    detection_mode,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+            let booth = booth_suppression_reference_with_options(
+                "Fe2O3",
+                "Fe",
+                Edge::K,
+                &energies,
+                None,
+                20.0,
+                5.24,
+                NOMINAL_CHI,
+                &BoothOptions {
+                    detection_mode,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+            let settings = AmeyanagiSuppressionSettings::builder()
+                .density(5.24)
+                .thickness_cm(20e-4)
+                .chi(NOMINAL_CHI)
+                .detection_mode(detection_mode)
+                .build()
+                .unwrap();
+            let ameyanagi =
+                ameyanagi_suppression_exact("Fe2O3", "Fe", Edge::K, &energies, settings).unwrap();
+            [
+                troger.summarize().r_mean,
+                booth.summarize().r_mean,
+                ameyanagi.summarize().r_mean,
+            ]
+        };
+
+        let resolved = r_mean(DetectionMode::EnergyResolved);
+        assert_eq!(
+            r_mean(DetectionMode::TotalYield {
+                scatter_fraction: 0.0
+            }),
+            resolved
+        );
+        let mut previous = resolved;
+        for scatter_fraction in [0.25, 0.5, 1.0] {
+            let current = r_mean(DetectionMode::TotalYield { scatter_fraction });
+            for (p, c) in previous.iter().zip(&current) {
+                assert!(c > p, "scatter {scatter_fraction}: {c} <= {p}");
+            }
+            previous = current;
+        }
+
+        let invalid = TrogerOptions {
+            detection_mode: DetectionMode::TotalYield {
+                scatter_fraction: 1.5,
+            },
+            ..Default::default()
+        };
+        assert!(troger_with_options("Fe2O3", "Fe", Edge::K, &energies, None, &invalid).is_err());
+        assert!(
+            AmeyanagiSuppressionSettings::builder()
+                .thickness_cm(1.0)
+                .chi(0.1)
+                .detection_mode(DetectionMode::TotalYield {
+                    scatter_fraction: -0.1
+                })
+                .build()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_amplitude_suppression_report() {
+        use crate::ameyanagi::{AmeyanagiSuppressionSettings, ameyanagi_suppression_exact};
+        use crate::booth::booth;
+        use crate::troger::troger;
+
+        let energies: Vec<f64> = (6900..=8000).step_by(5).map(|e| e as f64).collect();
+        let troger = troger("Fe2O3", "Fe", Edge::K, &energies, None).unwrap();
+        let k1 = troger
+            .amplitude_suppression_report((3.0, 12.0), 1.0)
+            .unwrap();
+        let k3 = troger
+            .amplitude_suppression_report((3.0, 12.0), 3.0)
+            .unwrap();
+        assert_eq!(k1.algorithm, Algorithm::Troger);
+        assert_eq!((k3.k_range, k3.k_weight), ((3.0, 12.0), 3.0));
+        assert!(k1.correction_factor >= 1.0 && k3.correction_factor >= 1.0);
+        assert!((k1.correction_factor * k1.amplitude_factor - 1.0).abs() < 1e-12);
+        assert!(
+            (k1.amplitude_factor - k3.amplitude_factor).abs() > 1e-4,
+            "{k1:?} {k3:?}"
+        );
+        assert!(k1.n_points > 10 && k1.n_points == k3.n_points);
+
+        let booth = booth("Fe2O3", "Fe", Edge::K, &energies, None, 10.0).unwrap();
+        let report = booth
+            .amplitude_suppression_report(0.1, 5.24, 10.0, (3.0, 12.0), 2.0)
+            .unwrap();
+        assert!(report.amplitude_factor > 0.0 && report.amplitude_factor < 1.0);
+
+        let settings = AmeyanagiSuppressionSettings::builder()
+            .density(5.24)
+            .thickness_cm(1e-3)
+            .chi(0.1)
+            .build()
+            .unwrap();
+        let exact =
+            ameyanagi_suppression_exact("Fe2O3", "Fe", Edge::K, &energies, settings).unwrap();
+        let report = exact
+            .amplitude_suppression_report((3.0, 12.0), 2.0)
+            .unwrap();
+        assert!(report.amplitude_factor > 0.0 && report.amplitude_factor < 1.0);
+        assert!(report.amplitude_error_percent > 0.0);
+
+        assert!(
+            troger
+                .amplitude_suppression_report((12.0, 3.0), 2.0)
+                .is_err()
+        );
+        assert!(
+            troger
+                .amplitude_suppression_report((30.0, 40.0), 2.0)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_correct_chi_into_matches_allocating() {
+        use crate::ameyanagi::{AmeyanagiCorrection, AmeyanagiSuppressionSettings};
+        use crate::atoms::atoms;
+        use crate::booth::booth;
+        use crate::troger::troger;
+
+        let energies: Vec<f64> = (6900..=8000).step_by(5).map(|e| e as f64).collect();
+        let n = energies.len();
+        let troger = troger("Fe2O3", "Fe", Edge::K, &energies, None).unwrap();
+        let chi: Vec<f64> = troger
+            .k
+            .iter()
+            .map(|&ki| 0.05 * (2.0 * 2.0 * ki).sin())
+            .collect();
+        let mut out = vec![f64::NAN; n + 3];
+
+        assert_eq!(troger.correct_chi_into(&chi, &mut out).unwrap(), n);
+        let expected = troger.correct_chi(&chi).unwrap();
+        assert_eq!(&out[..n], expected.as_slice());
+        assert!(out[n..].iter().all(|o| o.is_nan()));
+        let lazy: Vec<f64> = troger.corrected_iter(&chi).unwrap().collect();
+        assert_eq!(lazy, expected);
+        // A partly acquired spectrum corrects its leading points.
+        let mut head = vec![0.0; 40];
+        assert_eq!(troger.correct_chi_into(&chi[..40], &mut head).unwrap(), 40);
+        assert_eq!(head.as_slice(), &expected[..40]);
+        assert!(troger.correct_chi_into(&chi, &mut out[..n - 1]).is_err());
+        assert!(
+            troger
+                .correct_chi_into(&out, &mut vec![0.0; n + 3])
+                .is_err()
+        );
+        assert!(troger.corrected_iter(&out).is_err());
+
+        for thickness_um in [100_000.0, 5.0] {
+            let booth = booth("Fe2O3", "Fe", Edge::K, &energies, None, thickness_um).unwrap();
+            let expected = booth.correct_chi(&chi, 5.24, thickness_um);
+            assert_eq!(
+                booth
+                    .correct_chi_into(&chi, &mut out, 5.24, thickness_um)
+                    .unwrap(),
+                n
+            );
+            assert_eq!(&out[..n], expected.as_slice());
+            let lazy: Vec<f64> = booth
+                .corrected_iter(&chi, 5.24, thickness_um)
+                .unwrap()
+                .collect();
+            assert_eq!(lazy, expected);
+            assert!(
+                booth
+                    .correct_chi_into(&chi, &mut out[..10], 5.24, thickness_um)
+                    .is_err()
+            );
+        }
+
+        let atoms = atoms("Fe2O3", "Fe", Edge::K, &energies).unwrap();
+        let expected = atoms.correct_chi(&chi);
+        assert_eq!(atoms.correct_chi_into(&chi, &mut out).unwrap(), n);
+        assert_eq!(&out[..n], expected.as_slice());
+        let lazy: Vec<f64> = atoms.corrected_iter(&chi).unwrap().collect();
+        assert_eq!(lazy, expected);
+        assert!(atoms.correct_chi_into(&chi, &mut []).is_err());
+
+        let ctx = SelfAbsContext::new();
+        let info = ctx.sample_info("Fe2O3", "Fe", Edge::K).unwrap();
+        let settings = AmeyanagiSuppressionSettings::builder()
+            .density(5.24)
+            .thickness_cm(1e-3)
+            .chi(0.1)
+            .build()
+            .unwrap();
+        let ameyanagi = AmeyanagiCorrection::new(&ctx, &info, &energies, settings).unwrap();
+        let expected = ameyanagi.correct_chi(&chi).unwrap();
+        assert_eq!(ameyanagi.correct_chi_into(&chi, &mut out).unwrap(), n);
+        assert_eq!(&out[..n], expected.as_slice());
+        let lazy: Vec<f64> = ameyanagi
+            .corrected_iter(&chi)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(lazy, expected);
+        assert!(ameyanagi.correct_chi_into(&chi, &mut out[..n / 2]).is_err());
+    }
+}
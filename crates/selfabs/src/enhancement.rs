@@ -0,0 +1,246 @@
+//! Secondary-fluorescence enhancement of the absorber signal.
+//!
+//! Fluorescence of a heavier matrix element can itself excite the absorber
+//! edge (Fe Kα exciting Cr K in steel), which adds to the measured signal and
+//! partly offsets the self-absorption suppression. This module estimates the
+//! relative enhancement S/P with the Sherman double integral in the
+//! thick-sample limit, in the closed form of Shiraiwa & Fujino
+//! (Jpn. J. Appl. Phys. 5, 1966, 886):
+//!
+//! ```text
+//! S_j / P = ½ · w_j τ_j(E) · ω_j p_j (r_j − 1)/r_j · τ_a(E_j)/τ_a(E) · L
+//! L = (sin φ / μ(E)) ln(1 + μ(E) / (sin φ μ(E_j)))
+//!   + (sin θ / μ(E_i)) ln(1 + μ(E_i) / (sin θ μ(E_j)))
+//! ```
+//!
+//! with mass attenuation coefficients μ of the sample, the photoabsorption
+//! τ of the matrix element j and the absorber a, the matrix line energy E_j
+//! and the absorber line energy E_i. The thick-limit ratio does not depend on
+//! density or thickness.
+
+use xraydb::CrossSectionKind;
+
+use crate::common::{
+    Edge, ElementRef, FluorescenceGeometry, MuBackend, SampleInfo, SampleSpec, SelfAbsError,
+    validate_energy_grid,
+};
+use crate::context::SelfAbsContext;
+use crate::mu::{composition_mass_fractions, compound_mu_mass};
+
+/// One matrix emission line that can excite the absorber edge.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnhancementLine {
+    /// Matrix element symbol.
+    pub element: String,
+    /// Edge of the matrix element the line belongs to.
+    pub edge: String,
+    /// Line label, e.g. `"Ka1"`.
+    pub line: String,
+    /// Line energy in eV.
+    pub energy: f64,
+    /// S_j/P of this line at each energy point.
+    pub relative_enhancement: Vec<f64>,
+}
+
+/// Result of [`secondary_fluorescence_factor`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnhancementResult {
+    /// Energy grid (eV).
+    pub energies: Vec<f64>,
+    /// Σ_j S_j/P at each energy: secondary over primary absorber
+    /// fluorescence. 0 below the absorber edge or without exciting lines.
+    pub relative_enhancement: Vec<f64>,
+    /// Matrix lines above the absorber edge, strongest overall first. Empty
+    /// when no matrix line can excite the absorber.
+    pub contributions: Vec<EnhancementLine>,
+    /// Absorber edge energy (eV).
+    pub edge_energy: f64,
+}
+
+impl EnhancementResult {
+    /// Measured / primary fluorescence, 1 + S/P.
+    pub fn factor(&self) -> Vec<f64> {
+        self.relative_enhancement.iter().map(|s| 1.0 + s).collect()
+    }
+}
+
+/// Estimate the secondary-fluorescence enhancement of the `edge`
+/// fluorescence of `central_element` in a thick sample of `formula`.
+///
+/// Matrix edges use the jump-ratio share (r − 1)/r of their photoabsorption;
+/// cascades and Coster–Kronig transfers are ignored.
+pub fn secondary_fluorescence_factor(
+    formula: &str,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies: &[f64],
+    geometry: Option<FluorescenceGeometry>,
+) -> Result<EnhancementResult, SelfAbsError> {
+    let ctx = SelfAbsContext::new();
+    let info = ctx.sample_info(formula, central_element, edge)?;
+    secondary_fluorescence_with_sample_info(&ctx, &info, energies, geometry)
+}
+
+/// [`secondary_fluorescence_factor`] for any [`SampleSpec`].
+pub fn secondary_fluorescence_spec(
+    spec: &SampleSpec,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies: &[f64],
+    geometry: Option<FluorescenceGeometry>,
+) -> Result<EnhancementResult, SelfAbsError> {
+    let ctx = SelfAbsContext::new();
+    let info = SampleInfo::from_spec(ctx.db(), spec, central_element, edge)?;
+    secondary_fluorescence_with_sample_info(&ctx, &info, energies, geometry)
+}
+
+/// [`secondary_fluorescence_factor`] for a prebuilt [`SampleInfo`].
+pub fn secondary_fluorescence_with_sample_info(
+    ctx: &SelfAbsContext,
+    info: &SampleInfo,
+    energies: &[f64],
+    geometry: Option<FluorescenceGeometry>,
+) -> Result<EnhancementResult, SelfAbsError> {
+    validate_energy_grid(energies, info.edge_energy)?;
+    let geo = geometry.unwrap_or_default();
+    let sin_phi = geo.theta_incident_deg.to_radians().sin();
+    let sin_theta = geo.theta_fluorescence_deg.to_radians().sin();
+    if !(sin_phi > 0.0 && sin_theta > 0.0) {
+        return Err(SelfAbsError::InsufficientData(
+            "angles must be in (0°, 180°)".to_string(),
+        ));
+    }
+
+    let mass_fractions = composition_mass_fractions(ctx, &info.composition)?;
+    let tables = ctx.tables(MuBackend::Elam);
+    let mu_sample =
+        |e: &[f64]| compound_mu_mass(&tables, &mass_fractions, e, CrossSectionKind::Photo);
+    let mu = mu_sample(energies)?;
+    let tau_a = ctx.mu_elam(&info.central_symbol, energies, CrossSectionKind::Photo)?;
+    let absorber_lines: Vec<(f64, f64)> = {
+        let lines = info.emission_lines();
+        let energies_i: Vec<f64> = lines.iter().map(|l| l.energy).collect();
+        let total: f64 = lines.iter().map(|l| l.intensity).sum();
+        mu_sample(&energies_i)?
+            .into_iter()
+            .zip(lines)
+            .map(|(mu_i, l)| (mu_i, l.intensity / total))
+            .collect()
+    };
+
+    let mut matrix: Vec<&(String, f64)> = mass_fractions
+        .iter()
+        .filter(|(sym, _)| *sym != info.central_symbol)
+        .collect();
+    matrix.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut contributions = Vec::new();
+    for (symbol, w_j) in matrix {
+        let tau_j = ctx.mu_elam(symbol, energies, CrossSectionKind::Photo)?;
+        let mut edges: Vec<_> = ctx.xray_edges(symbol)?.into_iter().collect();
+        edges.sort_by(|a, b| a.0.cmp(&b.0));
+        for (edge_label, xe) in edges {
+            if xe.jump_ratio <= 1.0 || xe.fluorescence_yield <= 0.0 {
+                continue;
+            }
+            let shell_share = (xe.jump_ratio - 1.0) / xe.jump_ratio;
+            let mut lines: Vec<_> = ctx
+                .xray_lines(symbol, Some(&edge_label), None)?
+                .into_iter()
+                .filter(|(_, l)| l.energy > info.edge_energy && l.intensity > 0.0)
+                .collect();
+            lines.sort_by(|a, b| a.0.cmp(&b.0));
+            for (label, line) in lines {
+                let e_j = line.energy;
+                let mu_j = mu_sample(&[e_j])?[0];
+                let tau_a_j =
+                    ctx.mu_elam(&info.central_symbol, &[e_j], CrossSectionKind::Photo)?[0];
+                let emission = w_j * xe.fluorescence_yield * line.intensity * shell_share;
+                let relative_enhancement = energies
+                    .iter()
+                    .enumerate()
+                    .map(|(n, &e)| {
+                        if e <= info.edge_energy || e <= xe.energy || tau_a[n] <= 0.0 {
+                            return 0.0;
+                        }
+                        let incident = (sin_phi / mu[n]) * (1.0 + mu[n] / (sin_phi * mu_j)).ln();
+                        let l: f64 = absorber_lines
+                            .iter()
+                            .map(|&(mu_i, weight)| {
+                                let exit =
+                                    (sin_theta / mu_i) * (1.0 + mu_i / (sin_theta * mu_j)).ln();
+                                weight * (incident + exit)
+                            })
+                            .sum();
+                        0.5 * emission * tau_j[n] * (tau_a_j / tau_a[n]) * l
+                    })
+                    .collect();
+                contributions.push(EnhancementLine {
+                    element: symbol.clone(),
+                    edge: edge_label.clone(),
+                    line: label,
+                    energy: e_j,
+                    relative_enhancement,
+                });
+            }
+        }
+    }
+    let peak = |c: &EnhancementLine| c.relative_enhancement.iter().copied().fold(0.0, f64::max);
+    contributions.sort_by(|a, b| peak(b).total_cmp(&peak(a)));
+
+    let mut relative_enhancement = vec![0.0; energies.len()];
+    for c in &contributions {
+        for (total, s) in relative_enhancement.iter_mut().zip(&c.relative_enhancement) {
+            *total += s;
+        }
+    }
+
+    Ok(EnhancementResult {
+        energies: energies.to_vec(),
+        relative_enhancement,
+        contributions,
+        edge_energy: info.edge_energy,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cr_in_iron_oxide_is_enhanced() {
+        let energies: Vec<f64> = (5900..=8000).step_by(10).map(|e| e as f64).collect();
+        let result =
+            secondary_fluorescence_factor("Cr0.1Fe1.9O3", "Cr", Edge::K, &energies, None).unwrap();
+
+        // Fe K lines sit between the Cr and Fe K edges, so they only add to
+        // the Cr signal once the beam excites Fe.
+        assert!(!result.contributions.is_empty());
+        assert!(
+            result
+                .contributions
+                .iter()
+                .all(|c| c.element == "Fe" && c.edge == "K")
+        );
+        assert_eq!(result.contributions[0].line, "Ka1");
+        for (&e, &s) in energies.iter().zip(&result.relative_enhancement) {
+            if e < 7112.0 {
+                assert_eq!(s, 0.0, "E={e}");
+            } else {
+                assert!(s > 0.1 && s < 1.5, "E={e}: S/P={s}");
+            }
+        }
+        let factor = result.factor();
+        assert!((factor[0] - 1.0).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_no_enhancement_without_heavier_matrix_lines() {
+        let energies: Vec<f64> = (7000..=8000).step_by(10).map(|e| e as f64).collect();
+        let result =
+            secondary_fluorescence_factor("Fe2O3", "Fe", Edge::K, &energies, None).unwrap();
+        assert!(result.contributions.is_empty());
+        assert!(result.relative_enhancement.iter().all(|&s| s == 0.0));
+    }
+}
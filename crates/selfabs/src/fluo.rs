@@ -3,27 +3,48 @@
 //! The only algorithm that works in μ(E) space — applicable to XANES.
 //! Corrects normalized μ(E) point-by-point using tabulated cross-sections.
 
-use xraydb::{CrossSectionKind, XrayDb};
+use xraydb::CrossSectionKind;
 
 use crate::common::{
-    FluorescenceGeometry, SampleInfo, SelfAbsError, weighted_mu_background,
-    weighted_mu_total_single,
+    Edge, ElementRef, FluorescenceGeometry, FluorescenceLineMode, MuBackend, SampleInfo,
+    SampleSpec, SelfAbsError, SelfAbsWarning, energies_to_k, filter_transmission, sample_warnings,
+    validate_energy_grid, weighted_mu_background, weighted_mu_total_single, weighted_over_lines,
+};
+use crate::context::SelfAbsContext;
+use crate::correction::{
+    Algorithm, CorrectionSummary, NOMINAL_CHI, SelfAbsCorrection, check_chi_len,
 };
 
 /// Parameters for the Fluo correction, precomputed from the sample.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FluoParams {
     /// β = μ_total(E_fluor) / μ_absorber(E+).
     pub beta: f64,
     /// γ' = μ_background(E+) / μ_absorber(E+).
     pub gamma_prime: f64,
-    /// g = sin(θ_in) / sin(θ_out).
+    /// g = sin(θ_in) / sin(θ_out), averaged over any angular spread.
     pub ratio: f64,
     /// μ_background(E) / μ_absorber(E+) at each energy point.
     pub mu_background_norm: Vec<f64>,
     /// Edge energy (eV).
     pub edge_energy: f64,
-    /// Fluorescence energy (eV).
+    /// Fluorescence energy (eV), intensity-weighted over the lines used.
     pub fluorescence_energy: f64,
+    /// Conditions that make parts of the result unreliable.
+    pub warnings: Vec<SelfAbsWarning>,
+}
+
+/// Optional settings for [`fluo_params_with_options`].
+#[derive(Debug, Clone, Default)]
+pub struct FluoOptions {
+    /// Emission lines μ_f is averaged over.
+    pub fluorescence_lines: FluorescenceLineMode,
+    /// Cross-section tables of every μ.
+    pub mu_backend: MuBackend,
+    /// Filter in front of the detector, see
+    /// [`TrogerOptions::detector_filter`](crate::troger::TrogerOptions::detector_filter).
+    pub detector_filter: Option<(String, f64, f64)>,
 }
 
 /// Compute the Fluo correction parameters.
@@ -31,7 +52,7 @@ pub struct FluoParams {
 /// # Arguments
 /// - `formula` — sample chemical formula
 /// - `central_element` — absorbing element
-/// - `edge` — absorption edge (e.g. `"K"`)
+/// - `edge` — absorption edge (e.g. [`Edge::K`])
 /// - `energies` — energy grid in eV
 /// - `geometry` — measurement geometry (default 45°/45°)
 ///
@@ -39,32 +60,71 @@ pub struct FluoParams {
 /// [`FluoParams`] that can be used with [`correct_mu`] to correct normalized μ(E) data.
 pub fn fluo_params(
     formula: &str,
-    central_element: &str,
-    edge: &str,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
     energies: &[f64],
     geometry: Option<FluorescenceGeometry>,
 ) -> Result<FluoParams, SelfAbsError> {
-    let db = XrayDb::new();
-    let geo = geometry.unwrap_or_default();
-    let info = SampleInfo::new(&db, formula, central_element, edge)?;
+    fluo_params_with_options(
+        formula,
+        central_element,
+        edge,
+        energies,
+        geometry,
+        &FluoOptions::default(),
+    )
+}
+
+/// [`fluo_params`] with explicit [`FluoOptions`].
+pub fn fluo_params_with_options(
+    formula: &str,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies: &[f64],
+    geometry: Option<FluorescenceGeometry>,
+    options: &FluoOptions,
+) -> Result<FluoParams, SelfAbsError> {
+    SelfAbsContext::new().fluo(formula, central_element, edge, energies, geometry, options)
+}
 
-    let ratio = geo.ratio();
+/// [`fluo_params_with_options`] for a prebuilt [`SampleInfo`].
+pub fn fluo_params_with_sample_info(
+    ctx: &SelfAbsContext,
+    info: &SampleInfo,
+    energies: &[f64],
+    geometry: Option<FluorescenceGeometry>,
+    options: &FluoOptions,
+) -> Result<FluoParams, SelfAbsError> {
+    let grid = validate_energy_grid(energies, info.edge_energy)?;
+    let geo = geometry.unwrap_or_default();
+    let ratio = geo.effective_ratio()?;
 
     // E+ = slightly above the edge for reference cross-section
     let e_plus = info.edge_energy + 50.0;
+    let backend = options.mu_backend;
 
     // μ_absorber at E+
     let mu_a_plus = {
-        let mu = db.mu_elam(&info.central_symbol, &[e_plus], CrossSectionKind::Photo)?;
+        let mu = ctx.mu(
+            &info.central_symbol,
+            &[e_plus],
+            CrossSectionKind::Photo,
+            backend,
+        )?;
         info.central_count * mu[0]
     };
 
-    // μ_total at fluorescence energy
-    let mu_f = weighted_mu_total_single(&db, &info.composition, info.fluor_energy)?;
+    // μ_total at the fluorescence lines
+    let (mu_f, fluorescence_energy) = weighted_over_lines(
+        info,
+        info.lines_for(options.fluorescence_lines),
+        |e| filter_transmission(ctx, options.detector_filter.as_ref(), e, backend),
+        |e| weighted_mu_total_single(ctx, &info.composition, e, CrossSectionKind::Photo, backend),
+    )?;
 
     // μ_background(E+)
     let mu_b_plus = {
-        let mu_bg = weighted_mu_background(&db, &info, &[e_plus])?;
+        let mu_bg = weighted_mu_background(ctx, info, &[e_plus], backend)?;
         mu_bg[0]
     };
 
@@ -72,7 +132,7 @@ pub fn fluo_params(
     let gamma_prime = mu_b_plus / mu_a_plus;
 
     // μ_background(E) at each energy, normalized by μ_absorber(E+)
-    let mu_bg_all = weighted_mu_background(&db, &info, energies)?;
+    let mu_bg_all = weighted_mu_background(ctx, info, energies, backend)?;
     let mu_background_norm: Vec<f64> = mu_bg_all.iter().map(|&m| m / mu_a_plus).collect();
 
     Ok(FluoParams {
@@ -81,10 +141,96 @@ pub fn fluo_params(
         ratio,
         mu_background_norm,
         edge_energy: info.edge_energy,
-        fluorescence_energy: info.fluor_energy,
+        fluorescence_energy,
+        warnings: grid
+            .warning()
+            .into_iter()
+            .chain(sample_warnings(ctx, info, energies, backend)?)
+            .collect(),
     })
 }
 
+/// [`fluo_params`] for any [`SampleSpec`].
+pub fn fluo_params_spec(
+    spec: &SampleSpec,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies: &[f64],
+    geometry: Option<FluorescenceGeometry>,
+    options: &FluoOptions,
+) -> Result<FluoParams, SelfAbsError> {
+    let ctx = SelfAbsContext::new();
+    let info = SampleInfo::from_spec(&ctx, spec, central_element, edge)?;
+    fluo_params_with_sample_info(&ctx, &info, energies, geometry, options)
+}
+
+/// [`FluoParams`] bound to their energy grid as a [`SelfAbsCorrection`].
+///
+/// χ is corrected as a normalized μ(E) = 1 + χ, minus the correction of the
+/// bare edge step, so χ = 0 stays 0.
+pub struct FluoCorrection {
+    params: FluoParams,
+    energies: Vec<f64>,
+    k: Vec<f64>,
+}
+
+impl FluoCorrection {
+    /// Bind `params` to the grid they were computed on.
+    pub fn new(params: FluoParams, energies: &[f64]) -> Result<Self, SelfAbsError> {
+        if params.mu_background_norm.len() != energies.len() {
+            return Err(SelfAbsError::InsufficientData(format!(
+                "Fluo parameters have {} points but the energy grid has {}",
+                params.mu_background_norm.len(),
+                energies.len()
+            )));
+        }
+        Ok(Self {
+            k: energies_to_k(energies, params.edge_energy),
+            energies: energies.to_vec(),
+            params,
+        })
+    }
+
+    /// The underlying parameters.
+    pub fn params(&self) -> &FluoParams {
+        &self.params
+    }
+}
+
+impl SelfAbsCorrection for FluoCorrection {
+    fn energies(&self) -> &[f64] {
+        &self.energies
+    }
+
+    fn k(&self) -> &[f64] {
+        &self.k
+    }
+
+    fn correct_chi(&self, chi: &[f64]) -> Result<Vec<f64>, SelfAbsError> {
+        check_chi_len(chi, &self.energies)?;
+        let step = correct_mu(&self.params, &vec![1.0; chi.len()]);
+        let mu_norm: Vec<f64> = chi.iter().map(|c| 1.0 + c).collect();
+        Ok(correct_mu(&self.params, &mu_norm)
+            .iter()
+            .zip(&step)
+            .map(|(m, s)| m - s)
+            .collect())
+    }
+
+    /// R at χ_true = [`NOMINAL_CHI`] from the forward model
+    /// μ_exp = μ (βg + γ' + 1) / (βg + μ_b/μ_a(E+) + μ), the inverse of
+    /// [`correct_mu`].
+    fn summary(&self) -> CorrectionSummary {
+        let beta_g = self.params.beta * self.params.ratio;
+        let c = beta_g + self.params.gamma_prime + 1.0;
+        let r = self.params.mu_background_norm.iter().map(|&bg| {
+            let measured = |mu: f64| mu * c / (beta_g + bg + mu);
+            (measured(1.0 + NOMINAL_CHI) - measured(1.0)) / NOMINAL_CHI
+        });
+        CorrectionSummary::from_suppression(Algorithm::Fluo, &self.k, r)
+    }
+}
+
 /// Apply Fluo correction to normalized μ(E) data.
 ///
 /// ```text
@@ -121,7 +267,7 @@ mod tests {
     #[test]
     fn test_fluo_params_fe2o3() {
         let energies: Vec<f64> = (7000..=7500).step_by(5).map(|e| e as f64).collect();
-        let params = fluo_params("Fe2O3", "Fe", "K", &energies, None).unwrap();
+        let params = fluo_params("Fe2O3", "Fe", Edge::K, &energies, None).unwrap();
 
         assert!(params.beta > 0.0);
         assert!(params.gamma_prime > 0.0);
@@ -133,7 +279,7 @@ mod tests {
     fn test_fluo_correction_identity() {
         // For a very dilute sample, correction should be near identity
         let energies: Vec<f64> = (7000..=7500).step_by(5).map(|e| e as f64).collect();
-        let params = fluo_params("Fe0.001Si0.999O2", "Fe", "K", &energies, None).unwrap();
+        let params = fluo_params("Fe0.001Si0.999O2", "Fe", Edge::K, &energies, None).unwrap();
 
         // Simulate normalized mu data: 0 below edge, 1 above
         let mu_norm: Vec<f64> = energies
@@ -152,4 +298,155 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_fluo_params_with_sample_info() {
+        let energies: Vec<f64> = (7000..=7500).step_by(5).map(|e| e as f64).collect();
+        let ctx = SelfAbsContext::new();
+        let info = SampleInfo::new(&ctx, "Fe2O3", "Fe", Edge::K).unwrap();
+        assert_eq!(info.formula(), Some("Fe2O3"));
+        assert_eq!(info.central_symbol(), "Fe");
+        assert_eq!(info.central_z(), 26);
+        assert_eq!(info.central_count(), 2.0);
+        assert_eq!(info.composition()["O"], 3.0);
+        assert_eq!(info.edge(), Edge::K);
+        assert!((info.edge_energy() - 7112.0).abs() < 5.0);
+
+        // The chosen line is the strongest of the listed candidates.
+        let lines = info.emission_lines();
+        assert!(lines.len() > 1);
+        assert!(lines.windows(2).all(|w| w[0].intensity >= w[1].intensity));
+        assert_eq!(lines[0].label, info.fluorescence_line());
+        assert_eq!(lines[0].energy, info.strongest_line_energy());
+
+        let direct = fluo_params("Fe2O3", "Fe", Edge::K, &energies, None).unwrap();
+        let with_info =
+            fluo_params_with_sample_info(&ctx, &info, &energies, None, &FluoOptions::default())
+                .unwrap();
+        assert!((direct.beta - with_info.beta).abs() < 1e-12 * direct.beta);
+        assert!((direct.gamma_prime - with_info.gamma_prime).abs() < 1e-12 * direct.gamma_prime);
+        assert_eq!(direct.edge_energy, with_info.edge_energy);
+        for (a, b) in direct
+            .mu_background_norm
+            .iter()
+            .zip(&with_info.mu_background_norm)
+        {
+            assert!((a - b).abs() <= 1e-12 * a.abs());
+        }
+    }
+
+    #[test]
+    fn test_fluo_exit_spread_averages_ratio() {
+        let energies: Vec<f64> = (7000..=7500).step_by(5).map(|e| e as f64).collect();
+        let point = fluo_params("Fe2O3", "Fe", Edge::K, &energies, None).unwrap();
+        let spread = FluorescenceGeometry {
+            exit_spread_deg: 10.0,
+            ..Default::default()
+        };
+        let params = fluo_params("Fe2O3", "Fe", Edge::K, &energies, Some(spread)).unwrap();
+
+        // Midpoint-rule average of sin 45° / sin θ over 35°..55°.
+        let n = 10_000;
+        let numeric = (0..n)
+            .map(|i| {
+                let theta = 35.0 + 20.0 * (i as f64 + 0.5) / n as f64;
+                45f64.to_radians().sin() / theta.to_radians().sin()
+            })
+            .sum::<f64>()
+            / n as f64;
+        assert!((params.ratio - numeric).abs() < 1e-8);
+        // 1/sin θ is convex, so the spread raises g above the point value.
+        assert!(params.ratio > point.ratio + 1e-3);
+        assert_eq!(params.beta, point.beta);
+        assert_eq!(
+            FluorescenceGeometry::default().effective_ratio().unwrap(),
+            FluorescenceGeometry::default().ratio()
+        );
+    }
+
+    #[test]
+    fn test_fluo_spread_must_stay_in_range() {
+        let energies: Vec<f64> = (7000..=7500).step_by(5).map(|e| e as f64).collect();
+        for geo in [
+            FluorescenceGeometry {
+                exit_spread_deg: 45.0,
+                ..Default::default()
+            },
+            FluorescenceGeometry {
+                theta_incident_deg: 85.0,
+                incident_spread_deg: 10.0,
+                ..Default::default()
+            },
+            FluorescenceGeometry {
+                exit_spread_deg: -1.0,
+                ..Default::default()
+            },
+        ] {
+            assert!(geo.effective_ratio().is_err());
+            assert!(fluo_params("Fe2O3", "Fe", Edge::K, &energies, Some(geo)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_geometry_from_vectors() {
+        // Beam along +x onto a surface tilted 45°, detector along +y.
+        let s = std::f64::consts::FRAC_1_SQRT_2;
+        let geo =
+            FluorescenceGeometry::from_vectors([1.0, 0.0, 0.0], [-s, s, 0.0], [0.0, 1.0, 0.0])
+                .unwrap();
+        assert!((geo.theta_incident_deg - 45.0).abs() < 1e-9);
+        assert!((geo.theta_fluorescence_deg - 45.0).abs() < 1e-9);
+        assert!((geo.ratio() - 1.0).abs() < 1e-12);
+
+        // Normal incidence with the detector 30° above the surface; the
+        // vectors need not be unit length.
+        let geo = FluorescenceGeometry::from_vectors(
+            [0.0, 0.0, -2.0],
+            [0.0, 0.0, 1.0],
+            [3f64.sqrt(), 0.0, 1.0],
+        )
+        .unwrap();
+        assert!((geo.theta_incident_deg - 90.0).abs() < 1e-9);
+        assert!((geo.theta_fluorescence_deg - 30.0).abs() < 1e-9);
+        assert!((geo.ratio() - 2.0).abs() < 1e-12);
+
+        // Beam from behind, detector behind, degenerate vectors.
+        let n = [0.0, 0.0, 1.0];
+        assert!(FluorescenceGeometry::from_vectors([0.0, 0.0, 1.0], n, [1.0, 0.0, 1.0]).is_err());
+        assert!(FluorescenceGeometry::from_vectors([0.0, 0.0, -1.0], n, [1.0, 0.0, -1.0]).is_err());
+        assert!(FluorescenceGeometry::from_vectors([0.0; 3], n, [1.0, 0.0, 1.0]).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_fluo_params_geometry_and_error_serde_roundtrip() {
+        let energies: Vec<f64> = (7000..=7500).step_by(5).map(|e| e as f64).collect();
+        let geometry = FluorescenceGeometry {
+            theta_incident_deg: 30.0,
+            theta_fluorescence_deg: 60.0,
+            exit_spread_deg: 10.0,
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&geometry).unwrap();
+        let back: FluorescenceGeometry = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+        assert_eq!(
+            back.effective_ratio().unwrap(),
+            geometry.effective_ratio().unwrap()
+        );
+
+        let params = fluo_params("Fe2O3", "Fe", Edge::K, &energies, Some(geometry)).unwrap();
+        let json = serde_json::to_string(&params).unwrap();
+        let back: FluoParams = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+        assert_eq!(back.beta.to_bits(), params.beta.to_bits());
+        assert_eq!(back.mu_background_norm, params.mu_background_norm);
+
+        // Errors serialize as their report.
+        let error = fluo_params("Fe2O3", "Xx", Edge::K, &energies, None).unwrap_err();
+        let json = serde_json::to_string(&error).unwrap();
+        let report: crate::common::SelfAbsErrorReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(report, error.report());
+        assert_eq!(report.message, error.to_string());
+    }
 }
@@ -0,0 +1,102 @@
+//! Formula preprocessing shared by [`SampleInfo`](crate::SampleInfo) and the
+//! WASM bindings, so formula parsing agrees everywhere.
+
+/// Rewrite hydrate and adduct notation into groups the formula parser
+/// understands: `CuSO4·5H2O`, `CuSO4*5H2O` and `CuSO4.5H2O` all become
+/// `CuSO4(H2O)5`, and `CuSO4·0.5H2O` becomes `CuSO4(H2O)0.5`.
+///
+/// `·` and `*` always separate. Since `.` is also a decimal point, it only
+/// separates when it does not sit between two digits, or when the digits
+/// after it are followed by `H2O`; `Fe0.5Ni0.5` is left alone. Separators
+/// inside brackets are not touched.
+pub fn expand_hydrates(formula: &str) -> String {
+    let chars: Vec<char> = formula.chars().collect();
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut depth = 0usize;
+    for (i, &c) in chars.iter().enumerate() {
+        let separates = match c {
+            '(' | '[' | '{' => {
+                depth += 1;
+                false
+            }
+            ')' | ']' | '}' => {
+                depth = depth.saturating_sub(1);
+                false
+            }
+            '·' | '•' | '∙' | '*' => depth == 0,
+            '.' => {
+                let rest: String = chars[i + 1..].iter().collect();
+                let digit_before = i > 0 && chars[i - 1].is_ascii_digit();
+                let digit_after = rest.starts_with(|c: char| c.is_ascii_digit());
+                let water = rest
+                    .trim_start_matches(|c: char| c.is_ascii_digit() || c == '.')
+                    .starts_with("H2O");
+                // The decimal point of a hydrate multiplier.
+                let in_multiplier = start > 0
+                    && chars[start..i]
+                        .iter()
+                        .all(|c| c.is_ascii_digit() || *c == ' ');
+                depth == 0 && !in_multiplier && (!(digit_before && digit_after) || water)
+            }
+            _ => false,
+        };
+        if separates {
+            parts.push(chars[start..i].iter().collect::<String>());
+            start = i + 1;
+        }
+    }
+    if parts.is_empty() {
+        return formula.to_string();
+    }
+    parts.push(chars[start..].iter().collect());
+
+    let mut expanded = parts[0].trim().to_string();
+    for part in &parts[1..] {
+        let part = part.trim();
+        let split = part
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(part.len());
+        let (multiplier, group) = part.split_at(split);
+        expanded.push('(');
+        expanded.push_str(group.trim());
+        expanded.push(')');
+        expanded.push_str(multiplier);
+    }
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use xraydb::XrayDb;
+
+    use super::*;
+    use crate::{Edge, SampleInfo};
+
+    #[test]
+    fn test_expand_hydrates() {
+        for formula in ["CuSO4·5H2O", "CuSO4.5H2O", "CuSO4*5H2O", "CuSO4 · 5 H2O"] {
+            assert_eq!(expand_hydrates(formula), "CuSO4(H2O)5", "{formula}");
+        }
+        assert_eq!(expand_hydrates("CuSO4·0.5H2O"), "CuSO4(H2O)0.5");
+        assert_eq!(expand_hydrates("Fe0.5Ni0.5"), "Fe0.5Ni0.5");
+        assert_eq!(expand_hydrates("[Co(NH3)6]Cl3·H2O"), "[Co(NH3)6]Cl3(H2O)");
+    }
+
+    #[test]
+    fn test_hydrate_composition() {
+        let db = XrayDb::new();
+        for formula in ["CuSO4·5H2O", "CuSO4.5H2O", "CuSO4*5H2O", "CuSO4 · 5 H2O"] {
+            let info = SampleInfo::new(&db, formula, "Cu", Edge::K).unwrap();
+            let expected = [("Cu", 1.0), ("H", 10.0), ("O", 9.0), ("S", 1.0)];
+            let composition: Vec<(&str, f64)> = info
+                .composition()
+                .iter()
+                .map(|(sym, &n)| (sym.as_str(), n))
+                .collect();
+            assert_eq!(composition, expected, "{formula}");
+        }
+        let hemi = SampleInfo::new(&db, "CuSO4·0.5H2O", "Cu", Edge::K).unwrap();
+        assert_eq!(hemi.composition()["H"], 1.0);
+    }
+}
@@ -0,0 +1,141 @@
+//! Energy grids for XAS scans.
+
+use crate::common::{ETOK, SelfAbsError};
+
+/// Points closer than this (eV) are merged.
+const DEDUP_TOL_EV: f64 = 1e-9;
+
+/// Three-region XAS energy grid (eV) around `edge_energy`.
+///
+/// - pre-edge: `edge_energy + pre_edge.0` in steps of `pre_edge.1` eV, up to
+///   the start of the XANES region;
+/// - XANES: `edge_energy + xanes.0` to `edge_energy + xanes.1` inclusive in
+///   steps of `xanes.2` eV;
+/// - EXAFS: uniform in k from the XANES end in steps of `exafs.1` Å⁻¹ up to
+///   `exafs.0` Å⁻¹, with E = E₀ + k²/[`ETOK`].
+///
+/// The result is strictly increasing. The XANES region must end above the
+/// edge so the EXAFS region has a k origin.
+pub fn xas_grid(
+    edge_energy: f64,
+    pre_edge: (f64, f64),
+    xanes: (f64, f64, f64),
+    exafs: (f64, f64),
+) -> Result<Vec<f64>, SelfAbsError> {
+    let (pre_start, pre_step) = pre_edge;
+    let (xanes_start, xanes_end, xanes_step) = xanes;
+    let (k_max, k_step) = exafs;
+
+    let values = [
+        edge_energy,
+        pre_start,
+        pre_step,
+        xanes_start,
+        xanes_end,
+        xanes_step,
+        k_max,
+        k_step,
+    ];
+    if values.iter().any(|v| !v.is_finite()) {
+        return Err(SelfAbsError::InsufficientData(
+            "grid parameters must be finite".to_string(),
+        ));
+    }
+    if pre_step <= 0.0 || xanes_step <= 0.0 || k_step <= 0.0 {
+        return Err(SelfAbsError::InsufficientData(
+            "grid steps must be > 0".to_string(),
+        ));
+    }
+    if !(pre_start < xanes_start && xanes_start < xanes_end) {
+        return Err(SelfAbsError::InsufficientData(format!(
+            "regions must be ordered: pre-edge start {pre_start} < XANES start {xanes_start} < XANES end {xanes_end} (eV relative to E0)"
+        )));
+    }
+    if xanes_end <= 0.0 {
+        return Err(SelfAbsError::InsufficientData(
+            "the XANES region must end above the edge".to_string(),
+        ));
+    }
+    let k_start = (ETOK * xanes_end).sqrt();
+    if k_max <= k_start {
+        return Err(SelfAbsError::InsufficientData(format!(
+            "k_max {k_max} Å⁻¹ must exceed the k of the XANES end ({k_start:.3} Å⁻¹)"
+        )));
+    }
+
+    let mut grid = Vec::new();
+    let mut push = |e: f64| {
+        if grid
+            .last()
+            .is_none_or(|&last: &f64| e > last + DEDUP_TOL_EV)
+        {
+            grid.push(e);
+        }
+    };
+
+    // Steps are multiplied out rather than accumulated to avoid drift.
+    let mut i = 0;
+    while pre_start + i as f64 * pre_step < xanes_start - DEDUP_TOL_EV {
+        push(edge_energy + pre_start + i as f64 * pre_step);
+        i += 1;
+    }
+    let mut i = 0;
+    while xanes_start + i as f64 * xanes_step < xanes_end - DEDUP_TOL_EV {
+        push(edge_energy + xanes_start + i as f64 * xanes_step);
+        i += 1;
+    }
+    push(edge_energy + xanes_end);
+    let mut i = 1;
+    while k_start + i as f64 * k_step <= k_max + 1e-12 {
+        let k = k_start + i as f64 * k_step;
+        push(edge_energy + k * k / ETOK);
+        i += 1;
+    }
+    Ok(grid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Edge;
+    use crate::common::energies_to_k;
+    use crate::troger::troger;
+
+    #[test]
+    fn test_xas_grid_regions() {
+        let e0 = 7112.0;
+        let grid = xas_grid(e0, (-200.0, 10.0), (-30.0, 50.0, 0.5), (14.0, 0.05)).unwrap();
+
+        assert!(grid.windows(2).all(|w| w[1] > w[0]));
+        assert_eq!(grid[0], e0 - 200.0);
+        // The pre-edge stops short of the XANES start, which is on the grid.
+        assert!(grid.contains(&(e0 - 30.0)));
+        assert!(grid.contains(&(e0 - 40.0)));
+        let xanes_start = grid.iter().position(|&e| e == e0 - 30.0).unwrap();
+        assert!((grid[xanes_start + 1] - grid[xanes_start] - 0.5).abs() < 1e-9);
+        let xanes_end = grid.iter().position(|&e| e == e0 + 50.0).unwrap();
+        assert_eq!(xanes_end - xanes_start, 160);
+
+        // Uniform k above the XANES end, up to k_max.
+        let k = energies_to_k(&grid, e0);
+        let exafs = &k[xanes_end..];
+        for w in exafs.windows(2) {
+            assert!((w[1] - w[0] - 0.05).abs() < 1e-9, "{w:?}");
+        }
+        assert!((exafs.last().unwrap() - 14.0).abs() < 0.05);
+
+        // Usable as-is by the algorithms.
+        let result = troger("Fe2O3", "Fe", Edge::K, &grid, None).unwrap();
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_xas_grid_validation() {
+        let e0 = 7112.0;
+        assert!(xas_grid(e0, (-20.0, 10.0), (-30.0, 50.0, 0.5), (14.0, 0.05)).is_err());
+        assert!(xas_grid(e0, (-200.0, 10.0), (-30.0, -10.0, 0.5), (14.0, 0.05)).is_err());
+        assert!(xas_grid(e0, (-200.0, 0.0), (-30.0, 50.0, 0.5), (14.0, 0.05)).is_err());
+        assert!(xas_grid(e0, (-200.0, 10.0), (-30.0, 50.0, 0.5), (3.0, 0.05)).is_err());
+        assert!(xas_grid(e0, (-200.0, 10.0), (-30.0, 50.0, 0.5), (14.0, -0.05)).is_err());
+    }
+}
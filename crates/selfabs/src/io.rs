@@ -0,0 +1,401 @@
+//! Athena/Larch-style column files for χ(k) and normalized μ(E).
+//!
+//! Files are whitespace- or comma-separated columns with `#` comment lines,
+//! as written by Athena and Larch; `!`, `%` and `;` also start a comment
+//! when reading. Readers take the first two columns of
+//! two- or three-column files; the third, if present, is kept in
+//! [`ColumnFile::sigma`]. Writers prefix the data with a
+//! [`CorrectionHeader`] block, which [`CorrectionHeader::from_comments`]
+//! reads back:
+//!
+//! ```text
+//! # selfabs.version: 0.2.0
+//! # selfabs.algorithm: troger
+//! # selfabs.formula: Fe2O3
+//! # ...
+//! # ------------------------------------------------
+//! #  k chi
+//! ```
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use crate::common::{Edge, FluorescenceGeometry, SelfAbsError};
+use crate::correction::Algorithm;
+
+/// Lines starting with one of these are comments.
+const COMMENT_PREFIXES: [char; 4] = ['#', '!', '%', ';'];
+/// Prefix of the keys of a [`CorrectionHeader`] block.
+const KEY_PREFIX: &str = "selfabs.";
+const SEPARATOR: &str = "------------------------------------------------";
+
+/// Columns and comment lines of a file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnFile {
+    /// Comment lines, without the leading `#` and surrounding whitespace.
+    pub comments: Vec<String>,
+    /// First column: k (Å⁻¹) or E (eV).
+    pub x: Vec<f64>,
+    /// Second column: χ or normalized μ.
+    pub y: Vec<f64>,
+    /// Third column (usually the uncertainty of `y`), for 3-column files.
+    pub sigma: Option<Vec<f64>>,
+}
+
+/// Correction parameters recorded at the top of a written file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorrectionHeader {
+    pub algorithm: Algorithm,
+    pub formula: String,
+    pub central_element: String,
+    pub edge: Edge,
+    pub geometry: FluorescenceGeometry,
+    pub thickness_um: Option<f64>,
+    pub density_g_cm3: Option<f64>,
+    /// Free-form lines written before the parameters, e.g. the header of
+    /// the file the data came from.
+    pub notes: Vec<String>,
+}
+
+impl CorrectionHeader {
+    /// Header for `algorithm` on `formula`, with the default geometry.
+    pub fn new(algorithm: Algorithm, formula: &str, central_element: &str, edge: Edge) -> Self {
+        Self {
+            algorithm,
+            formula: formula.to_string(),
+            central_element: central_element.to_string(),
+            edge,
+            geometry: FluorescenceGeometry::default(),
+            thickness_um: None,
+            density_g_cm3: None,
+            notes: Vec::new(),
+        }
+    }
+
+    /// Parse the block written by [`write_chi`]/[`write_mu_norm`] out of
+    /// a file's comments. `None` if the comments hold no such block.
+    pub fn from_comments(comments: &[String]) -> Result<Option<Self>, SelfAbsError> {
+        let Some(start) = comments.iter().position(|c| c.starts_with(KEY_PREFIX)) else {
+            return Ok(None);
+        };
+        let mut algorithm = None;
+        let mut formula = None;
+        let mut central_element = None;
+        let mut edge = None;
+        let mut geometry = FluorescenceGeometry::default();
+        let mut thickness_um = None;
+        let mut density_g_cm3 = None;
+        for line in &comments[start..] {
+            let Some(entry) = line.strip_prefix(KEY_PREFIX) else {
+                continue;
+            };
+            let Some((key, value)) = entry.split_once(':') else {
+                return Err(SelfAbsError::Io(format!("malformed header line '{line}'")));
+            };
+            let value = value.trim();
+            let number = || {
+                value
+                    .parse::<f64>()
+                    .map_err(|_| SelfAbsError::Io(format!("{key}: '{value}' is not a number")))
+            };
+            match key {
+                "algorithm" => {
+                    algorithm = Some(
+                        Algorithm::ALL
+                            .into_iter()
+                            .find(|a| a.name() == value)
+                            .ok_or_else(|| {
+                                SelfAbsError::Io(format!("unknown algorithm '{value}'"))
+                            })?,
+                    )
+                }
+                "formula" => formula = Some(value.to_string()),
+                "central_element" => central_element = Some(value.to_string()),
+                "edge" => edge = Some(value.parse::<Edge>()?),
+                "theta_incident_deg" => geometry.theta_incident_deg = number()?,
+                "theta_fluorescence_deg" => geometry.theta_fluorescence_deg = number()?,
+                "incident_spread_deg" => geometry.incident_spread_deg = number()?,
+                "exit_spread_deg" => geometry.exit_spread_deg = number()?,
+                "thickness_um" => thickness_um = Some(number()?),
+                "density_g_cm3" => density_g_cm3 = Some(number()?),
+                // Version and keys from newer releases.
+                _ => {}
+            }
+        }
+        let missing = |key: &str| SelfAbsError::Io(format!("header has no {KEY_PREFIX}{key}"));
+        Ok(Some(Self {
+            algorithm: algorithm.ok_or_else(|| missing("algorithm"))?,
+            formula: formula.ok_or_else(|| missing("formula"))?,
+            central_element: central_element.ok_or_else(|| missing("central_element"))?,
+            edge: edge.ok_or_else(|| missing("edge"))?,
+            geometry,
+            thickness_um,
+            density_g_cm3,
+            notes: comments[..start].to_vec(),
+        }))
+    }
+
+    fn write(&self, text: &mut String) {
+        for note in &self.notes {
+            let _ = writeln!(text, "# {note}");
+        }
+        let mut entry = |key: &str, value: &dyn std::fmt::Display| {
+            let _ = writeln!(text, "# {KEY_PREFIX}{key}: {value}");
+        };
+        entry("version", &env!("CARGO_PKG_VERSION"));
+        entry("algorithm", &self.algorithm.name());
+        entry("formula", &self.formula);
+        entry("central_element", &self.central_element);
+        entry("edge", &self.edge);
+        entry("theta_incident_deg", &self.geometry.theta_incident_deg);
+        entry(
+            "theta_fluorescence_deg",
+            &self.geometry.theta_fluorescence_deg,
+        );
+        entry("incident_spread_deg", &self.geometry.incident_spread_deg);
+        entry("exit_spread_deg", &self.geometry.exit_spread_deg);
+        if let Some(thickness) = self.thickness_um {
+            entry("thickness_um", &thickness);
+        }
+        if let Some(density) = self.density_g_cm3 {
+            entry("density_g_cm3", &density);
+        }
+    }
+}
+
+/// Read a two- or three-column file. Blank lines and comment lines are
+/// skipped; every data row needs the same number of columns.
+pub fn read_columns(path: impl AsRef<Path>) -> Result<ColumnFile, SelfAbsError> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path)
+        .map_err(|e| SelfAbsError::Io(format!("cannot read {}: {e}", path.display())))?;
+    parse_columns(&text).map_err(|e| SelfAbsError::Io(format!("{}: {e}", path.display())))
+}
+
+/// k (Å⁻¹) and χ from a χ(k) file.
+pub fn read_chi(path: impl AsRef<Path>) -> Result<(Vec<f64>, Vec<f64>), SelfAbsError> {
+    let file = read_columns(path)?;
+    Ok((file.x, file.y))
+}
+
+/// E (eV) and normalized μ from a μ(E) file.
+pub fn read_mu_norm(path: impl AsRef<Path>) -> Result<(Vec<f64>, Vec<f64>), SelfAbsError> {
+    let file = read_columns(path)?;
+    Ok((file.x, file.y))
+}
+
+/// Write k and χ under `header`.
+pub fn write_chi(
+    path: impl AsRef<Path>,
+    k: &[f64],
+    chi: &[f64],
+    header: &CorrectionHeader,
+) -> Result<(), SelfAbsError> {
+    write_columns(path.as_ref(), "k chi", k, chi, header)
+}
+
+/// Write E and normalized μ under `header`.
+pub fn write_mu_norm(
+    path: impl AsRef<Path>,
+    energies: &[f64],
+    mu: &[f64],
+    header: &CorrectionHeader,
+) -> Result<(), SelfAbsError> {
+    write_columns(path.as_ref(), "energy norm", energies, mu, header)
+}
+
+fn write_columns(
+    path: &Path,
+    labels: &str,
+    x: &[f64],
+    y: &[f64],
+    header: &CorrectionHeader,
+) -> Result<(), SelfAbsError> {
+    if x.len() != y.len() {
+        return Err(SelfAbsError::InsufficientData(format!(
+            "{} abscissae but {} values",
+            x.len(),
+            y.len()
+        )));
+    }
+    let mut text = String::new();
+    header.write(&mut text);
+    let _ = writeln!(text, "# {SEPARATOR}");
+    let _ = writeln!(text, "#  {labels}");
+    for (xi, yi) in x.iter().zip(y) {
+        // `Display` for f64 is the shortest form that reads back exactly.
+        let _ = writeln!(text, "{xi} {yi}");
+    }
+    fs::write(path, text)
+        .map_err(|e| SelfAbsError::Io(format!("cannot write {}: {e}", path.display())))
+}
+
+fn parse_columns(text: &str) -> Result<ColumnFile, String> {
+    let mut file = ColumnFile {
+        comments: Vec::new(),
+        x: Vec::new(),
+        y: Vec::new(),
+        sigma: None,
+    };
+    let mut width = None;
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(comment) = line.strip_prefix(COMMENT_PREFIXES) {
+            file.comments.push(comment.trim().to_string());
+            continue;
+        }
+        let values = line
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|field| !field.is_empty())
+            .map(|field| {
+                field
+                    .parse::<f64>()
+                    .map_err(|_| format!("line {}: '{field}' is not a number", n + 1))
+            })
+            .collect::<Result<Vec<f64>, String>>()?;
+        if !(2..=3).contains(&values.len()) {
+            return Err(format!(
+                "line {}: expected 2 or 3 columns, found {}",
+                n + 1,
+                values.len()
+            ));
+        }
+        match width {
+            None => {
+                width = Some(values.len());
+                if values.len() == 3 {
+                    file.sigma = Some(Vec::new());
+                }
+            }
+            Some(w) if w != values.len() => {
+                return Err(format!(
+                    "line {}: expected {w} columns like the first row, found {}",
+                    n + 1,
+                    values.len()
+                ));
+            }
+            Some(_) => {}
+        }
+        file.x.push(values[0]);
+        file.y.push(values[1]);
+        if let Some(sigma) = &mut file.sigma {
+            sigma.push(values[2]);
+        }
+    }
+    if file.x.is_empty() {
+        return Err("no data rows".to_string());
+    }
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::SelfAbsErrorKind;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("selfabs_io_{}_{name}", std::process::id()))
+    }
+
+    fn header() -> CorrectionHeader {
+        let mut header = CorrectionHeader::new(Algorithm::Booth, "Fe2O3", "Fe", Edge::K);
+        header.geometry.theta_incident_deg = 10.0;
+        header.geometry.exit_spread_deg = 2.5;
+        header.thickness_um = Some(20.0);
+        header.density_g_cm3 = Some(5.24);
+        header.notes = vec!["Athena data file -- Athena version 0.9.26".to_string()];
+        header
+    }
+
+    #[test]
+    fn test_chi_and_mu_round_trip() {
+        let k: Vec<f64> = (0..200).map(|i| 0.05 * i as f64).collect();
+        let chi: Vec<f64> = k.iter().map(|k| 0.1 * (2.0 * k).sin() / 3.0).collect();
+        let path = temp_path("round_trip.chi");
+        write_chi(&path, &k, &chi, &header()).unwrap();
+        assert_eq!(read_chi(&path).unwrap(), (k, chi));
+
+        let file = read_columns(&path).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.contains(&format!("selfabs.version: {}", env!("CARGO_PKG_VERSION"))));
+        assert_eq!(
+            CorrectionHeader::from_comments(&file.comments).unwrap(),
+            Some(header())
+        );
+
+        let energies: Vec<f64> = (0..100).map(|i| 7000.0 + 1.7 * i as f64).collect();
+        let mu: Vec<f64> = energies.iter().map(|e| (e - 7112.0).atan()).collect();
+        let mut mu_header = CorrectionHeader::new(Algorithm::Fluo, "Fe2O3", "Fe", Edge::K);
+        mu_header.notes.clear();
+        write_mu_norm(&path, &energies, &mu, &mu_header).unwrap();
+        assert_eq!(read_mu_norm(&path).unwrap(), (energies, mu));
+        let file = read_columns(&path).unwrap();
+        assert_eq!(
+            CorrectionHeader::from_comments(&file.comments).unwrap(),
+            Some(mu_header)
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_reads_larch_style_files() {
+        let text = "# Larch chi file\n#  k  chi  chi_std\n\n  0.0\t0.0   0.01\n0.5  0.02 0.01  \n  1.0   -0.03\t0.02\n";
+        let file = parse_columns(text).unwrap();
+        assert_eq!(file.x, vec![0.0, 0.5, 1.0]);
+        assert_eq!(file.y, vec![0.0, 0.02, -0.03]);
+        assert_eq!(file.sigma, Some(vec![0.01, 0.01, 0.02]));
+        assert_eq!(file.comments, vec!["Larch chi file", "k  chi  chi_std"]);
+        assert_eq!(
+            CorrectionHeader::from_comments(&file.comments).unwrap(),
+            None
+        );
+
+        let csv = "! exported
+% energy,mu
+; note
+7000.0,0.1
+7001.0, 0.2
+";
+        let file = parse_columns(csv).unwrap();
+        assert_eq!(file.x, vec![7000.0, 7001.0]);
+        assert_eq!(file.y, vec![0.1, 0.2]);
+        assert_eq!(file.comments, vec!["exported", "energy,mu", "note"]);
+    }
+
+    #[test]
+    fn test_malformed_files_are_rejected() {
+        for (text, reason) in [
+            ("# only comments\n", "no data rows"),
+            ("1.0 0.1\n2.0 abc\n", "'abc' is not a number"),
+            ("1.0\n", "expected 2 or 3 columns"),
+            ("1 2 3 4\n", "expected 2 or 3 columns"),
+            ("1.0 0.1\n2.0 0.2 0.01\n", "like the first row"),
+        ] {
+            let err = parse_columns(text).unwrap_err();
+            assert!(err.contains(reason), "{text:?}: {err}");
+        }
+
+        let err = read_chi(temp_path("does_not_exist.chi")).unwrap_err();
+        assert_eq!(err.kind(), SelfAbsErrorKind::Io);
+
+        let path = temp_path("malformed.chi");
+        fs::write(&path, "0.0 0.1\n0.5 nan?\n").unwrap();
+        let err = read_chi(&path).unwrap_err();
+        assert!(err.to_string().contains("line 2"), "{err}");
+        fs::remove_file(&path).unwrap();
+
+        let comments = vec![
+            "selfabs.algorithm: magic".to_string(),
+            "selfabs.formula: Fe2O3".to_string(),
+        ];
+        assert!(CorrectionHeader::from_comments(&comments).is_err());
+        let comments = vec!["selfabs.algorithm: troger".to_string()];
+        assert!(CorrectionHeader::from_comments(&comments).is_err());
+        assert!(write_chi(&path, &[1.0, 2.0], &[0.1], &header()).is_err());
+    }
+}
@@ -7,13 +7,50 @@
 //! - **Booth** (Booth & Bridges, Phys. Scr. T115, 2005, 202) — handles thin & thick samples
 //! - **Atoms** (Ravel, J. Synch. Rad. 8:2, 2001, 314) — amplitude + σ² correction
 //! - **Ameyanagi** — exact Booth suppression factor R(E, χ) without inversion
+//!
+//! The free functions (e.g. [`troger::troger`]) each build a fresh
+//! [`xraydb::XrayDb`]. To reuse one database, run the algorithms as methods
+//! of a [`SelfAbsContext`] instead: `ctx.troger(..)`, `ctx.booth(..)`,
+//! `ctx.atoms(..)`, `ctx.ameyanagi(..)` and so on. [`SelfAbsContext::new`]
+//! owns its database. [`SelfAbsContext::with_db`] borrows one, e.g. a
+//! database a long-running service holds for the process lifetime. A context
+//! also caches parsed samples and μ tables across calls. The
+//! `*_with_sample_info` and `*_on` functions take the context explicitly.
 
 mod common;
 
+pub mod advisor;
 pub mod ameyanagi;
 pub mod atoms;
+pub mod batch;
 pub mod booth;
+pub mod compare;
+pub mod context;
+pub mod correction;
+pub mod enhancement;
 pub mod fluo;
+pub mod formula;
+pub mod grid;
+#[cfg(feature = "io")]
+pub mod io;
+pub mod mu;
+#[cfg(any(test, feature = "testing"))]
+pub mod reference;
+pub mod resample;
 pub mod troger;
+pub mod uncertainty;
 
-pub use common::{ETOK, FluorescenceGeometry, SelfAbsError};
+pub use common::{
+    DensitySource, DetectionMode, ETOK, Edge, EdgeOverlapWarning, ElementRef, EmissionLine,
+    FluorescenceGeometry, FluorescenceLineMode, GridSummary, LayerIncidence, LayerSpec,
+    LayeredSample, MuBackend, PreEdgeBaseline, PreEdgeMethod, PreEdgeOptions,
+    REABSORPTION_WINDOW_EV, ReabsorptionWarning, SampleInfo, SampleSpec, SelfAbsError,
+    SelfAbsErrorKind, SelfAbsErrorReport, SelfAbsWarning, detect_edge_overlaps,
+    detect_line_reabsorption, energies_to_k, k_to_energies, lookup_density, validate_energy_grid,
+};
+pub use context::{BackendTables, MuCacheStats, MuTables, SelfAbsContext};
+pub use correction::{
+    Algorithm, AmplitudeSuppressionReport, CorrectionSummary, RecommendedAction, SelfAbsCorrection,
+    Severity, SeverityThresholds,
+};
+pub use formula::expand_hydrates;
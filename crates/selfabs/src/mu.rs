@@ -0,0 +1,134 @@
+//! Compound attenuation helpers.
+//!
+//! Mass fractions are dimensionless, mass attenuation coefficients are in
+//! cm²/g and linear attenuation coefficients in cm⁻¹. Energies are in eV.
+
+use std::collections::BTreeMap;
+
+use xraydb::{CrossSectionKind, XrayDb};
+
+use crate::common::SelfAbsError;
+use crate::context::MuTables;
+
+/// Convert stoichiometry (element → atom count) to mass fractions that sum
+/// to 1.
+pub fn composition_mass_fractions(
+    db: &XrayDb,
+    composition: &BTreeMap<String, f64>,
+) -> Result<Vec<(String, f64)>, SelfAbsError> {
+    let mut masses = Vec::with_capacity(composition.len());
+    let mut total = 0.0;
+
+    for (sym, &count) in composition {
+        let mm = db.molar_mass(sym)?;
+        let mass = count * mm;
+        masses.push((sym.clone(), mass));
+        total += mass;
+    }
+
+    if total <= 0.0 || !total.is_finite() {
+        return Err(SelfAbsError::InsufficientData(
+            "formula produced non-positive total mass".to_string(),
+        ));
+    }
+
+    Ok(masses
+        .into_iter()
+        .map(|(sym, m)| (sym, m / total))
+        .collect())
+}
+
+/// Compound mass attenuation μ/ρ(E) in cm²/g from mass fractions.
+pub fn compound_mu_mass(
+    db: &impl MuTables,
+    mass_fractions: &[(String, f64)],
+    energies_ev: &[f64],
+    kind: CrossSectionKind,
+) -> Result<Vec<f64>, SelfAbsError> {
+    let mut mu_comp_mass = vec![0.0f64; energies_ev.len()];
+    for (sym, &w) in mass_fractions.iter().map(|(s, w)| (s, w)) {
+        let mu = db.mu_table(sym, energies_ev, kind)?;
+        for (i, &v) in mu.iter().enumerate() {
+            mu_comp_mass[i] += w * v;
+        }
+    }
+    Ok(mu_comp_mass)
+}
+
+/// Compound linear attenuation μ(E) in cm⁻¹ from mass fractions and density
+/// (g/cm³).
+pub fn compound_mu_linear(
+    db: &impl MuTables,
+    mass_fractions: &[(String, f64)],
+    density_g_cm3: f64,
+    energies_ev: &[f64],
+    kind: CrossSectionKind,
+) -> Result<Vec<f64>, SelfAbsError> {
+    Ok(compound_mu_mass(db, mass_fractions, energies_ev, kind)?
+        .into_iter()
+        .map(|mu_rho| density_g_cm3 * mu_rho)
+        .collect())
+}
+
+/// Compound linear attenuation μ in cm⁻¹ at one energy.
+pub fn compound_mu_linear_single(
+    db: &impl MuTables,
+    mass_fractions: &[(String, f64)],
+    density_g_cm3: f64,
+    energy_ev: f64,
+    kind: CrossSectionKind,
+) -> Result<f64, SelfAbsError> {
+    let mut mu_comp_mass = 0.0;
+    for (sym, &w) in mass_fractions.iter().map(|(s, w)| (s, w)) {
+        let mu = db.mu_table(sym, &[energy_ev], kind)?;
+        mu_comp_mass += w * mu[0];
+    }
+    Ok(density_g_cm3 * mu_comp_mass)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fe2o3(db: &XrayDb) -> Vec<(String, f64)> {
+        let composition = BTreeMap::from([("Fe".to_string(), 2.0), ("O".to_string(), 3.0)]);
+        composition_mass_fractions(db, &composition).unwrap()
+    }
+
+    #[test]
+    fn test_mass_fractions_fe2o3() {
+        let db = XrayDb::new();
+        let fractions = fe2o3(&db);
+        let total: f64 = fractions.iter().map(|(_, w)| w).sum();
+        assert!((total - 1.0).abs() < 1e-12);
+        let w_fe = fractions.iter().find(|(s, _)| s == "Fe").unwrap().1;
+        assert!((w_fe - 0.6994).abs() < 1e-3, "w_Fe={w_fe}");
+
+        let empty = BTreeMap::from([("Fe".to_string(), 0.0)]);
+        assert!(composition_mass_fractions(&db, &empty).is_err());
+    }
+
+    #[test]
+    fn test_linear_is_density_times_mass() {
+        let db = XrayDb::new();
+        let fractions = fe2o3(&db);
+        let energies = [6000.0, 7500.0, 9000.0];
+        let mass = compound_mu_mass(&db, &fractions, &energies, CrossSectionKind::Photo).unwrap();
+        let linear =
+            compound_mu_linear(&db, &fractions, 5.24, &energies, CrossSectionKind::Photo).unwrap();
+        for (i, (&m, &l)) in mass.iter().zip(&linear).enumerate() {
+            assert_eq!(l, 5.24 * m);
+            let single = compound_mu_linear_single(
+                &db,
+                &fractions,
+                5.24,
+                energies[i],
+                CrossSectionKind::Photo,
+            )
+            .unwrap();
+            assert!((single - l).abs() <= 1e-12 * l);
+        }
+        // Above the Fe K edge the mass attenuation jumps.
+        assert!(mass[1] > 2.0 * mass[0]);
+    }
+}
@@ -0,0 +1,271 @@
+//! Interpolation of result curves onto another energy grid.
+
+use crate::booth::BoothSuppressionResult;
+use crate::common::{SelfAbsError, energies_to_k};
+use crate::troger::TrogerResult;
+
+/// Interpolation scheme of [`interpolate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Interpolation {
+    /// Piecewise linear.
+    #[default]
+    Linear,
+    /// Monotone piecewise cubic Hermite (Fritsch–Carlson), as SciPy's
+    /// `PchipInterpolator`: no overshoot between the data points.
+    Pchip,
+}
+
+/// What [`interpolate`] returns outside the range of the data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OutOfRange {
+    /// The value at the nearest end point.
+    Clamp,
+    /// NaN.
+    Nan,
+    /// An error naming the first offending point.
+    Error,
+}
+
+/// Interpolate `y(x)` at `x_new`.
+///
+/// `x` must be strictly increasing with at least two points and one `y` per
+/// point; `x_new` may be in any order.
+pub fn interpolate(
+    x: &[f64],
+    y: &[f64],
+    x_new: &[f64],
+    method: Interpolation,
+    out_of_range: OutOfRange,
+) -> Result<Vec<f64>, SelfAbsError> {
+    if x.len() != y.len() {
+        return Err(SelfAbsError::InsufficientData(format!(
+            "x has {} points but y has {}",
+            x.len(),
+            y.len()
+        )));
+    }
+    if x.len() < 2 {
+        return Err(SelfAbsError::InsufficientData(
+            "interpolation needs at least 2 points".to_string(),
+        ));
+    }
+    if x.iter().any(|v| !v.is_finite()) || x.windows(2).any(|w| w[1] <= w[0]) {
+        return Err(SelfAbsError::InsufficientData(
+            "x must be finite and strictly increasing".to_string(),
+        ));
+    }
+
+    let slopes = match method {
+        Interpolation::Linear => Vec::new(),
+        Interpolation::Pchip => pchip_slopes(x, y),
+    };
+    let (x_lo, x_hi) = (x[0], x[x.len() - 1]);
+    x_new
+        .iter()
+        .enumerate()
+        .map(|(n, &xi)| {
+            if !(xi >= x_lo && xi <= x_hi) {
+                return match out_of_range {
+                    OutOfRange::Clamp if xi < x_lo => Ok(y[0]),
+                    OutOfRange::Clamp if xi > x_hi => Ok(y[y.len() - 1]),
+                    OutOfRange::Clamp | OutOfRange::Nan => Ok(f64::NAN),
+                    OutOfRange::Error => Err(SelfAbsError::InsufficientData(format!(
+                        "point {n} ({xi}) is outside [{x_lo}, {x_hi}]"
+                    ))),
+                };
+            }
+            // Interval [x[i], x[i + 1]] containing xi.
+            let i = x.partition_point(|&v| v <= xi).clamp(1, x.len() - 1) - 1;
+            let h = x[i + 1] - x[i];
+            let t = (xi - x[i]) / h;
+            Ok(match method {
+                Interpolation::Linear => y[i] + t * (y[i + 1] - y[i]),
+                Interpolation::Pchip => {
+                    let (t2, t3) = (t * t, t * t * t);
+                    (2.0 * t3 - 3.0 * t2 + 1.0) * y[i]
+                        + (t3 - 2.0 * t2 + t) * h * slopes[i]
+                        + (-2.0 * t3 + 3.0 * t2) * y[i + 1]
+                        + (t3 - t2) * h * slopes[i + 1]
+                }
+            })
+        })
+        .collect()
+}
+
+/// Fritsch–Carlson derivatives at the data points.
+fn pchip_slopes(x: &[f64], y: &[f64]) -> Vec<f64> {
+    let n = x.len();
+    let h: Vec<f64> = x.windows(2).map(|w| w[1] - w[0]).collect();
+    let delta: Vec<f64> = (0..n - 1).map(|i| (y[i + 1] - y[i]) / h[i]).collect();
+    if n == 2 {
+        return vec![delta[0]; 2];
+    }
+
+    let mut d = vec![0.0; n];
+    for k in 1..n - 1 {
+        if delta[k - 1] * delta[k] > 0.0 {
+            // Weighted harmonic mean of the neighbouring secants.
+            let w1 = 2.0 * h[k] + h[k - 1];
+            let w2 = h[k] + 2.0 * h[k - 1];
+            d[k] = (w1 + w2) / (w1 / delta[k - 1] + w2 / delta[k]);
+        }
+    }
+    // One-sided three-point ends, limited to keep the ends monotone.
+    let end = |h0: f64, h1: f64, d0: f64, d1: f64| {
+        let s = ((2.0 * h0 + h1) * d0 - h0 * d1) / (h0 + h1);
+        if s.signum() != d0.signum() || d0 == 0.0 {
+            0.0
+        } else if d0.signum() != d1.signum() && s.abs() > 3.0 * d0.abs() {
+            3.0 * d0
+        } else {
+            s
+        }
+    };
+    d[0] = end(h[0], h[1], delta[0], delta[1]);
+    d[n - 1] = end(h[n - 2], h[n - 3], delta[n - 2], delta[n - 3]);
+    d
+}
+
+impl TrogerResult {
+    /// This result on `energies`: s is interpolated and k and the correction
+    /// factor are recomputed from it.
+    pub fn resample_onto(
+        &self,
+        energies: &[f64],
+        method: Interpolation,
+        out_of_range: OutOfRange,
+    ) -> Result<Self, SelfAbsError> {
+        let s = interpolate(&self.energies, &self.s, energies, method, out_of_range)?;
+        let correction_factor = s
+            .iter()
+            .map(|&si| {
+                if (1.0 - si).abs() > 1e-10 {
+                    1.0 / (1.0 - si)
+                } else {
+                    1.0
+                }
+            })
+            .collect();
+        Ok(Self {
+            energies: energies.to_vec(),
+            k: energies_to_k(energies, self.edge_energy),
+            s,
+            correction_factor,
+            edge_energy: self.edge_energy,
+            fluorescence_energy: self.fluorescence_energy,
+            warnings: self.warnings.clone(),
+        })
+    }
+}
+
+impl BoothSuppressionResult {
+    /// This result with R(E) interpolated onto `energies`. The scalar
+    /// statistics still describe the original grid.
+    pub fn resample_onto(
+        &self,
+        energies: &[f64],
+        method: Interpolation,
+        out_of_range: OutOfRange,
+    ) -> Result<Self, SelfAbsError> {
+        Ok(Self {
+            energies: energies.to_vec(),
+            suppression_factor: interpolate(
+                &self.energies,
+                &self.suppression_factor,
+                energies,
+                method,
+                out_of_range,
+            )?,
+            ..self.clone()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::booth::booth_suppression_reference;
+    use crate::common::Edge;
+    use crate::troger::troger;
+
+    #[test]
+    fn test_interpolate_recovers_and_fills() {
+        let x = [0.0, 1.0, 2.0, 4.0, 5.0];
+        let y = [0.0, 1.0, 4.0, 16.0, 25.0];
+        for method in [Interpolation::Linear, Interpolation::Pchip] {
+            let same = interpolate(&x, &y, &x, method, OutOfRange::Error).unwrap();
+            assert_eq!(same, y);
+
+            // Monotone data stay monotone and bracketed at midpoints.
+            let mid = [0.5, 1.5, 3.0, 4.5];
+            let v = interpolate(&x, &y, &mid, method, OutOfRange::Error).unwrap();
+            for (j, &vi) in v.iter().enumerate() {
+                let i = x.iter().rposition(|&xi| xi < mid[j]).unwrap();
+                assert!(vi > y[i] && vi < y[i + 1], "{method:?} at {}: {vi}", mid[j]);
+            }
+        }
+        let linear = interpolate(&x, &y, &[3.0], Interpolation::Linear, OutOfRange::Error);
+        assert_eq!(linear.unwrap(), [10.0]);
+
+        // Out-of-range policies.
+        let outside = [-1.0, 6.0];
+        let clamp = interpolate(&x, &y, &outside, Interpolation::Pchip, OutOfRange::Clamp);
+        assert_eq!(clamp.unwrap(), [0.0, 25.0]);
+        let nan = interpolate(&x, &y, &outside, Interpolation::Linear, OutOfRange::Nan).unwrap();
+        assert!(nan.iter().all(|v| v.is_nan()));
+        assert!(interpolate(&x, &y, &outside, Interpolation::Linear, OutOfRange::Error).is_err());
+        assert!(
+            interpolate(
+                &[1.0, 0.0],
+                &[0.0, 1.0],
+                &[0.5],
+                Interpolation::Linear,
+                OutOfRange::Nan
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_resample_results() {
+        let energies: Vec<f64> = (7000..=8000).step_by(10).map(|e| e as f64).collect();
+        let fine: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+
+        let troger = troger("Fe2O3", "Fe", Edge::K, &energies, None).unwrap();
+        let same = troger
+            .resample_onto(&energies, Interpolation::Pchip, OutOfRange::Error)
+            .unwrap();
+        assert_eq!(same.s, troger.s);
+        assert_eq!(same.correction_factor, troger.correction_factor);
+        assert_eq!(same.k, troger.k);
+        let onto_fine = troger
+            .resample_onto(&fine, Interpolation::Linear, OutOfRange::Error)
+            .unwrap();
+        assert_eq!(onto_fine.k.len(), fine.len());
+        // Midpoints lie between their neighbours well above the edge.
+        let i = fine.iter().position(|&e| e == 7505.0).unwrap();
+        let (lo, hi) = (troger.s[50], troger.s[51]);
+        assert!(onto_fine.s[i] >= lo.min(hi) && onto_fine.s[i] <= lo.max(hi));
+
+        let booth =
+            booth_suppression_reference("Fe2O3", "Fe", Edge::K, &energies, None, 100.0, 5.24, 0.2)
+                .unwrap();
+        let same = booth
+            .resample_onto(&energies, Interpolation::Linear, OutOfRange::Error)
+            .unwrap();
+        assert_eq!(same.suppression_factor, booth.suppression_factor);
+        assert_eq!(same.r_mean, booth.r_mean);
+        let wider = [6900.0, 7500.0];
+        assert!(
+            booth
+                .resample_onto(&wider, Interpolation::Linear, OutOfRange::Error)
+                .is_err()
+        );
+        let clamped = booth
+            .resample_onto(&wider, Interpolation::Linear, OutOfRange::Clamp)
+            .unwrap();
+        assert_eq!(clamped.suppression_factor[0], booth.suppression_factor[0]);
+    }
+}
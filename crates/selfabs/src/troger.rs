@@ -3,14 +3,19 @@
 //! Simple χ(k) correction for thick samples.
 //! Divides χ(k) by `1 − s(k)` where `s(k) = μ_absorber(k) / α(k)`.
 
-use xraydb::XrayDb;
+use xraydb::CrossSectionKind;
 
 use crate::common::{
-    FluorescenceGeometry, SampleInfo, SelfAbsError, energies_to_k, weighted_mu_absorber,
-    weighted_mu_total, weighted_mu_total_single,
+    DetectionMode, Edge, ElementRef, FluorescenceGeometry, FluorescenceLineMode, MuBackend,
+    PreEdgeMethod, PreEdgeOptions, SampleInfo, SampleSpec, SelfAbsError, SelfAbsWarning,
+    energies_to_k, filter_transmission, resolve_e0, sample_warnings, validate_energy_grid,
+    weighted_mu_absorber_edge, weighted_mu_total, weighted_mu_total_single, weighted_over_lines,
 };
+use crate::context::SelfAbsContext;
 
 /// Result of the Tröger correction calculation.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TrogerResult {
     /// Energy grid (eV).
     pub energies: Vec<f64>,
@@ -21,10 +26,34 @@ pub struct TrogerResult {
     /// Correction factor 1/(1 − s(k)) at each point.
     /// Multiply measured χ(k) by this to correct.
     pub correction_factor: Vec<f64>,
-    /// Edge energy (eV).
+    /// E₀ (eV) of the k grid.
     pub edge_energy: f64,
-    /// Fluorescence energy (eV).
+    /// Fluorescence energy (eV), intensity-weighted over the lines used.
     pub fluorescence_energy: f64,
+    /// Conditions that make parts of the result unreliable.
+    pub warnings: Vec<SelfAbsWarning>,
+}
+
+/// Optional settings for [`troger_with_options`].
+#[derive(Debug, Clone, Default)]
+pub struct TrogerOptions {
+    /// Emission lines μ_f is averaged over.
+    pub fluorescence_lines: FluorescenceLineMode,
+    /// E₀ (eV) of the k grid instead of the tabulated edge energy, e.g. a
+    /// calibrated E₀.
+    pub e0_override: Option<f64>,
+    /// Detector type; a total-yield detector mixes scatter into α.
+    pub detection_mode: DetectionMode,
+    /// How μ_absorber is separated from the absorber's pre-edge μ.
+    pub pre_edge_method: PreEdgeMethod,
+    /// Window of [`PreEdgeMethod::Trendline`].
+    pub pre_edge_window: PreEdgeOptions,
+    /// Cross-section tables of every μ.
+    pub mu_backend: MuBackend,
+    /// Filter in front of the detector as `(formula, density g/cm³,
+    /// thickness μm)`, e.g. a Z−1 foil. Its transmission at each line
+    /// re-weights the lines before μ_f is averaged.
+    pub detector_filter: Option<(String, f64, f64)>,
 }
 
 /// Compute the Tröger self-absorption correction.
@@ -43,33 +72,90 @@ pub struct TrogerResult {
 /// - `geometry` — measurement geometry (default 45°/45°)
 pub fn troger(
     formula: &str,
-    central_element: &str,
-    edge: &str,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies: &[f64],
+    geometry: Option<FluorescenceGeometry>,
+) -> Result<TrogerResult, SelfAbsError> {
+    troger_with_options(
+        formula,
+        central_element,
+        edge,
+        energies,
+        geometry,
+        &TrogerOptions::default(),
+    )
+}
+
+/// [`troger`] with explicit [`TrogerOptions`].
+pub fn troger_with_options(
+    formula: &str,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies: &[f64],
+    geometry: Option<FluorescenceGeometry>,
+    options: &TrogerOptions,
+) -> Result<TrogerResult, SelfAbsError> {
+    SelfAbsContext::new().troger(formula, central_element, edge, energies, geometry, options)
+}
+
+/// [`troger_with_options`] for a prebuilt [`SampleInfo`].
+pub fn troger_with_sample_info(
+    ctx: &SelfAbsContext,
+    info: &SampleInfo,
     energies: &[f64],
     geometry: Option<FluorescenceGeometry>,
+    options: &TrogerOptions,
 ) -> Result<TrogerResult, SelfAbsError> {
-    let db = XrayDb::new();
     let geo = geometry.unwrap_or_default();
-    let info = SampleInfo::new(&db, formula, central_element, edge)?;
-    let ratio = geo.ratio();
+    let ratio = geo.effective_ratio()?;
+    options.detection_mode.validate()?;
 
-    let k = energies_to_k(energies, info.edge_energy);
+    let e0 = resolve_e0(info, options.e0_override)?;
+    let grid = validate_energy_grid(energies, e0)?;
+    let k = energies_to_k(energies, e0);
 
     // μ_total(E) for all atoms
-    let mu_t = weighted_mu_total(&db, &info.composition, energies)?;
+    let mu_t = weighted_mu_total(
+        ctx,
+        &info.composition,
+        energies,
+        CrossSectionKind::Photo,
+        options.mu_backend,
+    )?;
 
-    // μ_absorber(E) with pre-edge subtraction
-    let mu_a = weighted_mu_absorber(&db, &info, energies, true)?;
+    // μ_absorber(E), edge part only
+    let (mu_a, _) = weighted_mu_absorber_edge(
+        ctx,
+        info,
+        energies,
+        options.pre_edge_method,
+        &options.pre_edge_window,
+        options.mu_backend,
+    )?;
 
-    // μ_total at fluorescence energy
-    let mu_f = weighted_mu_total_single(&db, &info.composition, info.fluor_energy)?;
+    // μ_total at the fluorescence lines
+    let (mu_f, fluorescence_energy) = weighted_over_lines(
+        info,
+        info.lines_for(options.fluorescence_lines),
+        |e| filter_transmission(ctx, options.detector_filter.as_ref(), e, options.mu_backend),
+        |e| {
+            weighted_mu_total_single(
+                ctx,
+                &info.composition,
+                e,
+                CrossSectionKind::Photo,
+                options.mu_backend,
+            )
+        },
+    )?;
 
     let n = energies.len();
     let mut s = Vec::with_capacity(n);
     let mut correction_factor = Vec::with_capacity(n);
 
     for i in 0..n {
-        let alpha = mu_t[i] + ratio * mu_f;
+        let alpha = mu_t[i] + ratio * options.detection_mode.exit_mu(mu_f, mu_t[i]);
         let si = if alpha > 0.0 { mu_a[i] / alpha } else { 0.0 };
         let cf = if (1.0 - si).abs() > 1e-10 {
             1.0 / (1.0 - si)
@@ -85,11 +171,30 @@ pub fn troger(
         k,
         s,
         correction_factor,
-        edge_energy: info.edge_energy,
-        fluorescence_energy: info.fluor_energy,
+        edge_energy: e0,
+        fluorescence_energy,
+        warnings: grid
+            .warning()
+            .into_iter()
+            .chain(sample_warnings(ctx, info, energies, options.mu_backend)?)
+            .collect(),
     })
 }
 
+/// [`troger`] for any [`SampleSpec`].
+pub fn troger_spec(
+    spec: &SampleSpec,
+    central_element: impl Into<ElementRef>,
+    edge: impl TryInto<Edge, Error: Into<SelfAbsError>>,
+    energies: &[f64],
+    geometry: Option<FluorescenceGeometry>,
+    options: &TrogerOptions,
+) -> Result<TrogerResult, SelfAbsError> {
+    let ctx = SelfAbsContext::new();
+    let info = SampleInfo::from_spec(&ctx, spec, central_element, edge)?;
+    troger_with_sample_info(&ctx, &info, energies, geometry, options)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -97,7 +202,7 @@ mod tests {
     #[test]
     fn test_troger_fe2o3() {
         let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
-        let result = troger("Fe2O3", "Fe", "K", &energies, None).unwrap();
+        let result = troger("Fe2O3", "Fe", Edge::K, &energies, None).unwrap();
 
         // s(k) should be between 0 and 1
         for (i, &si) in result.s.iter().enumerate() {
@@ -121,11 +226,240 @@ mod tests {
     #[test]
     fn test_troger_dilute() {
         let energies: Vec<f64> = (7100..=7500).step_by(10).map(|e| e as f64).collect();
-        let result = troger("Fe0.001Si0.999O2", "Fe", "K", &energies, None).unwrap();
+        let result = troger("Fe0.001Si0.999O2", "Fe", Edge::K, &energies, None).unwrap();
 
         // For dilute sample, correction factor should be close to 1
         for &cf in &result.correction_factor {
             assert!(cf < 1.05, "dilute correction={cf} should be ~1");
         }
     }
+
+    #[test]
+    fn test_troger_with_sample_info() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        let ctx = SelfAbsContext::new();
+        let info = SampleInfo::new(&ctx, "Fe2O3", "Fe", Edge::K).unwrap();
+        let direct = troger("Fe2O3", "Fe", Edge::K, &energies, None).unwrap();
+        let with_info =
+            troger_with_sample_info(&ctx, &info, &energies, None, &TrogerOptions::default())
+                .unwrap();
+        assert_eq!(direct.k, with_info.k);
+        for (a, b) in direct.s.iter().zip(&with_info.s) {
+            assert!((a - b).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_troger_spec_representations_agree() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        let db = xraydb::XrayDb::new();
+        let fe = db.molar_mass("Fe").unwrap();
+        let o = db.molar_mass("O").unwrap();
+        let m = 2.0 * fe + 3.0 * o;
+
+        let formula = SampleSpec::Formula("Fe2O3".to_string());
+        let specs = [
+            SampleSpec::Composition(
+                [("Fe".to_string(), 2.0), ("O".to_string(), 3.0)]
+                    .into_iter()
+                    .collect(),
+            ),
+            SampleSpec::MassFractions(vec![
+                ("Fe".to_string(), 2.0 * fe / m),
+                ("O".to_string(), 3.0 * o / m),
+            ]),
+            SampleSpec::Mixture(vec![
+                (formula.clone(), 30.0),
+                (
+                    SampleSpec::Mixture(vec![(SampleSpec::Formula("Fe2O3".to_string()), 1.0)]),
+                    70.0,
+                ),
+            ]),
+        ];
+
+        let reference = troger_spec(
+            &formula,
+            "Fe",
+            Edge::K,
+            &energies,
+            None,
+            &TrogerOptions::default(),
+        )
+        .unwrap();
+        for spec in &specs {
+            let result = troger_spec(
+                spec,
+                "Fe",
+                Edge::K,
+                &energies,
+                None,
+                &TrogerOptions::default(),
+            )
+            .unwrap();
+            for (a, b) in reference.s.iter().zip(&result.s) {
+                assert!((a - b).abs() < 1e-12, "{spec:?}: {a} vs {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_troger_wt_percent_composite() {
+        let db = xraydb::XrayDb::new();
+        let info = SampleInfo::new(&db, "Pt5wt%/SiO2", "Pt", Edge::L3).unwrap();
+        let fractions = crate::mu::composition_mass_fractions(&db, info.composition()).unwrap();
+        let pt = fractions.iter().find(|(sym, _)| sym == "Pt").unwrap().1;
+        assert!((pt - 0.05).abs() < 1e-3, "Pt mass fraction {pt}");
+
+        // Dilute Pt barely self-absorbs.
+        let energies: Vec<f64> = (11400..=12500).step_by(5).map(|e| e as f64).collect();
+        let result = troger("Pt5wt%/SiO2", "Pt", Edge::L3, &energies, None).unwrap();
+        for &c in &result.correction_factor {
+            assert!((1.0..1.05).contains(&c), "correction {c}");
+        }
+
+        // Plain formulas keep their stoichiometry.
+        let plain = SampleInfo::new(&db, "Fe2O3", "Fe", Edge::K).unwrap();
+        assert_eq!(plain.composition()["Fe"], 2.0);
+        assert_eq!(plain.composition()["O"], 3.0);
+    }
+
+    #[test]
+    fn test_troger_weighted_lines_cu_k() {
+        let energies: Vec<f64> = (9000..=9900).step_by(10).map(|e| e as f64).collect();
+        let ctx = SelfAbsContext::new();
+        let info = SampleInfo::new(&ctx, "CuO", "Cu", Edge::K).unwrap();
+        // Kβ pulls the weighted energy ~100 eV above Kα1.
+        let shift = info.weighted_line_energy() - info.strongest_line_energy();
+        assert!((50.0..200.0).contains(&shift), "shift={shift}");
+
+        let strongest = troger_with_options(
+            "CuO",
+            "Cu",
+            Edge::K,
+            &energies,
+            None,
+            &TrogerOptions {
+                fluorescence_lines: FluorescenceLineMode::Strongest,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let weighted = troger("CuO", "Cu", Edge::K, &energies, None).unwrap();
+        assert!((strongest.fluorescence_energy - info.strongest_line_energy()).abs() < 1e-9);
+        assert!((weighted.fluorescence_energy - info.weighted_line_energy()).abs() < 1e-9);
+
+        // Less absorbing at the higher energy: slightly larger corrections,
+        // by about a percent.
+        for (s, w) in strongest
+            .correction_factor
+            .iter()
+            .zip(&weighted.correction_factor)
+        {
+            let rel = w / s - 1.0;
+            assert!((0.001..0.03).contains(&rel), "rel={rel}");
+        }
+    }
+
+    #[test]
+    fn test_energies_to_k_round_trip() {
+        let e0 = 7112.0;
+        let energies: Vec<f64> = (0..=200).map(|i| e0 + 0.5 + 5.0 * i as f64).collect();
+        let k = energies_to_k(&energies, e0);
+        let back = crate::common::k_to_energies(&k, e0);
+        for (e, b) in energies.iter().zip(&back) {
+            assert!((e - b).abs() < 1e-9, "{e} vs {b}");
+        }
+        assert_eq!(energies_to_k(&[e0 - 10.0, e0], e0), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_troger_e0_override_shifts_k() {
+        let energies: Vec<f64> = (7100..=7200).map(|e| e as f64).collect();
+        let tabulated = troger("Fe2O3", "Fe", Edge::K, &energies, None).unwrap();
+        let e0 = tabulated.edge_energy + 5.0;
+        let shifted = troger_with_options(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &energies,
+            None,
+            &TrogerOptions {
+                e0_override: Some(e0),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(shifted.edge_energy, e0);
+
+        let first_nonzero = |k: &[f64]| energies[k.iter().position(|&v| v > 0.0).unwrap()];
+        let shift = first_nonzero(&shifted.k) - first_nonzero(&tabulated.k);
+        assert!((4.0..=6.0).contains(&shift), "shift={shift}");
+        // The cross-sections, and so s, do not move with E0.
+        assert_eq!(shifted.s, tabulated.s);
+
+        let invalid = TrogerOptions {
+            e0_override: Some(f64::NAN),
+            ..Default::default()
+        };
+        assert!(troger_with_options("Fe2O3", "Fe", Edge::K, &energies, None, &invalid).is_err());
+    }
+
+    #[test]
+    fn test_troger_validates_energy_grid() {
+        let invalid: [&[f64]; 5] = [
+            &[],
+            &[7000.0, f64::NAN, 7200.0],
+            &[7000.0, f64::INFINITY],
+            &[7200.0, 7100.0, 7300.0],
+            &[7000.0, 7100.0, 7100.0],
+        ];
+        for energies in invalid {
+            assert!(
+                troger("Fe2O3", "Fe", Edge::K, energies, None).is_err(),
+                "{energies:?}"
+            );
+        }
+
+        let spanning: Vec<f64> = (7000..=7500).step_by(10).map(|e| e as f64).collect();
+        let result = troger("Fe2O3", "Fe", Edge::K, &spanning, None).unwrap();
+        assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+
+        let above: Vec<f64> = (7200..=7500).step_by(10).map(|e| e as f64).collect();
+        let result = troger("Fe2O3", "Fe", Edge::K, &above, None).unwrap();
+        assert!(matches!(
+            result.warnings.as_slice(),
+            [SelfAbsWarning::GridMissesEdge { e_min, .. }] if *e_min == 7200.0
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_troger_result_serde_roundtrip() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        let result = troger("Fe2O3", "Fe", Edge::K, &energies, None).unwrap();
+
+        let json = serde_json::to_string(&result).unwrap();
+        let back: TrogerResult = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+        assert_eq!(back.k, result.k);
+        assert_eq!(back.correction_factor, result.correction_factor);
+        assert_eq!(back.warnings, result.warnings);
+    }
+
+    #[test]
+    fn test_troger_is_bit_reproducible() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        let formula = "CaMg0.5Fe0.5Al2SiO6";
+        let first = troger(formula, "Fe", Edge::K, &energies, None).unwrap();
+        for _ in 0..50 {
+            let again = troger(formula, "Fe", Edge::K, &energies, None).unwrap();
+            assert!(
+                again
+                    .correction_factor
+                    .iter()
+                    .zip(&first.correction_factor)
+                    .all(|(a, b)| a.to_bits() == b.to_bits())
+            );
+        }
+    }
 }
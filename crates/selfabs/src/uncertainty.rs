@@ -0,0 +1,431 @@
+//! Monte Carlo propagation of sample and geometry uncertainties.
+//!
+//! Density, thickness and the two angles are drawn from independent
+//! distributions around their nominal values, and the Booth or exact
+//! Ameyanagi suppression is re-evaluated for every draw. All draws share one
+//! [`SelfAbsContext`], so the cross-section tables are computed once.
+//! The generator is seeded explicitly, so a run is reproducible.
+
+use crate::ameyanagi::{
+    AmeyanagiThicknessInput, ameyanagi_suppression_exact_with_sample_info, settings_for_geometry,
+};
+use crate::booth::{BoothOptions, booth_suppression_reference_on};
+use crate::common::{Edge, FluorescenceGeometry, SelfAbsError};
+use crate::context::SelfAbsContext;
+
+/// Redraws allowed per sample before an out-of-range parameter is an error.
+const MAX_REDRAWS: usize = 1000;
+
+/// Distribution of one parameter around its nominal value.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Distribution {
+    /// Always the nominal value.
+    #[default]
+    Fixed,
+    /// Normal with standard deviation `sigma`, in the parameter's unit.
+    Gaussian { sigma: f64 },
+    /// Uniform over nominal ± `half_width`, in the parameter's unit.
+    Uniform { half_width: f64 },
+}
+
+/// Model re-evaluated by [`monte_carlo`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum McAlgorithm {
+    /// Booth & Bridges reference suppression, branch chosen per draw.
+    Booth,
+    /// Exact Ameyanagi suppression.
+    #[default]
+    Ameyanagi,
+}
+
+/// Which curve [`monte_carlo`] summarizes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum McOutput {
+    /// The suppression R = χ_exp/χ_true.
+    #[default]
+    Suppression,
+    /// χ_true · R, the χ a measurement would show.
+    Chi,
+}
+
+/// Nominal sample and geometry, and their distributions, for
+/// [`monte_carlo`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct McSettings {
+    pub formula: String,
+    pub central_element: String,
+    pub edge: Edge,
+    /// Energy grid (eV).
+    pub energies: Vec<f64>,
+    pub algorithm: McAlgorithm,
+    pub output: McOutput,
+    /// True χ amplitude the suppression is evaluated at.
+    pub chi_true: f64,
+    /// Nominal density (g/cm³) and its distribution.
+    pub density_g_cm3: f64,
+    pub density_distribution: Distribution,
+    /// Nominal thickness (μm) and its distribution.
+    pub thickness_um: f64,
+    pub thickness_distribution: Distribution,
+    /// Nominal incident angle φ (degrees from the surface) and its
+    /// distribution.
+    pub phi_deg: f64,
+    pub phi_distribution: Distribution,
+    /// Nominal exit angle θ (degrees from the surface) and its distribution.
+    pub theta_deg: f64,
+    pub theta_distribution: Distribution,
+}
+
+/// Per-point statistics of [`monte_carlo`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct McResult {
+    /// Energy grid (eV).
+    pub energies: Vec<f64>,
+    /// Mean over the draws.
+    pub mean: Vec<f64>,
+    /// Sample standard deviation over the draws (0 for a single draw).
+    pub std_dev: Vec<f64>,
+    /// 16th percentile, the lower edge of a ±1σ band.
+    pub p16: Vec<f64>,
+    /// 84th percentile, the upper edge of a ±1σ band.
+    pub p84: Vec<f64>,
+    /// Number of draws.
+    pub n_samples: usize,
+}
+
+/// Propagate the distributions of `settings` through the suppression model
+/// with `n_samples` draws from a generator seeded with `seed`.
+///
+/// Draws with a non-positive density or thickness, or an angle outside
+/// (0°, 90°), are redrawn; a parameter that keeps missing the valid range is
+/// an error.
+pub fn monte_carlo(
+    settings: &McSettings,
+    n_samples: usize,
+    seed: u64,
+) -> Result<McResult, SelfAbsError> {
+    monte_carlo_with_context(&SelfAbsContext::new(), settings, n_samples, seed)
+}
+
+/// [`monte_carlo`] on a caller-provided context.
+pub fn monte_carlo_with_context(
+    ctx: &SelfAbsContext,
+    settings: &McSettings,
+    n_samples: usize,
+    seed: u64,
+) -> Result<McResult, SelfAbsError> {
+    if n_samples == 0 {
+        return Err(SelfAbsError::InsufficientData(
+            "n_samples must be > 0".to_string(),
+        ));
+    }
+    for distribution in [
+        settings.density_distribution,
+        settings.thickness_distribution,
+        settings.phi_distribution,
+        settings.theta_distribution,
+    ] {
+        let width = match distribution {
+            Distribution::Fixed => 0.0,
+            Distribution::Gaussian { sigma } => sigma,
+            Distribution::Uniform { half_width } => half_width,
+        };
+        if !(width.is_finite() && width >= 0.0) {
+            return Err(SelfAbsError::InsufficientData(
+                "distribution widths must be finite and >= 0".to_string(),
+            ));
+        }
+    }
+
+    let info = ctx.sample_info(&settings.formula, &settings.central_element, settings.edge)?;
+    let mut rng = Rng::new(seed);
+    let n_points = settings.energies.len();
+    let mut curves: Vec<Vec<f64>> = Vec::with_capacity(n_samples);
+    for _ in 0..n_samples {
+        let positive = |v: f64| v > 0.0;
+        let angle = |v: f64| v > 0.0 && v < 90.0;
+        let density = rng.draw(
+            settings.density_g_cm3,
+            settings.density_distribution,
+            positive,
+        )?;
+        let thickness_um = rng.draw(
+            settings.thickness_um,
+            settings.thickness_distribution,
+            positive,
+        )?;
+        let phi = rng.draw(settings.phi_deg, settings.phi_distribution, angle)?;
+        let theta = rng.draw(settings.theta_deg, settings.theta_distribution, angle)?;
+        let geometry = FluorescenceGeometry {
+            theta_incident_deg: phi,
+            theta_fluorescence_deg: theta,
+            ..Default::default()
+        };
+
+        let suppression = match settings.algorithm {
+            McAlgorithm::Booth => {
+                booth_suppression_reference_on(
+                    ctx,
+                    &settings.formula,
+                    &settings.central_element,
+                    settings.edge,
+                    &settings.energies,
+                    Some(geometry),
+                    thickness_um,
+                    density,
+                    settings.chi_true,
+                    &BoothOptions::default(),
+                    None,
+                )?
+                .suppression_factor
+            }
+            McAlgorithm::Ameyanagi => {
+                let exact = settings_for_geometry(
+                    Some(geometry),
+                    density,
+                    AmeyanagiThicknessInput::ThicknessCm(thickness_um * 1e-4),
+                    None,
+                    settings.chi_true,
+                )?;
+                ameyanagi_suppression_exact_with_sample_info(ctx, &info, &settings.energies, exact)?
+                    .suppression_factor
+            }
+        };
+        curves.push(match settings.output {
+            McOutput::Suppression => suppression,
+            McOutput::Chi => suppression
+                .into_iter()
+                .map(|r| settings.chi_true * r)
+                .collect(),
+        });
+    }
+
+    let mut mean = Vec::with_capacity(n_points);
+    let mut std_dev = Vec::with_capacity(n_points);
+    let mut p16 = Vec::with_capacity(n_points);
+    let mut p84 = Vec::with_capacity(n_points);
+    let mut column = vec![0.0; n_samples];
+    for i in 0..n_points {
+        for (value, curve) in column.iter_mut().zip(&curves) {
+            *value = curve[i];
+        }
+        let m = column.iter().sum::<f64>() / n_samples as f64;
+        let var = if n_samples > 1 {
+            column.iter().map(|v| (v - m).powi(2)).sum::<f64>() / (n_samples - 1) as f64
+        } else {
+            0.0
+        };
+        column.sort_by(f64::total_cmp);
+        mean.push(m);
+        std_dev.push(var.sqrt());
+        p16.push(percentile(&column, 0.16));
+        p84.push(percentile(&column, 0.84));
+    }
+
+    Ok(McResult {
+        energies: settings.energies.clone(),
+        mean,
+        std_dev,
+        p16,
+        p84,
+        n_samples,
+    })
+}
+
+/// Percentile `p` of sorted `values`, interpolating linearly between ranks.
+fn percentile(values: &[f64], p: f64) -> f64 {
+    let pos = p * (values.len() - 1) as f64;
+    let lo = pos.floor() as usize;
+    let hi = pos.ceil() as usize;
+    values[lo] + (pos - lo as f64) * (values[hi] - values[lo])
+}
+
+/// xoshiro256** generator, seeded through splitmix64.
+struct Rng {
+    s: [u64; 4],
+    /// Second Box–Muller deviate of the last pair.
+    spare: Option<f64>,
+}
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        let mut x = seed;
+        let mut splitmix = || {
+            x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = x;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^ (z >> 31)
+        };
+        Self {
+            s: [splitmix(), splitmix(), splitmix(), splitmix()],
+            spare: None,
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let s = &mut self.s;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    /// Uniform in [0, 1).
+    fn uniform(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    /// Standard normal deviate (Box–Muller).
+    fn normal(&mut self) -> f64 {
+        if let Some(z) = self.spare.take() {
+            return z;
+        }
+        let u1 = 1.0 - self.uniform();
+        let u2 = self.uniform();
+        let r = (-2.0 * u1.ln()).sqrt();
+        let (sin, cos) = (2.0 * std::f64::consts::PI * u2).sin_cos();
+        self.spare = Some(r * sin);
+        r * cos
+    }
+
+    /// A value of `distribution` around `nominal` satisfying `valid`.
+    fn draw(
+        &mut self,
+        nominal: f64,
+        distribution: Distribution,
+        valid: impl Fn(f64) -> bool,
+    ) -> Result<f64, SelfAbsError> {
+        for _ in 0..MAX_REDRAWS {
+            let value = match distribution {
+                Distribution::Fixed => nominal,
+                Distribution::Gaussian { sigma } => nominal + sigma * self.normal(),
+                Distribution::Uniform { half_width } => {
+                    nominal + half_width * (2.0 * self.uniform() - 1.0)
+                }
+            };
+            if valid(value) {
+                return Ok(value);
+            }
+            if distribution == Distribution::Fixed {
+                break;
+            }
+        }
+        Err(SelfAbsError::InsufficientData(format!(
+            "no valid draw around {nominal} with {distribution:?}"
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ameyanagi::ameyanagi_suppression_exact;
+    use crate::booth::booth_suppression_reference;
+
+    fn settings(algorithm: McAlgorithm) -> McSettings {
+        McSettings {
+            formula: "Fe2O3".to_string(),
+            central_element: "Fe".to_string(),
+            edge: Edge::K,
+            energies: (7000..=8000).step_by(20).map(|e| e as f64).collect(),
+            algorithm,
+            output: McOutput::Suppression,
+            chi_true: 0.1,
+            density_g_cm3: 5.24,
+            density_distribution: Distribution::Fixed,
+            thickness_um: 5.0,
+            thickness_distribution: Distribution::Fixed,
+            phi_deg: 45.0,
+            phi_distribution: Distribution::Fixed,
+            theta_deg: 45.0,
+            theta_distribution: Distribution::Fixed,
+        }
+    }
+
+    #[test]
+    fn test_zero_width_reproduces_deterministic() {
+        let booth = settings(McAlgorithm::Booth);
+        let mc = monte_carlo(&booth, 5, 1).unwrap();
+        let reference = booth_suppression_reference(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &booth.energies,
+            None,
+            5.0,
+            5.24,
+            0.1,
+        )
+        .unwrap();
+        let close = |a: &[f64], b: &[f64]| a.iter().zip(b).all(|(x, y)| (x - y).abs() < 1e-12);
+        assert!(close(&mc.mean, &reference.suppression_factor));
+        assert_eq!(mc.p16, reference.suppression_factor);
+        assert_eq!(mc.p84, reference.suppression_factor);
+        assert!(mc.std_dev.iter().all(|&s| s < 1e-12));
+
+        let exact = settings(McAlgorithm::Ameyanagi);
+        let mc = monte_carlo(&exact, 3, 1).unwrap();
+        let reference = ameyanagi_suppression_exact(
+            "Fe2O3",
+            "Fe",
+            Edge::K,
+            &exact.energies,
+            settings_for_geometry(
+                None,
+                5.24,
+                AmeyanagiThicknessInput::ThicknessCm(5.0e-4),
+                None,
+                0.1,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(close(&mc.mean, &reference.suppression_factor));
+    }
+
+    #[test]
+    fn test_density_spread_widens_band() {
+        let ctx = SelfAbsContext::new();
+        let mut narrow = settings(McAlgorithm::Ameyanagi);
+        narrow.density_distribution = Distribution::Gaussian { sigma: 0.1 * 5.24 };
+        let mut wide = narrow.clone();
+        wide.density_distribution = Distribution::Gaussian { sigma: 0.2 * 5.24 };
+
+        let a = monte_carlo_with_context(&ctx, &narrow, 200, 7).unwrap();
+        let misses = ctx.mu_cache_stats().misses;
+        let b = monte_carlo_with_context(&ctx, &wide, 200, 7).unwrap();
+        // Cross-sections come from the shared context after the first run.
+        assert_eq!(ctx.mu_cache_stats().misses, misses);
+
+        // Same seed, same draws.
+        let again = monte_carlo_with_context(&ctx, &narrow, 200, 7).unwrap();
+        assert_eq!(again.mean, a.mean);
+
+        let i = a.energies.iter().position(|&e| e == 7500.0).unwrap();
+        assert!(a.std_dev[i] > 0.0);
+        assert!(
+            b.std_dev[i] > 1.5 * a.std_dev[i],
+            "{} {}",
+            a.std_dev[i],
+            b.std_dev[i]
+        );
+        assert!(b.p84[i] - b.p16[i] > a.p84[i] - a.p16[i]);
+        assert!(a.p16[i] <= a.mean[i] && a.mean[i] <= a.p84[i]);
+
+        let mut invalid = narrow.clone();
+        invalid.density_distribution = Distribution::Gaussian { sigma: -1.0 };
+        assert!(monte_carlo(&invalid, 10, 7).is_err());
+        assert!(monte_carlo(&narrow, 0, 7).is_err());
+    }
+}
@@ -1,12 +1,9 @@
 use wasm_bindgen::prelude::*;
-use xraydb::{CrossSectionKind, XrayDb};
+use xraydb::CrossSectionKind;
 
+use crate::db::db;
 use crate::types::{DeltaBetaResult, MaterialInfo};
 
-fn db() -> XrayDb {
-    XrayDb::new()
-}
-
 fn to_js(e: xraydb::XrayDbError) -> JsError {
     JsError::new(&e.to_string())
 }
@@ -34,6 +31,31 @@ pub fn material_mu(
         .map_err(to_js)
 }
 
+/// [`material_mu`] written into a caller-allocated buffer of one point per
+/// energy (e.g. a `Float64Array`).
+#[wasm_bindgen]
+pub fn material_mu_into(
+    formula: &str,
+    density: f64,
+    energies: &[f64],
+    kind: &str,
+    out: &mut [f64],
+) -> Result<(), JsError> {
+    if out.len() != energies.len() {
+        return Err(JsError::new(&format!(
+            "out has {} points but energies has {}",
+            out.len(),
+            energies.len()
+        )));
+    }
+    let k = parse_kind(kind)?;
+    let mu = db()
+        .material_mu(formula, density, energies, k)
+        .map_err(to_js)?;
+    out.copy_from_slice(&mu);
+    Ok(())
+}
+
 /// Returns material mu by name from built-in database.
 #[wasm_bindgen]
 pub fn material_mu_named(
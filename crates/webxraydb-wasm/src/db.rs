@@ -0,0 +1,32 @@
+//! The X-ray database shared by every binding.
+
+use std::sync::LazyLock;
+
+use xraydb::XrayDb;
+
+/// One database for the lifetime of the module instance, loaded on first use.
+static DB: LazyLock<XrayDb> = LazyLock::new(XrayDb::new);
+
+/// The cached database. Every call shares one instance instead of loading
+/// the tables again.
+pub(crate) fn db() -> &'static XrayDb {
+    &DB
+}
+
+#[cfg(all(test, feature = "attenuation"))]
+mod tests {
+    use super::*;
+    use crate::attenuation::mu_elam;
+
+    #[test]
+    fn test_cached_db_matches_fresh() {
+        assert!(std::ptr::eq(db(), db()));
+        let energies: Vec<f64> = (7000..=8000).step_by(50).map(|e| e as f64).collect();
+        let fresh = XrayDb::new()
+            .mu_elam("Fe", &energies, xraydb::CrossSectionKind::Total)
+            .unwrap();
+        for _ in 0..1000 {
+            assert_eq!(mu_elam("Fe", &energies, "total").unwrap(), fresh);
+        }
+    }
+}
@@ -1,12 +1,8 @@
 use wasm_bindgen::prelude::*;
-use xraydb::XrayDb;
 
+use crate::db::db;
 use crate::types::{CoreholeWidthInfo, EdgeGuess, XrayEdgeInfo, XrayLineInfo};
 
-fn db() -> XrayDb {
-    XrayDb::new()
-}
-
 fn to_js(e: xraydb::XrayDbError) -> JsError {
     JsError::new(&e.to_string())
 }
@@ -3,10 +3,11 @@ use wasm_bindgen::prelude::*;
 use crate::types::{FormulaComponent, ParsedFormula};
 
 /// Parse a chemical formula and return its components.
-/// Supports complex formulas like "Pt5wt%/SiO2" via the chemical-formula crate.
+/// Supports complex formulas like "Pt5wt%/SiO2" via the chemical-formula crate,
+/// and hydrates like "CuSO4·5H2O".
 #[wasm_bindgen]
 pub fn parse_formula(input: &str) -> Result<ParsedFormula, JsError> {
-    let parsed = chemical_formula::prelude::parse_formula(input)
+    let parsed = chemical_formula::prelude::parse_formula(&selfabs::expand_hydrates(input))
         .map_err(|e| JsError::new(&format!("invalid formula: {e}")))?;
 
     // Convert to molecular formula to get stoichiometry
@@ -29,5 +30,5 @@ pub fn parse_formula(input: &str) -> Result<ParsedFormula, JsError> {
 /// Validate a chemical formula. Returns true if the formula is valid.
 #[wasm_bindgen]
 pub fn validate_formula(input: &str) -> bool {
-    chemical_formula::prelude::parse_formula(input).is_ok()
+    chemical_formula::prelude::parse_formula(&selfabs::expand_hydrates(input)).is_ok()
 }
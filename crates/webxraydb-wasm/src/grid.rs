@@ -0,0 +1,73 @@
+use wasm_bindgen::prelude::*;
+
+/// XAS energy grid (eV) around `edge_energy`: pre-edge from `pre_start` in
+/// steps of `pre_step`, XANES from `xanes_start` to `xanes_end` in steps of
+/// `xanes_step` (all eV relative to the edge), then uniform in k up to
+/// `k_max` in steps of `k_step` (Å⁻¹).
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn xas_energy_grid(
+    edge_energy: f64,
+    pre_start: f64,
+    pre_step: f64,
+    xanes_start: f64,
+    xanes_end: f64,
+    xanes_step: f64,
+    k_max: f64,
+    k_step: f64,
+) -> Result<Vec<f64>, JsError> {
+    selfabs::grid::xas_grid(
+        edge_energy,
+        (pre_start, pre_step),
+        (xanes_start, xanes_end, xanes_step),
+        (k_max, k_step),
+    )
+    .map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// k (Å⁻¹) of each energy (eV) relative to `e0`; 0 at and below `e0`.
+#[wasm_bindgen]
+pub fn etok(energies: &[f64], e0: f64) -> Vec<f64> {
+    selfabs::energies_to_k(energies, e0)
+}
+
+/// Energy (eV) of each k (Å⁻¹) relative to `e0`; negative k maps to `e0`.
+#[wasm_bindgen]
+pub fn ktoe(k: &[f64], e0: f64) -> Vec<f64> {
+    selfabs::k_to_energies(k, e0)
+}
+
+/// The E↔k constant: k = √(ETOK · (E − E₀)).
+#[wasm_bindgen]
+pub fn etok_constant() -> f64 {
+    selfabs::ETOK
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid_and_conversions() {
+        let e0 = 7112.0;
+        let grid = xas_energy_grid(e0, -200.0, 10.0, -30.0, 50.0, 0.5, 14.0, 0.05).unwrap();
+        assert!(grid.windows(2).all(|w| w[1] > w[0]));
+
+        // Uniform in k past the XANES end.
+        let k = etok(&grid, e0);
+        let exafs = grid.iter().position(|&e| e == e0 + 50.0).unwrap();
+        for w in k[exafs..].windows(2) {
+            assert!((w[1] - w[0] - 0.05).abs() < 1e-9);
+        }
+        let k_last = *k.last().unwrap();
+        assert!(k_last <= 14.0 + 1e-9 && k_last > 14.0 - 0.05, "{k_last}");
+
+        let back = ktoe(&k[exafs..], e0);
+        for (a, b) in back.iter().zip(&grid[exafs..]) {
+            assert!((a - b).abs() < 1e-9, "{a} vs {b}");
+        }
+        assert_eq!(etok(&[e0 - 10.0, e0], e0), [0.0, 0.0]);
+        assert_eq!(ktoe(&[-1.0], e0), [e0]);
+        assert_eq!(etok_constant(), selfabs::ETOK);
+    }
+}
@@ -1,10 +1,28 @@
+//! WASM bindings for the webxraydb-rs SPA.
+//!
+//! Each group of bindings is behind a cargo feature, all on by default:
+//! `edges` (edges, lines and core-hole widths), `attenuation`,
+//! `ionchamber`, `scattering`, `optics` (Darwin widths) and `selfabs` (the
+//! self-absorption corrections and the XAS energy grid). Element data and
+//! formula parsing are always built; formula parsing uses the hydrate
+//! expansion from `selfabs::formula`, so that crate is always linked.
+
 pub mod types;
 
+#[cfg(feature = "attenuation")]
 pub mod attenuation;
+mod db;
+#[cfg(feature = "edges")]
 pub mod edges_lines;
 pub mod element;
 pub mod formula;
+#[cfg(feature = "selfabs")]
+pub mod grid;
+#[cfg(feature = "ionchamber")]
 pub mod ionchamber;
+#[cfg(feature = "optics")]
 pub mod optics;
+#[cfg(feature = "scattering")]
 pub mod scattering;
+#[cfg(feature = "selfabs")]
 pub mod selfabs;
@@ -1,12 +1,9 @@
 use wasm_bindgen::prelude::*;
-use xraydb::{Polarization, XrayDb};
+use xraydb::Polarization;
 
+use crate::db::db;
 use crate::types::DarwinWidthResult;
 
-fn db() -> XrayDb {
-    XrayDb::new()
-}
-
 fn to_js(e: xraydb::XrayDbError) -> JsError {
     JsError::new(&e.to_string())
 }
@@ -1,9 +1,7 @@
 use wasm_bindgen::prelude::*;
-use xraydb::{ChantlerKind, XrayDb};
+use xraydb::ChantlerKind;
 
-fn db() -> XrayDb {
-    XrayDb::new()
-}
+use crate::db::db;
 
 fn to_js(e: xraydb::XrayDbError) -> JsError {
     JsError::new(&e.to_string())
@@ -1,7 +1,15 @@
 use wasm_bindgen::prelude::*;
 
+use selfabs::correction::{Algorithm, CorrectionSetup, correction_with_sample_info};
+use selfabs::{SampleInfo, SelfAbsContext, SelfAbsCorrection};
+
+use crate::db::db;
 use crate::types::{
-    AmeyanagiResult, AtomsResult, BoothResult, BoothSuppressionResult, FluoParamsResult,
+    AmeyanagiOptions, AmeyanagiResult, AmeyanagiScalars, AmeyanagiSettingsOptions, AtomsResult,
+    BoothOptions, BoothResult, BoothScalars, BoothSuppressionResult, CompareAlgorithm,
+    CompareCurve, CompareResult, CompareSummary, CorrectChiOptions, DilutionAdvisorResult,
+    EmissionLineInfo, FluoParamsResult, MaxThicknessResult, SaError, SampleComponent,
+    SampleInfoResult, SampleOptions, SuppressionMap, ThicknessInput, ThicknessScanResult,
     TrogerResult,
 };
 
@@ -13,11 +21,18 @@ fn make_geometry(
         (Some(ti), Some(tf)) => Some(selfabs::FluorescenceGeometry {
             theta_incident_deg: ti,
             theta_fluorescence_deg: tf,
+            ..Default::default()
         }),
         _ => None,
     }
 }
 
+/// Parse `edge` through [`selfabs::Edge`]'s `FromStr`, whose error lists
+/// the valid names.
+fn parse_edge(edge: &str) -> Result<selfabs::Edge, SaError> {
+    edge.parse().map_err(|e: selfabs::SelfAbsError| e.into())
+}
+
 /// Fluo algorithm (Haskel, Ravel, Stern).
 /// Computes parameters for correcting normalized μ(E). Applicable to XANES.
 #[wasm_bindgen]
@@ -28,19 +43,51 @@ pub fn sa_fluo(
     energies: &[f64],
     theta_incident: Option<f64>,
     theta_fluorescence: Option<f64>,
-) -> Result<FluoParamsResult, JsError> {
+) -> Result<FluoParamsResult, SaError> {
+    let edge = parse_edge(edge)?;
     let geo = make_geometry(theta_incident, theta_fluorescence);
-    let r = selfabs::fluo::fluo_params(formula, central_element, edge, energies, geo)
-        .map_err(|e| JsError::new(&e.to_string()))?;
+    let r = SelfAbsContext::with_db(Some(db()))
+        .fluo(
+            formula,
+            central_element,
+            edge,
+            energies,
+            geo,
+            &selfabs::fluo::FluoOptions::default(),
+        )
+        .map_err(SaError::from)?;
 
-    Ok(FluoParamsResult {
-        beta: r.beta,
-        gamma_prime: r.gamma_prime,
-        ratio: r.ratio,
-        mu_background_norm: r.mu_background_norm,
-        edge_energy: r.edge_energy,
-        fluorescence_energy: r.fluorescence_energy,
-    })
+    Ok(r.into())
+}
+
+/// Apply the Fluo correction of [`sa_fluo`]'s result to normalized μ(E)
+/// on the same energy grid, without recomputing the parameters.
+#[wasm_bindgen]
+pub fn sa_fluo_correct(params: FluoParamsResult, mu_norm: &[f64]) -> Result<Vec<f64>, SaError> {
+    fluo_correct(params, mu_norm).map_err(SaError::from)
+}
+
+fn fluo_correct(
+    params: FluoParamsResult,
+    mu_norm: &[f64],
+) -> Result<Vec<f64>, selfabs::SelfAbsError> {
+    if mu_norm.len() != params.mu_background_norm.len() {
+        return Err(selfabs::SelfAbsError::InsufficientData(format!(
+            "mu_norm has {} points but the Fluo grid has {}",
+            mu_norm.len(),
+            params.mu_background_norm.len()
+        )));
+    }
+    let params = selfabs::fluo::FluoParams {
+        beta: params.beta,
+        gamma_prime: params.gamma_prime,
+        ratio: params.ratio,
+        mu_background_norm: params.mu_background_norm,
+        edge_energy: params.edge_energy,
+        fluorescence_energy: params.fluorescence_energy,
+        warnings: Vec::new(),
+    };
+    Ok(selfabs::fluo::correct_mu(&params, mu_norm))
 }
 
 /// Tröger algorithm (Tröger et al., PRB 46:6, 1992).
@@ -53,19 +100,57 @@ pub fn sa_troger(
     energies: &[f64],
     theta_incident: Option<f64>,
     theta_fluorescence: Option<f64>,
-) -> Result<TrogerResult, JsError> {
+) -> Result<TrogerResult, SaError> {
+    let edge = parse_edge(edge)?;
     let geo = make_geometry(theta_incident, theta_fluorescence);
-    let r = selfabs::troger::troger(formula, central_element, edge, energies, geo)
-        .map_err(|e| JsError::new(&e.to_string()))?;
+    let r = SelfAbsContext::with_db(Some(db()))
+        .troger(
+            formula,
+            central_element,
+            edge,
+            energies,
+            geo,
+            &selfabs::troger::TrogerOptions::default(),
+        )
+        .map_err(SaError::from)?;
 
-    Ok(TrogerResult {
-        energies: r.energies,
-        k: r.k,
-        s: r.s,
-        correction_factor: r.correction_factor,
-        edge_energy: r.edge_energy,
-        fluorescence_energy: r.fluorescence_energy,
-    })
+    Ok(r.into())
+}
+
+/// χ(k) corrected by the Tröger algorithm; `chi` has one point per energy.
+#[wasm_bindgen]
+pub fn sa_troger_correct_chi(
+    formula: &str,
+    central_element: &str,
+    edge: &str,
+    energies: &[f64],
+    chi: &[f64],
+    theta_incident: Option<f64>,
+    theta_fluorescence: Option<f64>,
+) -> Result<Vec<f64>, SaError> {
+    let edge = parse_edge(edge)?;
+    let geo = make_geometry(theta_incident, theta_fluorescence);
+    troger_correct_chi(formula, central_element, edge, energies, chi, geo).map_err(SaError::from)
+}
+
+fn troger_correct_chi(
+    formula: &str,
+    central_element: &str,
+    edge: selfabs::Edge,
+    energies: &[f64],
+    chi: &[f64],
+    geo: Option<selfabs::FluorescenceGeometry>,
+) -> Result<Vec<f64>, selfabs::SelfAbsError> {
+    SelfAbsContext::with_db(Some(db()))
+        .troger(
+            formula,
+            central_element,
+            edge,
+            energies,
+            geo,
+            &selfabs::troger::TrogerOptions::default(),
+        )?
+        .correct_chi(chi)
 }
 
 /// Booth algorithm (Booth & Bridges, Phys. Scr. T115, 2005).
@@ -79,49 +164,203 @@ pub fn sa_booth(
     theta_incident: Option<f64>,
     theta_fluorescence: Option<f64>,
     thickness_um: f64,
-) -> Result<BoothResult, JsError> {
+) -> Result<BoothResult, SaError> {
+    let edge = parse_edge(edge)?;
     let geo = make_geometry(theta_incident, theta_fluorescence);
-    let r = selfabs::booth::booth(formula, central_element, edge, energies, geo, thickness_um)
-        .map_err(|e| JsError::new(&e.to_string()))?;
+    let r = SelfAbsContext::with_db(Some(db()))
+        .booth(
+            formula,
+            central_element,
+            edge,
+            energies,
+            geo,
+            thickness_um,
+            &selfabs::booth::BoothOptions::default(),
+        )
+        .map_err(SaError::from)?;
 
-    Ok(BoothResult {
-        energies: r.energies,
-        k: r.k,
+    Ok(r.into())
+}
+
+/// [`sa_booth`] writing k, s and α into caller-allocated buffers of one
+/// point per energy (e.g. `Float64Array`s), so large grids skip the
+/// per-element conversion of the result object.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn sa_booth_into(
+    formula: &str,
+    central_element: &str,
+    edge: &str,
+    energies: &[f64],
+    theta_incident: Option<f64>,
+    theta_fluorescence: Option<f64>,
+    thickness_um: f64,
+    k_out: &mut [f64],
+    s_out: &mut [f64],
+    alpha_out: &mut [f64],
+) -> Result<BoothScalars, SaError> {
+    for (name, out) in [
+        ("k_out", &*k_out),
+        ("s_out", &*s_out),
+        ("alpha_out", &*alpha_out),
+    ] {
+        check_out_len(name, out, energies.len())?;
+    }
+    let edge = parse_edge(edge)?;
+    let r = SelfAbsContext::with_db(Some(db())).booth(
+        formula,
+        central_element,
+        edge,
+        energies,
+        make_geometry(theta_incident, theta_fluorescence),
+        thickness_um,
+        &selfabs::booth::BoothOptions::default(),
+    )?;
+    k_out.copy_from_slice(&r.k);
+    s_out.copy_from_slice(&r.s);
+    alpha_out.copy_from_slice(&r.alpha);
+    Ok(BoothScalars {
+        n_points: energies.len(),
         is_thick: r.is_thick,
-        s: r.s,
-        alpha: r.alpha,
         sin_phi: r.sin_phi,
         edge_energy: r.edge_energy,
         fluorescence_energy: r.fluorescence_energy,
     })
 }
 
-/// Booth reference suppression ratio R(E, χ) = χexp/χ.
+/// Error naming `name` unless the output buffer holds `n` points.
+fn check_out_len(name: &str, out: &[f64], n: usize) -> Result<(), SaError> {
+    if out.len() == n {
+        Ok(())
+    } else {
+        Err(SaError::invalid(
+            name,
+            format!("{name} has {} points but energies has {n}", out.len()),
+        ))
+    }
+}
+
+/// χ(k) corrected by the Booth algorithm, thin or thick as
+/// [`sa_booth`] decides; `chi` has one point per energy.
 #[wasm_bindgen]
 #[allow(clippy::too_many_arguments)]
-pub fn sa_booth_reference(
+pub fn sa_booth_correct_chi(
     formula: &str,
     central_element: &str,
     edge: &str,
     energies: &[f64],
+    chi: &[f64],
     theta_incident: Option<f64>,
     theta_fluorescence: Option<f64>,
     thickness_um: f64,
     density_g_cm3: f64,
-    chi_assumed: f64,
-) -> Result<BoothSuppressionResult, JsError> {
+) -> Result<Vec<f64>, SaError> {
+    let edge = parse_edge(edge)?;
     let geo = make_geometry(theta_incident, theta_fluorescence);
-    let r = selfabs::booth::booth_suppression_reference(
+    booth_correct_chi(
         formula,
         central_element,
         edge,
         energies,
+        chi,
         geo,
         thickness_um,
         density_g_cm3,
-        chi_assumed,
     )
-    .map_err(|e| JsError::new(&e.to_string()))?;
+    .map_err(SaError::from)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn booth_correct_chi(
+    formula: &str,
+    central_element: &str,
+    edge: selfabs::Edge,
+    energies: &[f64],
+    chi: &[f64],
+    geo: Option<selfabs::FluorescenceGeometry>,
+    thickness_um: f64,
+    density_g_cm3: f64,
+) -> Result<Vec<f64>, selfabs::SelfAbsError> {
+    if chi.len() != energies.len() {
+        return Err(selfabs::SelfAbsError::InsufficientData(format!(
+            "chi has {} points but the energy grid has {}",
+            chi.len(),
+            energies.len()
+        )));
+    }
+    let r = SelfAbsContext::with_db(Some(db())).booth(
+        formula,
+        central_element,
+        edge,
+        energies,
+        geo,
+        thickness_um,
+        &selfabs::booth::BoothOptions::default(),
+    )?;
+    Ok(r.correct_chi(chi, density_g_cm3, thickness_um))
+}
+
+/// Booth suppression R(E) = χexp/χ at `chi_true`, the inverse of
+/// [`sa_booth_correct_chi`].
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn sa_booth_suppression(
+    formula: &str,
+    central_element: &str,
+    edge: &str,
+    energies: &[f64],
+    theta_incident: Option<f64>,
+    theta_fluorescence: Option<f64>,
+    thickness_um: f64,
+    density_g_cm3: f64,
+    chi_true: f64,
+) -> Result<Vec<f64>, SaError> {
+    let edge = parse_edge(edge)?;
+    let geo = make_geometry(theta_incident, theta_fluorescence);
+    SelfAbsContext::with_db(Some(db()))
+        .booth(
+            formula,
+            central_element,
+            edge,
+            energies,
+            geo,
+            thickness_um,
+            &selfabs::booth::BoothOptions::default(),
+        )
+        .and_then(|r| r.suppression_factor(chi_true, density_g_cm3, thickness_um))
+        .map_err(SaError::from)
+}
+
+/// Booth reference suppression ratio R(E, χ) = χexp/χ, the binding of
+/// [`selfabs::booth::booth_suppression_reference`].
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn sa_booth_reference(
+    formula: &str,
+    central_element: &str,
+    edge: &str,
+    energies: &[f64],
+    theta_incident: Option<f64>,
+    theta_fluorescence: Option<f64>,
+    thickness_um: f64,
+    density_g_cm3: f64,
+    chi_assumed: f64,
+) -> Result<BoothSuppressionResult, SaError> {
+    let edge = parse_edge(edge)?;
+    let geo = make_geometry(theta_incident, theta_fluorescence);
+    let r = SelfAbsContext::with_db(Some(db()))
+        .booth_reference(
+            formula,
+            central_element,
+            edge,
+            energies,
+            geo,
+            thickness_um,
+            density_g_cm3,
+            chi_assumed,
+            &selfabs::booth::BoothOptions::default(),
+        )
+        .map_err(SaError::from)?;
 
     Ok(BoothSuppressionResult {
         energies: r.energies,
@@ -129,6 +368,10 @@ pub fn sa_booth_reference(
         r_min: r.r_min,
         r_max: r.r_max,
         r_mean: r.r_mean,
+        r_min_exafs: r.r_min_exafs,
+        r_max_exafs: r.r_max_exafs,
+        r_mean_exafs: r.r_mean_exafs,
+        n_exafs: r.n_exafs,
         is_thick: r.is_thick,
         edge_energy: r.edge_energy,
         fluorescence_energy: r.fluorescence_energy,
@@ -151,40 +394,74 @@ pub fn sa_ameyanagi(
     pellet_mass_g: Option<f64>,
     pellet_diameter_cm: Option<f64>,
     chi_assumed: f64,
-) -> Result<AmeyanagiResult, JsError> {
-    let thickness_input = match (thickness_cm, pellet_mass_g, pellet_diameter_cm) {
-        (Some(d), _, _) => selfabs::ameyanagi::AmeyanagiThicknessInput::ThicknessCm(d),
-        (None, Some(m), Some(d)) => {
-            selfabs::ameyanagi::AmeyanagiThicknessInput::PelletMassDiameter {
-                mass_g: m,
-                diameter_cm: d,
-            }
-        }
-        _ => {
-            return Err(JsError::new(
-                "provide thickness_cm, or both pellet_mass_g and pellet_diameter_cm",
-            ));
-        }
-    };
+    areal_density_mg_cm2: Option<f64>,
+) -> Result<AmeyanagiResult, SaError> {
+    let edge = parse_edge(edge)?;
+    let thickness_input = thickness_input(
+        thickness_cm,
+        areal_density_mg_cm2,
+        pellet_mass_g,
+        pellet_diameter_cm,
+    )?;
+
+    let settings = ameyanagi_settings(
+        density_g_cm3,
+        phi_rad,
+        theta_rad,
+        thickness_input,
+        chi_assumed,
+    );
+    let r = SelfAbsContext::with_db(Some(db()))
+        .ameyanagi(formula, central_element, edge, energies, settings)
+        .map_err(SaError::from)?;
+
+    Ok(r.into())
+}
 
-    let r = selfabs::ameyanagi::ameyanagi_suppression_exact(
+/// [`sa_ameyanagi`] writing R(E) into a caller-allocated buffer of one
+/// point per energy (e.g. a `Float64Array`).
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn sa_ameyanagi_into(
+    formula: &str,
+    central_element: &str,
+    edge: &str,
+    energies: &[f64],
+    density_g_cm3: f64,
+    phi_rad: f64,
+    theta_rad: f64,
+    thickness_cm: Option<f64>,
+    pellet_mass_g: Option<f64>,
+    pellet_diameter_cm: Option<f64>,
+    chi_assumed: f64,
+    areal_density_mg_cm2: Option<f64>,
+    suppression_out: &mut [f64],
+) -> Result<AmeyanagiScalars, SaError> {
+    check_out_len("suppression_out", suppression_out, energies.len())?;
+    let edge = parse_edge(edge)?;
+    let thickness_input = thickness_input(
+        thickness_cm,
+        areal_density_mg_cm2,
+        pellet_mass_g,
+        pellet_diameter_cm,
+    )?;
+    let settings = ameyanagi_settings(
+        density_g_cm3,
+        phi_rad,
+        theta_rad,
+        thickness_input,
+        chi_assumed,
+    );
+    let r = SelfAbsContext::with_db(Some(db())).ameyanagi(
         formula,
         central_element,
         edge,
         energies,
-        selfabs::ameyanagi::AmeyanagiSuppressionSettings {
-            density_g_cm3,
-            phi_rad,
-            theta_rad,
-            thickness_input,
-            chi_assumed,
-        },
-    )
-    .map_err(|e| JsError::new(&e.to_string()))?;
-
-    Ok(AmeyanagiResult {
-        energies: r.energies,
-        suppression_factor: r.suppression_factor,
+        settings,
+    )?;
+    suppression_out.copy_from_slice(&r.suppression_factor);
+    Ok(AmeyanagiScalars {
+        n_points: energies.len(),
         r_min: r.r_min,
         r_max: r.r_max,
         r_mean: r.r_mean,
@@ -197,6 +474,420 @@ pub fn sa_ameyanagi(
     })
 }
 
+/// Ameyanagi R(E) for a χ profile aligned with `energies`, e.g. the
+/// measured χ(k) interpolated onto the energy grid, evaluating
+/// R(E, χ(E)) point by point instead of at one `chi_assumed`. The sample
+/// arguments are those of [`sa_ameyanagi`].
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn sa_ameyanagi_profile(
+    formula: &str,
+    central_element: &str,
+    edge: &str,
+    energies: &[f64],
+    density_g_cm3: f64,
+    phi_rad: f64,
+    theta_rad: f64,
+    thickness_cm: Option<f64>,
+    pellet_mass_g: Option<f64>,
+    pellet_diameter_cm: Option<f64>,
+    chi_profile: &[f64],
+    areal_density_mg_cm2: Option<f64>,
+) -> Result<AmeyanagiResult, SaError> {
+    let edge = parse_edge(edge)?;
+    let thickness_input = thickness_input(
+        thickness_cm,
+        areal_density_mg_cm2,
+        pellet_mass_g,
+        pellet_diameter_cm,
+    )?;
+    // χ comes from the profile; the scalar one is not used.
+    let sample =
+        ameyanagi_settings(density_g_cm3, phi_rad, theta_rad, thickness_input, 0.0).sample();
+    ameyanagi_profile(
+        formula,
+        central_element,
+        edge,
+        energies,
+        sample,
+        chi_profile,
+    )
+    .map_err(SaError::from)
+}
+
+fn ameyanagi_profile(
+    formula: &str,
+    central_element: &str,
+    edge: selfabs::Edge,
+    energies: &[f64],
+    sample: selfabs::ameyanagi::AmeyanagiSampleSettings,
+    chi_profile: &[f64],
+) -> Result<AmeyanagiResult, selfabs::SelfAbsError> {
+    if chi_profile.len() != energies.len() {
+        return Err(selfabs::SelfAbsError::InsufficientData(format!(
+            "chi_profile has {} points but energies has {}; interpolate chi onto the energy grid first",
+            chi_profile.len(),
+            energies.len()
+        )));
+    }
+    if chi_profile.iter().all(|&c| c == 0.0) {
+        return Err(selfabs::SelfAbsError::InsufficientData(
+            "chi_profile is all zero; pass the measured chi on the energy grid, or use sa_ameyanagi with a scalar chi_assumed".to_string(),
+        ));
+    }
+    let r = SelfAbsContext::with_db(Some(db())).ameyanagi_profile(
+        formula,
+        central_element,
+        edge,
+        energies,
+        sample,
+        chi_profile,
+    )?;
+    Ok(r.into())
+}
+
+/// Thickness input of the `sa_ameyanagi*` bindings: the first of a
+/// thickness, an areal density or a pellet mass and diameter.
+fn thickness_input(
+    thickness_cm: Option<f64>,
+    areal_density_mg_cm2: Option<f64>,
+    pellet_mass_g: Option<f64>,
+    pellet_diameter_cm: Option<f64>,
+) -> Result<selfabs::ameyanagi::AmeyanagiThicknessInput, SaError> {
+    use selfabs::ameyanagi::AmeyanagiThicknessInput;
+
+    match (
+        thickness_cm,
+        areal_density_mg_cm2,
+        pellet_mass_g,
+        pellet_diameter_cm,
+    ) {
+        (Some(d), _, _, _) => Ok(AmeyanagiThicknessInput::ThicknessCm(d)),
+        (None, Some(a), _, _) => Ok(AmeyanagiThicknessInput::ArealDensityMgCm2(a)),
+        (None, None, Some(m), Some(d)) => Ok(AmeyanagiThicknessInput::PelletMassDiameter {
+            mass_g: m,
+            diameter_cm: d,
+        }),
+        _ => Err(SaError::invalid(
+            "thickness_cm",
+            "provide thickness_cm, areal_density_mg_cm2, or both pellet_mass_g and pellet_diameter_cm"
+                .to_string(),
+        )),
+    }
+}
+
+/// Settings of the `sa_ameyanagi*` bindings.
+fn ameyanagi_settings(
+    density_g_cm3: f64,
+    phi_rad: f64,
+    theta_rad: f64,
+    thickness_input: selfabs::ameyanagi::AmeyanagiThicknessInput,
+    chi_assumed: f64,
+) -> selfabs::ameyanagi::AmeyanagiSuppressionSettings {
+    selfabs::ameyanagi::AmeyanagiSuppressionSettings {
+        density_g_cm3: Some(density_g_cm3),
+        phi_rad,
+        theta_rad,
+        thickness_input,
+        chi_assumed,
+        binder: None,
+        packing_fraction: None,
+        cross_section: xraydb::CrossSectionKind::Photo,
+        per_line: false,
+        exit_angles: None,
+        mu_a_method: selfabs::ameyanagi::AmeyanagiMuAMethod::EdgeOnlyTrendline,
+        e0_override: None,
+        pre_edge_window: selfabs::PreEdgeOptions::default(),
+        detection_mode: selfabs::DetectionMode::default(),
+        mu_backend: selfabs::MuBackend::default(),
+        detector_filter: None,
+    }
+}
+
+/// Ameyanagi R(E) summary for each of `thicknesses_cm`, computing the
+/// cross-sections once. With `include_curves` the full R(E) of every
+/// thickness is returned as well.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn sa_ameyanagi_thickness_scan(
+    formula: &str,
+    central_element: &str,
+    edge: &str,
+    energies: &[f64],
+    density_g_cm3: f64,
+    phi_rad: f64,
+    theta_rad: f64,
+    thicknesses_cm: &[f64],
+    chi_assumed: f64,
+    include_curves: bool,
+) -> Result<ThicknessScanResult, SaError> {
+    let edge = parse_edge(edge)?;
+    // The scan replaces the thickness; any valid one passes validation.
+    let settings = ameyanagi_settings(
+        density_g_cm3,
+        phi_rad,
+        theta_rad,
+        selfabs::ameyanagi::AmeyanagiThicknessInput::ThicknessCm(1.0),
+        chi_assumed,
+    );
+    let points = SelfAbsContext::with_db(Some(db()))
+        .ameyanagi_thickness_scan(
+            formula,
+            central_element,
+            edge,
+            energies,
+            settings,
+            thicknesses_cm,
+            include_curves,
+        )
+        .map_err(SaError::from)?;
+
+    Ok(ThicknessScanResult {
+        thicknesses_cm: thicknesses_cm.to_vec(),
+        r_min: points.iter().map(|p| p.r_min).collect(),
+        r_mean: points.iter().map(|p| p.r_mean).collect(),
+        r_max: points.iter().map(|p| p.r_max).collect(),
+        n_energies: energies.len(),
+        suppression_factor: include_curves.then(|| {
+            points
+                .into_iter()
+                .flat_map(|p| p.suppression_factor.unwrap_or_default())
+                .collect()
+        }),
+    })
+}
+
+/// Least dilution in `binder_formula` keeping r_min over the EXAFS region at
+/// or above `target_r_min`, for a thick sample or, with both pellet
+/// arguments, a pellet of that size. A target no dilution reaches returns
+/// `achievable: false` rather than an error.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn sa_dilution_advisor(
+    formula: &str,
+    central_element: &str,
+    edge: &str,
+    energies: &[f64],
+    theta_in: Option<f64>,
+    theta_out: Option<f64>,
+    binder_formula: &str,
+    target_r_min: f64,
+    pellet_thickness_cm: Option<f64>,
+    pellet_diameter_cm: Option<f64>,
+) -> Result<DilutionAdvisorResult, SaError> {
+    let edge = parse_edge(edge)?;
+    dilution_advisor(
+        formula,
+        central_element,
+        edge,
+        energies,
+        make_geometry(theta_in, theta_out),
+        binder_formula,
+        target_r_min,
+        pellet_thickness_cm.zip(pellet_diameter_cm),
+    )
+    .map_err(SaError::from)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn dilution_advisor(
+    formula: &str,
+    central_element: &str,
+    edge: selfabs::Edge,
+    energies: &[f64],
+    geometry: Option<selfabs::FluorescenceGeometry>,
+    binder_formula: &str,
+    target_r_min: f64,
+    pellet: Option<(f64, f64)>,
+) -> Result<DilutionAdvisorResult, selfabs::SelfAbsError> {
+    use selfabs::advisor::ThicknessConstraint;
+
+    let constraint = match pellet {
+        Some((thickness_cm, diameter_cm)) => ThicknessConstraint::Pellet {
+            thickness_cm,
+            diameter_cm,
+        },
+        None => ThicknessConstraint::Thick,
+    };
+    let geometry_used = geometry.unwrap_or_default();
+    let mut result = DilutionAdvisorResult {
+        achievable: true,
+        no_dilution_needed: false,
+        sample_mass_fraction: None,
+        binder_to_sample_ratio: None,
+        r_min: 0.0,
+        target_r_min,
+        effective_density_g_cm3: None,
+        pellet_mass_g: None,
+        sample_mass_g: None,
+        binder_formula: binder_formula.to_string(),
+        chi_assumed: selfabs::correction::NOMINAL_CHI,
+        theta_incident_deg: geometry_used.theta_incident_deg,
+        theta_fluorescence_deg: geometry_used.theta_fluorescence_deg,
+        pellet_thickness_cm: pellet.map(|(thickness_cm, _)| thickness_cm),
+        reason: None,
+    };
+    match selfabs::advisor::dilution_for_target_on(
+        &SelfAbsContext::with_db(Some(db())),
+        formula,
+        central_element,
+        edge,
+        energies,
+        geometry,
+        binder_formula,
+        target_r_min,
+        constraint,
+    ) {
+        Ok(advice) => {
+            result.no_dilution_needed = advice.no_dilution_needed;
+            result.sample_mass_fraction = Some(advice.sample_mass_fraction);
+            result.binder_to_sample_ratio = Some(advice.binder_to_sample_ratio);
+            result.r_min = advice.r_min;
+            result.effective_density_g_cm3 = Some(advice.effective_density_g_cm3);
+            result.pellet_mass_g = advice.pellet_mass_g;
+            result.sample_mass_g = advice.sample_mass_g;
+        }
+        Err(selfabs::SelfAbsError::TargetUnreachable {
+            best_r_min, reason, ..
+        }) => {
+            result.achievable = false;
+            result.r_min = best_r_min;
+            result.reason = Some(reason);
+        }
+        Err(e) => return Err(e),
+    }
+    Ok(result)
+}
+
+/// Largest sample thickness keeping r_min over the EXAFS region at or above
+/// `target_r_min` at `chi_assumed`. Pass `pellet_diameter_cm` to also get
+/// the pellet mass. A target even a 1 nm sample misses returns
+/// `achievable: false` rather than an error.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn sa_max_thickness(
+    formula: &str,
+    central_element: &str,
+    edge: &str,
+    energies: &[f64],
+    phi_rad: f64,
+    theta_rad: f64,
+    density_g_cm3: f64,
+    chi_assumed: f64,
+    target_r_min: f64,
+    pellet_diameter_cm: Option<f64>,
+) -> Result<MaxThicknessResult, SaError> {
+    let edge = parse_edge(edge)?;
+    max_thickness(
+        formula,
+        central_element,
+        edge,
+        energies,
+        phi_rad,
+        theta_rad,
+        density_g_cm3,
+        chi_assumed,
+        target_r_min,
+        pellet_diameter_cm,
+    )
+    .map_err(SaError::from)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn max_thickness(
+    formula: &str,
+    central_element: &str,
+    edge: selfabs::Edge,
+    energies: &[f64],
+    phi_rad: f64,
+    theta_rad: f64,
+    density_g_cm3: f64,
+    chi_assumed: f64,
+    target_r_min: f64,
+    pellet_diameter_cm: Option<f64>,
+) -> Result<MaxThicknessResult, selfabs::SelfAbsError> {
+    // The search replaces the thickness; any valid one passes validation.
+    let settings = ameyanagi_settings(
+        density_g_cm3,
+        phi_rad,
+        theta_rad,
+        selfabs::ameyanagi::AmeyanagiThicknessInput::ThicknessCm(1.0),
+        chi_assumed,
+    );
+    let mut result = MaxThicknessResult {
+        achievable: true,
+        max_thickness_cm: None,
+        r_min: 0.0,
+        target_r_min,
+        pellet_mass_g: None,
+        density_g_cm3,
+        chi_assumed,
+        phi_rad,
+        theta_rad,
+        reason: None,
+    };
+    match SelfAbsContext::with_db(Some(db())).ameyanagi_max_thickness(
+        formula,
+        central_element,
+        edge,
+        energies,
+        settings,
+        target_r_min,
+        pellet_diameter_cm,
+    ) {
+        Ok(rec) => {
+            result.max_thickness_cm = rec.max_thickness_cm;
+            result.r_min = rec.r_min;
+            result.pellet_mass_g = rec.pellet_mass_g;
+        }
+        Err(selfabs::SelfAbsError::TargetUnreachable {
+            best_r_min, reason, ..
+        }) => {
+            result.achievable = false;
+            result.r_min = best_r_min;
+            result.reason = Some(reason);
+        }
+        Err(e) => return Err(e),
+    }
+    Ok(result)
+}
+
+/// Ameyanagi R(E, d) over every `energies` × `thicknesses_cm` pair in one
+/// call, computing the cross-sections once for the whole map.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn sa_suppression_map(
+    formula: &str,
+    central_element: &str,
+    edge: &str,
+    energies: &[f64],
+    thicknesses_cm: &[f64],
+    density_g_cm3: f64,
+    phi_rad: f64,
+    theta_rad: f64,
+    chi_assumed: f64,
+) -> Result<SuppressionMap, SaError> {
+    let scan = sa_ameyanagi_thickness_scan(
+        formula,
+        central_element,
+        edge,
+        energies,
+        density_g_cm3,
+        phi_rad,
+        theta_rad,
+        thicknesses_cm,
+        chi_assumed,
+        true,
+    )?;
+    Ok(SuppressionMap {
+        energies: energies.to_vec(),
+        thicknesses: scan.thicknesses_cm,
+        values: scan.suppression_factor.unwrap_or_default(),
+        n_rows: thicknesses_cm.len(),
+        n_cols: energies.len(),
+    })
+}
+
 /// Atoms algorithm (Ravel, J. Synch. Rad. 8:2, 2001).
 /// Simplest: amplitude + σ² correction. No geometry needed.
 #[wasm_bindgen]
@@ -205,9 +896,17 @@ pub fn sa_atoms(
     central_element: &str,
     edge: &str,
     energies: &[f64],
-) -> Result<AtomsResult, JsError> {
-    let r = selfabs::atoms::atoms(formula, central_element, edge, energies)
-        .map_err(|e| JsError::new(&e.to_string()))?;
+) -> Result<AtomsResult, SaError> {
+    let edge = parse_edge(edge)?;
+    let r = SelfAbsContext::with_db(Some(db()))
+        .atoms(
+            formula,
+            central_element,
+            edge,
+            energies,
+            &selfabs::atoms::AtomsOptions::default(),
+        )
+        .map_err(SaError::from)?;
 
     Ok(AtomsResult {
         energies: r.energies,
@@ -222,3 +921,1173 @@ pub fn sa_atoms(
         fluorescence_energy: r.fluorescence_energy,
     })
 }
+
+/// χ(k) corrected by the Atoms algorithm; `chi` has one point per energy.
+#[wasm_bindgen]
+pub fn sa_atoms_correct_chi(
+    formula: &str,
+    central_element: &str,
+    edge: &str,
+    energies: &[f64],
+    chi: &[f64],
+) -> Result<Vec<f64>, SaError> {
+    let edge = parse_edge(edge)?;
+    atoms_correct_chi(formula, central_element, edge, energies, chi).map_err(SaError::from)
+}
+
+fn atoms_correct_chi(
+    formula: &str,
+    central_element: &str,
+    edge: selfabs::Edge,
+    energies: &[f64],
+    chi: &[f64],
+) -> Result<Vec<f64>, selfabs::SelfAbsError> {
+    SelfAbsContext::with_db(Some(db()))
+        .atoms(
+            formula,
+            central_element,
+            edge,
+            energies,
+            &selfabs::atoms::AtomsOptions::default(),
+        )
+        .and_then(|r| SelfAbsCorrection::correct_chi(&r, chi))
+}
+
+/// Fluo, Tröger, Booth (thick and thin), Atoms and Ameyanagi on one
+/// sample, sharing the database and parsed sample. `density_g_cm3` of
+/// `None` looks the formula up; `chi_assumed` is the χ of the Booth and
+/// Ameyanagi suppression.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn sa_compare(
+    formula: &str,
+    central_element: &str,
+    edge: &str,
+    energies: &[f64],
+    theta_incident: Option<f64>,
+    theta_fluorescence: Option<f64>,
+    thickness_um: f64,
+    density_g_cm3: Option<f64>,
+    chi_assumed: f64,
+) -> Result<CompareResult, SaError> {
+    let edge = parse_edge(edge)?;
+    let geo = make_geometry(theta_incident, theta_fluorescence);
+    compare(
+        formula,
+        central_element,
+        edge,
+        energies,
+        geo,
+        thickness_um,
+        density_g_cm3,
+        chi_assumed,
+    )
+    .map_err(SaError::from)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn compare(
+    formula: &str,
+    central_element: &str,
+    edge: selfabs::Edge,
+    energies: &[f64],
+    geo: Option<selfabs::FluorescenceGeometry>,
+    thickness_um: f64,
+    density_g_cm3: Option<f64>,
+    chi_assumed: f64,
+) -> Result<CompareResult, selfabs::SelfAbsError> {
+    use selfabs::compare::ComparedAlgorithm;
+
+    let ctx = SelfAbsContext::with_db(Some(db()));
+    let info = ctx.sample_info(formula, central_element, edge)?;
+    let report = selfabs::compare::run_all_on(
+        &ctx,
+        formula,
+        central_element,
+        edge,
+        energies,
+        geo,
+        density_g_cm3,
+        thickness_um,
+        chi_assumed,
+    )?;
+    let fluo = selfabs::fluo::fluo_params_with_sample_info(
+        &ctx,
+        &info,
+        energies,
+        geo,
+        &selfabs::fluo::FluoOptions::default(),
+    )?;
+    let fluo_summary = selfabs::fluo::FluoCorrection::new(fluo.clone(), energies)?.summary();
+
+    let label = |algorithm| match algorithm {
+        ComparedAlgorithm::Troger => CompareAlgorithm::Troger,
+        ComparedAlgorithm::BoothThick => CompareAlgorithm::BoothThick,
+        ComparedAlgorithm::BoothThin => CompareAlgorithm::BoothThin,
+        ComparedAlgorithm::Atoms => CompareAlgorithm::Atoms,
+        ComparedAlgorithm::Ameyanagi => CompareAlgorithm::Ameyanagi,
+    };
+    let mut summaries = vec![CompareSummary {
+        algorithm: CompareAlgorithm::Fluo,
+        r_mean: fluo_summary.r_mean,
+        r_min: fluo_summary.r_min,
+    }];
+    summaries.extend(report.curves.iter().map(|c| CompareSummary {
+        algorithm: label(c.algorithm),
+        r_mean: c.r_mean,
+        r_min: c.r_min,
+    }));
+    Ok(CompareResult {
+        energies: report.energies,
+        k: report.k,
+        curves: report
+            .curves
+            .into_iter()
+            .map(|c| CompareCurve {
+                algorithm: label(c.algorithm),
+                suppression: c.suppression,
+            })
+            .collect(),
+        summaries,
+        fluo: fluo.into(),
+        density_g_cm3: report.density_g_cm3,
+    })
+}
+
+/// Error naming `field` unless `value` is finite and positive.
+fn check_positive(field: &str, value: f64) -> Result<(), SaError> {
+    if value.is_finite() && value > 0.0 {
+        Ok(())
+    } else {
+        Err(SaError::invalid(
+            field,
+            format!("{field} must be finite and positive, got {value}"),
+        ))
+    }
+}
+
+/// [`sa_fluo`] with its arguments in one object.
+#[wasm_bindgen]
+pub fn sa_fluo_opts(opts: SampleOptions) -> Result<FluoParamsResult, SaError> {
+    sa_fluo(
+        &opts.formula,
+        &opts.central_element,
+        &opts.edge,
+        &opts.energies,
+        opts.theta_incident,
+        opts.theta_fluorescence,
+    )
+}
+
+/// [`sa_troger`] with its arguments in one object.
+#[wasm_bindgen]
+pub fn sa_troger_opts(opts: SampleOptions) -> Result<TrogerResult, SaError> {
+    sa_troger(
+        &opts.formula,
+        &opts.central_element,
+        &opts.edge,
+        &opts.energies,
+        opts.theta_incident,
+        opts.theta_fluorescence,
+    )
+}
+
+/// [`sa_booth`] with its arguments in one object.
+#[wasm_bindgen]
+pub fn sa_booth_opts(opts: BoothOptions) -> Result<BoothResult, SaError> {
+    check_positive("thickness_um", opts.thickness_um)?;
+    let sample = &opts.sample;
+    sa_booth(
+        &sample.formula,
+        &sample.central_element,
+        &sample.edge,
+        &sample.energies,
+        sample.theta_incident,
+        sample.theta_fluorescence,
+        opts.thickness_um,
+    )
+}
+
+/// [`sa_ameyanagi`] with its arguments in one object; `thickness` is one
+/// of the three ways to give the sample thickness.
+#[wasm_bindgen]
+pub fn sa_ameyanagi_opts(opts: AmeyanagiOptions) -> Result<AmeyanagiResult, SaError> {
+    let edge = parse_edge(&opts.edge)?;
+    let settings = ameyanagi_settings_from(&opts.settings)?;
+    SelfAbsContext::with_db(Some(db()))
+        .ameyanagi(
+            &opts.formula,
+            opts.central_element.as_str(),
+            edge,
+            &opts.energies,
+            settings,
+        )
+        .map(Into::into)
+        .map_err(SaError::from)
+}
+
+/// Validate `opts` into the settings [`sa_ameyanagi`] would build.
+fn ameyanagi_settings_from(
+    opts: &AmeyanagiSettingsOptions,
+) -> Result<selfabs::ameyanagi::AmeyanagiSuppressionSettings, SaError> {
+    use selfabs::ameyanagi::AmeyanagiThicknessInput;
+
+    check_positive("density_g_cm3", opts.density_g_cm3)?;
+    let thickness_input = match opts.thickness {
+        ThicknessInput::Thickness { thickness_cm } => {
+            check_positive("thickness.thickness_cm", thickness_cm)?;
+            AmeyanagiThicknessInput::ThicknessCm(thickness_cm)
+        }
+        ThicknessInput::ArealDensity {
+            areal_density_mg_cm2,
+        } => {
+            check_positive("thickness.areal_density_mg_cm2", areal_density_mg_cm2)?;
+            AmeyanagiThicknessInput::ArealDensityMgCm2(areal_density_mg_cm2)
+        }
+        ThicknessInput::Pellet {
+            mass_g,
+            diameter_cm,
+        } => {
+            check_positive("thickness.mass_g", mass_g)?;
+            check_positive("thickness.diameter_cm", diameter_cm)?;
+            AmeyanagiThicknessInput::PelletMassDiameter {
+                mass_g,
+                diameter_cm,
+            }
+        }
+    };
+    Ok(ameyanagi_settings(
+        opts.density_g_cm3,
+        opts.phi_rad,
+        opts.theta_rad,
+        thickness_input,
+        opts.chi_assumed,
+    ))
+}
+
+/// The edge energy, emission lines and composition the algorithms would
+/// use for `formula`, `central_element` and `edge`.
+#[wasm_bindgen]
+pub fn sa_sample_info(
+    formula: &str,
+    central_element: &str,
+    edge: &str,
+) -> Result<SampleInfoResult, SaError> {
+    let edge = parse_edge(edge)?;
+    sample_info(formula, central_element, edge).map_err(SaError::from)
+}
+
+fn sample_info(
+    formula: &str,
+    central_element: &str,
+    edge: selfabs::Edge,
+) -> Result<SampleInfoResult, selfabs::SelfAbsError> {
+    let info = SampleInfo::new(db(), formula, central_element, edge)?;
+    let fractions = selfabs::mu::composition_mass_fractions(db(), info.composition())?;
+    let fraction_of = |symbol: &str| {
+        fractions
+            .iter()
+            .find(|(s, _)| s == symbol)
+            .map_or(0.0, |(_, w)| *w)
+    };
+    Ok(SampleInfoResult {
+        central_element: info.central_symbol().to_string(),
+        edge: edge.to_string(),
+        edge_energy: info.edge_energy(),
+        lines: emission_lines(&info),
+        components: info
+            .composition()
+            .iter()
+            .map(|(symbol, &count)| SampleComponent {
+                symbol: symbol.clone(),
+                count,
+                mass_fraction: fraction_of(symbol),
+            })
+            .collect(),
+        absorber_mass_fraction: fraction_of(info.central_symbol()),
+    })
+}
+
+/// One sample on one energy grid, keeping the parsed sample and the μ
+/// tables between calls so that changing an angle, thickness or χ only
+/// redoes the algorithm itself.
+#[wasm_bindgen]
+pub struct SelfAbsSession {
+    ctx: SelfAbsContext<'static>,
+    info: SampleInfo,
+    energies: Vec<f64>,
+}
+
+#[wasm_bindgen]
+impl SelfAbsSession {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        formula: &str,
+        central_element: &str,
+        edge: &str,
+        energies: &[f64],
+    ) -> Result<SelfAbsSession, SaError> {
+        let edge = parse_edge(edge)?;
+        let ctx = SelfAbsContext::with_db(Some(db()));
+        let info = ctx
+            .sample_info(formula, central_element, edge)
+            .map_err(SaError::from)?;
+        Ok(Self {
+            ctx,
+            info,
+            energies: energies.to_vec(),
+        })
+    }
+
+    /// Absorption edge energy (eV).
+    #[wasm_bindgen(js_name = edgeEnergy)]
+    pub fn edge_energy(&self) -> f64 {
+        self.info.edge_energy()
+    }
+
+    /// Emission lines of the edge the algorithms average over.
+    #[wasm_bindgen(js_name = fluorescenceLines)]
+    pub fn fluorescence_lines(&self) -> Vec<EmissionLineInfo> {
+        emission_lines(&self.info)
+    }
+
+    /// [`sa_troger`] for this sample.
+    pub fn troger(
+        &self,
+        theta_incident: Option<f64>,
+        theta_fluorescence: Option<f64>,
+    ) -> Result<TrogerResult, SaError> {
+        selfabs::troger::troger_with_sample_info(
+            &self.ctx,
+            &self.info,
+            &self.energies,
+            make_geometry(theta_incident, theta_fluorescence),
+            &selfabs::troger::TrogerOptions::default(),
+        )
+        .map(Into::into)
+        .map_err(SaError::from)
+    }
+
+    /// [`sa_booth`] for this sample.
+    pub fn booth(
+        &self,
+        thickness_um: f64,
+        theta_incident: Option<f64>,
+        theta_fluorescence: Option<f64>,
+    ) -> Result<BoothResult, SaError> {
+        selfabs::booth::booth_with_sample_info(
+            &self.ctx,
+            &self.info,
+            &self.energies,
+            make_geometry(theta_incident, theta_fluorescence),
+            thickness_um,
+            &selfabs::booth::BoothOptions::default(),
+        )
+        .map(Into::into)
+        .map_err(SaError::from)
+    }
+
+    /// [`sa_ameyanagi_opts`] for this sample.
+    pub fn ameyanagi(&self, opts: AmeyanagiSettingsOptions) -> Result<AmeyanagiResult, SaError> {
+        let settings = ameyanagi_settings_from(&opts)?;
+        selfabs::ameyanagi::ameyanagi_suppression_exact_with_sample_info(
+            &self.ctx,
+            &self.info,
+            &self.energies,
+            settings,
+        )
+        .map(Into::into)
+        .map_err(SaError::from)
+    }
+
+    /// χ corrected by `algorithm` (`"fluo"`, `"troger"`, `"booth"`,
+    /// `"atoms"` or `"ameyanagi"`); `chi` has one point per energy.
+    #[wasm_bindgen(js_name = correctChi)]
+    pub fn correct_chi(
+        &self,
+        algorithm: &str,
+        chi: &[f64],
+        opts: CorrectChiOptions,
+    ) -> Result<Vec<f64>, SaError> {
+        self.correct_chi_with(algorithm, chi, &opts)
+    }
+}
+
+impl SelfAbsSession {
+    fn correct_chi_with(
+        &self,
+        algorithm: &str,
+        chi: &[f64],
+        opts: &CorrectChiOptions,
+    ) -> Result<Vec<f64>, SaError> {
+        let algorithm = Algorithm::ALL
+            .into_iter()
+            .find(|a| a.name() == algorithm)
+            .ok_or_else(|| {
+                SaError::invalid(
+                    "algorithm",
+                    format!(
+                        "unknown algorithm '{algorithm}', expected one of fluo, troger, booth, atoms, ameyanagi"
+                    ),
+                )
+            })?;
+        let thickness_um = match (algorithm, opts.thickness_um) {
+            (Algorithm::Booth | Algorithm::Ameyanagi, None) => {
+                return Err(SaError::invalid(
+                    "thickness_um",
+                    format!("thickness_um is required for {}", algorithm.name()),
+                ));
+            }
+            (_, thickness_um) => thickness_um.unwrap_or_default(),
+        };
+        let setup = CorrectionSetup {
+            geometry: make_geometry(opts.theta_incident, opts.theta_fluorescence),
+            thickness_um,
+            density_g_cm3: opts.density_g_cm3,
+        };
+        correction_with_sample_info(&self.ctx, &self.info, algorithm, &self.energies, &setup)
+            .and_then(|c| c.correct_chi(chi))
+            .map_err(SaError::from)
+    }
+}
+
+fn emission_lines(info: &SampleInfo) -> Vec<EmissionLineInfo> {
+    info.emission_lines()
+        .iter()
+        .map(|line| EmissionLineInfo {
+            label: line.label.clone(),
+            energy: line.energy,
+            intensity: line.intensity,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SaErrorKind;
+
+    #[test]
+    fn test_sa_fluo_correct_matches_correct_mu() {
+        let energies: Vec<f64> = (7000..=7400).step_by(5).map(|e| e as f64).collect();
+        let params =
+            selfabs::fluo::fluo_params("Fe2O3", "Fe", selfabs::Edge::K, &energies, None).unwrap();
+        let mu_norm: Vec<f64> = energies
+            .iter()
+            .map(|&e| if e > params.edge_energy { 1.0 } else { 0.0 })
+            .collect();
+        let expected = selfabs::fluo::correct_mu(&params, &mu_norm);
+
+        let result = sa_fluo("Fe2O3", "Fe", "K", &energies, None, None).unwrap();
+        assert_eq!(sa_fluo_correct(result, &mu_norm).unwrap(), expected);
+
+        let result = sa_fluo("Fe2O3", "Fe", "K", &energies, None, None).unwrap();
+        assert!(fluo_correct(result, &mu_norm[1..]).is_err());
+    }
+
+    #[test]
+    fn test_troger_and_atoms_correct_chi_match_native() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        let troger =
+            selfabs::troger::troger("Fe2O3", "Fe", selfabs::Edge::K, &energies, None).unwrap();
+        let chi: Vec<f64> = troger.k.iter().map(|&k| 0.05 * (4.0 * k).sin()).collect();
+
+        let corrected = sa_troger_correct_chi("Fe2O3", "Fe", "K", &energies, &chi, None, None);
+        assert_eq!(corrected.unwrap(), troger.correct_chi(&chi).unwrap());
+        let native = selfabs::atoms::atoms("Fe2O3", "Fe", selfabs::Edge::K, &energies).unwrap();
+        let corrected = sa_atoms_correct_chi("Fe2O3", "Fe", "K", &energies, &chi);
+        assert_eq!(corrected.unwrap(), native.correct_chi(&chi));
+
+        let short = &chi[1..];
+        assert!(
+            troger_correct_chi("Fe2O3", "Fe", selfabs::Edge::K, &energies, short, None).is_err()
+        );
+        assert!(atoms_correct_chi("Fe2O3", "Fe", selfabs::Edge::K, &energies, short).is_err());
+    }
+
+    #[test]
+    fn test_booth_correct_chi_and_suppression_match_native() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        for thickness_um in [5.0, 100_000.0] {
+            let native = selfabs::booth::booth(
+                "Fe2O3",
+                "Fe",
+                selfabs::Edge::K,
+                &energies,
+                None,
+                thickness_um,
+            )
+            .unwrap();
+            let chi: Vec<f64> = native.k.iter().map(|&k| 0.05 * (4.0 * k).sin()).collect();
+            let corrected = sa_booth_correct_chi(
+                "Fe2O3",
+                "Fe",
+                "K",
+                &energies,
+                &chi,
+                None,
+                None,
+                thickness_um,
+                5.24,
+            );
+            assert_eq!(
+                corrected.unwrap(),
+                native.correct_chi(&chi, 5.24, thickness_um)
+            );
+            let r = sa_booth_suppression(
+                "Fe2O3",
+                "Fe",
+                "K",
+                &energies,
+                None,
+                None,
+                thickness_um,
+                5.24,
+                0.1,
+            );
+            assert_eq!(
+                r.unwrap(),
+                native.suppression_factor(0.1, 5.24, thickness_um).unwrap()
+            );
+            assert!(
+                booth_correct_chi(
+                    "Fe2O3",
+                    "Fe",
+                    selfabs::Edge::K,
+                    &energies,
+                    &chi[1..],
+                    None,
+                    thickness_um,
+                    5.24,
+                )
+                .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_booth_reference_matches_native() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        let native = selfabs::booth::booth_suppression_reference(
+            "Fe2O3",
+            "Fe",
+            selfabs::Edge::K,
+            &energies,
+            None,
+            100_000.0,
+            5.24,
+            0.1,
+        )
+        .unwrap();
+        let r = sa_booth_reference(
+            "Fe2O3", "Fe", "K", &energies, None, None, 100_000.0, 5.24, 0.1,
+        )
+        .unwrap();
+        assert!(r.is_thick);
+        assert_eq!(r.suppression_factor, native.suppression_factor);
+        assert_eq!(
+            (r.r_min, r.r_max, r.r_mean),
+            (native.r_min, native.r_max, native.r_mean)
+        );
+        assert_eq!(
+            (r.r_min_exafs, r.r_max_exafs, r.r_mean_exafs, r.n_exafs),
+            (
+                native.r_min_exafs,
+                native.r_max_exafs,
+                native.r_mean_exafs,
+                native.n_exafs
+            )
+        );
+        assert_eq!(r.edge_energy, native.edge_energy);
+        assert_eq!(r.fluorescence_energy, native.fluorescence_energy);
+    }
+
+    #[test]
+    fn test_ameyanagi_thickness_scan() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        let phi = std::f64::consts::FRAC_PI_4;
+        let thicknesses = [1e-4, 5e-4, 2e-3, 1e-2];
+        let scan = sa_ameyanagi_thickness_scan(
+            "Fe2O3",
+            "Fe",
+            "K",
+            &energies,
+            5.24,
+            phi,
+            phi,
+            &thicknesses,
+            0.1,
+            true,
+        )
+        .unwrap();
+        assert_eq!(scan.thicknesses_cm, thicknesses);
+        for pair in scan.r_mean.windows(2) {
+            assert!(pair[1] < pair[0], "{:?}", scan.r_mean);
+        }
+
+        // Each row is the single-thickness result.
+        let curves = scan.suppression_factor.unwrap();
+        assert_eq!(curves.len(), thicknesses.len() * scan.n_energies);
+        let single = sa_ameyanagi(
+            "Fe2O3",
+            "Fe",
+            "K",
+            &energies,
+            5.24,
+            phi,
+            phi,
+            Some(thicknesses[2]),
+            None,
+            None,
+            0.1,
+            None,
+        )
+        .unwrap();
+        let row = &curves[2 * scan.n_energies..3 * scan.n_energies];
+        for (a, b) in row.iter().zip(&single.suppression_factor) {
+            assert!((a - b).abs() < 1e-12, "{a} vs {b}");
+        }
+        assert!((scan.r_mean[2] - single.r_mean).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_options_objects() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        let options = |thickness: &str| -> AmeyanagiOptions {
+            serde_json::from_str(&format!(
+                r#"{{"formula": "Fe2O3", "central_element": "Fe", "edge": "K",
+                    "energies": {energies:?}, "density_g_cm3": 5.24,
+                    "phi_rad": 0.7853981633974483, "theta_rad": 0.7853981633974483,
+                    "chi_assumed": 0.1, "thickness": {thickness}}}"#
+            ))
+            .unwrap()
+        };
+        let phi = std::f64::consts::FRAC_PI_4;
+        let positional = |thickness_cm, areal_density| {
+            sa_ameyanagi(
+                "Fe2O3",
+                "Fe",
+                "K",
+                &energies,
+                5.24,
+                phi,
+                phi,
+                thickness_cm,
+                None,
+                None,
+                0.1,
+                areal_density,
+            )
+            .unwrap()
+        };
+
+        let by_thickness =
+            sa_ameyanagi_opts(options(r#"{"kind": "thickness", "thickness_cm": 0.001}"#)).unwrap();
+        let expected = positional(Some(0.001), None);
+        assert_eq!(by_thickness.suppression_factor, expected.suppression_factor);
+        let by_areal = sa_ameyanagi_opts(options(
+            r#"{"kind": "areal_density", "areal_density_mg_cm2": 524.0}"#,
+        ))
+        .unwrap();
+        let expected = positional(None, Some(524.0));
+        assert_eq!(by_areal.suppression_factor, expected.suppression_factor);
+        assert!((by_areal.thickness_cm - 0.1).abs() < 1e-12);
+
+        let invalid = options(r#"{"kind": "pellet", "mass_g": 0.1, "diameter_cm": -1.0}"#);
+        let err = ameyanagi_settings_from(&invalid.settings).unwrap_err();
+        assert!(err.message.starts_with("thickness.diameter_cm"), "{err:?}");
+
+        // A missing field is named by the deserializer.
+        let err = serde_json::from_str::<BoothOptions>(
+            r#"{"formula": "Fe2O3", "central_element": "Fe", "edge": "K", "energies": [7200.0]}"#,
+        )
+        .err()
+        .unwrap()
+        .to_string();
+        assert!(err.contains("thickness_um"), "{err}");
+
+        let booth: BoothOptions = serde_json::from_str(&format!(
+            r#"{{"formula": "Fe2O3", "central_element": "Fe", "edge": "K",
+                "energies": {energies:?}, "thickness_um": 20.0}}"#
+        ))
+        .unwrap();
+        let expected = sa_booth("Fe2O3", "Fe", "K", &energies, None, None, 20.0).unwrap();
+        assert_eq!(sa_booth_opts(booth).unwrap().s, expected.s);
+    }
+
+    #[test]
+    fn test_session_matches_stateless_functions() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        let session = SelfAbsSession::new("Fe2O3", "Fe", "K", &energies).unwrap();
+        let troger = sa_troger("Fe2O3", "Fe", "K", &energies, None, None).unwrap();
+        assert_eq!(session.edge_energy(), troger.edge_energy);
+        let lines = session.fluorescence_lines();
+        assert!(lines.iter().any(|l| l.label == "Ka1"));
+
+        for (theta_in, theta_out) in [(None, None), (Some(30.0), Some(60.0))] {
+            let cached = session.troger(theta_in, theta_out).unwrap();
+            let fresh = sa_troger("Fe2O3", "Fe", "K", &energies, theta_in, theta_out).unwrap();
+            assert_eq!(cached.correction_factor, fresh.correction_factor);
+            let cached = session.booth(10.0, theta_in, theta_out).unwrap();
+            let fresh = sa_booth("Fe2O3", "Fe", "K", &energies, theta_in, theta_out, 10.0).unwrap();
+            assert_eq!(cached.s, fresh.s);
+            assert_eq!(cached.alpha, fresh.alpha);
+        }
+
+        let settings = || AmeyanagiSettingsOptions {
+            density_g_cm3: 5.24,
+            phi_rad: std::f64::consts::FRAC_PI_4,
+            theta_rad: std::f64::consts::FRAC_PI_4,
+            thickness: ThicknessInput::Thickness {
+                thickness_cm: 0.001,
+            },
+            chi_assumed: 0.1,
+        };
+        let cached = session.ameyanagi(settings()).unwrap();
+        let fresh = sa_ameyanagi_opts(AmeyanagiOptions {
+            formula: "Fe2O3".to_string(),
+            central_element: "Fe".to_string(),
+            edge: "K".to_string(),
+            energies: energies.clone(),
+            settings: settings(),
+        })
+        .unwrap();
+        assert_eq!(cached.suppression_factor, fresh.suppression_factor);
+
+        let chi: Vec<f64> = troger.k.iter().map(|&k| 0.05 * (4.0 * k).sin()).collect();
+        let opts = CorrectChiOptions::default();
+        let corrected = session.correct_chi_with("troger", &chi, &opts).unwrap();
+        let expected = sa_troger_correct_chi("Fe2O3", "Fe", "K", &energies, &chi, None, None);
+        assert_eq!(corrected, expected.unwrap());
+        let opts = CorrectChiOptions {
+            thickness_um: Some(10.0),
+            density_g_cm3: Some(5.24),
+            ..Default::default()
+        };
+        let corrected = session.correct_chi_with("booth", &chi, &opts).unwrap();
+        let expected =
+            sa_booth_correct_chi("Fe2O3", "Fe", "K", &energies, &chi, None, None, 10.0, 5.24);
+        assert_eq!(corrected, expected.unwrap());
+
+        let missing = session.correct_chi_with("booth", &chi, &CorrectChiOptions::default());
+        assert!(missing.unwrap_err().message.contains("thickness_um"));
+        assert!(session.correct_chi_with("tröger", &chi, &opts).is_err());
+        assert!(
+            session
+                .correct_chi_with("troger", &chi[1..], &opts)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_compare() {
+        let energies: Vec<f64> = (7000..=8000).step_by(5).map(|e| e as f64).collect();
+        let result = sa_compare(
+            "Fe2O3",
+            "Fe",
+            "K",
+            &energies,
+            None,
+            None,
+            2.0,
+            Some(5.24),
+            0.1,
+        )
+        .unwrap();
+        let order: Vec<CompareAlgorithm> = result.summaries.iter().map(|s| s.algorithm).collect();
+        assert_eq!(
+            order,
+            [
+                CompareAlgorithm::Fluo,
+                CompareAlgorithm::Troger,
+                CompareAlgorithm::BoothThick,
+                CompareAlgorithm::BoothThin,
+                CompareAlgorithm::Atoms,
+                CompareAlgorithm::Ameyanagi,
+            ]
+        );
+        assert_eq!(result.curves.len(), 5);
+        for curve in &result.curves {
+            assert_eq!(curve.suppression.len(), energies.len());
+        }
+        for s in &result.summaries {
+            assert!(s.r_min <= s.r_mean, "{:?}", s.algorithm);
+            assert!(s.r_mean > 0.0 && s.r_mean < 1.0, "{:?}", s.algorithm);
+        }
+        // A 2 μm film is far less suppressed than the thick limit.
+        let r_mean = |a| {
+            result
+                .summaries
+                .iter()
+                .find(|s| s.algorithm == a)
+                .unwrap()
+                .r_mean
+        };
+        assert!(r_mean(CompareAlgorithm::BoothThin) > r_mean(CompareAlgorithm::BoothThick));
+        assert!(r_mean(CompareAlgorithm::Ameyanagi) > r_mean(CompareAlgorithm::BoothThick));
+
+        let fluo = sa_fluo("Fe2O3", "Fe", "K", &energies, None, None).unwrap();
+        assert_eq!(result.fluo.beta, fluo.beta);
+    }
+
+    #[test]
+    fn test_sample_info() {
+        let info = sa_sample_info("Fe2O3", "Fe", "K").unwrap();
+        assert_eq!(info.central_element, "Fe");
+        assert_eq!(info.edge, "K");
+        assert!((info.edge_energy - 7112.0).abs() < 1.0);
+        let ka1 = info.lines.iter().find(|l| l.label == "Ka1").unwrap();
+        assert!((ka1.energy - 6404.0).abs() < 2.0);
+        assert!(ka1.intensity > 0.0);
+        assert_eq!(info.components.len(), 2);
+        let total: f64 = info.components.iter().map(|c| c.mass_fraction).sum();
+        assert!((total - 1.0).abs() < 1e-12);
+        let fe = info.components.iter().find(|c| c.symbol == "Fe").unwrap();
+        assert_eq!(fe.count, 2.0);
+        // 2 × 55.845 / 159.69
+        assert!((info.absorber_mass_fraction - 0.6994).abs() < 1e-3);
+
+        let bad = sample_info("Fe2Q3", "Fe", selfabs::Edge::K).err().unwrap();
+        assert!(bad.to_string().starts_with("invalid formula"), "{bad}");
+        let missing = sample_info("Al2O3", "Fe", selfabs::Edge::K).err().unwrap();
+        assert!(missing.to_string().contains("Fe"), "{missing}");
+    }
+
+    #[test]
+    fn test_dilution_advisor() {
+        let energies: Vec<f64> = (7000..=8000).step_by(10).map(|e| e as f64).collect();
+        let advice = sa_dilution_advisor(
+            "Fe2O3",
+            "Fe",
+            "K",
+            &energies,
+            None,
+            None,
+            "BN",
+            0.8,
+            Some(0.1),
+            Some(1.3),
+        )
+        .unwrap();
+        let native = selfabs::advisor::dilution_for_target(
+            "Fe2O3",
+            "Fe",
+            selfabs::Edge::K,
+            &energies,
+            None,
+            "BN",
+            0.8,
+            selfabs::advisor::ThicknessConstraint::Pellet {
+                thickness_cm: 0.1,
+                diameter_cm: 1.3,
+            },
+        )
+        .unwrap();
+        assert!(advice.achievable);
+        assert_eq!(
+            advice.sample_mass_fraction,
+            Some(native.sample_mass_fraction)
+        );
+        assert_eq!(advice.r_min, native.r_min);
+        assert_eq!(advice.pellet_mass_g, native.pellet_mass_g);
+        assert_eq!(advice.binder_formula, "BN");
+        assert_eq!(advice.theta_incident_deg, 45.0);
+        assert!(advice.reason.is_none());
+
+        // R ≤ 1, so no dilution reaches a target above 1.
+        let unreachable = sa_dilution_advisor(
+            "Fe2O3", "Fe", "K", &energies, None, None, "BN", 1.01, None, None,
+        )
+        .unwrap();
+        assert!(!unreachable.achievable);
+        assert!(unreachable.sample_mass_fraction.is_none());
+        assert!(unreachable.r_min > 0.9 && unreachable.r_min < 1.01);
+        assert!(unreachable.reason.unwrap().contains("1.01"));
+
+        // Other errors still fail.
+        let bad = dilution_advisor(
+            "Fe2O3",
+            "Fe",
+            selfabs::Edge::K,
+            &energies,
+            None,
+            "BN",
+            f64::NAN,
+            None,
+        );
+        assert!(bad.is_err());
+    }
+
+    #[test]
+    fn test_max_thickness() {
+        let energies: Vec<f64> = (7000..=8000).step_by(10).map(|e| e as f64).collect();
+        let quarter = std::f64::consts::FRAC_PI_4;
+        let rec = sa_max_thickness(
+            "Fe2O3",
+            "Fe",
+            "K",
+            &energies,
+            quarter,
+            quarter,
+            5.24,
+            0.2,
+            0.95,
+            Some(1.3),
+        )
+        .unwrap();
+        assert!(rec.achievable);
+        let d = rec.max_thickness_cm.unwrap();
+        assert!(d > 0.0 && d < 1e-3, "d={d}");
+        assert!(rec.r_min >= 0.95);
+        assert!(rec.pellet_mass_g.unwrap() > 0.0);
+        assert_eq!(rec.density_g_cm3, 5.24);
+        assert_eq!(rec.chi_assumed, 0.2);
+
+        let unreachable = sa_max_thickness(
+            "Fe2O3", "Fe", "K", &energies, quarter, quarter, 5.24, 0.2, 1.01, None,
+        )
+        .unwrap();
+        assert!(!unreachable.achievable);
+        assert!(unreachable.max_thickness_cm.is_none());
+        assert!(unreachable.reason.is_some());
+    }
+
+    #[test]
+    fn test_ameyanagi_profile_matches_native() {
+        let energies: Vec<f64> = (7000..=8000).step_by(10).map(|e| e as f64).collect();
+        let e0 = 7112.0;
+        let chi_profile: Vec<f64> = energies
+            .iter()
+            .map(|&e| {
+                if e > e0 {
+                    let k = selfabs::energies_to_k(&[e], e0)[0];
+                    0.3 * (-0.3 * k).exp()
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        let quarter = std::f64::consts::FRAC_PI_4;
+        let result = sa_ameyanagi_profile(
+            "Fe2O3",
+            "Fe",
+            "K",
+            &energies,
+            5.24,
+            quarter,
+            quarter,
+            Some(1e-3),
+            None,
+            None,
+            &chi_profile,
+            None,
+        )
+        .unwrap();
+
+        let sample = ameyanagi_settings(
+            5.24,
+            quarter,
+            quarter,
+            selfabs::ameyanagi::AmeyanagiThicknessInput::ThicknessCm(1e-3),
+            0.0,
+        )
+        .sample();
+        let native = selfabs::ameyanagi::ameyanagi_suppression_profile(
+            "Fe2O3",
+            "Fe",
+            selfabs::Edge::K,
+            &energies,
+            sample.clone(),
+            &chi_profile,
+        )
+        .unwrap();
+        assert_eq!(result.suppression_factor, native.suppression_factor);
+        assert_eq!(result.r_min, native.r_min);
+        assert_eq!(result.suppression_factor.len(), energies.len());
+
+        let short = ameyanagi_profile(
+            "Fe2O3",
+            "Fe",
+            selfabs::Edge::K,
+            &energies,
+            sample.clone(),
+            &chi_profile[1..],
+        )
+        .err()
+        .unwrap();
+        assert!(short.to_string().contains("interpolate"), "{short}");
+        let zeros = vec![0.0; energies.len()];
+        let zero = ameyanagi_profile("Fe2O3", "Fe", selfabs::Edge::K, &energies, sample, &zeros)
+            .err()
+            .unwrap();
+        assert!(zero.to_string().contains("all zero"), "{zero}");
+    }
+
+    #[test]
+    fn test_suppression_map_rows_match_sa_ameyanagi() {
+        let energies: Vec<f64> = (7000..=8000).step_by(10).map(|e| e as f64).collect();
+        let thicknesses = [1e-4, 1e-3, 1e-2];
+        let quarter = std::f64::consts::FRAC_PI_4;
+        let map = sa_suppression_map(
+            "Fe2O3",
+            "Fe",
+            "K",
+            &energies,
+            &thicknesses,
+            5.24,
+            quarter,
+            quarter,
+            0.1,
+        )
+        .unwrap();
+        assert_eq!(map.n_rows, thicknesses.len());
+        assert_eq!(map.n_cols, energies.len());
+        assert_eq!(map.values.len(), map.n_rows * map.n_cols);
+
+        let single = sa_ameyanagi(
+            "Fe2O3",
+            "Fe",
+            "K",
+            &energies,
+            5.24,
+            quarter,
+            quarter,
+            Some(thicknesses[0]),
+            None,
+            None,
+            0.1,
+            None,
+        )
+        .unwrap();
+        for (m, s) in map.values[..map.n_cols]
+            .iter()
+            .zip(&single.suppression_factor)
+        {
+            assert!((m - s).abs() < 1e-12, "{m} vs {s}");
+        }
+        // Thicker rows are more suppressed.
+        let last = &map.values[2 * map.n_cols..];
+        assert!(
+            last.iter()
+                .zip(&single.suppression_factor)
+                .all(|(t, s)| t <= s)
+        );
+    }
+
+    #[test]
+    fn test_errors_carry_kind_and_context() {
+        let energies: Vec<f64> = (7000..=7400).step_by(5).map(|e| e as f64).collect();
+        let troger = |formula: &str, element: &str, edge: &str| {
+            sa_troger(formula, element, edge, &energies, None, None)
+                .err()
+                .unwrap()
+        };
+
+        let formula = troger("Fe2Q3", "Fe", "K");
+        assert_eq!(formula.kind, SaErrorKind::InvalidFormula);
+        assert_eq!(formula.context.as_deref(), Some("formula"));
+        assert!(formula.message.starts_with("invalid formula"));
+
+        let edge = troger("Fe2O3", "Fe", "Q");
+        assert_eq!(edge.kind, SaErrorKind::InvalidEdge);
+        assert_eq!(edge.context.as_deref(), Some("edge"));
+
+        let element = troger("Fe2O3", "Xx", "K");
+        assert_eq!(element.kind, SaErrorKind::InvalidElement);
+        assert_eq!(element.context.as_deref(), Some("central_element"));
+
+        let lines = troger("H2O", "H", "K");
+        assert_eq!(lines.kind, SaErrorKind::NoEmissionLines, "{lines:?}");
+
+        let opts: BoothOptions = serde_json::from_str(
+            r#"{"formula": "Fe2O3", "central_element": "Fe", "edge": "K",
+                "energies": [7000.0, 7200.0], "thickness_um": -1.0}"#,
+        )
+        .unwrap();
+        let thickness = sa_booth_opts(opts).err().unwrap();
+        assert_eq!(thickness.kind, SaErrorKind::InsufficientData);
+        assert_eq!(thickness.context.as_deref(), Some("thickness_um"));
+    }
+
+    #[test]
+    fn test_into_buffers_match_result_objects() {
+        // A fine 40k-point grid, the size the buffers are meant for.
+        let energies: Vec<f64> = (0..40_000).map(|i| 7000.0 + 0.025 * i as f64).collect();
+        let n = energies.len();
+
+        let (mut k, mut s, mut alpha) = (vec![0.0; n], vec![0.0; n], vec![0.0; n]);
+        let scalars = sa_booth_into(
+            "Fe2O3", "Fe", "K", &energies, None, None, 20.0, &mut k, &mut s, &mut alpha,
+        )
+        .unwrap();
+        let booth = sa_booth("Fe2O3", "Fe", "K", &energies, None, None, 20.0).unwrap();
+        assert_eq!(scalars.n_points, n);
+        assert_eq!(scalars.is_thick, booth.is_thick);
+        assert_eq!(scalars.edge_energy, booth.edge_energy);
+        assert_eq!(k, booth.k);
+        assert_eq!(s, booth.s);
+        assert_eq!(alpha, booth.alpha);
+
+        let quarter = std::f64::consts::FRAC_PI_4;
+        let mut r = vec![0.0; n];
+        let scalars = sa_ameyanagi_into(
+            "Fe2O3",
+            "Fe",
+            "K",
+            &energies,
+            5.24,
+            quarter,
+            quarter,
+            Some(1e-3),
+            None,
+            None,
+            0.1,
+            None,
+            &mut r,
+        )
+        .unwrap();
+        let ameyanagi = sa_ameyanagi(
+            "Fe2O3",
+            "Fe",
+            "K",
+            &energies,
+            5.24,
+            quarter,
+            quarter,
+            Some(1e-3),
+            None,
+            None,
+            0.1,
+            None,
+        )
+        .unwrap();
+        assert_eq!(r, ameyanagi.suppression_factor);
+        assert_eq!(scalars.r_mean, ameyanagi.r_mean);
+
+        let err = sa_booth_into(
+            "Fe2O3",
+            "Fe",
+            "K",
+            &energies,
+            None,
+            None,
+            20.0,
+            &mut k[1..],
+            &mut s,
+            &mut alpha,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(err.context.as_deref(), Some("k_out"));
+    }
+
+    #[cfg(feature = "attenuation")]
+    #[test]
+    fn test_material_mu_into_matches_material_mu() {
+        let energies: Vec<f64> = (0..40_000).map(|i| 7000.0 + 0.025 * i as f64).collect();
+        let mut mu = vec![0.0; energies.len()];
+        crate::attenuation::material_mu_into("Fe2O3", 5.24, &energies, "total", &mut mu).unwrap();
+        let expected = crate::attenuation::material_mu("Fe2O3", 5.24, &energies, "total").unwrap();
+        assert_eq!(mu, expected);
+    }
+}
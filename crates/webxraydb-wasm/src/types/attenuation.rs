@@ -0,0 +1,18 @@
+use serde::Serialize;
+use tsify_next::Tsify;
+
+#[derive(Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct MaterialInfo {
+    pub name: String,
+    pub formula: String,
+    pub density: f64,
+}
+
+#[derive(Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct DeltaBetaResult {
+    pub delta: f64,
+    pub beta: f64,
+    pub attenuation_length_cm: f64,
+}
@@ -0,0 +1,35 @@
+use serde::Serialize;
+use tsify_next::Tsify;
+
+#[derive(Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct XrayEdgeInfo {
+    pub label: String,
+    pub energy: f64,
+    pub fluorescence_yield: f64,
+    pub jump_ratio: f64,
+}
+
+#[derive(Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct XrayLineInfo {
+    pub label: String,
+    pub energy: f64,
+    pub intensity: f64,
+    pub initial_level: String,
+    pub final_level: String,
+}
+
+#[derive(Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct EdgeGuess {
+    pub element: String,
+    pub edge: String,
+}
+
+#[derive(Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct CoreholeWidthInfo {
+    pub edge: String,
+    pub width: f64,
+}
@@ -0,0 +1,27 @@
+use serde::Serialize;
+use tsify_next::Tsify;
+
+#[derive(Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct IonChamberResult {
+    pub incident: f64,
+    pub transmitted: f64,
+    pub photo: f64,
+    pub incoherent: f64,
+    pub coherent: f64,
+}
+
+#[derive(Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct ComptonResult {
+    pub xray_90deg: f64,
+    pub xray_mean: f64,
+    pub electron_mean: f64,
+}
+
+#[derive(serde::Deserialize, Tsify)]
+#[tsify(from_wasm_abi)]
+pub struct GasMixture {
+    pub name: String,
+    pub fraction: f64,
+}
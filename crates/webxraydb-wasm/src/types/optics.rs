@@ -0,0 +1,20 @@
+use serde::Serialize;
+use tsify_next::Tsify;
+
+#[derive(Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct DarwinWidthResult {
+    pub theta: f64,
+    pub theta_offset: f64,
+    pub theta_width: f64,
+    pub theta_fwhm: f64,
+    pub rocking_theta_fwhm: f64,
+    pub energy_width: f64,
+    pub energy_fwhm: f64,
+    pub rocking_energy_fwhm: f64,
+    pub zeta: Vec<f64>,
+    pub dtheta: Vec<f64>,
+    pub denergy: Vec<f64>,
+    pub intensity: Vec<f64>,
+    pub rocking_curve: Vec<f64>,
+}
@@ -0,0 +1,480 @@
+use serde::Serialize;
+use tsify_next::Tsify;
+
+/// Fluo algorithm result (operates on μ(E)).
+#[derive(Serialize, serde::Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct FluoParamsResult {
+    pub beta: f64,
+    pub gamma_prime: f64,
+    pub ratio: f64,
+    pub mu_background_norm: Vec<f64>,
+    pub edge_energy: f64,
+    pub fluorescence_energy: f64,
+}
+
+impl From<selfabs::fluo::FluoParams> for FluoParamsResult {
+    fn from(r: selfabs::fluo::FluoParams) -> Self {
+        Self {
+            beta: r.beta,
+            gamma_prime: r.gamma_prime,
+            ratio: r.ratio,
+            mu_background_norm: r.mu_background_norm,
+            edge_energy: r.edge_energy,
+            fluorescence_energy: r.fluorescence_energy,
+        }
+    }
+}
+
+/// Tröger algorithm result (χ(k) correction).
+#[derive(Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct TrogerResult {
+    pub energies: Vec<f64>,
+    pub k: Vec<f64>,
+    pub s: Vec<f64>,
+    pub correction_factor: Vec<f64>,
+    pub edge_energy: f64,
+    pub fluorescence_energy: f64,
+}
+
+impl From<selfabs::troger::TrogerResult> for TrogerResult {
+    fn from(r: selfabs::troger::TrogerResult) -> Self {
+        Self {
+            energies: r.energies,
+            k: r.k,
+            s: r.s,
+            correction_factor: r.correction_factor,
+            edge_energy: r.edge_energy,
+            fluorescence_energy: r.fluorescence_energy,
+        }
+    }
+}
+
+/// Booth algorithm result (χ(k) correction, thin + thick).
+#[derive(Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct BoothResult {
+    pub energies: Vec<f64>,
+    pub k: Vec<f64>,
+    pub is_thick: bool,
+    pub s: Vec<f64>,
+    pub alpha: Vec<f64>,
+    pub sin_phi: f64,
+    pub edge_energy: f64,
+    pub fluorescence_energy: f64,
+}
+
+impl From<selfabs::booth::BoothResult> for BoothResult {
+    fn from(r: selfabs::booth::BoothResult) -> Self {
+        Self {
+            energies: r.energies,
+            k: r.k,
+            is_thick: r.is_thick,
+            s: r.s,
+            alpha: r.alpha,
+            sin_phi: r.sin_phi,
+            edge_energy: r.edge_energy,
+            fluorescence_energy: r.fluorescence_energy,
+        }
+    }
+}
+
+/// Scalars of a [`BoothResult`], returned by `sa_booth_into` while the
+/// arrays go to caller-allocated buffers.
+#[derive(Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct BoothScalars {
+    pub n_points: usize,
+    pub is_thick: bool,
+    pub sin_phi: f64,
+    pub edge_energy: f64,
+    pub fluorescence_energy: f64,
+}
+
+/// Booth suppression reference result (R(E, χ) = χexp/χ).
+#[derive(Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct BoothSuppressionResult {
+    pub energies: Vec<f64>,
+    pub suppression_factor: Vec<f64>,
+    pub r_min: f64,
+    pub r_max: f64,
+    pub r_mean: f64,
+    pub r_min_exafs: f64,
+    pub r_max_exafs: f64,
+    pub r_mean_exafs: f64,
+    pub n_exafs: usize,
+    pub is_thick: bool,
+    pub edge_energy: f64,
+    pub fluorescence_energy: f64,
+}
+
+/// Ameyanagi algorithm result (exact suppression factor R(E, χ)).
+#[derive(Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct AmeyanagiResult {
+    pub energies: Vec<f64>,
+    pub suppression_factor: Vec<f64>,
+    pub r_min: f64,
+    pub r_max: f64,
+    pub r_mean: f64,
+    pub mu_f: f64,
+    pub thickness_cm: f64,
+    pub geometry_g: f64,
+    pub beta: f64,
+    pub edge_energy: f64,
+    pub fluorescence_energy_weighted: f64,
+}
+
+impl From<selfabs::ameyanagi::AmeyanagiSuppressionResult> for AmeyanagiResult {
+    fn from(r: selfabs::ameyanagi::AmeyanagiSuppressionResult) -> Self {
+        Self {
+            energies: r.energies,
+            suppression_factor: r.suppression_factor,
+            r_min: r.r_min,
+            r_max: r.r_max,
+            r_mean: r.r_mean,
+            mu_f: r.mu_f,
+            thickness_cm: r.thickness_cm,
+            geometry_g: r.geometry_g,
+            beta: r.beta,
+            edge_energy: r.edge_energy,
+            fluorescence_energy_weighted: r.fluorescence_energy_weighted,
+        }
+    }
+}
+
+/// Scalars of an [`AmeyanagiResult`], returned by `sa_ameyanagi_into` while
+/// R(E) goes to a caller-allocated buffer.
+#[derive(Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct AmeyanagiScalars {
+    pub n_points: usize,
+    pub r_min: f64,
+    pub r_max: f64,
+    pub r_mean: f64,
+    pub mu_f: f64,
+    pub thickness_cm: f64,
+    pub geometry_g: f64,
+    pub beta: f64,
+    pub edge_energy: f64,
+    pub fluorescence_energy_weighted: f64,
+}
+
+/// Ameyanagi suppression over a list of thicknesses.
+#[derive(Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct ThicknessScanResult {
+    pub thicknesses_cm: Vec<f64>,
+    pub r_min: Vec<f64>,
+    pub r_mean: Vec<f64>,
+    pub r_max: Vec<f64>,
+    pub n_energies: usize,
+    /// R(E) of every thickness, row-major (`thicknesses_cm.len()` rows of
+    /// `n_energies`), when curves were requested.
+    pub suppression_factor: Option<Vec<f64>>,
+}
+
+/// Ameyanagi R over a thickness × energy grid, see `sa_suppression_map`.
+#[derive(Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct SuppressionMap {
+    pub energies: Vec<f64>,
+    pub thicknesses: Vec<f64>,
+    /// R row-major with one row per thickness: the value at
+    /// `thicknesses[i]`, `energies[j]` is `values[i * n_cols + j]`.
+    pub values: Vec<f64>,
+    /// `thicknesses.len()`.
+    pub n_rows: usize,
+    /// `energies.len()`.
+    pub n_cols: usize,
+}
+
+/// Least dilution meeting an r_min target, see `sa_dilution_advisor`.
+#[derive(Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct DilutionAdvisorResult {
+    /// False when even a 10⁻⁴ sample fraction misses the target; the
+    /// recommendation fields are then `None` and `reason` says why.
+    pub achievable: bool,
+    pub no_dilution_needed: bool,
+    pub sample_mass_fraction: Option<f64>,
+    pub binder_to_sample_ratio: Option<f64>,
+    /// r_min over the EXAFS region at the recommendation, or the best one
+    /// found when not achievable.
+    pub r_min: f64,
+    pub target_r_min: f64,
+    pub effective_density_g_cm3: Option<f64>,
+    pub pellet_mass_g: Option<f64>,
+    pub sample_mass_g: Option<f64>,
+    pub binder_formula: String,
+    /// χ the suppression was evaluated at.
+    pub chi_assumed: f64,
+    pub theta_incident_deg: f64,
+    pub theta_fluorescence_deg: f64,
+    /// Pellet thickness (cm), or `None` for a thick sample.
+    pub pellet_thickness_cm: Option<f64>,
+    pub reason: Option<String>,
+}
+
+/// Largest thickness meeting an r_min target, see `sa_max_thickness`.
+#[derive(Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct MaxThicknessResult {
+    /// False when even a 1 nm sample misses the target; `reason` says why.
+    pub achievable: bool,
+    /// Thickness limit (cm), or `None` when the thick limit already meets
+    /// the target (or it is not achievable).
+    pub max_thickness_cm: Option<f64>,
+    /// r_min over the EXAFS region at the recommendation, or the best one
+    /// found when not achievable.
+    pub r_min: f64,
+    pub target_r_min: f64,
+    pub pellet_mass_g: Option<f64>,
+    pub density_g_cm3: f64,
+    pub chi_assumed: f64,
+    pub phi_rad: f64,
+    pub theta_rad: f64,
+    pub reason: Option<String>,
+}
+
+/// Atoms algorithm result (amplitude + σ² correction).
+#[derive(Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct AtomsResult {
+    pub energies: Vec<f64>,
+    pub k: Vec<f64>,
+    pub correction: Vec<f64>,
+    pub amplitude: f64,
+    pub sigma_squared_self: f64,
+    pub sigma_squared_norm: f64,
+    pub sigma_squared_i0: f64,
+    pub sigma_squared_net: f64,
+    pub edge_energy: f64,
+    pub fluorescence_energy: f64,
+}
+
+/// Sample and grid of the `sa_*_opts` functions.
+#[derive(serde::Deserialize, Tsify)]
+#[tsify(from_wasm_abi)]
+pub struct SampleOptions {
+    pub formula: String,
+    pub central_element: String,
+    pub edge: String,
+    pub energies: Vec<f64>,
+    /// Incident angle from the surface (deg); the default geometry needs
+    /// both angles unset.
+    #[serde(default)]
+    #[tsify(optional)]
+    pub theta_incident: Option<f64>,
+    /// Exit angle from the surface (deg).
+    #[serde(default)]
+    #[tsify(optional)]
+    pub theta_fluorescence: Option<f64>,
+}
+
+/// Options of `sa_booth_opts`.
+#[derive(serde::Deserialize, Tsify)]
+#[tsify(from_wasm_abi)]
+pub struct BoothOptions {
+    #[serde(flatten)]
+    pub sample: SampleOptions,
+    pub thickness_um: f64,
+}
+
+/// How the Ameyanagi sample thickness is given, tagged by `kind`.
+#[derive(serde::Deserialize, Tsify)]
+#[tsify(from_wasm_abi)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ThicknessInput {
+    Thickness { thickness_cm: f64 },
+    ArealDensity { areal_density_mg_cm2: f64 },
+    Pellet { mass_g: f64, diameter_cm: f64 },
+}
+
+/// Measurement settings of `sa_ameyanagi_opts` and
+/// `SelfAbsSession.ameyanagi`.
+#[derive(serde::Deserialize, Tsify)]
+#[tsify(from_wasm_abi)]
+pub struct AmeyanagiSettingsOptions {
+    pub density_g_cm3: f64,
+    pub phi_rad: f64,
+    pub theta_rad: f64,
+    pub thickness: ThicknessInput,
+    pub chi_assumed: f64,
+}
+
+/// Options of `sa_ameyanagi_opts`.
+#[derive(serde::Deserialize, Tsify)]
+#[tsify(from_wasm_abi)]
+pub struct AmeyanagiOptions {
+    pub formula: String,
+    pub central_element: String,
+    pub edge: String,
+    pub energies: Vec<f64>,
+    #[serde(flatten)]
+    pub settings: AmeyanagiSettingsOptions,
+}
+
+/// One emission line of the absorber's edge.
+#[derive(Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct EmissionLineInfo {
+    pub label: String,
+    pub energy: f64,
+    pub intensity: f64,
+}
+
+/// Options of `SelfAbsSession.correctChi`.
+#[derive(serde::Deserialize, Tsify, Default)]
+#[tsify(from_wasm_abi)]
+pub struct CorrectChiOptions {
+    /// Incident angle from the surface (deg).
+    #[serde(default)]
+    #[tsify(optional)]
+    pub theta_incident: Option<f64>,
+    /// Exit angle from the surface (deg).
+    #[serde(default)]
+    #[tsify(optional)]
+    pub theta_fluorescence: Option<f64>,
+    /// Sample thickness (μm); required by booth and ameyanagi.
+    #[serde(default)]
+    #[tsify(optional)]
+    pub thickness_um: Option<f64>,
+    /// Sample density (g/cm³); looked up by formula when unset.
+    #[serde(default)]
+    #[tsify(optional)]
+    pub density_g_cm3: Option<f64>,
+}
+
+/// Algorithm label of a [`CompareResult`] entry.
+#[derive(Serialize, Tsify, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum CompareAlgorithm {
+    Fluo,
+    Troger,
+    BoothThick,
+    BoothThin,
+    Atoms,
+    Ameyanagi,
+}
+
+/// Suppression R(E) = χexp/χ of one χ algorithm.
+#[derive(Serialize, Tsify)]
+pub struct CompareCurve {
+    pub algorithm: CompareAlgorithm,
+    pub suppression: Vec<f64>,
+}
+
+/// R statistics over the EXAFS region of one algorithm.
+#[derive(Serialize, Tsify)]
+pub struct CompareSummary {
+    pub algorithm: CompareAlgorithm,
+    pub r_mean: f64,
+    pub r_min: f64,
+}
+
+/// Every algorithm on one sample and grid.
+#[derive(Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct CompareResult {
+    pub energies: Vec<f64>,
+    pub k: Vec<f64>,
+    /// R(E) of every χ algorithm; Fluo corrects μ(E) and is in `fluo`.
+    pub curves: Vec<CompareCurve>,
+    /// One row per algorithm, Fluo first.
+    pub summaries: Vec<CompareSummary>,
+    pub fluo: FluoParamsResult,
+    pub density_g_cm3: f64,
+}
+
+/// One element of a parsed sample.
+#[derive(Serialize, Tsify)]
+pub struct SampleComponent {
+    pub symbol: String,
+    /// Atoms per formula unit.
+    pub count: f64,
+    pub mass_fraction: f64,
+}
+
+/// What the self-absorption algorithms resolve a formula/element/edge to.
+#[derive(Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct SampleInfoResult {
+    pub central_element: String,
+    pub edge: String,
+    pub edge_energy: f64,
+    /// Emission lines of the edge, as averaged over by the algorithms.
+    pub lines: Vec<EmissionLineInfo>,
+    pub components: Vec<SampleComponent>,
+    pub absorber_mass_fraction: f64,
+}
+
+/// Variant of an [`SaError`], one per `selfabs::SelfAbsErrorKind`.
+#[derive(Serialize, Tsify, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SaErrorKind {
+    Xraydb,
+    NoEmissionLines,
+    InvalidFormula,
+    InsufficientData,
+    Io,
+    InvalidEdge,
+    InvalidElement,
+    TargetUnreachable,
+}
+
+impl From<selfabs::SelfAbsErrorKind> for SaErrorKind {
+    fn from(kind: selfabs::SelfAbsErrorKind) -> Self {
+        use selfabs::SelfAbsErrorKind as K;
+        match kind {
+            K::Xraydb => Self::Xraydb,
+            K::NoEmissionLines => Self::NoEmissionLines,
+            K::InvalidFormula => Self::InvalidFormula,
+            K::InsufficientData => Self::InsufficientData,
+            K::Io => Self::Io,
+            K::InvalidEdge => Self::InvalidEdge,
+            K::InvalidElement => Self::InvalidElement,
+            K::TargetUnreachable => Self::TargetUnreachable,
+        }
+    }
+}
+
+/// Failure of an `sa_*` function, thrown to JS as this plain object.
+#[derive(Serialize, Tsify, Clone, Debug)]
+#[tsify(into_wasm_abi)]
+pub struct SaError {
+    pub kind: SaErrorKind,
+    pub message: String,
+    /// Argument or option field at fault, when known, e.g. `"formula"`
+    /// or `"thickness.diameter_cm"`.
+    pub context: Option<String>,
+}
+
+impl SaError {
+    /// An invalid argument rejected before any calculation.
+    pub(crate) fn invalid(context: &str, message: String) -> Self {
+        Self {
+            kind: SaErrorKind::InsufficientData,
+            message,
+            context: Some(context.to_string()),
+        }
+    }
+}
+
+impl From<selfabs::SelfAbsError> for SaError {
+    fn from(e: selfabs::SelfAbsError) -> Self {
+        let kind = SaErrorKind::from(e.kind());
+        let context = match kind {
+            SaErrorKind::InvalidFormula => Some("formula"),
+            SaErrorKind::InvalidElement => Some("central_element"),
+            SaErrorKind::InvalidEdge | SaErrorKind::NoEmissionLines => Some("edge"),
+            _ => None,
+        };
+        Self {
+            kind,
+            message: e.to_string(),
+            context: context.map(str::to_string),
+        }
+    }
+}
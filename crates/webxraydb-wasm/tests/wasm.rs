@@ -0,0 +1,328 @@
+//! Bindings exercised inside a WASM runtime (`wasm-pack test --node`).
+//! Each test is gated on the features it needs, so every shipped feature
+//! subset runs the ones it builds.
+
+#![cfg(target_arch = "wasm32")]
+
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn test_parse_formula_expands_hydrates() {
+    use webxraydb_wasm::formula::{parse_formula, validate_formula};
+
+    let parsed = parse_formula("CuSO4·5H2O").unwrap();
+    let hydrogen = parsed.components.iter().find(|c| c.symbol == "H").unwrap();
+    assert_eq!(hydrogen.count, 10.0);
+    assert!(validate_formula("CuSO4.5H2O"));
+    assert!(!validate_formula("Xx2"));
+}
+
+#[cfg(feature = "attenuation")]
+#[wasm_bindgen_test]
+fn test_mu_elam_reuses_cached_db() {
+    use webxraydb_wasm::attenuation::mu_elam;
+
+    let energies: Vec<f64> = (7000..=8000).step_by(50).map(|e| e as f64).collect();
+    let first = mu_elam("Fe", &energies, "total").unwrap();
+    for _ in 0..1000 {
+        assert_eq!(mu_elam("Fe", &energies, "total").unwrap(), first);
+    }
+}
+
+#[cfg(all(feature = "edges", feature = "attenuation"))]
+#[wasm_bindgen_test]
+fn test_mu_elam_jumps_at_edge_energy() {
+    use webxraydb_wasm::attenuation::mu_elam;
+    use webxraydb_wasm::edges_lines::xray_edge_energy;
+
+    let e0 = xray_edge_energy("Fe", "K").unwrap();
+    let mu = mu_elam("Fe", &[e0 - 10.0, e0 + 10.0], "photo").unwrap();
+    assert!(mu[1] > 5.0 * mu[0], "{mu:?}");
+}
+
+#[cfg(feature = "selfabs")]
+#[wasm_bindgen_test]
+fn test_sa_troger_in_wasm() {
+    use webxraydb_wasm::selfabs::sa_troger;
+
+    let energies: Vec<f64> = (7200..=7800).step_by(10).map(|e| e as f64).collect();
+    let r = sa_troger("Fe2O3", "Fe", "K", &energies, None, None).unwrap();
+    assert_eq!(r.s.len(), energies.len());
+    assert!(r.s.iter().all(|s| (0.0..1.0).contains(s)));
+    assert!(sa_troger("Fe2O3", "Fe", "Q1", &energies, None, None).is_err());
+}
+
+#[cfg(feature = "selfabs")]
+#[wasm_bindgen_test]
+fn test_sa_dilution_advisor_fe2o3() {
+    use webxraydb_wasm::selfabs::sa_dilution_advisor;
+
+    let energies: Vec<f64> = (7000..=8000).step_by(10).map(|e| e as f64).collect();
+    let advice = sa_dilution_advisor(
+        "Fe2O3",
+        "Fe",
+        "K",
+        &energies,
+        None,
+        None,
+        "BN",
+        0.8,
+        Some(0.1),
+        Some(1.3),
+    )
+    .unwrap();
+    assert!(advice.achievable);
+    assert!(!advice.no_dilution_needed);
+    let fraction = advice.sample_mass_fraction.unwrap();
+    assert!(fraction > 0.0 && fraction < 1.0, "{fraction}");
+    assert!(advice.r_min >= 0.8);
+    assert!(advice.pellet_mass_g.unwrap() > advice.sample_mass_g.unwrap());
+
+    let unreachable = sa_dilution_advisor(
+        "Fe2O3", "Fe", "K", &energies, None, None, "BN", 1.01, None, None,
+    )
+    .unwrap();
+    assert!(!unreachable.achievable);
+    assert!(unreachable.reason.is_some());
+}
+
+#[cfg(feature = "selfabs")]
+#[wasm_bindgen_test]
+fn test_sa_max_thickness_fe2o3() {
+    use webxraydb_wasm::selfabs::sa_max_thickness;
+
+    let energies: Vec<f64> = (7000..=8000).step_by(10).map(|e| e as f64).collect();
+    let quarter = std::f64::consts::FRAC_PI_4;
+    let rec = sa_max_thickness(
+        "Fe2O3",
+        "Fe",
+        "K",
+        &energies,
+        quarter,
+        quarter,
+        5.24,
+        0.2,
+        0.95,
+        Some(1.3),
+    )
+    .unwrap();
+    assert!(rec.achievable);
+    let d = rec.max_thickness_cm.unwrap();
+    assert!(d > 0.0 && d < 1e-3, "d={d}");
+    assert!(rec.r_min >= 0.95);
+    assert!(rec.pellet_mass_g.unwrap() > 0.0);
+
+    let unreachable = sa_max_thickness(
+        "Fe2O3", "Fe", "K", &energies, quarter, quarter, 5.24, 0.2, 1.01, None,
+    )
+    .unwrap();
+    assert!(!unreachable.achievable);
+    assert!(unreachable.max_thickness_cm.is_none());
+}
+
+/// Deserialize `json` the way an options object arrives from JS.
+#[cfg(feature = "selfabs")]
+fn from_js<T: serde::de::DeserializeOwned>(json: &str) -> Result<T, serde_wasm_bindgen::Error> {
+    serde_wasm_bindgen::from_value(js_sys::JSON::parse(json).unwrap())
+}
+
+#[cfg(feature = "selfabs")]
+#[wasm_bindgen_test]
+fn test_options_objects_from_js() {
+    use webxraydb_wasm::selfabs::{sa_ameyanagi, sa_ameyanagi_opts, sa_booth, sa_booth_opts};
+    use webxraydb_wasm::types::{AmeyanagiOptions, BoothOptions};
+
+    let energies: Vec<f64> = (7000..=8000).step_by(10).map(|e| e as f64).collect();
+    let options = |thickness: &str| -> AmeyanagiOptions {
+        from_js(&format!(
+            r#"{{"formula": "Fe2O3", "central_element": "Fe", "edge": "K",
+                "energies": {energies:?}, "density_g_cm3": 5.24,
+                "phi_rad": 0.7853981633974483, "theta_rad": 0.7853981633974483,
+                "chi_assumed": 0.1, "thickness": {thickness}}}"#
+        ))
+        .unwrap()
+    };
+    let quarter = std::f64::consts::FRAC_PI_4;
+    let positional = |thickness_cm, areal_density| {
+        sa_ameyanagi(
+            "Fe2O3",
+            "Fe",
+            "K",
+            &energies,
+            5.24,
+            quarter,
+            quarter,
+            thickness_cm,
+            None,
+            None,
+            0.1,
+            areal_density,
+        )
+        .unwrap()
+    };
+
+    let by_thickness =
+        sa_ameyanagi_opts(options(r#"{"kind": "thickness", "thickness_cm": 0.001}"#)).unwrap();
+    assert_eq!(
+        by_thickness.suppression_factor,
+        positional(Some(0.001), None).suppression_factor
+    );
+    let by_areal = sa_ameyanagi_opts(options(
+        r#"{"kind": "areal_density", "areal_density_mg_cm2": 524.0}"#,
+    ))
+    .unwrap();
+    assert_eq!(
+        by_areal.suppression_factor,
+        positional(None, Some(524.0)).suppression_factor
+    );
+    assert!((by_areal.thickness_cm - 0.1).abs() < 1e-12);
+
+    // A missing field is named by the deserializer.
+    let err = from_js::<BoothOptions>(
+        r#"{"formula": "Fe2O3", "central_element": "Fe", "edge": "K", "energies": [7200.0]}"#,
+    )
+    .err()
+    .unwrap()
+    .to_string();
+    assert!(err.contains("thickness_um"), "{err}");
+
+    let booth: BoothOptions = from_js(&format!(
+        r#"{{"formula": "Fe2O3", "central_element": "Fe", "edge": "K",
+            "energies": {energies:?}, "thickness_um": 20.0}}"#
+    ))
+    .unwrap();
+    let expected = sa_booth("Fe2O3", "Fe", "K", &energies, None, None, 20.0).unwrap();
+    assert_eq!(sa_booth_opts(booth).unwrap().s, expected.s);
+}
+
+#[cfg(feature = "selfabs")]
+#[wasm_bindgen_test]
+fn test_sa_errors_reach_js_with_kind() {
+    use wasm_bindgen::JsValue;
+    use webxraydb_wasm::selfabs::sa_troger;
+
+    let energies: Vec<f64> = (7000..=7400).step_by(5).map(|e| e as f64).collect();
+    // The plain object JS catches, read back field by field.
+    let thrown = |formula: &str, element: &str, edge: &str| {
+        let err = sa_troger(formula, element, edge, &energies, None, None)
+            .err()
+            .unwrap();
+        let value = JsValue::from(err);
+        let field = |name: &str| {
+            js_sys::Reflect::get(&value, &JsValue::from_str(name))
+                .unwrap()
+                .as_string()
+        };
+        (field("kind").unwrap(), field("context"), field("message"))
+    };
+
+    let (kind, context, message) = thrown("Fe2Q3", "Fe", "K");
+    assert_eq!(kind, "InvalidFormula");
+    assert_eq!(context.as_deref(), Some("formula"));
+    assert!(message.unwrap().starts_with("invalid formula"));
+
+    let (kind, context, _) = thrown("Fe2O3", "Fe", "Q");
+    assert_eq!(kind, "InvalidEdge");
+    assert_eq!(context.as_deref(), Some("edge"));
+
+    let (kind, context, _) = thrown("Fe2O3", "Xx", "K");
+    assert_eq!(kind, "InvalidElement");
+    assert_eq!(context.as_deref(), Some("central_element"));
+}
+
+#[cfg(feature = "selfabs")]
+#[wasm_bindgen_test]
+fn test_sa_into_fills_typed_arrays() {
+    use js_sys::Float64Array;
+    use webxraydb_wasm::selfabs::{sa_ameyanagi, sa_ameyanagi_into, sa_booth, sa_booth_into};
+
+    let energies: Vec<f64> = (0..4_000).map(|i| 7000.0 + 0.25 * i as f64).collect();
+    let n = energies.len();
+    let (mut k, mut s, mut alpha) = (vec![0.0; n], vec![0.0; n], vec![0.0; n]);
+    let scalars = sa_booth_into(
+        "Fe2O3", "Fe", "K", &energies, None, None, 20.0, &mut k, &mut s, &mut alpha,
+    )
+    .unwrap();
+    let booth = sa_booth("Fe2O3", "Fe", "K", &energies, None, None, 20.0).unwrap();
+    assert_eq!(scalars.n_points, n);
+    assert_eq!(scalars.is_thick, booth.is_thick);
+    // The buffers as JS sees them after the call.
+    for (buffer, expected) in [(&k, &booth.k), (&s, &booth.s), (&alpha, &booth.alpha)] {
+        let array = Float64Array::from(buffer.as_slice());
+        assert_eq!(array.length() as usize, n);
+        assert_eq!(&array.to_vec(), expected);
+    }
+
+    let quarter = std::f64::consts::FRAC_PI_4;
+    let mut r = vec![0.0; n];
+    let scalars = sa_ameyanagi_into(
+        "Fe2O3",
+        "Fe",
+        "K",
+        &energies,
+        5.24,
+        quarter,
+        quarter,
+        Some(1e-3),
+        None,
+        None,
+        0.1,
+        None,
+        &mut r,
+    )
+    .unwrap();
+    let ameyanagi = sa_ameyanagi(
+        "Fe2O3",
+        "Fe",
+        "K",
+        &energies,
+        5.24,
+        quarter,
+        quarter,
+        Some(1e-3),
+        None,
+        None,
+        0.1,
+        None,
+    )
+    .unwrap();
+    assert_eq!(scalars.r_mean, ameyanagi.r_mean);
+    assert_eq!(
+        Float64Array::from(r.as_slice()).to_vec(),
+        ameyanagi.suppression_factor
+    );
+
+    // A buffer of the wrong length is rejected and named.
+    let err = sa_booth_into(
+        "Fe2O3",
+        "Fe",
+        "K",
+        &energies,
+        None,
+        None,
+        20.0,
+        &mut k,
+        &mut s[1..],
+        &mut alpha,
+    )
+    .err()
+    .unwrap();
+    assert_eq!(err.context.as_deref(), Some("s_out"));
+}
+
+#[cfg(feature = "attenuation")]
+#[wasm_bindgen_test]
+fn test_material_mu_into_fills_typed_array() {
+    use webxraydb_wasm::attenuation::{material_mu, material_mu_into};
+
+    let energies: Vec<f64> = (0..4_000).map(|i| 7000.0 + 0.25 * i as f64).collect();
+    let mut mu = vec![0.0; energies.len()];
+    material_mu_into("Fe2O3", 5.24, &energies, "total", &mut mu).unwrap();
+    let array = js_sys::Float64Array::from(mu.as_slice());
+    assert_eq!(array.length() as usize, energies.len());
+    assert_eq!(
+        array.to_vec(),
+        material_mu("Fe2O3", 5.24, &energies, "total").unwrap()
+    );
+    assert!(material_mu_into("Fe2O3", 5.24, &energies, "total", &mut mu[1..]).is_err());
+}